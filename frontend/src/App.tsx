@@ -35,7 +35,7 @@ export default function App() {
       field_name?: string;
       field_highlights?: { start: number; end: number }[];
     }
-    | { kind: "attachment"; file_id: string; highlights?: { start: number; end: number }[]; display_name?: string }
+    | { kind: "attachment"; file_id: string; highlights?: { start: number; end: number }[]; display_name?: string; line?: number; ocr?: { page: number; regions: { x: number; y: number; w: number; h: number }[]; image_width: number; image_height: number } }
     | { kind: "annotation"; annotation_id: string }
     | null;
   } | null>(null);
@@ -35,8 +35,39 @@ type SearchResult =
     locator: any;
     content: string;
     highlights: { start: number; end: number }[];
+  }
+  | {
+    // doc/wps/ofd/文本附件正文段落；page 仅 OFD 有，行号仅文本附件有
+    kind: "attachment_block";
+    archive_id: string;
+    file_id: string;
+    display_name: string;
+    block_id: string;
+    page?: number | null;
+    line_start?: number | null;
+    line_end?: number | null;
+    text: string;
+    highlights: { start: number; end: number }[];
+    truncated?: boolean;
+  }
+  | {
+    // 图片/扫描件 OCR 文字块；坐标为原图像素
+    kind: "ocr_text";
+    archive_id: string;
+    file_id: string;
+    display_name: string;
+    page: number;
+    block_idx: number;
+    text: string;
+    highlights: { start: number; end: number }[];
+    region: OcrRegion;
+    highlight_regions: OcrRegion[];
+    image_width: number;
+    image_height: number;
   };
 
+type OcrRegion = { x: number; y: number; w: number; h: number };
+
 type SearchFilters = {
   date_from?: number | null;
   date_to?: number | null;
@@ -69,6 +100,7 @@ type FileTypeKey =
   | "image"
   | "video"
   | "docx_other"
+  | "text"
   | "other"
   | "zip_child";
 
@@ -85,7 +117,7 @@ export default function SearchPage({
     archiveId: string,
     open:
       | { kind: "docx"; block_id?: string; highlights?: { start: number; end: number }[]; field_name?: string; field_highlights?: { start: number; end: number }[] }
-      | { kind: "attachment"; file_id: string; highlights?: { start: number; end: number }[]; display_name?: string }
+      | { kind: "attachment"; file_id: string; highlights?: { start: number; end: number }[]; display_name?: string; line?: number; ocr?: { page: number; regions: { x: number; y: number; w: number; h: number }[]; image_width: number; image_height: number } }
       | { kind: "annotation"; annotation_id: string }
       | null
   ) => void;
@@ -119,6 +151,7 @@ export default function SearchPage({
     image: true,
     video: true,
     docx_other: true,
+    text: true,
     other: true,
     zip_child: true,
   });
@@ -215,6 +248,8 @@ export default function SearchPage({
         fields: Extract<SearchResult, { kind: "main_doc_field" }>[];
         annotations: Extract<SearchResult, { kind: "annotation" }>[];
         attachments: Extract<SearchResult, { kind: "attachment_name" }>[];
+        attachment_texts: Extract<SearchResult, { kind: "attachment_block" }>[];
+        ocr_texts: Extract<SearchResult, { kind: "ocr_text" }>[];
       }
     >();
 
@@ -228,12 +263,16 @@ export default function SearchPage({
           fields: [],
           annotations: [],
           attachments: [],
+          attachment_texts: [],
+          ocr_texts: [],
         };
       g.first_idx = Math.min(g.first_idx, idx);
       if (r.kind === "docx_block") g.docx_blocks.push(r);
       else if (r.kind === "main_doc_field") g.fields.push(r);
       else if (r.kind === "annotation") g.annotations.push(r);
-      else g.attachments.push(r);
+      else if (r.kind === "attachment_name") g.attachments.push(r);
+      else if (r.kind === "attachment_block") g.attachment_texts.push(r);
+      else g.ocr_texts.push(r);
       groups.set(r.archive_id, g);
     });
 
@@ -250,7 +289,13 @@ export default function SearchPage({
 
   const groupedCards = useMemo(() => {
     return groupedResults.map((g) => {
-      const total_hits = g.docx_blocks.length + g.fields.length + g.annotations.length + g.attachments.length;
+      const total_hits =
+        g.docx_blocks.length +
+        g.fields.length +
+        g.annotations.length +
+        g.attachments.length +
+        g.attachment_texts.length +
+        g.ocr_texts.length;
 
       const candidates: {
         key: string;
@@ -303,6 +348,30 @@ export default function SearchPage({
         });
       }
 
+      for (const r of g.attachment_texts) {
+        const sn = makeSnippet(r.text, r.highlights);
+        candidates.push({
+          key: `atext:${r.file_id}:${r.block_id}`,
+          tag: "附件正文",
+          text: sn.text,
+          ranges: sn.ranges,
+          r,
+          order: 1_800_000,
+        });
+      }
+
+      for (const r of g.ocr_texts) {
+        const sn = makeSnippet(r.text, r.highlights);
+        candidates.push({
+          key: `ocr:${r.file_id}:${r.page}:${r.block_idx}`,
+          tag: "图片文字",
+          text: sn.text,
+          ranges: sn.ranges,
+          r,
+          order: 1_900_000,
+        });
+      }
+
       for (const r of annoSorted) {
         const sn = makeSnippet(r.content, r.highlights, 10, 80);
         candidates.push({
@@ -332,7 +401,7 @@ export default function SearchPage({
         docx_hits: g.docx_blocks.length,
         field_hits: g.fields.length,
         annotation_hits: g.annotations.length,
-        attach_hits: g.attachments.length,
+        attach_hits: g.attachments.length + g.attachment_texts.length + g.ocr_texts.length,
         snippets: unique,
       };
     });
@@ -428,12 +497,26 @@ export default function SearchPage({
       } else {
         onOpenArchive(r.archive_id, { kind: "docx", field_name: r.field_name, field_highlights: r.highlights });
       }
+    } else if (r.kind === "annotation") {
+      onOpenArchive(r.archive_id, { kind: "annotation", annotation_id: r.annotation_id });
+    } else if (r.kind === "attachment_name") {
+      onOpenArchive(r.archive_id, { kind: "attachment", file_id: r.file_id, highlights: r.highlights, display_name: r.display_name });
+    } else if (r.kind === "attachment_block") {
+      // 文本附件正文命中带起始行，打开后定位到该行
+      onOpenArchive(r.archive_id, { kind: "attachment", file_id: r.file_id, display_name: r.display_name, line: r.line_start ?? undefined });
     } else {
-      if (r.kind === "annotation") {
-        onOpenArchive(r.archive_id, { kind: "annotation", annotation_id: r.annotation_id });
-      } else {
-        onOpenArchive(r.archive_id, { kind: "attachment", file_id: r.file_id, highlights: r.highlights, display_name: r.display_name });
-      }
+      // 图片文字命中：打开后在图上框出命中位置，高亮区域缺失时框整行
+      onOpenArchive(r.archive_id, {
+        kind: "attachment",
+        file_id: r.file_id,
+        display_name: r.display_name,
+        ocr: {
+          page: r.page,
+          regions: r.highlight_regions.length ? r.highlight_regions : [r.region],
+          image_width: r.image_width,
+          image_height: r.image_height,
+        },
+      });
     }
   }
 
@@ -505,6 +588,7 @@ export default function SearchPage({
                   {renderTypeToggle("图片", "image", typeSel, setTypeSel)}
                   {renderTypeToggle("视频", "video", typeSel, setTypeSel)}
                   {renderTypeToggle("附加docx", "docx_other", typeSel, setTypeSel)}
+                  {renderTypeToggle("文本/表格", "text", typeSel, setTypeSel)}
                   {renderTypeToggle("子ZIP", "zip_child", typeSel, setTypeSel)}
                   {renderTypeToggle("其它", "other", typeSel, setTypeSel)}
                 </div>
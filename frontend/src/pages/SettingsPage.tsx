@@ -7,12 +7,23 @@ type LibraryStatus = {
   has_data: boolean;
 };
 
+type OcrStatus = {
+  available: boolean;
+  enabled: boolean;
+  pending: number;
+  running: number;
+  done: number;
+  failed: number;
+  skipped: number;
+};
+
 export default function SettingsPage() {
   const [status, setStatus] = useState<LibraryStatus | null>(null);
   const [newRoot, setNewRoot] = useState("");
   const [migrateTo, setMigrateTo] = useState("");
   const [msg, setMsg] = useState("");
   const [busy, setBusy] = useState(false);
+  const [ocr, setOcr] = useState<OcrStatus | null>(null);
 
   async function refresh() {
     setMsg("");
@@ -20,6 +31,33 @@ export default function SettingsPage() {
     setStatus(s);
     setNewRoot(s.library_root);
     setMigrateTo(s.library_root);
+    setOcr(await invoke<OcrStatus>("get_ocr_status"));
+  }
+
+  async function toggleOcr(enabled: boolean) {
+    setBusy(true);
+    setMsg("");
+    try {
+      setOcr(await invoke<OcrStatus>("set_ocr_enabled", { enabled }));
+    } catch (e: any) {
+      setMsg(String(e?.message ?? e));
+    } finally {
+      setBusy(false);
+    }
+  }
+
+  async function retryFailedOcr() {
+    setBusy(true);
+    setMsg("");
+    try {
+      const n = await invoke<number>("retry_failed_ocr");
+      setMsg(`已重新排队 ${n} 个识别失败的附件`);
+      setOcr(await invoke<OcrStatus>("get_ocr_status"));
+    } catch (e: any) {
+      setMsg(String(e?.message ?? e));
+    } finally {
+      setBusy(false);
+    }
   }
 
   useEffect(() => {
@@ -150,6 +188,39 @@ export default function SettingsPage() {
               </div>
             </div>
 
+            {/* 文字识别 */}
+            {ocr ? (
+              <div className="card" style={{ padding: 20 }}>
+                <h3 style={{ fontSize: 16, fontWeight: 600, marginBottom: 8 }}>图片文字识别（OCR）</h3>
+                <p style={{ fontSize: 13, color: "var(--text-muted)", marginBottom: 16 }}>
+                  开启后在后台识别图片和扫描件 PDF 中的文字，识别结果可被搜索，并在图片上框出命中位置。
+                </p>
+                <div style={{ display: "flex", gap: 16, alignItems: "center", flexWrap: "wrap", fontSize: 13 }}>
+                  <label style={{ display: "flex", gap: 6, alignItems: "center" }}>
+                    <input
+                      type="checkbox"
+                      checked={ocr.enabled}
+                      disabled={busy || (!ocr.available && !ocr.enabled)}
+                      onChange={(e) => toggleOcr(e.target.checked)}
+                    />
+                    启用
+                  </label>
+                  {ocr.available ? (
+                    <span style={{ color: "var(--text-muted)" }}>
+                      待识别 {ocr.pending + ocr.running} · 已完成 {ocr.done} · 无需识别 {ocr.skipped} · 失败 {ocr.failed}
+                    </span>
+                  ) : (
+                    <span style={{ color: "#b45309" }}>OCR 不可用：当前版本未包含 OCR 引擎，或找不到中文识别模型 chi_sim.traineddata（库目录下 ocr/tessdata）</span>
+                  )}
+                  {ocr.failed > 0 ? (
+                    <button disabled={busy} onClick={retryFailedOcr}>
+                      重试失败项
+                    </button>
+                  ) : null}
+                </div>
+              </div>
+            ) : null}
+
             {/* 迁移卡片 */}
             <div className="card" style={{ padding: 20, borderTop: "4px solid #f59e0b" }}>
               <h3 style={{ fontSize: 16, fontWeight: 600, marginBottom: 8 }}>库迁移</h3>
@@ -4,8 +4,19 @@ import AnnotationsSidebar from "./AnnotationsSidebar";
 import DocxAttachmentPreview from "./DocxAttachmentPreview";
 import ExcelViewer from "./ExcelViewer";
 import PdfAllPagesViewer from "./PdfAllPagesViewer";
+import OcrImage from "./OcrImage";
+import TextAttachmentPreview from "./TextAttachmentPreview";
 import TextHighlighter from "./TextHighlighter";
 
+// 搜索命中的 OCR 文字块：page 为图片/扫描页序号，regions 为原图像素坐标
+type OcrFocus = {
+  file_id: string;
+  page: number;
+  regions: { x: number; y: number; w: number; h: number }[];
+  image_width: number;
+  image_height: number;
+};
+
 type ArchiveDetail = {
   archive: {
     archive_id: string;
@@ -30,6 +41,7 @@ type ArchiveDetail = {
     source_depth: number;
     container_virtual_path?: string | null;
     virtual_path: string;
+    container_kind?: string;
     cached_path?: string | null;
   }[];
   annotations: any[];
@@ -53,7 +65,7 @@ export default function ArchiveDetail({
     field_name?: string;
     field_highlights?: { start: number; end: number }[];
   }
-  | { kind: "attachment"; file_id: string; highlights?: { start: number; end: number }[]; display_name?: string }
+  | { kind: "attachment"; file_id: string; highlights?: { start: number; end: number }[]; display_name?: string; line?: number; ocr?: { page: number; regions: { x: number; y: number; w: number; h: number }[]; image_width: number; image_height: number } }
   | { kind: "annotation"; annotation_id: string }
   | null;
   onArchiveDeleted?: () => void | Promise<void>;
@@ -89,6 +101,8 @@ export default function ArchiveDetail({
     null
   );
   const [excelFocus, setExcelFocus] = useState<{ file_id: string; sheet_name: string; row: number; col?: number } | null>(null);
+  const [textFocus, setTextFocus] = useState<{ file_id: string; line: number } | null>(null);
+  const [ocrFocus, setOcrFocus] = useState<OcrFocus | null>(null);
   const [docxAttachmentFocus, setDocxAttachmentFocus] = useState<
     { file_id: string; page?: number; para_idx?: number; image_index?: number; ranges?: { start: number; end: number }[] } | null
   >(null);
@@ -160,6 +174,8 @@ export default function ArchiveDetail({
     setFocusFieldRange(null);
     setFocusAttachmentName(null);
     setExcelFocus(null);
+    setTextFocus(null);
+    setOcrFocus(null);
     setDocxAttachmentFocus(null);
     refreshDetailAndBlocks();
   }, [archiveId]);
@@ -207,12 +223,19 @@ export default function ArchiveDetail({
       setFocusFieldRange(null);
       setFocusAttachmentName({ file_id: open.file_id, ranges: open.highlights ?? [] });
       setExcelFocus(null);
+      setTextFocus(open.line ? { file_id: open.file_id, line: open.line } : null);
+      setOcrFocus(open.ocr ? { file_id: open.file_id, ...open.ocr } : null);
       setDocxAttachmentFocus(null);
 
       // 延时滚动，确保 DOM 已渲染
       setTimeout(() => {
         const el = document.querySelector(`[data-file-id="${open.file_id}"]`);
         el?.scrollIntoView({ block: "center", behavior: "smooth" });
+        // 扫描件 PDF 的 OCR 命中：定位到对应页
+        if (open.ocr && open.ocr.page > 1) {
+          const anchor = document.getElementById(pdfAnchorPrefix(open.file_id) + "-p-" + open.ocr.page);
+          anchor?.scrollIntoView({ block: "start" });
+        }
       }, 100);
       return;
     }
@@ -242,6 +265,8 @@ export default function ArchiveDetail({
     setAttachmentType(null);
     setPdfPage(1);
     setExcelFocus(null);
+    setTextFocus(null);
+    setOcrFocus(null);
     setDocxAttachmentFocus(null);
     setTimeout(() => {
       const a = (annotationsList ?? []).find((x: any) => x.annotation_id === open.annotation_id);
@@ -269,6 +294,9 @@ export default function ArchiveDetail({
     setFocusFieldRange(null);
     setFocusAttachmentName(null);
     setExcelFocus(null);
+    setTextFocus(null);
+    // 放大预览同一张图时保留 OCR 命中框
+    setOcrFocus((f) => (f?.file_id === fileId ? f : null));
     setDocxAttachmentFocus(null);
     setMsg("");
     try {
@@ -520,6 +548,8 @@ export default function ArchiveDetail({
         setAttachmentType("docx_other");
         setPdfPage(1);
         setExcelFocus(null);
+        setTextFocus(null);
+        setOcrFocus(null);
         const p = a.locator?.page;
         const paraIdx = a.locator?.para_idx;
         const imgIdx = a.locator?.image_index;
@@ -1093,6 +1123,8 @@ export default function ArchiveDetail({
                                   setAnnotationsOpen(true);
                                   setActiveAnnotationId(id);
                                 }}
+                                textFocusLine={textFocus?.file_id === a.file_id ? textFocus.line : null}
+                                ocrFocus={ocrFocus?.file_id === a.file_id ? ocrFocus : null}
                               />
                             </div>
                           </div>
@@ -1191,11 +1223,25 @@ export default function ArchiveDetail({
                       setActiveAnnotationId(id);
                     }}
                   />
-                ) : attachmentType === "image" ? (
-                  <img
-                    src={convertFileSrc(attachmentPreview.path)}
-                    style={{ maxWidth: "100%", border: "1px solid #eee", borderRadius: 12, background: "#fff" }}
+                ) : attachmentType === "text" ? (
+                  <TextAttachmentPreview
+                    fileId={attachmentPreview.file_id}
+                    focusLine={textFocus?.file_id === selectedAttachmentId ? textFocus.line : null}
                   />
+                ) : attachmentType === "image" ? (
+                  ocrFocus?.file_id === selectedAttachmentId ? (
+                    <OcrImage
+                      src={convertFileSrc(attachmentPreview.path)}
+                      regions={ocrFocus.regions}
+                      imageWidth={ocrFocus.image_width}
+                      imageHeight={ocrFocus.image_height}
+                    />
+                  ) : (
+                    <img
+                      src={convertFileSrc(attachmentPreview.path)}
+                      style={{ maxWidth: "100%", border: "1px solid #eee", borderRadius: 12, background: "#fff" }}
+                    />
+                  )
                 ) : attachmentType === "video" ? (
                   <video
                     controls
@@ -1283,6 +1329,8 @@ function InlineAttachmentPreview({
   docxFocus,
   onDocxContextMenuCreate,
   onDocxAnnotationClick,
+  textFocusLine,
+  ocrFocus,
 }: {
   fileId: string;
   fileType: string;
@@ -1300,6 +1348,8 @@ function InlineAttachmentPreview({
   docxFocus?: { page?: number; para_idx?: number; image_index?: number; ranges?: { start: number; end: number }[] } | null;
   onDocxContextMenuCreate?: (req: any, x: number, y: number) => void;
   onDocxAnnotationClick?: (annotationId: string) => void;
+  textFocusLine?: number | null;
+  ocrFocus?: OcrFocus | null;
 }) {
   const [msg, setMsg] = useState("");
   const [path, setPath] = useState<string | null>(null);
@@ -1340,11 +1390,31 @@ function InlineAttachmentPreview({
     );
   }
 
+  if (fileType === "text") {
+    return (
+      <div style={{ display: "grid", gap: 8 }}>
+        <div style={{ fontSize: 12, opacity: 0.7 }}>文本/表格预览（csv/tsv 按表格显示）</div>
+        <TextAttachmentPreview fileId={fileId} focusLine={textFocusLine} maxHeight={260} />
+      </div>
+    );
+  }
+
   if (!path) {
     return msg ? <div style={{ whiteSpace: "pre-wrap", color: "#b00" }}>{msg}</div> : <div style={{ fontSize: 12, opacity: 0.7 }}>加载预览中...</div>;
   }
 
   if (fileType === "image") {
+    if (ocrFocus) {
+      return (
+        <OcrImage
+          src={convertFileSrc(path)}
+          regions={ocrFocus.regions}
+          imageWidth={ocrFocus.image_width}
+          imageHeight={ocrFocus.image_height}
+          maxHeight={260}
+        />
+      );
+    }
     return (
       <img
         src={convertFileSrc(path)}
@@ -1459,9 +1529,11 @@ function groupAttachments(
     file_type: string;
     source_depth: number;
     container_virtual_path?: string | null;
+    container_kind?: string;
   }[]
 ) {
   const main: typeof attachments = [];
+  const embedded: typeof attachments = [];
   const byChild: Record<string, typeof attachments> = {};
 
   for (const a of attachments) {
@@ -1469,6 +1541,10 @@ function groupAttachments(
       main.push(a);
       continue;
     }
+    if (a.container_kind === "main_docx") {
+      embedded.push(a);
+      continue;
+    }
     const child =
       extractChildZipNameFromContainerPath(a.container_virtual_path) ??
       extractChildZipName(a.display_name) ??
@@ -1479,6 +1555,7 @@ function groupAttachments(
 
   const groups: { key: string; title: string; items: typeof attachments }[] = [];
   if (main.length) groups.push({ key: "main", title: "主ZIP", items: main });
+  if (embedded.length) groups.push({ key: "embedded", title: "主文内嵌对象", items: embedded });
   for (const k of Object.keys(byChild).sort()) {
     groups.push({ key: `child:${k}`, title: `子ZIP：${k}`, items: byChild[k] });
   }
@@ -2,10 +2,20 @@ import { useEffect, useMemo, useRef, useState } from "react";
 import { convertFileSrc, invoke } from "../../tauri";
 import TextHighlighter from "./TextHighlighter";
 
+type DocxImage = {
+  image_id: string;
+  block_id: string;
+  target: string;
+  alt?: string | null;
+  width_px?: number | null;
+  height_px?: number | null;
+};
+
 type PreviewResp = {
   file_id: string;
+  archive_id: string;
   paragraphs: string[];
-  image_paths: string[];
+  images: DocxImage[];
 };
 
 export default function DocxAttachmentPreview({
@@ -67,7 +77,7 @@ export default function DocxAttachmentPreview({
     return out.filter((x) => x.paras.length > 0);
   }, [data]);
 
-  const imgs = data?.image_paths ?? [];
+  const imgs = data?.images ?? [];
 
   useEffect(() => {
     if (!focus) return;
@@ -228,7 +238,7 @@ export default function DocxAttachmentPreview({
         >
           <div style={{ fontSize: 12, opacity: 0.7, marginBottom: 8 }}>图片（右键可对图片批注）</div>
           <div style={{ display: "grid", gridTemplateColumns: "repeat(3, minmax(0, 1fr))", gap: 8 }}>
-            {imgs.slice(0, 30).map((p, idx) => {
+            {imgs.map((img, idx) => {
               const annoIds = annotations?.byImage?.[idx] ?? [];
               return (
                 <div
@@ -270,26 +280,68 @@ export default function DocxAttachmentPreview({
                       {annoIds.length}
                     </div>
                   ) : null}
-                  <img
-                    src={convertFileSrc(p)}
-                    style={{
-                      width: "100%",
-                      height: 140,
-                      objectFit: "cover",
-                      display: "block",
-                    }}
-                  />
+                  <LazyDocxImage archiveId={data?.archive_id ?? ""} fileId={fileId} image={img} />
                 </div>
               );
             })}
           </div>
-          {imgs.length > 30 ? <div style={{ marginTop: 8, fontSize: 12, opacity: 0.7 }}>还有 {imgs.length - 30} 张图片</div> : null}
         </div>
       ) : null}
     </div>
   );
 }
 
+// 滚动到可见时才向后端要图片文件（后端首次请求时才从 docx 解出）
+function LazyDocxImage({ archiveId, fileId, image }: { archiveId: string; fileId: string; image: DocxImage }) {
+  const ref = useRef<HTMLDivElement | null>(null);
+  const [visible, setVisible] = useState(false);
+  const [path, setPath] = useState<string | null>(null);
+
+  useEffect(() => {
+    const el = ref.current;
+    if (!el) return;
+    const io = new IntersectionObserver((entries) => {
+      if (entries.some((e) => e.isIntersecting)) {
+        setVisible(true);
+        io.disconnect();
+      }
+    });
+    io.observe(el);
+    return () => io.disconnect();
+  }, []);
+
+  useEffect(() => {
+    if (!visible || !archiveId) return;
+    let cancelled = false;
+    invoke<string>("get_docx_image", { archiveId, imageId: image.image_id, fileId })
+      .then((p) => {
+        if (!cancelled) setPath(p);
+      })
+      .catch(() => {});
+    return () => {
+      cancelled = true;
+    };
+  }, [visible, archiveId, fileId, image.image_id]);
+
+  return (
+    <div ref={ref} style={{ width: "100%", height: 140, background: "#f8fafc" }}>
+      {path ? (
+        <img
+          src={convertFileSrc(path)}
+          alt={image.alt ?? ""}
+          title={image.alt ?? ""}
+          style={{
+            width: "100%",
+            height: 140,
+            objectFit: "cover",
+            display: "block",
+          }}
+        />
+      ) : null}
+    </div>
+  );
+}
+
 function pageAnchorId(fileId: string, page: number) {
   return `docxatt-${safeId(fileId)}-page-${page}`;
 }
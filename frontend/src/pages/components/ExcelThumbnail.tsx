@@ -1,10 +1,14 @@
 import { useEffect, useState } from "react";
-import { invoke } from "../../tauri";
+import { convertFileSrc, invoke } from "../../tauri";
 
 type SheetInfo = { name: string; rows: number; cols: number };
 type InfoResp = { file_id: string; sheets: SheetInfo[]; default_sheet?: string | null };
-type CellsResp = { row_start: number; col_start: number; cells: string[][] };
+type CellsResp = { row_start: number; col_start: number; cells: { display: string }[][] };
+type ThumbnailResp = { file_id: string; size: number; status: "ready" | "unsupported"; path?: string | null; mime?: string | null };
 
+const MAX_HEIGHT = 220;
+
+// 优先用后端缓存的缩略图；后端生成不了时退回读取左上角单元格
 export default function ExcelThumbnail({
   fileId,
   maxRows = 18,
@@ -16,13 +20,24 @@ export default function ExcelThumbnail({
 }) {
   const [msg, setMsg] = useState("");
   const [cells, setCells] = useState<string[][]>([]);
+  const [thumbPath, setThumbPath] = useState<string | null>(null);
 
   useEffect(() => {
     let cancelled = false;
     setMsg("");
     setCells([]);
+    setThumbPath(null);
 
     async function run() {
+      const thumb = await invoke<ThumbnailResp>("get_thumbnail", {
+        fileId,
+        size: Math.round(MAX_HEIGHT * (window.devicePixelRatio || 1)),
+      }).catch(() => null);
+      if (cancelled) return;
+      if (thumb?.status === "ready" && thumb.path) {
+        setThumbPath(thumb.path);
+        return;
+      }
       const info = await invoke<InfoResp>("get_excel_sheet_info", { fileId });
       const sheet = info.default_sheet ?? info.sheets[0]?.name;
       if (!sheet) return;
@@ -37,7 +52,7 @@ export default function ExcelThumbnail({
         },
       });
       if (cancelled) return;
-      setCells(resp.cells ?? []);
+      setCells((resp.cells ?? []).map((row) => row.map((c) => c.display)));
     }
 
     run().catch((e) => setMsg(String(e?.message ?? e)));
@@ -46,8 +61,16 @@ export default function ExcelThumbnail({
     };
   }, [fileId, maxRows, maxCols]);
 
+  if (thumbPath) {
+    return (
+      <div style={{ border: "1px solid #eee", borderRadius: 10, overflow: "hidden", background: "#fff", display: "flex", justifyContent: "center" }}>
+        <img src={convertFileSrc(thumbPath)} style={{ display: "block", maxWidth: "100%", maxHeight: MAX_HEIGHT }} />
+      </div>
+    );
+  }
+
   return (
-    <div style={{ border: "1px solid #eee", borderRadius: 10, overflow: "auto", background: "#fff", maxHeight: 220 }}>
+    <div style={{ border: "1px solid #eee", borderRadius: 10, overflow: "auto", background: "#fff", maxHeight: MAX_HEIGHT }}>
       {msg ? <div style={{ padding: 8, whiteSpace: "pre-wrap", color: "#b00" }}>{msg}</div> : null}
       <table style={{ borderCollapse: "collapse", width: "100%" }}>
         <tbody>
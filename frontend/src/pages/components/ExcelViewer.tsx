@@ -3,7 +3,69 @@ import { invoke } from "../../tauri";
 
 type SheetInfo = { name: string; rows: number; cols: number };
 type InfoResp = { file_id: string; sheets: SheetInfo[]; default_sheet?: string | null };
-type CellsResp = { row_start: number; col_start: number; cells: string[][] };
+type ExcelCell = {
+  kind: "empty" | "string" | "number" | "bool" | "date" | "error";
+  value: unknown;
+  display: string;
+  formula?: string | null;
+  style?: number | null;
+};
+type CellStyle = {
+  bold: boolean;
+  italic: boolean;
+  underline: boolean;
+  strike: boolean;
+  font_color?: string | null;
+  fill_color?: string | null;
+  h_align?: string | null;
+  v_align?: string | null;
+  wrap: boolean;
+};
+type MergedRange = { first_row: number; first_col: number; last_row: number; last_col: number };
+type CellsResp = {
+  row_start: number;
+  col_start: number;
+  cells: ExcelCell[][];
+  styles: CellStyle[];
+  merges: MergedRange[];
+  col_widths: (number | null)[];
+  row_heights: (number | null)[];
+  hidden_rows: number[];
+  hidden_cols: number[];
+};
+type SortSpec = { col: number; descending: boolean };
+type FilterOp =
+  | "contains"
+  | "not_contains"
+  | "equals"
+  | "not_equals"
+  | "starts_with"
+  | "ends_with"
+  | "gt"
+  | "gte"
+  | "lt"
+  | "lte"
+  | "empty"
+  | "not_empty";
+type FilterSpec = { col: number; op: FilterOp; value: string };
+type QueryResp = { total_rows: number; source_rows: number[]; window: CellsResp };
+type FindHit = { sheet_name: string; row: number; col: number; cell_ref: string; display: string };
+type FindResp = { hits: FindHit[]; truncated: boolean };
+
+const FILTER_OPS: { op: FilterOp; label: string; needsValue: boolean }[] = [
+  { op: "contains", label: "包含", needsValue: true },
+  { op: "not_contains", label: "不包含", needsValue: true },
+  { op: "equals", label: "等于", needsValue: true },
+  { op: "not_equals", label: "不等于", needsValue: true },
+  { op: "starts_with", label: "开头是", needsValue: true },
+  { op: "ends_with", label: "结尾是", needsValue: true },
+  { op: "gt", label: ">", needsValue: true },
+  { op: "gte", label: "≥", needsValue: true },
+  { op: "lt", label: "<", needsValue: true },
+  { op: "lte", label: "≤", needsValue: true },
+  { op: "empty", label: "为空", needsValue: false },
+  { op: "not_empty", label: "不为空", needsValue: false },
+];
 
 const ROW_HEIGHT = 26;
 const COL_WIDTH = 140;
@@ -13,6 +75,8 @@ const OVERSCAN_ROWS = 12;
 const OVERSCAN_COLS = 4;
 const MAX_WINDOW_ROWS = 260;
 const MAX_WINDOW_COLS = 60;
+/** 筛选列下拉最多列出的列数 */
+const MAX_FILTER_COLS = 200;
 
 export default function ExcelViewer({
   fileId,
@@ -33,12 +97,23 @@ export default function ExcelViewer({
 }) {
   const [info, setInfo] = useState<InfoResp | null>(null);
   const [sheet, setSheet] = useState<string>("");
-  const [cells, setCells] = useState<string[][]>([]);
+  const [resp, setResp] = useState<CellsResp | null>(null);
   const [rowStart, setRowStart] = useState(0);
   const [colStart, setColStart] = useState(0);
   const [viewport, setViewport] = useState({ w: 0, h: 0, scrollLeft: 0, scrollTop: 0 });
   const [msg, setMsg] = useState("");
+  const [notice, setNotice] = useState("");
   const [focusCell, setFocusCell] = useState<{ row: number; col?: number } | null>(null);
+  // 外部定位与查找结果跳转共用
+  const [jump, setJump] = useState<{ sheet_name: string; row: number; col?: number } | null>(null);
+  const [headerRows, setHeaderRows] = useState(0);
+  const [sort, setSort] = useState<SortSpec | null>(null);
+  const [filters, setFilters] = useState<FilterSpec[]>([]);
+  const [draft, setDraft] = useState<FilterSpec>({ col: 0, op: "contains", value: "" });
+  const [sourceRows, setSourceRows] = useState<number[] | null>(null);
+  const [viewTotal, setViewTotal] = useState<number | null>(null);
+  const [findText, setFindText] = useState("");
+  const [findResp, setFindResp] = useState<FindResp | null>(null);
   const scrollRef = useRef<HTMLDivElement | null>(null);
   const cacheRef = useRef<Map<string, CellsResp>>(new Map());
 
@@ -47,6 +122,11 @@ export default function ExcelViewer({
     return { rows: s?.rows ?? 0, cols: s?.cols ?? 0 };
   }, [info, sheet]);
 
+  // 有排序/筛选时走后端整表查询，行号为结果中的位置
+  const viewActive = !!sort || filters.length > 0;
+  const viewKey = viewActive ? JSON.stringify({ headerRows, sort, filters }) : "";
+  const totalRows = viewActive ? viewTotal ?? sheetDim.rows : sheetDim.rows;
+
   const windowSize = useMemo(() => {
     const visibleRows = viewport.h > 0 ? Math.ceil(viewport.h / ROW_HEIGHT) : 20;
     const visibleCols = viewport.w > 0 ? Math.ceil(viewport.w / COL_WIDTH) : 10;
@@ -61,7 +141,8 @@ export default function ExcelViewer({
   useEffect(() => {
     setMsg("");
     setInfo(null);
-    setCells([]);
+    setResp(null);
+    setFindResp(null);
     cacheRef.current.clear();
     invoke<InfoResp>("get_excel_sheet_info", { fileId })
       .then((r) => {
@@ -114,17 +195,39 @@ export default function ExcelViewer({
     el.scrollTop = 0;
     setRowStart(0);
     setColStart(0);
-    setCells([]);
+    setResp(null);
+    setSort(null);
+    setFilters([]);
     cacheRef.current.clear();
   }, [sheet]);
 
   useEffect(() => {
+    // 换了排序/筛选条件后从第一行看起
+    const el = scrollRef.current;
+    if (el) el.scrollTop = 0;
+    setRowStart(0);
+    setSourceRows(null);
+    setViewTotal(null);
+  }, [viewKey]);
+
+  useEffect(() => {
+    if (focus) setJump(focus);
+  }, [focus]);
+
+  useEffect(() => {
+    const focus = jump;
     if (!focus) return;
     if (!focus.sheet_name) return;
     if (focus.sheet_name !== sheet) {
       setSheet(focus.sheet_name);
       return;
     }
+    // 定位用原表行号，先退出排序/筛选
+    if (viewActive) {
+      setSort(null);
+      setFilters([]);
+      return;
+    }
     setFocusCell({ row: focus.row, col: focus.col });
     const el = scrollRef.current;
     if (!el) return;
@@ -132,11 +235,12 @@ export default function ExcelViewer({
     const left = typeof focus.col === "number" ? ROW_HEADER_WIDTH + Math.max(0, focus.col) * COL_WIDTH : null;
     el.scrollTop = Math.max(0, top - ROW_HEIGHT * 2);
     if (left !== null) el.scrollLeft = Math.max(0, left - COL_WIDTH * 1);
-  }, [focus, sheet]);
+  }, [jump, sheet, viewActive]);
 
   useEffect(() => {
     if (!sheet) return;
-    const { rows, cols } = sheetDim;
+    const rows = totalRows;
+    const { cols } = sheetDim;
     if (!rows || !cols) return;
 
     const leftCol = Math.max(
@@ -155,12 +259,13 @@ export default function ExcelViewer({
     if (nextRowStart === rowStart && nextColStart === colStart) return;
     setRowStart(nextRowStart);
     setColStart(nextColStart);
-  }, [viewport.scrollLeft, viewport.scrollTop, sheetDim, rowStart, colStart]);
+  }, [viewport.scrollLeft, viewport.scrollTop, sheetDim, totalRows, rowStart, colStart]);
 
   useEffect(() => {
     if (!sheet) return;
     setMsg("");
-    const { rows, cols } = sheetDim;
+    const rows = totalRows;
+    const { cols } = sheetDim;
     if (!rows || !cols) return;
 
     const r0 = clamp(rowStart, 0, rows);
@@ -169,10 +274,33 @@ export default function ExcelViewer({
     const c1 = clamp(colEnd, c0, cols);
     if (r1 <= r0 || c1 <= c0) return;
 
-    const key = `${fileId}|${sheet}|${r0}|${r1}|${c0}|${c1}`;
+    const key = `${fileId}|${sheet}|${viewKey}|${r0}|${r1}|${c0}|${c1}`;
     const cached = cacheRef.current.get(key);
-    if (cached) {
-      setCells(cached.cells);
+    if (cached && !viewActive) {
+      setResp(cached);
+      return;
+    }
+
+    if (viewActive) {
+      invoke<QueryResp>("query_excel_sheet", {
+        req: {
+          file_id: fileId,
+          sheet_name: sheet,
+          header_rows: headerRows,
+          sort,
+          filters,
+          row_start: r0,
+          row_end: r1,
+          col_start: c0,
+          col_end: c1,
+        },
+      })
+        .then((r) => {
+          setViewTotal(r.total_rows);
+          setSourceRows(r.source_rows);
+          setResp(r.window);
+        })
+        .catch((e) => setMsg(String(e?.message ?? e)));
       return;
     }
 
@@ -181,10 +309,73 @@ export default function ExcelViewer({
     })
       .then((r) => {
         cacheRef.current.set(key, r);
-        setCells(r.cells);
+        setResp(r);
       })
       .catch((e) => setMsg(String(e?.message ?? e)));
-  }, [fileId, sheet, rowStart, colStart, rowEnd, colEnd]);
+  }, [fileId, sheet, rowStart, colStart, rowEnd, colEnd, totalRows, viewKey]);
+
+  const cells = resp?.cells ?? [];
+
+  /** 结果视图中的行 -> 原表行号（批注、定位都按原表行号） */
+  function sourceRowOf(viewRow: number) {
+    if (!sourceRows || !resp) return viewRow;
+    return sourceRows[viewRow - resp.row_start] ?? viewRow;
+  }
+
+  function toggleSort(col: number) {
+    setSort((s) => {
+      if (!s || s.col !== col) return { col, descending: false };
+      if (!s.descending) return { col, descending: true };
+      return null;
+    });
+  }
+
+  function addFilter() {
+    const op = FILTER_OPS.find((o) => o.op === draft.op);
+    if (op?.needsValue && !draft.value.trim()) return;
+    setFilters((fs) => [...fs, { ...draft, value: op?.needsValue ? draft.value.trim() : "" }]);
+    setDraft((d) => ({ ...d, value: "" }));
+  }
+
+  function runFind() {
+    const q = findText.trim();
+    if (!q) {
+      setFindResp(null);
+      return;
+    }
+    invoke<FindResp>("find_in_excel", { fileId, query: q })
+      .then(setFindResp)
+      .catch((e) => setMsg(String(e?.message ?? e)));
+  }
+
+  function exportView(format: "csv" | "xlsx") {
+    setNotice("");
+    invoke<number>("export_excel_range", {
+      req: { file_id: fileId, sheet_name: sheet, header_rows: headerRows, sort, filters, format },
+    })
+      .then((n) => {
+        if (n > 0) setNotice(`已导出 ${n} 行`);
+      })
+      .catch((e) => setMsg(String(e?.message ?? e)));
+  }
+
+  // 合并区域：起点格画成整块，其余被覆盖的格留空
+  const mergeAt = useMemo(() => {
+    const anchors = new Map<string, MergedRange>();
+    const covered = new Set<string>();
+    for (const m of resp?.merges ?? []) {
+      anchors.set(`${m.first_row}|${m.first_col}`, m);
+      for (let r = m.first_row; r <= m.last_row; r++) {
+        for (let c = m.first_col; c <= m.last_col; c++) {
+          if (r !== m.first_row || c !== m.first_col) covered.add(`${r}|${c}`);
+        }
+      }
+    }
+    return { anchors, covered };
+  }, [resp]);
+
+  const hiddenRows = useMemo(() => new Set(resp?.hidden_rows ?? []), [resp]);
+  const hiddenCols = useMemo(() => new Set(resp?.hidden_cols ?? []), [resp]);
 
   const annotationsForSheet = useMemo(() => {
     return (annotations ?? []).filter((a) => a.sheet_name === sheet);
@@ -226,8 +417,103 @@ export default function ExcelViewer({
         </label>
         <div style={{ opacity: 0.7 }}>
           行 {rowStart}-{rowEnd - 1}，列 {colStart}-{colEnd - 1}（滚动浏览全表）
+          {viewActive && viewTotal !== null ? `，筛选结果 ${Math.max(0, viewTotal - headerRows)} 行` : ""}
         </div>
+        <label style={{ fontSize: 12 }}>
+          <input
+            type="checkbox"
+            checked={headerRows > 0}
+            onChange={(e) => setHeaderRows(e.target.checked ? 1 : 0)}
+          />
+          首行为表头
+        </label>
+        <button onClick={() => exportView("csv")} disabled={!sheet}>
+          导出CSV
+        </button>
+        <button onClick={() => exportView("xlsx")} disabled={!sheet}>
+          导出xlsx
+        </button>
+        {notice ? <span style={{ fontSize: 12, opacity: 0.7 }}>{notice}</span> : null}
+      </div>
+      <div style={{ display: "flex", gap: 8, alignItems: "center", flexWrap: "wrap", fontSize: 12 }}>
+        <span>筛选：</span>
+        <select value={draft.col} onChange={(e) => setDraft((d) => ({ ...d, col: Number(e.target.value) }))}>
+          {Array.from({ length: Math.min(sheetDim.cols, MAX_FILTER_COLS) }).map((_, c) => (
+            <option key={c} value={c}>
+              {toColName(c)}列
+            </option>
+          ))}
+        </select>
+        <select value={draft.op} onChange={(e) => setDraft((d) => ({ ...d, op: e.target.value as FilterOp }))}>
+          {FILTER_OPS.map((o) => (
+            <option key={o.op} value={o.op}>
+              {o.label}
+            </option>
+          ))}
+        </select>
+        {FILTER_OPS.find((o) => o.op === draft.op)?.needsValue ? (
+          <input
+            value={draft.value}
+            onChange={(e) => setDraft((d) => ({ ...d, value: e.target.value }))}
+            onKeyDown={(e) => {
+              if (e.key === "Enter") addFilter();
+            }}
+            placeholder="文本、数字或日期"
+            style={{ width: 140 }}
+          />
+        ) : null}
+        <button onClick={addFilter}>添加</button>
+        {filters.map((f, i) => (
+          <span
+            key={i}
+            style={{ background: "#eef2ff", borderRadius: 999, padding: "2px 8px", display: "inline-flex", gap: 4 }}
+          >
+            {toColName(f.col)} {FILTER_OPS.find((o) => o.op === f.op)?.label} {f.value}
+            <span style={{ cursor: "pointer" }} onClick={() => setFilters((fs) => fs.filter((_, j) => j !== i))}>
+              ×
+            </span>
+          </span>
+        ))}
+        {viewActive ? (
+          <button
+            onClick={() => {
+              setSort(null);
+              setFilters([]);
+            }}
+          >
+            清除排序/筛选
+          </button>
+        ) : null}
+        <span style={{ marginLeft: 12 }}>查找：</span>
+        <input
+          value={findText}
+          onChange={(e) => setFindText(e.target.value)}
+          onKeyDown={(e) => {
+            if (e.key === "Enter") runFind();
+          }}
+          placeholder="在所有Sheet中查找"
+          style={{ width: 160 }}
+        />
+        <button onClick={runFind}>查找</button>
       </div>
+      {findResp ? (
+        <div style={{ maxHeight: 160, overflow: "auto", border: "1px solid #eee", borderRadius: 8, fontSize: 12 }}>
+          {findResp.hits.length === 0 ? <div style={{ padding: 6, opacity: 0.7 }}>未找到</div> : null}
+          {findResp.hits.map((h, i) => (
+            <div
+              key={i}
+              onClick={() => setJump({ sheet_name: h.sheet_name, row: h.row, col: h.col })}
+              style={{ padding: "3px 6px", cursor: "pointer", display: "flex", gap: 8 }}
+            >
+              <span style={{ opacity: 0.6, whiteSpace: "nowrap" }}>
+                {h.sheet_name}!{h.cell_ref}
+              </span>
+              <span style={{ overflow: "hidden", textOverflow: "ellipsis", whiteSpace: "nowrap" }}>{h.display}</span>
+            </div>
+          ))}
+          {findResp.truncated ? <div style={{ padding: 6, opacity: 0.7 }}>结果过多，仅显示前 {findResp.hits.length} 条</div> : null}
+        </div>
+      ) : null}
       {msg ? <div style={{ whiteSpace: "pre-wrap", color: "#b00" }}>{msg}</div> : null}
       <div
         ref={scrollRef}
@@ -243,7 +529,7 @@ export default function ExcelViewer({
         <div
           style={{
             width: ROW_HEADER_WIDTH + sheetDim.cols * COL_WIDTH,
-            height: COL_HEADER_HEIGHT + sheetDim.rows * ROW_HEIGHT,
+            height: COL_HEADER_HEIGHT + totalRows * ROW_HEIGHT,
             position: "relative",
           }}
         />
@@ -289,9 +575,15 @@ export default function ExcelViewer({
                   alignItems: "center",
                   justifyContent: "center",
                   boxSizing: "border-box",
+                  opacity: hiddenCols.has(c) ? 0.4 : undefined,
+                  cursor: "pointer",
+                  userSelect: "none",
                 }}
+                title={hiddenCols.has(c) ? "隐藏列（点击排序）" : "点击排序"}
+                onClick={() => toggleSort(c)}
               >
                 {toColName(c)}
+                {sort?.col === c ? (sort.descending ? " ▼" : " ▲") : ""}
               </div>
             );
           })}
@@ -307,12 +599,12 @@ export default function ExcelViewer({
           }}
         >
           {Array.from({ length: Math.max(0, rowEnd - rowStart) }).map((_, i) => {
-            const r = rowStart + i;
+            const r = sourceRowOf(rowStart + i);
             const ids = rowAnno.get(r) ?? [];
             const isFocus = focusCell?.row === r && typeof focusCell?.col !== "number";
             return (
               <div
-                key={r}
+                key={i}
                 onContextMenu={(e) => {
                   e.preventDefault();
                   e.stopPropagation();
@@ -347,8 +639,11 @@ export default function ExcelViewer({
                   boxSizing: "border-box",
                   cursor: ids.length ? "pointer" : "default",
                   position: "relative",
+                  opacity: hiddenRows.has(r) ? 0.4 : undefined,
                 }}
-                title={ids.length ? `该行有批注 ${ids.length} 条（点击打开）` : undefined}
+                title={
+                  ids.length ? `该行有批注 ${ids.length} 条（点击打开）` : hiddenRows.has(r) ? "隐藏行" : undefined
+                }
               >
                 {r + 1}
                 {ids.length ? (
@@ -378,78 +673,113 @@ export default function ExcelViewer({
         >
           {cells.map((row, rIdx) => (
             <div key={rIdx} style={{ display: "flex" }}>
-              {row.map((v, cIdx) => (
-                <div
-                  key={cIdx}
-                  style={{
-                    width: COL_WIDTH,
-                    height: ROW_HEIGHT,
-                    borderRight: "1px solid #f0f0f0",
-                    borderBottom: "1px solid #f0f0f0",
-                    padding: "4px 6px",
-                    fontSize: 12,
-                    whiteSpace: "nowrap",
-                    overflow: "hidden",
-                    textOverflow: "ellipsis",
-                    boxSizing: "border-box",
-                    position: "relative",
-                    outline:
-                      focusCell?.row === rowStart + rIdx && focusCell?.col === colStart + cIdx
-                        ? "2px solid #3b82f6"
-                        : undefined,
-                  }}
-                  title={v}
-                  onContextMenu={(e) => {
-                    e.preventDefault();
-                    e.stopPropagation();
-                    if (!onCellContextMenu) return;
-                    const r = rowStart + rIdx;
-                    const c = colStart + cIdx;
-                    setFocusCell({ row: r, col: c });
-                    onCellContextMenu(
-                      {
-                        archive_id: "",
-                        target_kind: "excel",
-                        target_ref: fileId,
-                        locator: { sheet_name: sheet, row: r, col: c },
-                        content: "",
-                      },
-                      e.clientX,
-                      e.clientY
-                    );
-                  }}
-                  onClick={() => {
-                    const r = rowStart + rIdx;
-                    const c = colStart + cIdx;
-                    setFocusCell({ row: r, col: c });
-                    const ids = cellAnno.get(`${r}|${c}`) ?? [];
-                    if (!ids.length) return;
-                    onAnnotationClick?.(ids[0]);
-                  }}
-                >
-                  {v}
-                  {(() => {
-                    const r = rowStart + rIdx;
-                    const c = colStart + cIdx;
-                    const ids = cellAnno.get(`${r}|${c}`) ?? [];
-                    if (!ids.length) return null;
-                    return (
-                      <span
-                        style={{
-                          position: "absolute",
-                          right: 2,
-                          top: 2,
-                          width: 8,
-                          height: 8,
-                          borderRadius: 999,
-                          background: "#f59e0b",
-                        }}
-                        title="该单元格有批注"
-                      />
-                    );
-                  })()}
-                </div>
-              ))}
+              {row.map((cell, cIdx) => {
+                const r = sourceRowOf(rowStart + rIdx);
+                const c = colStart + cIdx;
+                const st = typeof cell.style === "number" ? resp?.styles[cell.style] : undefined;
+                const merge = mergeAt.anchors.get(`${r}|${c}`);
+                const covered = mergeAt.covered.has(`${r}|${c}`);
+                const title = cell.formula ? `=${cell.formula}\n${cell.display}` : cell.display;
+                const content = covered ? null : (
+                  <div
+                    style={{
+                      position: merge ? "absolute" : undefined,
+                      top: merge ? 0 : undefined,
+                      left: merge ? 0 : undefined,
+                      width: merge ? (merge.last_col - merge.first_col + 1) * COL_WIDTH : undefined,
+                      height: merge ? (merge.last_row - merge.first_row + 1) * ROW_HEIGHT : undefined,
+                      padding: merge ? "4px 6px" : undefined,
+                      boxSizing: "border-box",
+                      background: merge ? (st?.fill_color ? `#${st.fill_color}` : "#fff") : undefined,
+                      borderRight: merge ? "1px solid #f0f0f0" : undefined,
+                      borderBottom: merge ? "1px solid #f0f0f0" : undefined,
+                      zIndex: merge ? 1 : undefined,
+                      display: "flex",
+                      alignItems: st?.v_align === "top" ? "flex-start" : st?.v_align === "bottom" ? "flex-end" : "center",
+                      justifyContent: cellJustify(cell, st),
+                      fontWeight: st?.bold ? 600 : undefined,
+                      fontStyle: st?.italic ? "italic" : undefined,
+                      textDecoration:
+                        [st?.underline ? "underline" : "", st?.strike ? "line-through" : ""].filter(Boolean).join(" ") ||
+                        undefined,
+                      color: cell.kind === "error" ? "#b00" : st?.font_color ? `#${st.font_color}` : undefined,
+                      whiteSpace: st?.wrap ? "pre-wrap" : "nowrap",
+                      overflow: "hidden",
+                      textOverflow: "ellipsis",
+                    }}
+                  >
+                    <span style={{ overflow: "hidden", textOverflow: "ellipsis" }}>{cell.display}</span>
+                  </div>
+                );
+                return (
+                  <div
+                    key={cIdx}
+                    style={{
+                      width: COL_WIDTH,
+                      height: ROW_HEIGHT,
+                      borderRight: covered && mergeAt.covered.has(`${r}|${c + 1}`) ? undefined : "1px solid #f0f0f0",
+                      borderBottom: covered && mergeAt.covered.has(`${r + 1}|${c}`) ? undefined : "1px solid #f0f0f0",
+                      padding: merge ? 0 : "4px 6px",
+                      fontSize: 12,
+                      whiteSpace: "nowrap",
+                      overflow: merge ? "visible" : "hidden",
+                      textOverflow: "ellipsis",
+                      boxSizing: "border-box",
+                      position: "relative",
+                      background: !merge && st?.fill_color ? `#${st.fill_color}` : undefined,
+                      outline:
+                        focusCell?.row === r && focusCell?.col === c
+                          ? "2px solid #3b82f6"
+                          : undefined,
+                    }}
+                    title={title || undefined}
+                    onContextMenu={(e) => {
+                      e.preventDefault();
+                      e.stopPropagation();
+                      if (!onCellContextMenu) return;
+                      setFocusCell({ row: r, col: c });
+                      onCellContextMenu(
+                        {
+                          archive_id: "",
+                          target_kind: "excel",
+                          target_ref: fileId,
+                          locator: { sheet_name: sheet, row: r, col: c },
+                          content: "",
+                        },
+                        e.clientX,
+                        e.clientY
+                      );
+                    }}
+                    onClick={() => {
+                      setFocusCell({ row: r, col: c });
+                      const ids = cellAnno.get(`${r}|${c}`) ?? [];
+                      if (!ids.length) return;
+                      onAnnotationClick?.(ids[0]);
+                    }}
+                  >
+                    {content}
+                    {(() => {
+                      const ids = cellAnno.get(`${r}|${c}`) ?? [];
+                      if (!ids.length) return null;
+                      return (
+                        <span
+                          style={{
+                            position: "absolute",
+                            right: 2,
+                            top: 2,
+                            width: 8,
+                            height: 8,
+                            borderRadius: 999,
+                            background: "#f59e0b",
+                            zIndex: 2,
+                          }}
+                          title="该单元格有批注"
+                        />
+                      );
+                    })()}
+                  </div>
+                );
+              })}
             </div>
           ))}
         </div>
@@ -458,6 +788,22 @@ export default function ExcelViewer({
   );
 }
 
+function cellJustify(cell: ExcelCell, st?: CellStyle) {
+  switch (st?.h_align) {
+    case "center":
+    case "centerContinuous":
+      return "center";
+    case "right":
+      return "flex-end";
+    case "left":
+      return "flex-start";
+  }
+  // 常规对齐：数字/日期靠右，布尔/错误居中
+  if (cell.kind === "number" || cell.kind === "date") return "flex-end";
+  if (cell.kind === "bool" || cell.kind === "error") return "center";
+  return undefined;
+}
+
 function clamp(n: number, a: number, b: number) {
   return Math.max(a, Math.min(b, n));
 }
@@ -0,0 +1,63 @@
+import { useEffect, useRef } from "react";
+
+type OcrRegion = { x: number; y: number; w: number; h: number };
+
+// 图片上叠加 OCR 命中框：坐标为原图像素，按百分比定位，随图片缩放
+export default function OcrImage({
+  src,
+  regions,
+  imageWidth,
+  imageHeight,
+  maxHeight,
+}: {
+  src: string;
+  regions: OcrRegion[];
+  imageWidth: number;
+  imageHeight: number;
+  maxHeight?: number;
+}) {
+  const firstRef = useRef<HTMLDivElement | null>(null);
+
+  useEffect(() => {
+    // 大图命中框可能在可视区外
+    setTimeout(() => firstRef.current?.scrollIntoView({ block: "center", inline: "center" }), 0);
+  }, [src, regions]);
+
+  const pct = (v: number, total: number) => `${(v / total) * 100}%`;
+
+  return (
+    <div
+      style={{
+        position: "relative",
+        display: "inline-block",
+        maxWidth: "100%",
+        lineHeight: 0,
+        border: "1px solid #eee",
+        borderRadius: 10,
+        background: "#fff",
+        overflow: "hidden",
+      }}
+    >
+      <img src={src} style={{ display: "block", maxWidth: "100%", maxHeight }} />
+      {imageWidth > 0 && imageHeight > 0
+        ? regions.map((r, i) => (
+            <div
+              key={i}
+              ref={i === 0 ? firstRef : undefined}
+              style={{
+                position: "absolute",
+                left: pct(r.x, imageWidth),
+                top: pct(r.y, imageHeight),
+                width: pct(r.w, imageWidth),
+                height: pct(r.h, imageHeight),
+                background: "rgba(250, 204, 21, 0.35)",
+                outline: "2px solid #f59e0b",
+                borderRadius: 2,
+                pointerEvents: "none",
+              }}
+            />
+          ))
+        : null}
+    </div>
+  );
+}
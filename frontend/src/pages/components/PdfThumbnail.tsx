@@ -1,8 +1,10 @@
-import { useEffect, useMemo, useRef, useState } from "react";
-import { convertFileSrc } from "../../tauri";
+import { useEffect, useRef, useState } from "react";
+import { convertFileSrc, invoke } from "../../tauri";
 
 type PdfJs = any;
 
+type ThumbnailResp = { file_id: string; size: number; status: "ready" | "unsupported"; path?: string | null; mime?: string | null };
+
 function getPdfJs(): PdfJs | null {
   return (window as any).pdfjsLib ?? null;
 }
@@ -16,27 +18,53 @@ function ensurePdfJs(): PdfJs {
   return lib;
 }
 
+// 首页优先用后端缓存的缩略图；后端生成不了或要看其他页时，再用 PDF.js 整份加载渲染
 export default function PdfThumbnail({
-  filePath,
+  fileId,
   page = 1,
   maxHeight = 220,
 }: {
-  filePath: string;
+  fileId: string;
   page?: number;
   maxHeight?: number;
 }) {
   const canvasRef = useRef<HTMLCanvasElement | null>(null);
   const [msg, setMsg] = useState("");
+  const [thumbPath, setThumbPath] = useState<string | null>(null);
+  const [fallback, setFallback] = useState(false);
 
-  const src = useMemo(() => convertFileSrc(filePath), [filePath]);
+  useEffect(() => {
+    let cancelled = false;
+    setThumbPath(null);
+    setFallback(false);
+    if (page !== 1) {
+      setFallback(true);
+      return;
+    }
+    invoke<ThumbnailResp>("get_thumbnail", { fileId, size: Math.round(maxHeight * (window.devicePixelRatio || 1)) })
+      .then((r) => {
+        if (cancelled) return;
+        if (r.status === "ready" && r.path) setThumbPath(r.path);
+        else setFallback(true);
+      })
+      .catch(() => {
+        if (!cancelled) setFallback(true);
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, [fileId, page, maxHeight]);
 
   useEffect(() => {
+    if (!fallback) return;
     let cancelled = false;
     setMsg("");
 
     async function run() {
       const pdfjsLib = ensurePdfJs();
-      const ab = await fetch(src).then((r) => r.arrayBuffer());
+      const p = await invoke<{ file_id: string; path: string }>("get_attachment_preview_path", { fileId });
+      if (cancelled) return;
+      const ab = await fetch(convertFileSrc(p.path)).then((r) => r.arrayBuffer());
       const doc = await pdfjsLib.getDocument({ data: ab }).promise;
       if (cancelled) return;
       const pg = await doc.getPage(page);
@@ -57,13 +85,17 @@ export default function PdfThumbnail({
     return () => {
       cancelled = true;
     };
-  }, [src, page, maxHeight]);
+  }, [fallback, fileId, page, maxHeight]);
 
   return (
     <div style={{ border: "1px solid #eee", borderRadius: 10, overflow: "hidden", background: "#fff" }}>
       {msg ? <div style={{ padding: 8, whiteSpace: "pre-wrap", color: "#b00" }}>{msg}</div> : null}
       <div style={{ display: "flex", justifyContent: "center" }}>
-        <canvas ref={canvasRef} style={{ display: "block" }} />
+        {thumbPath ? (
+          <img src={convertFileSrc(thumbPath)} style={{ display: "block", maxWidth: "100%", maxHeight }} />
+        ) : (
+          <canvas ref={canvasRef} style={{ display: "block" }} />
+        )}
       </div>
     </div>
   );
@@ -0,0 +1,156 @@
+import { useEffect, useRef, useState } from "react";
+import { invoke } from "../../tauri";
+
+type TextRow = { line: number; cells: string[] };
+
+type TextPreviewResp = {
+  file_id: string;
+  encoding: string;
+  mode: "text" | "table";
+  delimiter?: string | null;
+  offset: number;
+  total: number;
+  lines: string[];
+  rows: TextRow[];
+  cols: number;
+  truncated: boolean;
+};
+
+const PAGE_SIZE = 500;
+
+const DELIMITER_LABEL: Record<string, string> = { "\t": "制表符", ",": "逗号", ";": "分号" };
+
+export default function TextAttachmentPreview({
+  fileId,
+  focusLine,
+  maxHeight,
+}: {
+  fileId: string;
+  // 搜索命中的起始行（从 1 开始）
+  focusLine?: number | null;
+  maxHeight?: number;
+}) {
+  const [msg, setMsg] = useState("");
+  const [data, setData] = useState<TextPreviewResp | null>(null);
+  const [asTable, setAsTable] = useState<boolean | null>(null);
+  // 为 null 时按 focusLine 定位到所在页，翻页后改为按序号取
+  const [pageOffset, setPageOffset] = useState<number | null>(null);
+  const rootRef = useRef<HTMLDivElement | null>(null);
+  const targetLine = pageOffset === null ? focusLine ?? null : null;
+
+  useEffect(() => {
+    setAsTable(null);
+    setPageOffset(null);
+  }, [fileId, focusLine]);
+
+  useEffect(() => {
+    let cancelled = false;
+    setMsg("");
+    invoke<TextPreviewResp>("get_text_preview", {
+      fileId,
+      offset: pageOffset,
+      limit: PAGE_SIZE,
+      line: targetLine,
+      asTable,
+    })
+      .then((r) => {
+        if (!cancelled) setData(r);
+      })
+      .catch((e) => {
+        if (!cancelled) setMsg(String(e?.message ?? e));
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, [fileId, pageOffset, targetLine, asTable]);
+
+  useEffect(() => {
+    if (!data || !targetLine) return;
+    setTimeout(() => {
+      // 表格记录只标起始行，取不超过目标行的最后一条
+      const els = Array.from(rootRef.current?.querySelectorAll<HTMLElement>("[data-line]") ?? []);
+      const el = els.filter((x) => Number(x.dataset.line) <= targetLine).pop();
+      el?.scrollIntoView({ block: "center" });
+    }, 0);
+  }, [data, targetLine]);
+
+  if (!data) {
+    return msg ? <div style={{ whiteSpace: "pre-wrap", color: "#b00" }}>{msg}</div> : <div style={{ fontSize: 12, opacity: 0.7 }}>加载预览中...</div>;
+  }
+
+  const isTable = data.mode === "table";
+  const pageCount = data.rows.length || data.lines.length;
+  const hasPrev = data.offset > 0;
+  const hasNext = data.offset + pageCount < data.total;
+  // 表格模式下一条记录可能跨多行，高亮命中行所在的那条
+  const focusRow = isTable && targetLine ? data.rows.reduce((hit, r) => (r.line <= targetLine ? r.line : hit), 0) : 0;
+  const cellStyle = { border: "1px solid #eee", padding: "2px 6px", whiteSpace: "pre-wrap" as const, verticalAlign: "top" as const };
+
+  return (
+    <div ref={rootRef} style={{ display: "grid", gap: 8 }}>
+      <div style={{ display: "flex", gap: 8, alignItems: "center", flexWrap: "wrap", fontSize: 12 }}>
+        <span style={{ opacity: 0.7 }}>
+          编码 {data.encoding}
+          {isTable && data.delimiter ? ` · 分隔符 ${DELIMITER_LABEL[data.delimiter] ?? data.delimiter}` : ""}
+          {` · 共 ${data.total} ${isTable ? "条记录" : "行"}`}
+          {data.truncated ? " · 文件较大，仅预览开头部分" : ""}
+        </span>
+        <button
+          onClick={() => {
+            setAsTable(!isTable);
+            setPageOffset(null);
+          }}
+        >
+          {isTable ? "按文本查看" : "按表格查看"}
+        </button>
+        {hasPrev || hasNext ? (
+          <>
+            <button disabled={!hasPrev} onClick={() => setPageOffset(Math.max(0, data.offset - PAGE_SIZE))}>
+              上一页
+            </button>
+            <span style={{ opacity: 0.7 }}>
+              {data.offset + 1}-{data.offset + pageCount}
+            </span>
+            <button disabled={!hasNext} onClick={() => setPageOffset(data.offset + PAGE_SIZE)}>
+              下一页
+            </button>
+          </>
+        ) : null}
+      </div>
+      {msg ? <div style={{ whiteSpace: "pre-wrap", color: "#b00" }}>{msg}</div> : null}
+
+      <div style={{ overflow: "auto", maxHeight: maxHeight ?? 600, border: "1px solid #eee", borderRadius: 10, background: "#fff" }}>
+        {isTable ? (
+          <table style={{ borderCollapse: "collapse", fontSize: 12 }}>
+            <tbody>
+              {data.rows.map((r, i) => (
+                <tr key={data.offset + i} data-line={r.line} style={{ background: r.line === focusRow ? "#fef9c3" : undefined }}>
+                  <td style={{ ...cellStyle, color: "#9ca3af", textAlign: "right", background: "#fafafa" }}>{r.line}</td>
+                  {Array.from({ length: data.cols }, (_, c) => (
+                    <td key={c} style={cellStyle}>
+                      {r.cells[c] ?? ""}
+                    </td>
+                  ))}
+                </tr>
+              ))}
+            </tbody>
+          </table>
+        ) : (
+          <div style={{ fontFamily: "monospace", fontSize: 12, lineHeight: 1.6 }}>
+            {data.lines.map((l, i) => {
+              const line = data.offset + i + 1;
+              return (
+                <div key={line} data-line={line} style={{ display: "flex", background: line === targetLine ? "#fef9c3" : undefined }}>
+                  <span style={{ width: 56, flex: "none", paddingRight: 8, textAlign: "right", color: "#9ca3af", userSelect: "none" }}>
+                    {line}
+                  </span>
+                  <span style={{ whiteSpace: "pre-wrap", wordBreak: "break-all" }}>{l}</span>
+                </div>
+              );
+            })}
+          </div>
+        )}
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,312 @@
+use crate::db;
+use crate::library_root::LibraryRootState;
+use crate::search::{self, Range};
+use anyhow::Result;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use tauri::State;
+
+/// 单条文本最多标出的命中数（文档内查找需要完整计数，比搜索结果宽松）
+const MAX_RANGES_PER_ITEM: usize = 2000;
+
+/// 主文字段按文档里出现的顺序；content 由段落组成，段落命中已覆盖
+const DOC_FIELDS: [&str; 3] = ["instruction_no", "title", "issued_at"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveMatch {
+    /// 在 items 中的下标
+    pub index: usize,
+    /// docx_block | main_doc_field | annotation | attachment_name | attachment_block | ocr_text
+    pub kind: String,
+    /// block_id / field_name / annotation_id / file_id
+    pub ref_id: String,
+    /// ocr_text：附件内第几页、第几行；attachment_block：OFD 页号、文本附件起始行
+    pub page: Option<i64>,
+    pub line: Option<i64>,
+    /// 字段对应的段落；attachment_block 为附件内段落，便于定位
+    pub anchor_block_id: Option<String>,
+    pub text: String,
+    pub highlights: Vec<Range>,
+    pub match_count: usize,
+    /// 本条第一个命中在全部命中中的序号（从 0 开始）
+    pub first_match_ordinal: usize,
+    pub prev_index: Option<usize>,
+    pub next_index: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveSearchResponse {
+    pub archive_id: String,
+    pub query: String,
+    pub total_matches: usize,
+    pub items: Vec<ArchiveMatch>,
+}
+
+#[tauri::command]
+pub fn search_in_archive(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    archive_id: String,
+    query: String,
+) -> Result<ArchiveSearchResponse, String> {
+    search_in_archive_impl(&app, &state, &archive_id, &query).map_err(db::err_to_string)
+}
+
+fn search_in_archive_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    archive_id: &str,
+    query: &str,
+) -> Result<ArchiveSearchResponse> {
+    let (_root, conn) = db::open_conn(app, state)?;
+    let mut items = Vec::new();
+    if !query.trim().is_empty() {
+        collect_document(&conn, archive_id, query, &mut items)?;
+        collect_annotations(&conn, archive_id, query, &mut items)?;
+        collect_attachments(&conn, archive_id, query, &mut items)?;
+        collect_attachment_blocks(&conn, archive_id, query, &mut items)?;
+        collect_ocr(&conn, archive_id, query, &mut items)?;
+    }
+
+    // 编号与上一条/下一条
+    let n = items.len();
+    let mut ordinal = 0usize;
+    for (i, item) in items.iter_mut().enumerate() {
+        item.index = i;
+        item.first_match_ordinal = ordinal;
+        ordinal += item.match_count;
+        item.prev_index = i.checked_sub(1);
+        item.next_index = (i + 1 < n).then_some(i + 1);
+    }
+
+    Ok(ArchiveSearchResponse {
+        archive_id: archive_id.to_string(),
+        query: query.to_string(),
+        total_matches: ordinal,
+        items,
+    })
+}
+
+fn push_match(
+    items: &mut Vec<ArchiveMatch>,
+    kind: &str,
+    ref_id: String,
+    anchor_block_id: Option<String>,
+    text: String,
+    query: &str,
+) {
+    let highlights = search::find_highlights_utf16(&text, query, MAX_RANGES_PER_ITEM);
+    if highlights.is_empty() {
+        return;
+    }
+    items.push(ArchiveMatch {
+        index: 0,
+        kind: kind.to_string(),
+        ref_id,
+        page: None,
+        line: None,
+        anchor_block_id,
+        text,
+        match_count: highlights.len(),
+        highlights,
+        first_match_ordinal: 0,
+        prev_index: None,
+        next_index: None,
+    });
+}
+
+/// 正文段落按 block_id 顺序；字段命中排在其所在段落之前
+fn collect_document(
+    conn: &Connection,
+    archive_id: &str,
+    query: &str,
+    items: &mut Vec<ArchiveMatch>,
+) -> Result<()> {
+    let main_doc = conn
+        .query_row(
+            "SELECT instruction_no, title, issued_at, field_block_map_json FROM main_doc WHERE archive_id=?",
+            [archive_id],
+            |r| {
+                Ok((
+                    r.get::<_, String>(0)?,
+                    r.get::<_, String>(1)?,
+                    r.get::<_, String>(2)?,
+                    r.get::<_, String>(3)?,
+                ))
+            },
+        )
+        .optional()?;
+
+    // block_id -> 落在该段落的字段
+    let mut fields_at: HashMap<String, Vec<(&str, String)>> = HashMap::new();
+    if let Some((instruction_no, title, issued_at, map_json)) = main_doc {
+        let map: Value = serde_json::from_str(&map_json).unwrap_or(serde_json::json!({}));
+        for (name, text) in DOC_FIELDS.iter().zip([instruction_no, title, issued_at]) {
+            let anchor = map
+                .get(*name)
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
+            match anchor {
+                Some(bid) => fields_at.entry(bid).or_default().push((*name, text)),
+                // 找不到段落的字段放在最前
+                None => push_match(items, "main_doc_field", name.to_string(), None, text, query),
+            }
+        }
+    }
+
+    // 正文段落在前，页眉页脚/脚注/批注在后
+    let mut stmt = conn.prepare(
+        "SELECT block_id, text FROM docx_blocks WHERE archive_id=?
+         ORDER BY block_id LIKE 'p:%' DESC, block_id",
+    )?;
+    let rows = stmt.query_map([archive_id], |r| {
+        Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?))
+    })?;
+    for row in rows {
+        let (block_id, text) = row?;
+        if let Some(fields) = fields_at.remove(&block_id) {
+            for (name, field_text) in fields {
+                push_match(
+                    items,
+                    "main_doc_field",
+                    name.to_string(),
+                    Some(block_id.clone()),
+                    field_text,
+                    query,
+                );
+            }
+        }
+        push_match(items, "docx_block", block_id, None, text, query);
+    }
+    // 映射到已不存在段落的字段（旧解析结果）：补在正文末尾
+    let mut rest = fields_at.into_iter().collect::<Vec<_>>();
+    rest.sort_by(|a, b| a.0.cmp(&b.0));
+    for (block_id, fields) in rest {
+        for (name, field_text) in fields {
+            push_match(
+                items,
+                "main_doc_field",
+                name.to_string(),
+                Some(block_id.clone()),
+                field_text,
+                query,
+            );
+        }
+    }
+    Ok(())
+}
+
+fn collect_annotations(
+    conn: &Connection,
+    archive_id: &str,
+    query: &str,
+    items: &mut Vec<ArchiveMatch>,
+) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT annotation_id, content FROM annotations WHERE archive_id=? ORDER BY created_at ASC",
+    )?;
+    let rows = stmt.query_map([archive_id], |r| {
+        Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?))
+    })?;
+    for row in rows {
+        let (annotation_id, content) = row?;
+        push_match(items, "annotation", annotation_id, None, content, query);
+    }
+    Ok(())
+}
+
+/// 附件名与附件详情列表同序
+fn collect_attachments(
+    conn: &Connection,
+    archive_id: &str,
+    query: &str,
+    items: &mut Vec<ArchiveMatch>,
+) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT file_id, display_name FROM attachments WHERE archive_id=? ORDER BY source_depth, display_name",
+    )?;
+    let rows = stmt.query_map([archive_id], |r| {
+        Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?))
+    })?;
+    for row in rows {
+        let (file_id, display_name) = row?;
+        push_match(items, "attachment_name", file_id, None, display_name, query);
+    }
+    Ok(())
+}
+
+/// doc/wps/ofd/文本附件正文，按附件、段落排列
+fn collect_attachment_blocks(
+    conn: &Connection,
+    archive_id: &str,
+    query: &str,
+    items: &mut Vec<ArchiveMatch>,
+) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT b.file_id, b.block_id, b.page, b.line_start, b.text
+         FROM attachment_blocks b JOIN attachments a ON a.file_id=b.file_id
+         WHERE b.archive_id=?
+         ORDER BY a.source_depth, a.display_name, b.block_id",
+    )?;
+    let rows = stmt.query_map([archive_id], |r| {
+        Ok((
+            r.get::<_, String>(0)?,
+            r.get::<_, String>(1)?,
+            r.get::<_, Option<i64>>(2)?,
+            r.get::<_, Option<i64>>(3)?,
+            r.get::<_, String>(4)?,
+        ))
+    })?;
+    for row in rows {
+        let (file_id, block_id, page, line, text) = row?;
+        let before = items.len();
+        push_match(
+            items,
+            "attachment_block",
+            file_id,
+            Some(block_id),
+            text,
+            query,
+        );
+        if let Some(m) = items.get_mut(before) {
+            m.page = page;
+            m.line = line;
+        }
+    }
+    Ok(())
+}
+
+/// 已做过 OCR 的附件，按附件、页、行排列
+fn collect_ocr(
+    conn: &Connection,
+    archive_id: &str,
+    query: &str,
+    items: &mut Vec<ArchiveMatch>,
+) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT b.file_id, b.page, b.block_idx, b.text
+         FROM ocr_blocks b JOIN attachments a ON a.file_id=b.file_id
+         WHERE b.archive_id=?
+         ORDER BY a.source_depth, a.display_name, b.page, b.block_idx",
+    )?;
+    let rows = stmt.query_map([archive_id], |r| {
+        Ok((
+            r.get::<_, String>(0)?,
+            r.get::<_, i64>(1)?,
+            r.get::<_, i64>(2)?,
+            r.get::<_, String>(3)?,
+        ))
+    })?;
+    for row in rows {
+        let (file_id, page, line, text) = row?;
+        let before = items.len();
+        push_match(items, "ocr_text", file_id, None, text, query);
+        if let Some(m) = items.get_mut(before) {
+            m.page = Some(page);
+            m.line = Some(line);
+        }
+    }
+    Ok(())
+}
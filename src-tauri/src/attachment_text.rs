@@ -0,0 +1,299 @@
+use crate::cache;
+use crate::db;
+use crate::doc_legacy;
+use crate::file_type;
+use crate::library_root::LibraryRootState;
+use crate::ofd;
+use crate::search::{self, ArchiveFilter, SearchResult};
+use crate::text_preview;
+use anyhow::{anyhow, Result};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use tauri::State;
+
+/// 抽取正文并建索引的附件类型（docx 附件走原有预览，不在此列）
+pub const TEXT_FILE_TYPES: [&str; 4] = [
+    file_type::WORD,
+    file_type::WPS,
+    file_type::OFD,
+    file_type::TEXT,
+];
+/// 超过该大小的文档不抽取，避免导入时占用过多内存
+const MAX_TEXT_FILE_BYTES: u64 = 64 * 1024 * 1024;
+/// 旧库补建附件正文索引的版本标记（分类规则修正后也要递增，让新识别出的文本附件补建）
+const META_ATTACHMENT_TEXT_VERSION: &str = "attachment_text_version";
+const ATTACHMENT_TEXT_VERSION: &str = "3";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentBlock {
+    pub block_id: String,
+    /// OFD 有页号；doc/wps 没有分页信息时为空
+    pub page: Option<i64>,
+    /// 纯文本附件按行切块，记录块的起止行号（从 1 开始）
+    pub line_start: Option<i64>,
+    pub line_end: Option<i64>,
+    pub text: String,
+}
+
+pub fn is_text_type(file_type: &str) -> bool {
+    TEXT_FILE_TYPES.contains(&file_type)
+}
+
+/// 导入时调用：head 为已读出的文件头，r 为剩余内容；非文本类型或解析失败返回 None
+pub fn extract<R: Read>(
+    file_type: &str,
+    size: u64,
+    head: &[u8],
+    r: &mut R,
+) -> Option<Vec<AttachmentBlock>> {
+    if !is_text_type(file_type) || size > MAX_TEXT_FILE_BYTES {
+        return None;
+    }
+    let mut bytes = head.to_vec();
+    r.read_to_end(&mut bytes).ok()?;
+    extract_blocks(file_type, &bytes).ok()
+}
+
+/// 纯文本按行切块；其余按内容判断格式：OLE 按 Word 二进制（WPS 2003 起与 doc 同构），ZIP 按 OFD
+pub fn extract_blocks(file_type: &str, bytes: &[u8]) -> Result<Vec<AttachmentBlock>> {
+    if file_type == file_type::TEXT {
+        return Ok(text_blocks(bytes));
+    }
+    let pages: Vec<(Option<i64>, Vec<String>)> = if doc_legacy::is_ole(bytes) {
+        vec![(None, doc_legacy::extract_paragraphs(bytes, false)?)]
+    } else if ofd::is_ofd(bytes) {
+        ofd::extract_pages(bytes)?
+            .into_iter()
+            .enumerate()
+            .map(|(i, lines)| (Some(i as i64 + 1), lines))
+            .collect()
+    } else {
+        return Err(anyhow!("不支持的文档格式"));
+    };
+    let mut out = Vec::new();
+    for (page, paragraphs) in pages {
+        for text in paragraphs {
+            out.push(AttachmentBlock {
+                block_id: format!("p:{:06}", out.len() + 1),
+                page,
+                line_start: None,
+                line_end: None,
+                text,
+            });
+        }
+    }
+    Ok(out)
+}
+
+fn text_blocks(bytes: &[u8]) -> Vec<AttachmentBlock> {
+    let (text, _) = text_preview::decode(bytes);
+    text_preview::chunk_lines(&text)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (start, end, text))| AttachmentBlock {
+            block_id: format!("l:{:06}", i + 1),
+            page: None,
+            line_start: Some(start as i64),
+            line_end: Some(end as i64),
+            text,
+        })
+        .collect()
+}
+
+pub fn insert(
+    conn: &Connection,
+    archive_id: &str,
+    file_id: &str,
+    blocks: &[AttachmentBlock],
+) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "INSERT OR REPLACE INTO attachment_blocks(file_id,archive_id,block_id,page,line_start,line_end,text)
+         VALUES(?,?,?,?,?,?,?)",
+    )?;
+    let mut fts = conn.prepare(
+        "INSERT INTO attachment_blocks_fts(archive_id,file_id,block_id,search_text,source_text) VALUES(?,?,?,?,?)",
+    )?;
+    for b in blocks {
+        stmt.execute(params![
+            file_id,
+            archive_id,
+            b.block_id,
+            b.page,
+            b.line_start,
+            b.line_end,
+            b.text
+        ])?;
+        // 空段落只保留版面，不进索引
+        if !b.text.trim().is_empty() {
+            fts.execute(params![
+                archive_id,
+                file_id,
+                b.block_id,
+                search::build_search_text(&b.text),
+                b.text
+            ])?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn delete_archive(conn: &Connection, archive_id: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM attachment_blocks_fts WHERE archive_id=?",
+        [archive_id],
+    )?;
+    Ok(())
+}
+
+pub(crate) fn backfill_pending(conn: &Connection) -> Result<bool> {
+    Ok(
+        db::get_meta_value(conn, META_ATTACHMENT_TEXT_VERSION)?.as_deref()
+            != Some(ATTACHMENT_TEXT_VERSION),
+    )
+}
+
+/// 旧库首次打开时为已导入的 doc/wps/ofd/文本附件补建正文索引（只做一次，失败的附件跳过）
+pub fn ensure_backfilled(conn: &Connection, root: &Path) -> Result<()> {
+    if !backfill_pending(conn)? {
+        return Ok(());
+    }
+    let mut todo = Vec::new();
+    {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT a.file_id, a.archive_id, a.file_type FROM attachments a
+             WHERE a.file_type IN ({})
+               AND NOT EXISTS (SELECT 1 FROM attachment_blocks b WHERE b.file_id=a.file_id)",
+            TEXT_FILE_TYPES.map(|t| format!("'{t}'")).join(",")
+        ))?;
+        let rows = stmt.query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+            ))
+        })?;
+        for row in rows {
+            todo.push(row?);
+        }
+    }
+    for (file_id, archive_id, file_type) in todo {
+        let blocks = cache::ensure_cached_file(root, conn, &file_id)
+            .and_then(|p| Ok(fs::read(p)?))
+            .and_then(|bytes| extract_blocks(&file_type, &bytes));
+        match blocks {
+            Ok(blocks) => {
+                let tx = conn.unchecked_transaction()?;
+                insert(&tx, &archive_id, &file_id, &blocks)?;
+                tx.commit()?;
+            }
+            Err(e) => eprintln!("附件正文抽取失败 {file_id}: {e:#}"),
+        }
+    }
+    db::set_meta_value(conn, META_ATTACHMENT_TEXT_VERSION, ATTACHMENT_TEXT_VERSION)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_attachment_blocks(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    file_id: String,
+) -> Result<Vec<AttachmentBlock>, String> {
+    get_attachment_blocks_impl(&app, &state, &file_id).map_err(db::err_to_string)
+}
+
+fn get_attachment_blocks_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    file_id: &str,
+) -> Result<Vec<AttachmentBlock>> {
+    let (_root, conn) = db::open_conn(app, state)?;
+    let mut stmt = conn.prepare(
+        "SELECT block_id, page, line_start, line_end, text FROM attachment_blocks
+         WHERE file_id=? ORDER BY block_id",
+    )?;
+    let rows = stmt.query_map([file_id], |r| {
+        Ok(AttachmentBlock {
+            block_id: r.get(0)?,
+            page: r.get(1)?,
+            line_start: r.get(2)?,
+            line_end: r.get(3)?,
+            text: r.get(4)?,
+        })
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+/// 搜索：doc/wps/ofd/文本附件正文段落命中，按附件类型过滤
+pub(crate) fn query_attachment_blocks(
+    conn: &Connection,
+    match_query: &str,
+    limit: usize,
+    allowed_archives: &Option<HashSet<String>>,
+    want_types: &Option<HashSet<String>>,
+) -> Result<Vec<SearchResult>> {
+    let mut types: Vec<&str> = TEXT_FILE_TYPES.to_vec();
+    if let Some(want) = want_types {
+        types.retain(|t| want.contains(*t));
+        if types.is_empty() {
+            return Ok(vec![]);
+        }
+    }
+    let (archive_clause, archive_params) =
+        match search::archive_in_clause("attachment_blocks_fts.archive_id", allowed_archives)? {
+            ArchiveFilter::All => (String::new(), vec![]),
+            ArchiveFilter::Empty => return Ok(vec![]),
+            ArchiveFilter::In(sql, params) => (sql, params),
+        };
+    let type_clause = format!(
+        " AND a.file_type IN ({})",
+        types.iter().map(|_| "?").collect::<Vec<_>>().join(",")
+    );
+    let sql = format!(
+        "SELECT b.archive_id, b.file_id, a.display_name, b.block_id, b.page, b.line_start, b.line_end, b.text
+         FROM attachment_blocks_fts
+         JOIN attachment_blocks b ON b.file_id=attachment_blocks_fts.file_id AND b.block_id=attachment_blocks_fts.block_id
+         JOIN attachments a ON a.file_id=b.file_id
+         JOIN main_doc m ON m.archive_id=b.archive_id
+         WHERE attachment_blocks_fts MATCH ? {archive_clause} {type_clause}
+         ORDER BY COALESCE(m.issued_at_ts, 0) DESC, b.archive_id ASC, b.file_id ASC, b.block_id ASC
+         LIMIT ?"
+    );
+    let mut bind: Vec<rusqlite::types::Value> = Vec::new();
+    bind.push(rusqlite::types::Value::from(match_query.to_string()));
+    bind.extend(archive_params);
+    bind.extend(
+        types
+            .iter()
+            .map(|t| rusqlite::types::Value::from(t.to_string())),
+    );
+    bind.push(rusqlite::types::Value::from(limit as i64));
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(bind), |r| {
+        Ok(SearchResult::AttachmentBlock {
+            archive_id: r.get(0)?,
+            file_id: r.get(1)?,
+            display_name: r.get(2)?,
+            block_id: r.get(3)?,
+            page: r.get(4)?,
+            line_start: r.get(5)?,
+            line_end: r.get(6)?,
+            text: r.get(7)?,
+            highlights: vec![],
+            truncated: false,
+        })
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
@@ -0,0 +1,115 @@
+use crate::attachment_text;
+use crate::db;
+use crate::docx;
+use crate::docx_images;
+use crate::file_type;
+use crate::media_meta;
+use crate::outline;
+use crate::progress;
+use crate::similar;
+use anyhow::Result;
+use rusqlite::Connection;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static WORKER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// 旧库补建项：说明、是否尚未补建、补建（各自按 meta 版本只做一次）
+struct Step {
+    label: &'static str,
+    pending: fn(&Connection) -> Result<bool>,
+    run: fn(&Connection, &Path) -> Result<()>,
+}
+
+/// 按顺序执行；媒体元数据要在附件重新分类之后
+static STEPS: [Step; 7] = [
+    Step {
+        label: "附件类型",
+        pending: file_type::reclassify_pending,
+        run: file_type::ensure_reclassified,
+    },
+    Step {
+        label: "相似档案词表",
+        pending: similar::terms_pending,
+        run: archive_terms,
+    },
+    Step {
+        label: "附件正文索引",
+        pending: attachment_text::backfill_pending,
+        run: attachment_text::ensure_backfilled,
+    },
+    Step {
+        label: "页眉页脚/脚注/批注段落",
+        pending: docx::supplements_pending,
+        run: docx::ensure_supplements_backfilled,
+    },
+    Step {
+        label: "主文目录",
+        pending: outline::backfill_pending,
+        run: outline::ensure_backfilled,
+    },
+    Step {
+        label: "主文图片索引",
+        pending: docx_images::backfill_pending,
+        run: docx_images::ensure_backfilled,
+    },
+    Step {
+        label: "图片/视频元数据",
+        pending: media_meta::backfill_pending,
+        run: media_meta::ensure_backfilled,
+    },
+];
+
+fn archive_terms(conn: &Connection, _root: &Path) -> Result<()> {
+    similar::ensure_archive_terms_synced(conn)
+}
+
+/// 启动后台线程补建旧库缺少的索引；已有线程在跑或都已补建时直接返回
+pub fn spawn_worker(app: &tauri::AppHandle, root: &Path) {
+    if WORKER_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    let root = root.to_path_buf();
+    std::thread::spawn(move || {
+        if let Err(e) = run_worker(&app, &root) {
+            eprintln!("索引补建后台任务失败: {e:#}");
+        }
+        WORKER_RUNNING.store(false, Ordering::SeqCst);
+    });
+}
+
+fn run_worker(app: &tauri::AppHandle, root: &Path) -> Result<()> {
+    let conn = db::open_conn_at(root)?;
+    let mut todo = Vec::new();
+    for step in &STEPS {
+        if (step.pending)(&conn)? {
+            todo.push(step);
+        }
+    }
+    if todo.is_empty() {
+        return Ok(());
+    }
+    let total = todo.len();
+    for (i, step) in todo.into_iter().enumerate() {
+        progress::emit(
+            app,
+            progress::ProgressEvent::new(
+                "backfill",
+                i,
+                total,
+                "补建索引",
+                &format!("正在补建{}", step.label),
+            ),
+        );
+        // 单项失败不影响其他项，未写入版本号，下次启动重试
+        if let Err(e) = (step.run)(&conn, root) {
+            eprintln!("{}补建失败: {e:#}", step.label);
+        }
+    }
+    progress::emit(
+        app,
+        progress::ProgressEvent::complete("backfill", "旧库索引补建完成"),
+    );
+    Ok(())
+}
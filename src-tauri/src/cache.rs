@@ -1,4 +1,5 @@
 use crate::db;
+use crate::embedded;
 use crate::library_root::{resolve_library_root, LibraryRootState};
 use crate::progress;
 use anyhow::{anyhow, Context, Result};
@@ -6,7 +7,7 @@ use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::{Read, Seek};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tauri::State;
 use zip::ZipArchive;
 
@@ -102,10 +103,18 @@ pub(crate) fn get_attachment_preview_path_impl(
     let root = resolve_library_root(app, state)?;
     db::init_db(app, &root)?;
     let conn = Connection::open(root.join("db.sqlite"))?;
+    let abs = ensure_cached_file(&root, &conn, file_id)?;
+    Ok(PreviewPathResp {
+        file_id: file_id.to_string(),
+        path: abs.to_string_lossy().to_string(),
+    })
+}
 
+/// 确保附件已解压到缓存目录，返回绝对路径（预览、OCR 等共用）
+pub(crate) fn ensure_cached_file(root: &Path, conn: &Connection, file_id: &str) -> Result<PathBuf> {
     let row = conn
         .query_row(
-            "SELECT archive_id, file_type, source_depth, container_virtual_path, virtual_path, cached_path, display_name
+            "SELECT archive_id, file_type, source_depth, container_virtual_path, virtual_path, cached_path, display_name, container_kind
              FROM attachments WHERE file_id=?",
             [file_id],
             |r| {
@@ -117,6 +126,7 @@ pub(crate) fn get_attachment_preview_path_impl(
                     r.get::<_, String>(4)?,
                     r.get::<_, Option<String>>(5)?,
                     r.get::<_, String>(6)?,
+                    r.get::<_, String>(7)?,
                 ))
             },
         )
@@ -131,15 +141,13 @@ pub(crate) fn get_attachment_preview_path_impl(
         virtual_path,
         cached_path,
         display_name,
+        container_kind,
     ) = row;
 
     if let Some(rel) = cached_path {
         let abs = root.join(&rel);
         if abs.exists() {
-            return Ok(PreviewPathResp {
-                file_id: file_id.to_string(),
-                path: abs.to_string_lossy().to_string(),
-            });
+            return Ok(abs);
         }
     }
 
@@ -154,7 +162,14 @@ pub(crate) fn get_attachment_preview_path_impl(
         return Err(anyhow!("原始ZIP不存在: {}", stored_rel));
     }
 
-    let bytes = if source_depth == 0 {
+    let bytes = if container_kind == embedded::CONTAINER_MAIN_DOCX {
+        // 主文内嵌对象：container_virtual_path 为主 docx 在 ZIP 内的路径
+        let main_path = container_virtual_path
+            .clone()
+            .ok_or_else(|| anyhow!("内嵌对象缺少 container_virtual_path"))?;
+        let main_docx_bytes = read_entry_from_zip_file(&zip_abs, &main_path)?;
+        embedded::read_payload(&main_docx_bytes, &virtual_path)?
+    } else if source_depth == 0 {
         read_entry_from_zip_file(&zip_abs, &virtual_path)?
     } else if source_depth == 1 {
         let child_path = container_virtual_path
@@ -180,10 +195,7 @@ pub(crate) fn get_attachment_preview_path_impl(
         params![rel_cache, file_id],
     )?;
 
-    Ok(PreviewPathResp {
-        file_id: file_id.to_string(),
-        path: abs_cache.to_string_lossy().to_string(),
-    })
+    Ok(abs_cache)
 }
 
 fn read_entry_from_zip_file(zip_path: &Path, virtual_path: &str) -> Result<Vec<u8>> {
@@ -1,4 +1,5 @@
 use crate::library_root::{resolve_library_root, LibraryRootState};
+use crate::media_meta::{MediaFilter, MediaMeta};
 use crate::progress;
 use anyhow::{anyhow, Context, Result};
 use chrono::{FixedOffset, NaiveDate, NaiveDateTime, TimeZone};
@@ -17,11 +18,14 @@ pub struct MetaRecord {
     pub tz: String,
 }
 
+/// 分词/同义词配置变化后置 1，提示用户重建搜索索引
+pub const META_SEARCH_INDEX_STALE: &str = "search_index_stale";
+
 fn db_path(root: &Path) -> PathBuf {
     root.join("db.sqlite")
 }
 
-fn open_conn_at(root: &Path) -> Result<Connection> {
+pub(crate) fn open_conn_at(root: &Path) -> Result<Connection> {
     let p = db_path(root);
     let conn = Connection::open(p).context("打开 db.sqlite 失败")?;
     conn.pragma_update(None, "foreign_keys", "ON")?;
@@ -31,8 +35,14 @@ fn open_conn_at(root: &Path) -> Result<Connection> {
 pub fn init_db(app: &tauri::AppHandle, root: &Path) -> Result<()> {
     let conn = open_conn_at(root)?;
     apply_migrations(&conn)?;
+    // 分词器用户词典与同义词表需在任何 build_search_text 之前加载
+    crate::search::ensure_tokenizer_loaded(root)?;
+    crate::synonyms::ensure_loaded(root, &conn)?;
     // 确保批注FTS存在并与主表一致（数据量小，直接对齐）
     ensure_annotations_fts_synced(&conn)?;
+    // 拼音索引（模糊搜索）：旧库首次打开时补建
+    crate::fuzzy::ensure_pinyin_fts_synced(&conn)?;
+    // 重新分类、词表、附件正文、目录等需要读文件的补建在启动后的后台任务里做（见 backfill）
     // 修复/写入 meta
     let existing: Option<String> = conn
         .query_row("SELECT value FROM meta WHERE key='library_root'", [], |r| {
@@ -178,13 +188,194 @@ CREATE TABLE IF NOT EXISTS annotations (
   updated_at INTEGER NOT NULL,
   FOREIGN KEY(archive_id) REFERENCES archives(archive_id) ON DELETE CASCADE
 );
+
+CREATE VIRTUAL TABLE IF NOT EXISTS pinyin_fts USING fts5(
+  archive_id UNINDEXED,
+  kind UNINDEXED,
+  ref_id UNINDEXED,
+  full_text,
+  initials_text,
+  source_text UNINDEXED
+);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS pinyin_fts_vocab USING fts5vocab(pinyin_fts, 'row');
+
+CREATE TABLE IF NOT EXISTS synonym_groups (
+  group_id TEXT PRIMARY KEY,
+  terms_json TEXT NOT NULL,
+  updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS saved_searches (
+  search_id TEXT PRIMARY KEY,
+  name TEXT NOT NULL,
+  query TEXT NOT NULL,
+  filters_json TEXT NOT NULL,
+  sort TEXT,
+  fuzzy INTEGER NOT NULL DEFAULT 0,
+  mode TEXT,
+  created_at INTEGER NOT NULL,
+  updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS notifications (
+  notification_id TEXT PRIMARY KEY,
+  search_id TEXT NOT NULL,
+  archive_id TEXT NOT NULL,
+  hit_count INTEGER NOT NULL,
+  sample_json TEXT NOT NULL,
+  created_at INTEGER NOT NULL,
+  read_at INTEGER,
+  FOREIGN KEY(search_id) REFERENCES saved_searches(search_id) ON DELETE CASCADE,
+  FOREIGN KEY(archive_id) REFERENCES archives(archive_id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_notifications_created_at ON notifications(created_at DESC);
+
+CREATE TABLE IF NOT EXISTS search_history (
+  history_id TEXT PRIMARY KEY,
+  query TEXT NOT NULL,
+  filters_json TEXT NOT NULL,
+  hit_count INTEGER NOT NULL,
+  has_more INTEGER NOT NULL DEFAULT 0,
+  search_count INTEGER NOT NULL DEFAULT 1,
+  searched_at INTEGER NOT NULL,
+  pinned INTEGER NOT NULL DEFAULT 0,
+  UNIQUE(query, filters_json)
+);
+CREATE INDEX IF NOT EXISTS idx_search_history_searched_at ON search_history(searched_at DESC);
+
+-- 联想词改用 archive_terms，旧库里的 FTS 词表视图不再需要
+DROP TABLE IF EXISTS main_doc_fts_vocab;
+DROP TABLE IF EXISTS docx_blocks_fts_vocab;
+
+CREATE TABLE IF NOT EXISTS archive_terms (
+  archive_id TEXT NOT NULL,
+  term TEXT NOT NULL,
+  tf INTEGER NOT NULL,
+  PRIMARY KEY(archive_id, term),
+  FOREIGN KEY(archive_id) REFERENCES archives(archive_id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_archive_terms_term ON archive_terms(term);
+
+CREATE TABLE IF NOT EXISTS ocr_jobs (
+  file_id TEXT PRIMARY KEY,
+  archive_id TEXT NOT NULL,
+  status TEXT NOT NULL,
+  error TEXT,
+  updated_at INTEGER NOT NULL,
+  FOREIGN KEY(file_id) REFERENCES attachments(file_id) ON DELETE CASCADE,
+  FOREIGN KEY(archive_id) REFERENCES archives(archive_id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_ocr_jobs_status ON ocr_jobs(status, updated_at);
+
+CREATE TABLE IF NOT EXISTS ocr_blocks (
+  file_id TEXT NOT NULL,
+  archive_id TEXT NOT NULL,
+  page INTEGER NOT NULL,
+  block_idx INTEGER NOT NULL,
+  text TEXT NOT NULL,
+  x INTEGER NOT NULL,
+  y INTEGER NOT NULL,
+  w INTEGER NOT NULL,
+  h INTEGER NOT NULL,
+  confidence INTEGER NOT NULL,
+  image_width INTEGER NOT NULL,
+  image_height INTEGER NOT NULL,
+  PRIMARY KEY(file_id, page, block_idx),
+  FOREIGN KEY(file_id) REFERENCES attachments(file_id) ON DELETE CASCADE,
+  FOREIGN KEY(archive_id) REFERENCES archives(archive_id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS attachment_meta (
+  file_id TEXT PRIMARY KEY,
+  archive_id TEXT NOT NULL,
+  captured_at TEXT,
+  captured_at_ts INTEGER,
+  camera_make TEXT,
+  camera_model TEXT,
+  gps_lat REAL,
+  gps_lon REAL,
+  width INTEGER,
+  height INTEGER,
+  duration_ms INTEGER,
+  video_codec TEXT,
+  audio_codec TEXT,
+  FOREIGN KEY(file_id) REFERENCES attachments(file_id) ON DELETE CASCADE,
+  FOREIGN KEY(archive_id) REFERENCES archives(archive_id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_attachment_meta_captured ON attachment_meta(captured_at_ts);
+CREATE INDEX IF NOT EXISTS idx_attachment_meta_duration ON attachment_meta(duration_ms);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS ocr_fts USING fts5(
+  archive_id UNINDEXED,
+  file_id UNINDEXED,
+  page UNINDEXED,
+  block_idx UNINDEXED,
+  search_text,
+  source_text
+);
+
+CREATE TABLE IF NOT EXISTS attachment_blocks (
+  file_id TEXT NOT NULL,
+  archive_id TEXT NOT NULL,
+  block_id TEXT NOT NULL,
+  page INTEGER,
+  text TEXT NOT NULL,
+  PRIMARY KEY(file_id, block_id),
+  FOREIGN KEY(file_id) REFERENCES attachments(file_id) ON DELETE CASCADE,
+  FOREIGN KEY(archive_id) REFERENCES archives(archive_id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_attachment_blocks_archive ON attachment_blocks(archive_id);
+
+CREATE TABLE IF NOT EXISTS docx_revisions (
+  archive_id TEXT NOT NULL,
+  block_id TEXT NOT NULL,
+  original_text TEXT NOT NULL,
+  revisions_json TEXT NOT NULL,
+  PRIMARY KEY(archive_id, block_id),
+  FOREIGN KEY(archive_id) REFERENCES archives(archive_id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS docx_outline (
+  archive_id TEXT NOT NULL,
+  ord INTEGER NOT NULL,
+  block_id TEXT NOT NULL,
+  level INTEGER NOT NULL,
+  title TEXT NOT NULL,
+  PRIMARY KEY(archive_id, ord),
+  FOREIGN KEY(archive_id) REFERENCES archives(archive_id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS docx_images (
+  archive_id TEXT NOT NULL,
+  ord INTEGER NOT NULL,
+  block_id TEXT NOT NULL,
+  image_id TEXT NOT NULL,
+  target TEXT NOT NULL,
+  alt TEXT,
+  width_px INTEGER,
+  height_px INTEGER,
+  PRIMARY KEY(archive_id, ord),
+  FOREIGN KEY(archive_id) REFERENCES archives(archive_id) ON DELETE CASCADE
+);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS attachment_blocks_fts USING fts5(
+  archive_id UNINDEXED,
+  file_id UNINDEXED,
+  block_id UNINDEXED,
+  search_text,
+  source_text
+);
 "#,
     )?;
     ensure_main_doc_issued_at_ts(conn)?;
+    ensure_attachments_container_kind(conn)?;
+    ensure_attachment_blocks_lines(conn)?;
+    ensure_saved_searches_mode(conn)?;
     Ok(())
 }
 
-fn tz_offset() -> FixedOffset {
+pub(crate) fn tz_offset() -> FixedOffset {
     FixedOffset::east_opt(8 * 3600).expect("tz")
 }
 
@@ -262,6 +453,38 @@ fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool> {
     Ok(false)
 }
 
+/// 附件所在容器：zip（主ZIP/子ZIP条目）或 main_docx（主文内嵌对象）
+fn ensure_attachments_container_kind(conn: &Connection) -> Result<()> {
+    if !column_exists(conn, "attachments", "container_kind")? {
+        conn.execute(
+            "ALTER TABLE attachments ADD COLUMN container_kind TEXT NOT NULL DEFAULT 'zip'",
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+/// 文本附件按行切块后的起止行号；doc/wps/ofd 段落为空
+fn ensure_attachment_blocks_lines(conn: &Connection) -> Result<()> {
+    for column in ["line_start", "line_end"] {
+        if !column_exists(conn, "attachment_blocks", column)? {
+            conn.execute(
+                &format!("ALTER TABLE attachment_blocks ADD COLUMN {column} INTEGER"),
+                [],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// 保存的搜索的匹配模式（regex/wildcard），为空即全文检索
+fn ensure_saved_searches_mode(conn: &Connection) -> Result<()> {
+    if !column_exists(conn, "saved_searches", "mode")? {
+        conn.execute("ALTER TABLE saved_searches ADD COLUMN mode TEXT", [])?;
+    }
+    Ok(())
+}
+
 fn ensure_main_doc_issued_at_ts(conn: &Connection) -> Result<()> {
     if !column_exists(conn, "main_doc", "issued_at_ts")? {
         conn.execute(
@@ -309,6 +532,20 @@ pub fn write_meta(app: &tauri::AppHandle, root: &Path, meta: MetaRecord) -> Resu
     Ok(())
 }
 
+pub fn get_meta_value(conn: &Connection, key: &str) -> Result<Option<String>> {
+    Ok(conn
+        .query_row("SELECT value FROM meta WHERE key=?", [key], |r| r.get(0))
+        .optional()?)
+}
+
+pub fn set_meta_value(conn: &Connection, key: &str, value: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO meta(key,value) VALUES(?,?) ON CONFLICT(key) DO UPDATE SET value=excluded.value",
+        [key, value],
+    )?;
+    Ok(())
+}
+
 pub fn read_meta(_app: &tauri::AppHandle, root: &Path) -> Result<MetaRecord> {
     let conn = open_conn_at(root)?;
     apply_migrations(&conn)?;
@@ -386,8 +623,12 @@ pub struct AttachmentRow {
     pub source_depth: i64,
     pub container_virtual_path: Option<String>,
     pub virtual_path: String,
+    /// zip / main_docx，见 embedded::CONTAINER_*
+    pub container_kind: String,
     pub cached_path: Option<String>,
     pub size_bytes: Option<i64>,
+    /// 图片 EXIF / 视频容器头信息
+    pub meta: Option<MediaMeta>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -415,6 +656,11 @@ pub struct ListArchivesReq {
     pub date_to: Option<i64>,
     pub limit: Option<usize>,
     pub offset: Option<usize>,
+    /// 只列出含有符合条件的图片/视频附件的档案
+    pub captured_from: Option<i64>,
+    pub captured_to: Option<i64>,
+    pub min_duration_ms: Option<i64>,
+    pub max_duration_ms: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -464,6 +710,10 @@ pub fn list_archives(
         date_to: None,
         limit: Some(200),
         offset: Some(0),
+        captured_from: None,
+        captured_to: None,
+        min_duration_ms: None,
+        max_duration_ms: None,
     });
     let limit = req.limit.unwrap_or(200).min(1000) as i64;
     let offset = req.offset.unwrap_or(0) as i64;
@@ -479,6 +729,24 @@ pub fn list_archives(
             req.date_to.unwrap_or(i64::MAX),
         ));
     }
+    let media = MediaFilter {
+        captured_from: req.captured_from,
+        captured_to: req.captured_to,
+        min_duration_ms: req.min_duration_ms,
+        max_duration_ms: req.max_duration_ms,
+    };
+    if media.is_active() {
+        let (cond, params) = media.sql("am");
+        where_sql.push_str(if where_sql.is_empty() {
+            " WHERE "
+        } else {
+            " AND "
+        });
+        where_sql.push_str(&format!(
+            "a.archive_id IN (SELECT am.archive_id FROM attachment_meta am WHERE {cond}) "
+        ));
+        params_vec.extend(params);
+    }
 
     let sql = format!(
         "SELECT a.archive_id, a.original_name, a.zip_date, a.imported_at, a.status, m.instruction_no, m.title, m.content, m.issued_at
@@ -749,6 +1017,18 @@ pub fn update_archive_title(
             [&archive_id, &search_text, &new_title],
         )
         .map_err(|e| err_to_string(anyhow!(e).context("更新索引失败")))?;
+
+        let instruction_no: String = tx
+            .query_row(
+                "SELECT instruction_no FROM main_doc WHERE archive_id=?",
+                [&archive_id],
+                |r| r.get(0),
+            )
+            .map_err(|e| err_to_string(anyhow!(e)))?;
+        crate::fuzzy::index_main_doc_fields(&tx, &archive_id, &instruction_no, &new_title)
+            .map_err(|e| err_to_string(e.context("更新拼音索引失败")))?;
+        crate::similar::index_archive(&tx, &archive_id)
+            .map_err(|e| err_to_string(e.context("更新相似档案词表失败")))?;
     } else {
         return Err(format!("档案 {} 没有 main_doc 记录", archive_id));
     }
@@ -802,6 +1082,9 @@ fn delete_archive_impl(root: &Path, conn: &mut Connection, archive_id: &str) ->
         "DELETE FROM annotations_fts WHERE archive_id=?",
         [archive_id],
     )?;
+    crate::fuzzy::delete_archive(&tx, archive_id)?;
+    crate::ocr::delete_archive(&tx, archive_id)?;
+    crate::attachment_text::delete_archive(&tx, archive_id)?;
 
     // 再删除主表（外键级联清理 main_doc/docx_blocks/attachments/annotations）
     tx.execute("DELETE FROM archives WHERE archive_id=?", [archive_id])?;
@@ -856,7 +1139,7 @@ pub fn get_archive_detail(
     {
         let mut stmt = conn
             .prepare(
-                "SELECT file_id,display_name,file_type,source_depth,container_virtual_path,virtual_path,cached_path,size_bytes FROM attachments WHERE archive_id=? ORDER BY source_depth, display_name",
+                "SELECT file_id,display_name,file_type,source_depth,container_virtual_path,virtual_path,cached_path,size_bytes,container_kind FROM attachments WHERE archive_id=? ORDER BY source_depth, display_name",
             )
             .map_err(|e| err_to_string(anyhow!(e)))?;
         let rows = stmt
@@ -870,6 +1153,8 @@ pub fn get_archive_detail(
                     virtual_path: r.get(5)?,
                     cached_path: r.get(6).ok(),
                     size_bytes: r.get(7).ok(),
+                    container_kind: r.get(8)?,
+                    meta: None,
                 })
             })
             .map_err(|e| err_to_string(anyhow!(e)))?;
@@ -877,6 +1162,11 @@ pub fn get_archive_detail(
             attachments.push(row.map_err(|e| err_to_string(anyhow!(e)))?);
         }
     }
+    let mut metas = crate::media_meta::load_for_archive(&conn, &archive_id)
+        .map_err(|e| err_to_string(e.context("读取附件元数据失败")))?;
+    for a in attachments.iter_mut() {
+        a.meta = metas.remove(&a.file_id);
+    }
 
     let mut annotations = Vec::new();
     {
@@ -0,0 +1,327 @@
+// Word 97-2003 二进制文档（.doc）文字抽取。
+// 只读 OLE 容器里的 WordDocument 流和表流（0Table/1Table）：按 FIB 找到 Clx，
+// 再按分段表（piece table）拼出正文字符，不解析样式与格式。
+
+use crate::docx::{self, MainDocParsed};
+use anyhow::{anyhow, Context, Result};
+use std::io::{Cursor, Read};
+
+/// FIB 中 FibRgFcLcb97 的 fcClx/lcbClx 下标（以 4 字节为单位）
+const FC_CLX_INDEX: usize = 66;
+/// FibRgLw97 中 ccpText（正文字符数）的下标
+const CCP_TEXT_INDEX: usize = 3;
+/// Word 97 起的 nFib；更早的 Word 6/95 格式不同
+const MIN_NFIB: u16 = 0x00C1;
+const FC_COMPRESSED: u32 = 0x4000_0000;
+
+pub fn is_ole(bytes: &[u8]) -> bool {
+    bytes.starts_with(&[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1])
+}
+
+/// .doc 主文：段落划分与 block_id 规则与 docx 相同，字段抽取共用
+pub fn parse_main_doc(bytes: &[u8]) -> Result<MainDocParsed> {
+    let paragraphs = extract_paragraphs(bytes, false)?;
+    docx::parse_main_paragraphs(paragraphs)
+}
+
+/// 返回正文段落（不含页眉页脚、脚注）。表格按单元格结束符跳过，与 docx 忽略表格的处理对应；
+/// 单元格内有多段时前几段无法区分，会当普通段落保留。
+/// mark_pagebreak 为 true 时分页符保留为 \u{000C}，否则换成换行。
+pub fn extract_paragraphs(bytes: &[u8], mark_pagebreak: bool) -> Result<Vec<String>> {
+    let text = read_main_text(bytes)?;
+    Ok(split_paragraphs(&text, mark_pagebreak))
+}
+
+fn read_main_text(bytes: &[u8]) -> Result<Vec<u16>> {
+    let mut cfb = cfb::CompoundFile::open(Cursor::new(bytes)).context("doc不是有效的OLE文件")?;
+    let word = read_stream(&mut cfb, "/WordDocument").context("doc缺少 WordDocument 流")?;
+    let table_name = table_stream_name(&word)?;
+    let table =
+        read_stream(&mut cfb, table_name).with_context(|| format!("doc缺少表流 {table_name}"))?;
+    decode_main_text(&word, &table)
+}
+
+/// 校验 FibBase，返回分段表所在的表流名
+fn table_stream_name(word: &[u8]) -> Result<&'static str> {
+    if word.len() < 32 || u16_at(word, 0) != Some(0xA5EC) {
+        return Err(anyhow!("WordDocument 流头无效"));
+    }
+    let n_fib = u16_at(word, 2).unwrap_or(0);
+    if n_fib < MIN_NFIB {
+        return Err(anyhow!("不支持 Word 95 及更早版本的 doc"));
+    }
+    let flags = u16_at(word, 0x0A).unwrap_or(0);
+    if flags & 0x0100 != 0 {
+        return Err(anyhow!("doc已加密，无法读取"));
+    }
+    Ok(if flags & 0x0200 != 0 {
+        "/1Table"
+    } else {
+        "/0Table"
+    })
+}
+
+/// 按 FIB 在表流里找到 Clx，再按 PlcPcd 从 WordDocument 流拼出正文字符
+fn decode_main_text(word: &[u8], table: &[u8]) -> Result<Vec<u16>> {
+    // FibBase(32) | csw | fibRgW | cslw | fibRgLw | cbRgFcLcb | fibRgFcLcb
+    let csw = u16_at(word, 32).ok_or_else(|| anyhow!("FIB 截断"))? as usize;
+    let lw_start = 32 + 2 + csw * 2 + 2;
+    let cslw = u16_at(word, lw_start - 2).ok_or_else(|| anyhow!("FIB 截断"))? as usize;
+    let ccp_text = u32_at(word, lw_start + CCP_TEXT_INDEX * 4).unwrap_or(u32::MAX) as usize;
+    let fc_start = lw_start + cslw * 4 + 2;
+    let fc_clx =
+        u32_at(word, fc_start + FC_CLX_INDEX * 4).ok_or_else(|| anyhow!("FIB 截断"))? as usize;
+    let lcb_clx = u32_at(word, fc_start + (FC_CLX_INDEX + 1) * 4)
+        .ok_or_else(|| anyhow!("FIB 截断"))? as usize;
+    let clx = table
+        .get(fc_clx..fc_clx.saturating_add(lcb_clx))
+        .ok_or_else(|| anyhow!("Clx 超出表流范围"))?;
+    let plc = find_plc_pcd(clx)?;
+
+    // PlcPcd：n+1 个 CP，后跟 n 个 8 字节 PCD
+    let n = plc.len().saturating_sub(4) / 12;
+    let mut out = Vec::new();
+    for i in 0..n {
+        let cp_start = u32_at(plc, i * 4).unwrap_or(0) as usize;
+        let cp_end = u32_at(plc, (i + 1) * 4).unwrap_or(0) as usize;
+        if cp_start >= ccp_text || cp_end <= cp_start {
+            continue;
+        }
+        // 只要正文部分，脚注/页眉等故事紧随其后
+        let len = cp_end.min(ccp_text) - cp_start;
+        let pcd = (n + 1) * 4 + i * 8;
+        let fc = u32_at(plc, pcd + 2).ok_or_else(|| anyhow!("PCD 截断"))?;
+        if fc & FC_COMPRESSED != 0 {
+            // 8 位压缩字符，按 cp1252 解码
+            let off = ((fc & !FC_COMPRESSED) / 2) as usize;
+            let raw = word.get(off..off + len).unwrap_or_default();
+            let (s, _) = encoding_rs::WINDOWS_1252.decode_without_bom_handling(raw);
+            out.extend(s.encode_utf16());
+        } else {
+            let off = fc as usize;
+            let raw = word.get(off..off + len * 2).unwrap_or_default();
+            out.extend(
+                raw.chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]])),
+            );
+        }
+    }
+    Ok(out)
+}
+
+/// Clx = 若干 Prc（0x01）+ 一个 Pcdt（0x02），只取 Pcdt 里的 PlcPcd
+fn find_plc_pcd(clx: &[u8]) -> Result<&[u8]> {
+    let mut pos = 0;
+    while pos < clx.len() {
+        match clx[pos] {
+            0x01 => {
+                let cb = u16_at(clx, pos + 1).ok_or_else(|| anyhow!("Clx 截断"))? as usize;
+                pos += 3 + cb;
+            }
+            0x02 => {
+                let lcb = u32_at(clx, pos + 1).ok_or_else(|| anyhow!("Clx 截断"))? as usize;
+                return clx
+                    .get(pos + 5..pos + 5 + lcb)
+                    .ok_or_else(|| anyhow!("PlcPcd 截断"));
+            }
+            b => return Err(anyhow!("Clx 格式无效: {b:#04x}")),
+        }
+    }
+    Err(anyhow!("doc缺少分段表"))
+}
+
+/// 按段落标记切分，并处理域代码、表格单元格等控制字符
+fn split_paragraphs(text: &[u16], mark_pagebreak: bool) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut cur: Vec<u16> = Vec::new();
+    // 域嵌套：true 表示处于域代码部分（0x13 与 0x14 之间），要丢弃
+    let mut fields: Vec<bool> = Vec::new();
+    let in_code = |fields: &[bool]| fields.iter().any(|c| *c);
+
+    for &ch in text {
+        match ch {
+            0x13 => fields.push(true),
+            0x14 => {
+                if let Some(last) = fields.last_mut() {
+                    *last = false;
+                }
+            }
+            0x15 => {
+                fields.pop();
+            }
+            _ if in_code(&fields) => {}
+            // 段落结束
+            0x0D => out.push(finish(&mut cur)),
+            // 单元格/行结束：这一段属于表格，跳过
+            0x07 => cur.clear(),
+            0x0B => cur.push('\n' as u16),
+            0x0C => cur.push(if mark_pagebreak { 0x0C } else { '\n' as u16 }),
+            0x09 => cur.push(ch),
+            // 不间断连字符 / 可选连字符
+            0x1E => cur.push('-' as u16),
+            0x1F => {}
+            // 图片、批注引用、脚注引用等占位符
+            c if c < 0x20 => {}
+            c => cur.push(c),
+        }
+    }
+    if !cur.is_empty() {
+        out.push(finish(&mut cur));
+    }
+    out
+}
+
+fn finish(cur: &mut Vec<u16>) -> String {
+    let s = String::from_utf16_lossy(cur);
+    cur.clear();
+    s.replace('\u{00A0}', " ").replace('\u{3000}', " ")
+}
+
+fn read_stream<F: Read + std::io::Seek>(
+    cfb: &mut cfb::CompoundFile<F>,
+    name: &str,
+) -> Result<Vec<u8>> {
+    let mut s = cfb.open_stream(name)?;
+    let mut buf = Vec::new();
+    s.read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+fn u16_at(b: &[u8], off: usize) -> Option<u16> {
+    b.get(off..off + 2)
+        .map(|s| u16::from_le_bytes([s[0], s[1]]))
+}
+
+fn u32_at(b: &[u8], off: usize) -> Option<u32> {
+    b.get(off..off + 4)
+        .map(|s| u32::from_le_bytes([s[0], s[1], s[2], s[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 按 Word 97 的 FIB 布局拼一个最小 WordDocument 流：csw=14、cslw=22、cbRgFcLcb=93
+    fn word_stream(flags: u16, ccp_text: u32, fc_clx: u32, lcb_clx: u32) -> Vec<u8> {
+        let mut w = vec![0u8; 700];
+        w[0..2].copy_from_slice(&0xA5ECu16.to_le_bytes());
+        w[2..4].copy_from_slice(&MIN_NFIB.to_le_bytes());
+        w[0x0A..0x0C].copy_from_slice(&flags.to_le_bytes());
+        w[32..34].copy_from_slice(&14u16.to_le_bytes());
+        w[62..64].copy_from_slice(&22u16.to_le_bytes());
+        let lw = 64;
+        w[lw + CCP_TEXT_INDEX * 4..lw + CCP_TEXT_INDEX * 4 + 4]
+            .copy_from_slice(&ccp_text.to_le_bytes());
+        w[152..154].copy_from_slice(&93u16.to_le_bytes());
+        let fc = 154 + FC_CLX_INDEX * 4;
+        w[fc..fc + 4].copy_from_slice(&fc_clx.to_le_bytes());
+        w[fc + 4..fc + 8].copy_from_slice(&lcb_clx.to_le_bytes());
+        // 第一段：UTF-16 “第一段\r第二”；第二段：8 位压缩 “ab\rxyz”
+        let unicode: Vec<u8> = "第一段\r第二"
+            .encode_utf16()
+            .flat_map(|u| u.to_le_bytes())
+            .collect();
+        w[512..512 + unicode.len()].copy_from_slice(&unicode);
+        w[600..606].copy_from_slice(b"ab\rxyz");
+        w
+    }
+
+    fn plc_pcd(cps: &[u32], fcs: &[u32]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for cp in cps {
+            out.extend_from_slice(&cp.to_le_bytes());
+        }
+        for fc in fcs {
+            out.extend_from_slice(&[0, 0]);
+            out.extend_from_slice(&fc.to_le_bytes());
+            out.extend_from_slice(&[0, 0]);
+        }
+        out
+    }
+
+    /// Clx：一个 Prc 后跟 Pcdt
+    fn clx(plc: &[u8]) -> Vec<u8> {
+        let mut out = vec![0x01, 2, 0, 0xAA, 0xBB, 0x02];
+        out.extend_from_slice(&(plc.len() as u32).to_le_bytes());
+        out.extend_from_slice(plc);
+        out
+    }
+
+    /// 表流前面留 16 字节，Clx 不从 0 开始
+    fn doc(ccp_text: u32) -> (Vec<u8>, Vec<u8>) {
+        let plc = plc_pcd(&[0, 6, 12], &[512, (600 * 2) | FC_COMPRESSED]);
+        let clx = clx(&plc);
+        let mut table = vec![0u8; 16];
+        table.extend_from_slice(&clx);
+        (word_stream(0, ccp_text, 16, clx.len() as u32), table)
+    }
+
+    fn units(s: &str) -> Vec<u16> {
+        s.encode_utf16().collect()
+    }
+
+    #[test]
+    fn decodes_unicode_and_compressed_pieces() {
+        let (word, table) = doc(9);
+        let text = decode_main_text(&word, &table).unwrap();
+        assert_eq!(String::from_utf16_lossy(&text), "第一段\r第二ab\r");
+        assert_eq!(split_paragraphs(&text, false), ["第一段", "第二ab"]);
+    }
+
+    #[test]
+    fn stops_at_ccp_text() {
+        let (word, table) = doc(4);
+        let text = decode_main_text(&word, &table).unwrap();
+        assert_eq!(String::from_utf16_lossy(&text), "第一段\r");
+    }
+
+    #[test]
+    fn rejects_truncated_fib_and_clx() {
+        let (word, table) = doc(9);
+        assert!(decode_main_text(&word[..300], &table).is_err());
+        // Clx 超出表流
+        assert!(decode_main_text(&word, &table[..20]).is_err());
+    }
+
+    #[test]
+    fn fib_base_checks() {
+        assert_eq!(
+            table_stream_name(&word_stream(0, 0, 0, 0)).unwrap(),
+            "/0Table"
+        );
+        assert_eq!(
+            table_stream_name(&word_stream(0x0200, 0, 0, 0)).unwrap(),
+            "/1Table"
+        );
+        assert!(table_stream_name(&word_stream(0x0100, 0, 0, 0)).is_err());
+        let mut old = word_stream(0, 0, 0, 0);
+        old[2..4].copy_from_slice(&0x0065u16.to_le_bytes());
+        assert!(table_stream_name(&old).is_err());
+        assert!(table_stream_name(&[0u8; 16]).is_err());
+    }
+
+    #[test]
+    fn find_plc_pcd_skips_prc() {
+        let plc = plc_pcd(&[0, 3], &[512]);
+        assert_eq!(find_plc_pcd(&clx(&plc)).unwrap(), &plc[..]);
+        // 只有 Prc、未知类型、PlcPcd 截断
+        assert!(find_plc_pcd(&[0x01, 1, 0, 0xAA]).is_err());
+        assert!(find_plc_pcd(&[0x03, 0, 0]).is_err());
+        let mut cut = clx(&plc);
+        cut.truncate(cut.len() - 1);
+        assert!(find_plc_pcd(&cut).is_err());
+    }
+
+    #[test]
+    fn split_drops_field_codes_and_table_cells() {
+        let text = units("a\u{13} HYPERLINK \"x\" \u{14}链接\u{15}b\r格1\u{7}格2\u{7}\u{7}表后\r");
+        assert_eq!(split_paragraphs(&text, false), ["a链接b", "表后"]);
+    }
+
+    #[test]
+    fn split_page_breaks_and_spaces() {
+        let text = units("一\u{c}二\u{a0}三\u{3000}四");
+        assert_eq!(split_paragraphs(&text, true), ["一\u{c}二 三 四"]);
+        assert_eq!(split_paragraphs(&text, false), ["一\n二 三 四"]);
+    }
+}
@@ -1,10 +1,14 @@
 use crate::cache;
 use crate::db;
+use crate::docx_images::{self, DocxImage};
+use crate::importer;
 use crate::library_root::{resolve_library_root, LibraryRootState};
+use crate::search;
 use anyhow::{anyhow, Context, Result};
 use quick_xml::events::Event;
 use quick_xml::Reader as XmlReader;
 use regex::Regex;
+use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::fs;
@@ -13,6 +17,17 @@ use std::path::Path;
 use tauri::State;
 use zip::ZipArchive;
 
+/// docx_blocks 的 block_id 前缀：正文段落为 p:，其余为附加部件
+pub const BODY_PREFIX: &str = "p:";
+pub const HEADER_PREFIX: &str = "hdr:";
+pub const FOOTER_PREFIX: &str = "ftr:";
+pub const FOOTNOTE_PREFIX: &str = "fn:";
+pub const ENDNOTE_PREFIX: &str = "en:";
+pub const COMMENT_PREFIX: &str = "cm:";
+/// 旧库补建附加部件段落的版本标记
+const META_SUPPLEMENTS_VERSION: &str = "docx_supplements_version";
+const SUPPLEMENTS_VERSION: &str = "1";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DocxBlock {
     pub block_id: String,
@@ -27,13 +42,43 @@ pub struct MainDocParsed {
     pub content: String,
     pub field_block_map_json: String,
     pub blocks: Vec<DocxBlock>,
+    /// 含修订痕迹的正文段落；blocks 中的文本为接受全部修订后的最终稿
+    #[serde(default)]
+    pub revisions: Vec<BlockRevisions>,
+}
+
+/// 一处修订：ins/del，移动视为 move_to/move_from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Revision {
+    pub kind: String,
+    pub author: Option<String>,
+    pub date: Option<String>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockRevisions {
+    pub block_id: String,
+    /// 拒绝全部修订后的原稿文本
+    pub original_text: String,
+    pub revisions: Vec<Revision>,
+}
+
+/// 段落的最终稿文本，另带原稿文本与修订记录（无修订时 revisions 为空）
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ParagraphText {
+    pub text: String,
+    pub original: String,
+    pub revisions: Vec<Revision>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DocxAttachmentPreview {
     pub file_id: String,
+    pub archive_id: String,
     pub paragraphs: Vec<String>,
-    pub image_paths: Vec<String>,
+    /// 按内容去重；文件经 get_docx_image 按需解出
+    pub images: Vec<DocxImage>,
 }
 
 #[tauri::command]
@@ -47,7 +92,9 @@ pub fn get_docx_blocks(
     let conn = rusqlite::Connection::open(root.join("db.sqlite"))
         .map_err(|e| db::err_to_string(anyhow!(e)))?;
     let mut stmt = conn
-        .prepare("SELECT block_id,text FROM docx_blocks WHERE archive_id=? ORDER BY block_id")
+        .prepare(
+            "SELECT block_id,text FROM docx_blocks WHERE archive_id=? AND block_id LIKE 'p:%' ORDER BY block_id",
+        )
         .map_err(|e| db::err_to_string(anyhow!(e)))?;
     let rows = stmt
         .query_map([archive_id.as_str()], |r| {
@@ -64,6 +111,95 @@ pub fn get_docx_blocks(
     Ok(out)
 }
 
+/// 页眉页脚、脚注尾注与批注段落，来源见 block_id 前缀
+#[tauri::command]
+pub fn get_docx_supplement_blocks(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    archive_id: String,
+) -> Result<Vec<DocxBlock>, String> {
+    get_docx_supplement_blocks_impl(&app, &state, &archive_id).map_err(db::err_to_string)
+}
+
+fn get_docx_supplement_blocks_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    archive_id: &str,
+) -> Result<Vec<DocxBlock>> {
+    let (_root, conn) = db::open_conn(app, state)?;
+    let mut stmt = conn.prepare(
+        "SELECT block_id,text FROM docx_blocks WHERE archive_id=? AND block_id NOT LIKE 'p:%' ORDER BY block_id",
+    )?;
+    let rows = stmt.query_map([archive_id], |r| {
+        Ok(DocxBlock {
+            block_id: r.get(0)?,
+            text: r.get(1)?,
+        })
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+pub(crate) fn supplements_pending(conn: &Connection) -> Result<bool> {
+    Ok(db::get_meta_value(conn, META_SUPPLEMENTS_VERSION)?.as_deref() != Some(SUPPLEMENTS_VERSION))
+}
+
+/// 旧库首次打开时为已导入的 docx 主文补建附加部件段落与索引（字段需重新解析才会补全）
+pub fn ensure_supplements_backfilled(conn: &Connection, root: &Path) -> Result<()> {
+    if !supplements_pending(conn)? {
+        return Ok(());
+    }
+    let mut todo = Vec::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT m.archive_id FROM main_doc m
+             WHERE NOT EXISTS (
+               SELECT 1 FROM docx_blocks b WHERE b.archive_id=m.archive_id AND b.block_id NOT LIKE 'p:%'
+             )",
+        )?;
+        let rows = stmt.query_map([], |r| r.get::<_, String>(0))?;
+        for row in rows {
+            todo.push(row?);
+        }
+    }
+    for archive_id in todo {
+        // .doc/.wps 主文不是 zip，.ofd 没有 word/ 部件，都会得到空结果
+        let blocks = importer::read_main_document(root, conn, &archive_id)
+            .and_then(|(_, bytes)| extract_supplement_blocks(&bytes));
+        let blocks = match blocks {
+            Ok(b) if !b.is_empty() => b,
+            Ok(_) => continue,
+            Err(e) => {
+                eprintln!("附加部件补建跳过 {archive_id}: {e:#}");
+                continue;
+            }
+        };
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut stmt =
+                tx.prepare("INSERT INTO docx_blocks(archive_id,block_id,text) VALUES(?,?,?)")?;
+            let mut fts = tx.prepare(
+                "INSERT INTO docx_blocks_fts(archive_id,block_id,search_text,source_text) VALUES(?,?,?,?)",
+            )?;
+            for b in &blocks {
+                stmt.execute(params![archive_id, b.block_id, b.text])?;
+                fts.execute(params![
+                    archive_id,
+                    b.block_id,
+                    search::build_search_text(&b.text),
+                    b.text
+                ])?;
+            }
+        }
+        tx.commit()?;
+    }
+    db::set_meta_value(conn, META_SUPPLEMENTS_VERSION, SUPPLEMENTS_VERSION)?;
+    Ok(())
+}
+
 #[tauri::command]
 pub fn get_docx_attachment_preview(
     app: tauri::AppHandle,
@@ -99,27 +235,57 @@ fn get_docx_attachment_preview_impl(
     let document_xml = read_docx_document_xml(&bytes)?;
     let paragraphs = extract_paragraph_texts_ignore_tables_with_pagebreak(&document_xml, true)?;
 
-    // 尝试提取 docx 内嵌图片（常见于附加docx）
-    let image_paths =
-        extract_docx_images_to_cache(&bytes, &root, &archive_id, file_id).unwrap_or_default();
+    // docx 内嵌图片（常见于附加docx）只列位置，不在这里解出文件
+    let images = docx_images::dedup(docx_images::scan(&bytes).unwrap_or_default());
 
     Ok(DocxAttachmentPreview {
         file_id: file_id.to_string(),
+        archive_id,
         paragraphs,
-        image_paths,
+        images,
     })
 }
 
 pub fn parse_main_docx(docx_bytes: &[u8]) -> Result<MainDocParsed> {
     let document_xml = read_docx_document_xml(docx_bytes)?;
-    let paragraphs = extract_paragraph_texts_ignore_tables_with_pagebreak(&document_xml, false)?;
+    let paragraphs = extract_body_paragraphs(&document_xml, false)?;
+    let mut texts = Vec::with_capacity(paragraphs.len());
+    let mut revisions = Vec::new();
+    for (idx, p) in paragraphs.into_iter().enumerate() {
+        if !p.revisions.is_empty() {
+            revisions.push(BlockRevisions {
+                block_id: format!("{BODY_PREFIX}{:06}", idx + 1),
+                original_text: p.original,
+                revisions: p.revisions,
+            });
+        }
+        texts.push(p.text);
+    }
+    // 附加部件解析失败不影响主文入库
+    let supplements = extract_supplement_blocks(docx_bytes).unwrap_or_default();
+    let mut parsed = parse_main_blocks(texts, supplements)?;
+    parsed.revisions = revisions;
+    Ok(parsed)
+}
+
+/// 段落 -> blocks + 字段；docx 与 .doc 主文共用
+pub fn parse_main_paragraphs(paragraphs: Vec<String>) -> Result<MainDocParsed> {
+    parse_main_blocks(paragraphs, Vec::new())
+}
+
+/// supplements 为页眉页脚等附加部件的段落，参与字段补全与索引，排在正文之后
+fn parse_main_blocks(
+    paragraphs: Vec<String>,
+    supplements: Vec<DocxBlock>,
+) -> Result<MainDocParsed> {
     let mut blocks = Vec::new();
     for (idx, text) in paragraphs.into_iter().enumerate() {
-        let block_id = format!("p:{:06}", idx + 1);
+        let block_id = format!("{BODY_PREFIX}{:06}", idx + 1);
         blocks.push(DocxBlock { block_id, text });
     }
     let (instruction_no, title, issued_at, content, field_block_map_json) =
-        extract_fields_and_map(&blocks)?;
+        extract_fields_and_map(&blocks, &supplements)?;
+    blocks.extend(supplements);
 
     Ok(MainDocParsed {
         instruction_no,
@@ -128,89 +294,103 @@ pub fn parse_main_docx(docx_bytes: &[u8]) -> Result<MainDocParsed> {
         content,
         field_block_map_json,
         blocks,
+        revisions: Vec::new(),
     })
 }
 
-fn read_docx_document_xml(docx_bytes: &[u8]) -> Result<String> {
+/// 页眉、页脚、脚注、尾注与批注的段落，各自编号；空段落与重复的页眉页脚不保留
+pub(crate) fn extract_supplement_blocks(docx_bytes: &[u8]) -> Result<Vec<DocxBlock>> {
     let cursor = Cursor::new(docx_bytes);
     let mut zip = ZipArchive::new(cursor).context("docx不是有效的zip")?;
-    let mut f = zip
-        .by_name("word/document.xml")
-        .context("docx缺少 word/document.xml")?;
-    let mut xml = String::new();
-    f.read_to_string(&mut xml)?;
-    Ok(xml)
-}
+    let names: Vec<String> = zip.file_names().map(|s| s.to_string()).collect();
 
-fn extract_docx_images_to_cache(
-    docx_bytes: &[u8],
-    library_root: &Path,
-    archive_id: &str,
-    file_id: &str,
-) -> Result<Vec<String>> {
-    let cursor = Cursor::new(docx_bytes);
-    let mut zip = ZipArchive::new(cursor).context("docx不是有效的zip")?;
-
-    let mut rels_xml = String::new();
-    if let Ok(mut f) = zip.by_name("word/_rels/document.xml.rels") {
-        f.read_to_string(&mut rels_xml)?;
-    } else {
-        return Ok(vec![]);
-    }
-
-    let rels = parse_docx_relationships(&rels_xml)?;
-    if rels.is_empty() {
-        return Ok(vec![]);
-    }
-
-    let document_xml = read_docx_document_xml(docx_bytes)?;
-    let rid_order = collect_embed_rids(&document_xml);
-    if rid_order.is_empty() {
-        return Ok(vec![]);
+    let mut out = Vec::new();
+    for (prefix, stem) in [
+        (HEADER_PREFIX, "header"),
+        (FOOTER_PREFIX, "footer"),
+        (FOOTNOTE_PREFIX, "footnotes"),
+        (ENDNOTE_PREFIX, "endnotes"),
+        (COMMENT_PREFIX, "comments"),
+    ] {
+        // header1.xml、header2.xml…按序号排，避免 header10 排在 header2 前
+        let mut parts: Vec<(u32, &String)> = names
+            .iter()
+            .filter_map(|n| {
+                let rest = n.strip_prefix("word/")?.strip_prefix(stem)?;
+                let num = rest.strip_suffix(".xml")?;
+                if num.is_empty() {
+                    Some((0, n))
+                } else {
+                    num.parse().ok().map(|v| (v, n))
+                }
+            })
+            .collect();
+        parts.sort();
+
+        let mut seen = std::collections::HashSet::<String>::new();
+        let mut idx = 0usize;
+        for (_, name) in parts {
+            let mut xml = String::new();
+            zip.by_name(name)?.read_to_string(&mut xml)?;
+            for text in extract_all_paragraph_texts(&xml)? {
+                if text.trim().is_empty() {
+                    continue;
+                }
+                // 首页/奇偶页页眉常常内容相同
+                if (prefix == HEADER_PREFIX || prefix == FOOTER_PREFIX)
+                    && !seen.insert(text.clone())
+                {
+                    continue;
+                }
+                idx += 1;
+                out.push(DocxBlock {
+                    block_id: format!("{prefix}{idx:06}"),
+                    text,
+                });
+            }
+        }
     }
+    Ok(out)
+}
 
-    let out_dir = library_root
-        .join("cache")
-        .join(archive_id)
-        .join(file_id)
-        .join("docx_media");
-    fs::create_dir_all(&out_dir)?;
-
+/// 与正文不同，页眉页脚常用表格排版，表格内段落也要取
+fn extract_all_paragraph_texts(xml: &str) -> Result<Vec<String>> {
+    let mut reader = XmlReader::from_str(xml);
+    reader.config_mut().trim_text(false);
+    let mut buf = Vec::new();
     let mut out = Vec::new();
-    let mut seen = std::collections::HashSet::<String>::new();
-
-    for (idx, rid) in rid_order.into_iter().enumerate().take(30) {
-        if !seen.insert(rid.clone()) {
-            continue;
+    let mut table_depth = 0usize;
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                if local_name(e.name().as_ref()) == b"p" {
+                    let p = read_paragraph_text(&mut reader, &mut table_depth, false)?;
+                    out.push(normalize_text_minimal(&p.text));
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(anyhow!("XML解析失败: {e:?}")),
+            _ => {}
         }
-        let Some(target) = rels.get(&rid) else {
-            continue;
-        };
-        let norm = normalize_docx_rel_target(target);
-        let internal = if norm.starts_with("word/") {
-            norm
-        } else {
-            format!("word/{norm}")
-        };
-
-        let mut f = zip
-            .by_name(&internal)
-            .with_context(|| format!("读取docx图片失败: {internal}"))?;
-        let mut buf = Vec::new();
-        f.read_to_end(&mut buf)?;
-
-        let ext = Path::new(&internal)
-            .extension()
-            .and_then(|s| s.to_str())
-            .unwrap_or("bin");
-        let abs = out_dir.join(format!("{:02}.{}", idx + 1, ext));
-        fs::write(&abs, buf)?;
-        out.push(abs.to_string_lossy().to_string());
+        buf.clear();
     }
     Ok(out)
 }
 
-fn parse_docx_relationships(rels_xml: &str) -> Result<std::collections::HashMap<String, String>> {
+pub(crate) fn read_docx_document_xml(docx_bytes: &[u8]) -> Result<String> {
+    let cursor = Cursor::new(docx_bytes);
+    let mut zip = ZipArchive::new(cursor).context("docx不是有效的zip")?;
+    let mut f = zip
+        .by_name("word/document.xml")
+        .context("docx缺少 word/document.xml")?;
+    let mut xml = String::new();
+    f.read_to_string(&mut xml)?;
+    Ok(xml)
+}
+
+pub(crate) fn parse_docx_relationships(
+    rels_xml: &str,
+) -> Result<std::collections::HashMap<String, String>> {
     let mut reader = XmlReader::from_str(rels_xml);
     reader.config_mut().trim_text(true);
     let mut buf = Vec::new();
@@ -252,15 +432,7 @@ fn parse_docx_relationships(rels_xml: &str) -> Result<std::collections::HashMap<
     Ok(out)
 }
 
-fn collect_embed_rids(document_xml: &str) -> Vec<String> {
-    // docx 图片一般通过 a:blip 的 r:embed="rIdX" 引用
-    let re = Regex::new(r#"r:embed="([^"]+)""#).expect("valid regex");
-    re.captures_iter(document_xml)
-        .filter_map(|c| c.get(1).map(|m| m.as_str().to_string()))
-        .collect()
-}
-
-fn normalize_docx_rel_target(target: &str) -> String {
+pub(crate) fn normalize_docx_rel_target(target: &str) -> String {
     // 常见 target: "media/image1.png" 或 "../media/image1.png"
     let mut t = target.replace('\\', "/");
     while t.starts_with("../") {
@@ -269,17 +441,28 @@ fn normalize_docx_rel_target(target: &str) -> String {
     t
 }
 
-fn local_name(name: &[u8]) -> &[u8] {
+pub(crate) fn local_name(name: &[u8]) -> &[u8] {
     match name.iter().rposition(|b| *b == b':') {
         Some(i) => &name[i + 1..],
         None => name,
     }
 }
 
-fn extract_paragraph_texts_ignore_tables_with_pagebreak(
+pub(crate) fn extract_paragraph_texts_ignore_tables_with_pagebreak(
     document_xml: &str,
     mark_pagebreak: bool,
 ) -> Result<Vec<String>> {
+    Ok(extract_body_paragraphs(document_xml, mark_pagebreak)?
+        .into_iter()
+        .map(|p| p.text)
+        .collect())
+}
+
+/// 正文顶层段落（不含表格），文本按最终稿；带修订的段落另附原稿
+pub(crate) fn extract_body_paragraphs(
+    document_xml: &str,
+    mark_pagebreak: bool,
+) -> Result<Vec<ParagraphText>> {
     let mut reader = XmlReader::from_str(document_xml);
     reader.config_mut().trim_text(false);
 
@@ -296,9 +479,15 @@ fn extract_paragraph_texts_ignore_tables_with_pagebreak(
                 if n == b"tbl" {
                     table_depth += 1;
                 } else if n == b"p" && table_depth == 0 {
-                    let text = read_paragraph_text(&mut reader, &mut table_depth, mark_pagebreak)?;
-                    let norm = normalize_text_minimal(&text);
-                    out.push(norm);
+                    let mut p = read_paragraph_text(&mut reader, &mut table_depth, mark_pagebreak)?;
+                    p.text = normalize_text_minimal(&p.text);
+                    p.original = normalize_text_minimal(&p.original);
+                    for r in p.revisions.iter_mut() {
+                        r.text = normalize_text_minimal(&r.text);
+                    }
+                    // 只改了段落标记等不含文字的修订不保留
+                    p.revisions.retain(|r| !r.text.is_empty());
+                    out.push(p);
                 }
             }
             Ok(Event::End(e)) => {
@@ -322,9 +511,12 @@ fn read_paragraph_text(
     reader: &mut XmlReader<&[u8]>,
     table_depth: &mut usize,
     mark_pagebreak: bool,
-) -> Result<String> {
+) -> Result<ParagraphText> {
     let mut buf = Vec::new();
-    let mut out = String::new();
+    let mut out = ParagraphText::default();
+    // 外层到内层的 w:ins / w:del 在 out.revisions 中的下标（如插入后又删除的文字会嵌套），
+    // 文字归最内层
+    let mut rev_stack: Vec<usize> = Vec::new();
     loop {
         match reader.read_event_into(&mut buf) {
             Ok(Event::Start(e)) => {
@@ -332,15 +524,23 @@ fn read_paragraph_text(
                 let n = local_name(&name);
                 if n == b"tbl" {
                     *table_depth += 1;
+                } else if let Some(kind) = revision_kind(n) {
+                    out.revisions.push(Revision {
+                        kind: kind.to_string(),
+                        author: attr_value(&e, b"author"),
+                        date: attr_value(&e, b"date"),
+                        text: String::new(),
+                    });
+                    rev_stack.push(out.revisions.len() - 1);
                 } else if n == b"t" {
                     // w:t 的文本会在 Event::Text 给出
                 } else if n == b"tab" {
-                    out.push('\t');
+                    push_revised(&mut out, rev_stack.last().copied(), "\t");
                 } else if n == b"lastRenderedPageBreak" {
                     if mark_pagebreak {
-                        out.push('\u{000C}');
+                        push_revised(&mut out, rev_stack.last().copied(), "\u{000C}");
                     } else {
-                        out.push('\n');
+                        push_revised(&mut out, rev_stack.last().copied(), "\n");
                     }
                 } else if n == b"br" || n == b"cr" {
                     if mark_pagebreak && n == b"br" {
@@ -358,12 +558,12 @@ fn read_paragraph_text(
                             }
                         }
                         if is_page {
-                            out.push('\u{000C}');
+                            push_revised(&mut out, rev_stack.last().copied(), "\u{000C}");
                         } else {
-                            out.push('\n');
+                            push_revised(&mut out, rev_stack.last().copied(), "\n");
                         }
                     } else {
-                        out.push('\n');
+                        push_revised(&mut out, rev_stack.last().copied(), "\n");
                     }
                 }
             }
@@ -373,12 +573,15 @@ fn read_paragraph_text(
                 if n == b"p" {
                     break;
                 }
+                if revision_kind(n).is_some() {
+                    rev_stack.pop();
+                }
                 if n == b"tbl" && *table_depth > 0 {
                     *table_depth -= 1;
                 }
             }
             Ok(Event::Text(t)) => {
-                out.push_str(&t.unescape()?.to_string());
+                push_revised(&mut out, rev_stack.last().copied(), &t.unescape()?);
             }
             Ok(Event::Eof) => break,
             Err(e) => return Err(anyhow!("XML解析失败: {e:?}")),
@@ -389,15 +592,117 @@ fn read_paragraph_text(
     Ok(out)
 }
 
-fn normalize_text_minimal(s: &str) -> String {
+/// w:del/w:moveFrom 只进原稿（其中的 w:delText 同理），w:ins/w:moveTo 只进最终稿
+fn push_revised(p: &mut ParagraphText, cur_rev: Option<usize>, s: &str) {
+    let Some(idx) = cur_rev else {
+        p.text.push_str(s);
+        p.original.push_str(s);
+        return;
+    };
+    let rev = &mut p.revisions[idx];
+    rev.text.push_str(s);
+    if rev.kind == "del" || rev.kind == "move_from" {
+        p.original.push_str(s);
+    } else {
+        p.text.push_str(s);
+    }
+}
+
+/// 只认作为容器出现的修订元素；w:rPr 里标记段落符的 <w:del/> 是空元素，不会走到这里
+fn revision_kind(local: &[u8]) -> Option<&'static str> {
+    match local {
+        b"ins" => Some("ins"),
+        b"del" => Some("del"),
+        b"moveTo" => Some("move_to"),
+        b"moveFrom" => Some("move_from"),
+        _ => None,
+    }
+}
+
+fn attr_value(e: &quick_xml::events::BytesStart, key: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| local_name(a.key.as_ref()) == key)
+        .and_then(|a| a.unescape_value().ok().map(|v| v.to_string()))
+}
+
+pub(crate) fn normalize_text_minimal(s: &str) -> String {
     s.replace("\r\n", "\n")
         .replace('\u{00A0}', " ")
         .replace('\u{3000}', " ")
 }
 
+/// 单次字段扫描的结果，值与所在段落一一对应
+#[derive(Debug, Default)]
+struct FieldScan {
+    instruction_no: String,
+    title: String,
+    issued_at: String,
+    content: String,
+    map_instruction_no: Option<String>,
+    map_title: Option<String>,
+    map_issued_at: Option<String>,
+    content_anchor: Option<String>,
+    content_block_ids: Vec<String>,
+}
+
 fn extract_fields_and_map(
     blocks: &[DocxBlock],
+    supplements: &[DocxBlock],
 ) -> Result<(String, String, String, String, String)> {
+    let mut f = scan_fields(blocks);
+
+    // 正文缺字段时，从页眉页脚/脚注/批注里补（正文内容不从这些部件取）
+    if !supplements.is_empty()
+        && (f.instruction_no.is_empty() || f.title.is_empty() || f.issued_at.is_empty())
+    {
+        let s = scan_fields(supplements);
+        if f.instruction_no.is_empty() {
+            f.instruction_no = s.instruction_no;
+            f.map_instruction_no = s.map_instruction_no;
+        }
+        if f.title.is_empty() {
+            f.title = s.title;
+            f.map_title = s.map_title;
+        }
+        if f.issued_at.is_empty() {
+            f.issued_at = s.issued_at;
+            f.map_issued_at = s.map_issued_at;
+        }
+    }
+    // 页眉里的文号常常不带标签，如“国办发〔2023〕12号”
+    if f.instruction_no.is_empty() {
+        let re_doc_no = Regex::new(r#"[\p{Han}]{1,12}[〔\[［（(]\d{4}[〕\]］）)]\d{1,5}号"#)
+            .expect("valid regex");
+        for b in supplements.iter().filter(|b| {
+            b.block_id.starts_with(HEADER_PREFIX) || b.block_id.starts_with(FOOTER_PREFIX)
+        }) {
+            if let Some(m) = re_doc_no.find(&b.text) {
+                f.instruction_no = m.as_str().to_string();
+                f.map_instruction_no = Some(b.block_id.clone());
+                break;
+            }
+        }
+    }
+
+    let field_block_map = json!({
+        "instruction_no": f.map_instruction_no,
+        "title": f.map_title,
+        "issued_at": f.map_issued_at,
+        "content": f.content_block_ids,
+        "content_anchor": f.content_anchor
+    });
+    let field_block_map_json = serde_json::to_string(&field_block_map)?;
+    Ok((
+        f.instruction_no,
+        f.title,
+        f.issued_at,
+        f.content,
+        field_block_map_json,
+    ))
+}
+
+fn scan_fields(blocks: &[DocxBlock]) -> FieldScan {
     // 支持两种常见格式：
     // 1) 每行/每段落以“指令标题：xxx”开头
     // 2) 同一段落内连续出现“指令编号：xxx 指令标题：yyy 下发时间：zzz 指令内容：ccc”
@@ -555,20 +860,15 @@ fn extract_fields_and_map(
         }
     }
 
-    let content = content_lines.join("\n");
-    let field_block_map = json!({
-        "instruction_no": map_instruction_no,
-        "title": map_title,
-        "issued_at": map_issued_at,
-        "content": content_block_ids,
-        "content_anchor": content_anchor
-    });
-    let field_block_map_json = serde_json::to_string(&field_block_map)?;
-    Ok((
+    FieldScan {
         instruction_no,
         title,
         issued_at,
-        content,
-        field_block_map_json,
-    ))
+        content: content_lines.join("\n"),
+        map_instruction_no,
+        map_title,
+        map_issued_at,
+        content_anchor,
+        content_block_ids,
+    }
 }
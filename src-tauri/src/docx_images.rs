@@ -0,0 +1,262 @@
+use crate::cache;
+use crate::db;
+use crate::docx_rich::{self, RichBlock, RichInline};
+use crate::importer;
+use crate::library_root::LibraryRootState;
+use anyhow::{anyhow, Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use tauri::State;
+use zip::ZipArchive;
+
+/// 旧库补建图片索引的版本标记
+const META_IMAGES_VERSION: &str = "docx_images_version";
+const IMAGES_VERSION: &str = "1";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocxImage {
+    /// 图片内容 sha256；同一档案内相同图片只缓存一份
+    pub image_id: String,
+    /// 图片所在段落（表格内为单元格段落 id）
+    pub block_id: String,
+    /// 包内路径，如 word/media/image1.png
+    pub target: String,
+    pub alt: Option<String>,
+    pub width_px: Option<i64>,
+    pub height_px: Option<i64>,
+}
+
+/// 按文档顺序列出 docx 中的图片引用（同一图片出现多次则有多条）
+pub fn scan(docx_bytes: &[u8]) -> Result<Vec<DocxImage>> {
+    Ok(scan_blocks(&docx_rich::parse_rich_docx(docx_bytes)?))
+}
+
+/// 同 scan，直接用已解析的富文本模型（导入时与目录共用一次解析）
+pub fn scan_blocks(blocks: &[RichBlock]) -> Vec<DocxImage> {
+    let mut out = Vec::new();
+    docx_rich::for_each_image(blocks, &mut |block_id, run| {
+        if let RichInline::Image {
+            target: Some(target),
+            image_id: Some(image_id),
+            alt,
+            width_px,
+            height_px,
+            ..
+        } = run
+        {
+            out.push(DocxImage {
+                image_id: image_id.clone(),
+                block_id: block_id.to_string(),
+                target: target.clone(),
+                alt: alt.clone(),
+                width_px: *width_px,
+                height_px: *height_px,
+            });
+        }
+    });
+    out
+}
+
+/// 图库展示用：按内容去重，保留首次出现
+pub fn dedup(images: Vec<DocxImage>) -> Vec<DocxImage> {
+    let mut seen = HashSet::new();
+    images
+        .into_iter()
+        .filter(|img| seen.insert(img.image_id.clone()))
+        .collect()
+}
+
+/// 重建单个档案主文的图片索引（导入/重解析后调用）；只记位置，文件在首次查看时才解出。
+/// .doc/.wps/.ofd 主文没有富文本模型，rich_blocks 为空
+pub fn index_archive(conn: &Connection, archive_id: &str, rich_blocks: &[RichBlock]) -> Result<()> {
+    conn.execute("DELETE FROM docx_images WHERE archive_id=?", [archive_id])?;
+    let images = scan_blocks(rich_blocks);
+    let mut stmt = conn.prepare(
+        "INSERT INTO docx_images(archive_id,ord,block_id,image_id,target,alt,width_px,height_px)
+         VALUES(?,?,?,?,?,?,?,?)",
+    )?;
+    for (i, img) in images.into_iter().enumerate() {
+        stmt.execute(params![
+            archive_id,
+            i as i64,
+            img.block_id,
+            img.image_id,
+            img.target,
+            img.alt,
+            img.width_px,
+            img.height_px
+        ])?;
+    }
+    Ok(())
+}
+
+pub(crate) fn backfill_pending(conn: &Connection) -> Result<bool> {
+    Ok(db::get_meta_value(conn, META_IMAGES_VERSION)?.as_deref() != Some(IMAGES_VERSION))
+}
+
+/// 旧库首次打开时为已导入档案补建图片索引（只做一次）
+pub fn ensure_backfilled(conn: &Connection, root: &Path) -> Result<()> {
+    if !backfill_pending(conn)? {
+        return Ok(());
+    }
+    let mut todo = Vec::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT m.archive_id FROM main_doc m
+             WHERE NOT EXISTS (SELECT 1 FROM docx_images i WHERE i.archive_id=m.archive_id)",
+        )?;
+        let rows = stmt.query_map([], |r| r.get::<_, String>(0))?;
+        for row in rows {
+            todo.push(row?);
+        }
+    }
+    for archive_id in todo {
+        let bytes = match importer::read_main_document(root, conn, &archive_id) {
+            Ok((_, bytes)) => bytes,
+            Err(e) => {
+                eprintln!("图片索引补建失败 {archive_id}: {e:#}");
+                continue;
+            }
+        };
+        let rich = docx_rich::parse_rich_docx(&bytes).unwrap_or_default();
+        let tx = conn.unchecked_transaction()?;
+        index_archive(&tx, &archive_id, &rich)?;
+        tx.commit()?;
+    }
+    db::set_meta_value(conn, META_IMAGES_VERSION, IMAGES_VERSION)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_docx_images(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    archive_id: String,
+) -> Result<Vec<DocxImage>, String> {
+    list_docx_images_impl(&app, &state, &archive_id).map_err(db::err_to_string)
+}
+
+fn list_docx_images_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    archive_id: &str,
+) -> Result<Vec<DocxImage>> {
+    let (_root, conn) = db::open_conn(app, state)?;
+    let mut stmt = conn.prepare(
+        "SELECT image_id,block_id,target,alt,width_px,height_px FROM docx_images
+         WHERE archive_id=? ORDER BY ord",
+    )?;
+    let rows = stmt.query_map([archive_id], |r| {
+        Ok(DocxImage {
+            image_id: r.get(0)?,
+            block_id: r.get(1)?,
+            target: r.get(2)?,
+            alt: r.get(3)?,
+            width_px: r.get(4)?,
+            height_px: r.get(5)?,
+        })
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+/// 取图片文件路径（按需解出到缓存）；file_id 为空时取主文图片，否则取该 docx 附件内的图片
+#[tauri::command]
+pub fn get_docx_image(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    archive_id: String,
+    image_id: String,
+    file_id: Option<String>,
+) -> Result<String, String> {
+    get_docx_image_impl(&app, &state, &archive_id, &image_id, file_id.as_deref())
+        .map(|p| p.to_string_lossy().to_string())
+        .map_err(db::err_to_string)
+}
+
+fn get_docx_image_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    archive_id: &str,
+    image_id: &str,
+    file_id: Option<&str>,
+) -> Result<PathBuf> {
+    // image_id 直接拼进缓存路径，先确认是 sha256 十六进制
+    if image_id.len() != 64 || !image_id.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(anyhow!("无效的图片id: {image_id}"));
+    }
+    let (root, conn) = db::open_conn(app, state)?;
+
+    let (docx_bytes, target) = match file_id {
+        None => {
+            let target: String = conn
+                .query_row(
+                    "SELECT target FROM docx_images WHERE archive_id=? AND image_id=? LIMIT 1",
+                    params![archive_id, image_id],
+                    |r| r.get(0),
+                )
+                .optional()?
+                .ok_or_else(|| anyhow!("主文中找不到图片: {image_id}"))?;
+            let abs = cache_path(&root, archive_id, image_id, &target);
+            if abs.exists() {
+                return Ok(abs);
+            }
+            let (_, bytes) = importer::read_main_document(&root, &conn, archive_id)?;
+            (bytes, target)
+        }
+        Some(file_id) => {
+            let owner: String = conn
+                .query_row(
+                    "SELECT archive_id FROM attachments WHERE file_id=?",
+                    [file_id],
+                    |r| r.get(0),
+                )
+                .with_context(|| format!("找不到附件: {file_id}"))?;
+            if owner != archive_id {
+                return Err(anyhow!("附件不属于该档案: {file_id}"));
+            }
+            let path = cache::ensure_cached_file(&root, &conn, file_id)?;
+            let bytes = fs::read(&path)?;
+            let target = scan(&bytes)?
+                .into_iter()
+                .find(|img| img.image_id == image_id)
+                .map(|img| img.target)
+                .ok_or_else(|| anyhow!("附件中找不到图片: {image_id}"))?;
+            (bytes, target)
+        }
+    };
+
+    let abs = cache_path(&root, archive_id, image_id, &target);
+    if abs.exists() {
+        return Ok(abs);
+    }
+    let mut zip = ZipArchive::new(Cursor::new(docx_bytes)).context("docx不是有效的zip")?;
+    let mut f = zip
+        .by_name(&target)
+        .with_context(|| format!("读取docx图片失败: {target}"))?;
+    let mut buf = Vec::new();
+    f.read_to_end(&mut buf)?;
+    fs::create_dir_all(abs.parent().unwrap())?;
+    fs::write(&abs, buf)?;
+    Ok(abs)
+}
+
+/// cache/<archive_id>/docx_images/<sha256>.<ext>，主文与附件共用
+fn cache_path(root: &Path, archive_id: &str, image_id: &str, target: &str) -> PathBuf {
+    let ext = Path::new(target)
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("bin")
+        .to_ascii_lowercase();
+    root.join("cache")
+        .join(archive_id)
+        .join("docx_images")
+        .join(format!("{image_id}.{ext}"))
+}
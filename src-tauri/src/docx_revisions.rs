@@ -0,0 +1,122 @@
+use crate::db;
+use crate::docx::{BlockRevisions, Revision};
+use crate::library_root::LibraryRootState;
+use anyhow::{anyhow, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockVersions {
+    pub block_id: String,
+    /// 接受全部修订（与索引、docx_blocks 一致）
+    pub final_text: String,
+    /// 拒绝全部修订；无修订时与 final_text 相同
+    pub original_text: String,
+    pub revisions: Vec<Revision>,
+}
+
+/// 重建单个档案的修订记录（导入/重解析后调用）
+pub fn index_archive(
+    conn: &Connection,
+    archive_id: &str,
+    revisions: &[BlockRevisions],
+) -> Result<()> {
+    conn.execute(
+        "DELETE FROM docx_revisions WHERE archive_id=?",
+        [archive_id],
+    )?;
+    let mut stmt = conn.prepare(
+        "INSERT INTO docx_revisions(archive_id,block_id,original_text,revisions_json) VALUES(?,?,?,?)",
+    )?;
+    for b in revisions {
+        stmt.execute(params![
+            archive_id,
+            b.block_id,
+            b.original_text,
+            serde_json::to_string(&b.revisions)?
+        ])?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_docx_revisions(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    archive_id: String,
+) -> Result<Vec<BlockRevisions>, String> {
+    list_docx_revisions_impl(&app, &state, &archive_id).map_err(db::err_to_string)
+}
+
+fn list_docx_revisions_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    archive_id: &str,
+) -> Result<Vec<BlockRevisions>> {
+    let (_root, conn) = db::open_conn(app, state)?;
+    let mut stmt = conn.prepare(
+        "SELECT block_id, original_text, revisions_json FROM docx_revisions WHERE archive_id=? ORDER BY block_id",
+    )?;
+    let rows = stmt.query_map([archive_id], |r| {
+        Ok((
+            r.get::<_, String>(0)?,
+            r.get::<_, String>(1)?,
+            r.get::<_, String>(2)?,
+        ))
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        let (block_id, original_text, revisions_json) = row?;
+        out.push(BlockRevisions {
+            block_id,
+            original_text,
+            revisions: serde_json::from_str(&revisions_json).unwrap_or_default(),
+        });
+    }
+    Ok(out)
+}
+
+#[tauri::command]
+pub fn get_docx_block_versions(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    archive_id: String,
+    block_id: String,
+) -> Result<BlockVersions, String> {
+    get_docx_block_versions_impl(&app, &state, &archive_id, &block_id).map_err(db::err_to_string)
+}
+
+fn get_docx_block_versions_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    archive_id: &str,
+    block_id: &str,
+) -> Result<BlockVersions> {
+    let (_root, conn) = db::open_conn(app, state)?;
+    let final_text: String = conn
+        .query_row(
+            "SELECT text FROM docx_blocks WHERE archive_id=? AND block_id=?",
+            [archive_id, block_id],
+            |r| r.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| anyhow!("找不到段落: {block_id}"))?;
+    let revised: Option<(String, String)> = conn
+        .query_row(
+            "SELECT original_text, revisions_json FROM docx_revisions WHERE archive_id=? AND block_id=?",
+            [archive_id, block_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()?;
+    let (original_text, revisions) = match revised {
+        Some((original, json)) => (original, serde_json::from_str(&json).unwrap_or_default()),
+        None => (final_text.clone(), Vec::new()),
+    };
+    Ok(BlockVersions {
+        block_id: block_id.to_string(),
+        final_text,
+        original_text,
+        revisions,
+    })
+}
@@ -0,0 +1,996 @@
+use crate::db;
+use crate::docx::{self, local_name, normalize_text_minimal};
+use crate::importer;
+use crate::library_root::{resolve_library_root, LibraryRootState};
+use anyhow::{anyhow, Context, Result};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader as XmlReader;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
+use tauri::State;
+use zip::ZipArchive;
+
+/// 缓存格式版本，模型字段或解析规则变化时递增，旧缓存自动作废
+const RICH_CACHE_VERSION: u32 = 4;
+/// 1 EMU = 1/914400 英寸，按 96dpi 换算成像素
+const EMU_PER_PX: i64 = 9525;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RichBlock {
+    Paragraph(RichParagraph),
+    Table(RichTable),
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RichParagraph {
+    /// 正文顶层段落与 docx_blocks 的 block_id 一致；表格内段落带表格前缀
+    pub block_id: String,
+    pub style_id: Option<String>,
+    pub style_name: Option<String>,
+    /// 1~9；来自 outlineLvl 或“标题 N / heading N”样式
+    pub heading_level: Option<u8>,
+    /// 已计算好的编号文本，如 “一、” “1.2” “•”
+    pub numbering: Option<String>,
+    pub num_level: Option<u8>,
+    /// left / center / right / justify
+    pub align: Option<String>,
+    /// 缩进，单位 twip（1/20 磅）；悬挂缩进为负的首行缩进
+    pub indent_left: Option<i64>,
+    pub indent_first_line: Option<i64>,
+    pub runs: Vec<RichInline>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RichInline {
+    Text {
+        text: String,
+        bold: bool,
+        italic: bool,
+        underline: bool,
+        strike: bool,
+        /// 十六进制 RGB，如 "FF0000"；auto 视为未设置
+        color: Option<String>,
+        highlight: Option<String>,
+    },
+    /// 内嵌图片占位，target 为包内路径（word/media/...）；
+    /// image_id 为图片内容 sha256，前端用它向 get_docx_image 按需取文件
+    Image {
+        rid: String,
+        target: Option<String>,
+        image_id: Option<String>,
+        alt: Option<String>,
+        width_px: Option<i64>,
+        height_px: Option<i64>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RichTable {
+    pub block_id: String,
+    pub rows: Vec<Vec<RichCell>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RichCell {
+    pub col_span: u32,
+    /// restart：纵向合并起点；continue：被上方单元格合并
+    pub v_merge: Option<String>,
+    pub blocks: Vec<RichBlock>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RichCache {
+    version: u32,
+    blocks: Vec<RichBlock>,
+}
+
+#[tauri::command]
+pub fn get_docx_rich_blocks(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    archive_id: String,
+) -> Result<Vec<RichBlock>, String> {
+    get_docx_rich_blocks_impl(&app, &state, &archive_id).map_err(db::err_to_string)
+}
+
+fn get_docx_rich_blocks_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    archive_id: &str,
+) -> Result<Vec<RichBlock>> {
+    let root = resolve_library_root(app, state)?;
+    db::init_db(app, &root)?;
+    let conn = db::open_conn_at(&root)?;
+
+    let cache_path = cache_file(&root, archive_id);
+    if let Ok(bytes) = fs::read(&cache_path) {
+        if let Ok(c) = serde_json::from_slice::<RichCache>(&bytes) {
+            if c.version == RICH_CACHE_VERSION {
+                return Ok(c.blocks);
+            }
+        }
+    }
+
+    let (_, bytes) = importer::read_main_document(&root, &conn, archive_id)?;
+    let blocks = match parse_rich_docx(&bytes) {
+        Ok(b) => b,
+        // .doc/.wps/.ofd 主文没有格式信息，按已入库的纯文本段落降级
+        Err(_) => plain_blocks(&conn, archive_id)?,
+    };
+
+    let cache = RichCache {
+        version: RICH_CACHE_VERSION,
+        blocks,
+    };
+    if let Some(parent) = cache_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&cache_path, serde_json::to_vec(&cache)?)
+        .with_context(|| format!("写入富文本缓存失败: {}", cache_path.display()))?;
+    Ok(cache.blocks)
+}
+
+/// 主文重新解析后调用，下次请求时重建
+pub(crate) fn invalidate(root: &Path, archive_id: &str) {
+    let _ = fs::remove_file(cache_file(root, archive_id));
+}
+
+fn cache_file(root: &Path, archive_id: &str) -> PathBuf {
+    root.join("cache").join(archive_id).join("docx_rich.json")
+}
+
+fn plain_blocks(conn: &Connection, archive_id: &str) -> Result<Vec<RichBlock>> {
+    let mut stmt = conn.prepare(
+        "SELECT block_id,text FROM docx_blocks WHERE archive_id=? AND block_id LIKE 'p:%' ORDER BY block_id",
+    )?;
+    let rows = stmt.query_map([archive_id], |r| {
+        Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?))
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        let (block_id, text) = row?;
+        out.push(RichBlock::Paragraph(RichParagraph {
+            block_id,
+            runs: vec![plain_run(text)],
+            ..Default::default()
+        }));
+    }
+    Ok(out)
+}
+
+fn plain_run(text: String) -> RichInline {
+    RichInline::Text {
+        text,
+        bold: false,
+        italic: false,
+        underline: false,
+        strike: false,
+        color: None,
+        highlight: None,
+    }
+}
+
+pub fn parse_rich_docx(docx_bytes: &[u8]) -> Result<Vec<RichBlock>> {
+    let document_xml = docx::read_docx_document_xml(docx_bytes)?;
+    let mut zip = ZipArchive::new(Cursor::new(docx_bytes)).context("docx不是有效的zip")?;
+    let styles = read_part(&mut zip, "word/styles.xml")
+        .map(|x| parse_styles(&x))
+        .transpose()?
+        .unwrap_or_default();
+    let numbering = read_part(&mut zip, "word/numbering.xml")
+        .map(|x| parse_numbering(&x))
+        .transpose()?
+        .unwrap_or_default();
+    let rels = read_part(&mut zip, "word/_rels/document.xml.rels")
+        .map(|x| docx::parse_docx_relationships(&x))
+        .transpose()?
+        .unwrap_or_default();
+
+    let mut ctx = Ctx {
+        styles,
+        numbering,
+        rels,
+        counters: HashMap::new(),
+    };
+    let mut reader = XmlReader::from_str(&document_xml);
+    reader.config_mut().trim_text(false);
+    let mut blocks = parse_body(&mut reader, &mut ctx)?;
+    fill_image_ids(&mut blocks, &mut zip);
+    Ok(blocks)
+}
+
+/// 按图片内容算 image_id，同一图片多处引用（不同 rId）也得到同一个 id
+fn fill_image_ids<R: Read + Seek>(blocks: &mut [RichBlock], zip: &mut ZipArchive<R>) {
+    let mut by_target: HashMap<String, Option<String>> = HashMap::new();
+    for_each_image_mut(blocks, &mut |target, image_id| {
+        let Some(target) = target else {
+            return;
+        };
+        *image_id = by_target
+            .entry(target.to_string())
+            .or_insert_with(|| {
+                let mut f = zip.by_name(target).ok()?;
+                let mut buf = Vec::new();
+                f.read_to_end(&mut buf).ok()?;
+                Some(format!("{:x}", Sha256::digest(&buf)))
+            })
+            .clone();
+    });
+}
+
+fn for_each_image_mut(
+    blocks: &mut [RichBlock],
+    f: &mut dyn FnMut(Option<&str>, &mut Option<String>),
+) {
+    for b in blocks {
+        match b {
+            RichBlock::Paragraph(p) => {
+                for run in p.runs.iter_mut() {
+                    if let RichInline::Image {
+                        target, image_id, ..
+                    } = run
+                    {
+                        f(target.as_deref(), image_id);
+                    }
+                }
+            }
+            RichBlock::Table(t) => {
+                for cell in t.rows.iter_mut().flatten() {
+                    for_each_image_mut(&mut cell.blocks, f);
+                }
+            }
+        }
+    }
+}
+
+/// 按文档顺序遍历所有图片：(所在段落 block_id, 图片)
+pub(crate) fn for_each_image<'a>(
+    blocks: &'a [RichBlock],
+    f: &mut dyn FnMut(&'a str, &'a RichInline),
+) {
+    for b in blocks {
+        match b {
+            RichBlock::Paragraph(p) => {
+                for run in &p.runs {
+                    if matches!(run, RichInline::Image { .. }) {
+                        f(&p.block_id, run);
+                    }
+                }
+            }
+            RichBlock::Table(t) => {
+                for cell in t.rows.iter().flatten() {
+                    for_each_image(&cell.blocks, f);
+                }
+            }
+        }
+    }
+}
+
+fn read_part<R: Read + Seek>(zip: &mut ZipArchive<R>, name: &str) -> Option<String> {
+    let mut f = zip.by_name(name).ok()?;
+    let mut s = String::new();
+    f.read_to_string(&mut s).ok()?;
+    Some(s)
+}
+
+struct Ctx {
+    styles: HashMap<String, StyleDef>,
+    numbering: Numbering,
+    rels: HashMap<String, String>,
+    /// abstractNumId -> 各级当前计数
+    counters: HashMap<String, [Option<u32>; 9]>,
+}
+
+/// 正文顶层：段落编号规则与 docx::extract_paragraph_texts_ignore_tables_with_pagebreak 一致，
+/// 只数表格外的 <w:p>，这样 block_id 与 docx_blocks、批注高亮对得上；
+/// 段落里文本框内的表格同样计入表格层级（见 skip_open_tables）
+fn parse_body(reader: &mut XmlReader<&[u8]>, ctx: &mut Ctx) -> Result<Vec<RichBlock>> {
+    let mut buf = Vec::new();
+    let mut out = Vec::new();
+    let mut p_idx = 0usize;
+    let mut t_idx = 0usize;
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                let name = e.name().as_ref().to_vec();
+                let n = local_name(&name);
+                if n == b"tbl" {
+                    t_idx += 1;
+                    let block_id = format!("t:{:06}", t_idx);
+                    out.push(RichBlock::Table(parse_table(reader, ctx, block_id)?));
+                } else if n == b"p" {
+                    p_idx += 1;
+                    let block_id = format!("p:{:06}", p_idx);
+                    let mut table_depth = 0usize;
+                    out.push(RichBlock::Paragraph(parse_paragraph(
+                        reader,
+                        ctx,
+                        block_id,
+                        &mut table_depth,
+                    )?));
+                    skip_open_tables(reader, &mut table_depth)?;
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(anyhow!("XML解析失败: {e:?}")),
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(out)
+}
+
+fn parse_table(
+    reader: &mut XmlReader<&[u8]>,
+    ctx: &mut Ctx,
+    block_id: String,
+) -> Result<RichTable> {
+    let mut buf = Vec::new();
+    let mut rows: Vec<Vec<RichCell>> = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                let name = e.name().as_ref().to_vec();
+                let n = local_name(&name);
+                if n == b"tr" {
+                    rows.push(Vec::new());
+                } else if n == b"tc" {
+                    let r = rows.len().max(1);
+                    let c = rows.last().map(|x| x.len()).unwrap_or(0) + 1;
+                    let prefix = format!("{block_id}:r{r}c{c}");
+                    let cell = parse_cell(reader, ctx, &prefix)?;
+                    match rows.last_mut() {
+                        Some(row) => row.push(cell),
+                        None => rows.push(vec![cell]),
+                    }
+                }
+            }
+            Ok(Event::End(e)) => {
+                if local_name(e.name().as_ref()) == b"tbl" {
+                    break;
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(anyhow!("XML解析失败: {e:?}")),
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(RichTable { block_id, rows })
+}
+
+fn parse_cell(reader: &mut XmlReader<&[u8]>, ctx: &mut Ctx, prefix: &str) -> Result<RichCell> {
+    let mut buf = Vec::new();
+    let mut cell = RichCell {
+        col_span: 1,
+        v_merge: None,
+        blocks: Vec::new(),
+    };
+    let mut p_idx = 0usize;
+    let mut t_idx = 0usize;
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                let name = e.name().as_ref().to_vec();
+                let n = local_name(&name);
+                if n == b"p" {
+                    p_idx += 1;
+                    let id = format!("{prefix}:p{p_idx}");
+                    let mut table_depth = 0usize;
+                    cell.blocks.push(RichBlock::Paragraph(parse_paragraph(
+                        reader,
+                        ctx,
+                        id,
+                        &mut table_depth,
+                    )?));
+                    skip_open_tables(reader, &mut table_depth)?;
+                } else if n == b"tbl" {
+                    t_idx += 1;
+                    let id = format!("{prefix}:t{t_idx}");
+                    cell.blocks
+                        .push(RichBlock::Table(parse_table(reader, ctx, id)?));
+                } else {
+                    cell_prop(&mut cell, &e);
+                }
+            }
+            Ok(Event::Empty(e)) => cell_prop(&mut cell, &e),
+            Ok(Event::End(e)) => {
+                if local_name(e.name().as_ref()) == b"tc" {
+                    break;
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(anyhow!("XML解析失败: {e:?}")),
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(cell)
+}
+
+/// 段落在文本框内的表格里就结束时（任意 </w:p> 即结束），纯文本解析会把表格余下部分当作表格内容跳过，
+/// 这里同样跳到这些表格结束，避免其中的段落被当成正文段落
+fn skip_open_tables(reader: &mut XmlReader<&[u8]>, table_depth: &mut usize) -> Result<()> {
+    let mut buf = Vec::new();
+    while *table_depth > 0 {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) if local_name(e.name().as_ref()) == b"tbl" => *table_depth += 1,
+            Ok(Event::End(e)) if local_name(e.name().as_ref()) == b"tbl" => *table_depth -= 1,
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(anyhow!("XML解析失败: {e:?}")),
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(())
+}
+
+fn cell_prop(cell: &mut RichCell, e: &BytesStart) {
+    let name = e.name().as_ref().to_vec();
+    let n = local_name(&name);
+    if n == b"gridSpan" {
+        if let Some(v) = attr(e, b"val").and_then(|v| v.parse().ok()) {
+            cell.col_span = v;
+        }
+    } else if n == b"vMerge" {
+        // 省略 val 表示延续上方的合并
+        cell.v_merge = Some(attr(e, b"val").unwrap_or_else(|| "continue".to_string()));
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct RunProps {
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    strike: bool,
+    color: Option<String>,
+    highlight: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct PendingImage {
+    alt: Option<String>,
+    width_px: Option<i64>,
+    height_px: Option<i64>,
+}
+
+/// table_depth 记录段落内（文本框里）打开但尚未结束的表格层数，规则同 docx::read_paragraph_text
+fn parse_paragraph(
+    reader: &mut XmlReader<&[u8]>,
+    ctx: &mut Ctx,
+    block_id: String,
+    table_depth: &mut usize,
+) -> Result<RichParagraph> {
+    let mut buf = Vec::new();
+    let mut p = RichParagraph {
+        block_id,
+        ..Default::default()
+    };
+    let mut outline: Option<u8> = None;
+    let mut num: (Option<String>, Option<u8>) = (None, None);
+
+    let mut in_ppr = false;
+    let mut in_rpr = false;
+    let mut in_t = false;
+    // w:del/w:moveFrom 内的内容只属于原稿，与 docx 纯文本的最终稿保持一致
+    let mut removed = 0usize;
+    let mut props = RunProps::default();
+    let mut image = PendingImage::default();
+
+    loop {
+        buf.clear();
+        let (e, is_start) = match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => (e, true),
+            Ok(Event::Empty(e)) => (e, false),
+            Ok(Event::End(e)) => {
+                let name = e.name().as_ref().to_vec();
+                match local_name(&name) {
+                    // 与 docx 纯文本分段保持一致：遇到任意 </w:p> 即结束（文本框内段落也算）
+                    b"p" => break,
+                    b"pPr" => in_ppr = false,
+                    b"rPr" => in_rpr = false,
+                    b"t" => in_t = false,
+                    b"tbl" => *table_depth = table_depth.saturating_sub(1),
+                    b"del" | b"moveFrom" => removed = removed.saturating_sub(1),
+                    _ => {}
+                }
+                continue;
+            }
+            Ok(Event::Text(t)) => {
+                if in_t && removed == 0 {
+                    push_text(&mut p.runs, &props, &t.unescape()?);
+                }
+                continue;
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(anyhow!("XML解析失败: {e:?}")),
+            _ => continue,
+        };
+        let name = e.name().as_ref().to_vec();
+        let n = local_name(&name);
+
+        if in_ppr {
+            match n {
+                b"pStyle" => p.style_id = attr(&e, b"val"),
+                b"numId" => num.0 = attr(&e, b"val"),
+                b"ilvl" => num.1 = attr(&e, b"val").and_then(|v| v.parse().ok()),
+                b"jc" => p.align = attr(&e, b"val").map(|v| normalize_align(&v)),
+                b"outlineLvl" => outline = attr(&e, b"val").and_then(|v| v.parse().ok()),
+                b"ind" => {
+                    p.indent_left = attr(&e, b"left")
+                        .or_else(|| attr(&e, b"start"))
+                        .and_then(|v| v.parse().ok());
+                    p.indent_first_line = match attr(&e, b"hanging") {
+                        Some(h) => h.parse::<i64>().ok().map(|v| -v),
+                        None => attr(&e, b"firstLine").and_then(|v| v.parse().ok()),
+                    };
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        match n {
+            b"pPr" if is_start => in_ppr = true,
+            b"tbl" if is_start => *table_depth += 1,
+            // w:rPr 里标记段落符的 <w:del/> 是空元素，不是容器
+            b"del" | b"moveFrom" if is_start => removed += 1,
+            b"r" if is_start => props = RunProps::default(),
+            b"rPr" if is_start => in_rpr = true,
+            b"t" if is_start => in_t = true,
+            b"b" if in_rpr => props.bold = toggle_on(&e),
+            b"i" if in_rpr => props.italic = toggle_on(&e),
+            b"strike" | b"dstrike" if in_rpr => props.strike = toggle_on(&e),
+            b"u" if in_rpr => {
+                props.underline = attr(&e, b"val").map(|v| v != "none").unwrap_or(true)
+            }
+            b"color" if in_rpr => {
+                props.color = attr(&e, b"val").filter(|v| !v.eq_ignore_ascii_case("auto"))
+            }
+            b"highlight" if in_rpr => props.highlight = attr(&e, b"val").filter(|v| v != "none"),
+            b"tab" if removed == 0 => push_text(&mut p.runs, &props, "\t"),
+            b"br" | b"cr" if removed == 0 => push_text(&mut p.runs, &props, "\n"),
+            b"docPr" => {
+                image.alt = attr(&e, b"descr")
+                    .filter(|v| !v.is_empty())
+                    .or_else(|| attr(&e, b"name"));
+            }
+            b"extent" => {
+                image.width_px = attr(&e, b"cx")
+                    .and_then(|v| v.parse::<i64>().ok())
+                    .map(|v| v / EMU_PER_PX);
+                image.height_px = attr(&e, b"cy")
+                    .and_then(|v| v.parse::<i64>().ok())
+                    .map(|v| v / EMU_PER_PX);
+            }
+            // DrawingML 用 a:blip r:embed，旧版 VML 用 v:imagedata r:id
+            b"blip" | b"imagedata" if removed == 0 => {
+                let rid = attr(&e, b"embed").or_else(|| attr(&e, b"id"));
+                if let Some(rid) = rid {
+                    let target = ctx
+                        .rels
+                        .get(&rid)
+                        .map(|t| format!("word/{}", docx::normalize_docx_rel_target(t)));
+                    let img = std::mem::take(&mut image);
+                    p.runs.push(RichInline::Image {
+                        rid,
+                        target,
+                        image_id: None,
+                        alt: img.alt,
+                        width_px: img.width_px,
+                        height_px: img.height_px,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    resolve_paragraph_style(&mut p, ctx, outline, num);
+    Ok(p)
+}
+
+/// 合并相邻同格式文本，减少前端渲染的 span 数量
+fn push_text(runs: &mut Vec<RichInline>, props: &RunProps, s: &str) {
+    let s = normalize_text_minimal(s);
+    if let Some(RichInline::Text {
+        text,
+        bold,
+        italic,
+        underline,
+        strike,
+        color,
+        highlight,
+    }) = runs.last_mut()
+    {
+        let same = *bold == props.bold
+            && *italic == props.italic
+            && *underline == props.underline
+            && *strike == props.strike
+            && *color == props.color
+            && *highlight == props.highlight;
+        if same {
+            text.push_str(&s);
+            return;
+        }
+    }
+    runs.push(RichInline::Text {
+        text: s,
+        bold: props.bold,
+        italic: props.italic,
+        underline: props.underline,
+        strike: props.strike,
+        color: props.color.clone(),
+        highlight: props.highlight.clone(),
+    });
+}
+
+/// 直接格式优先，其次沿 basedOn 链查样式；最后按样式名识别标题
+fn resolve_paragraph_style(
+    p: &mut RichParagraph,
+    ctx: &mut Ctx,
+    mut outline: Option<u8>,
+    (mut num_id, mut ilvl): (Option<String>, Option<u8>),
+) {
+    let mut style_id = p.style_id.clone();
+    let mut guard = 0;
+    while let Some(id) = style_id {
+        let Some(st) = ctx.styles.get(&id) else {
+            break;
+        };
+        if guard == 0 {
+            p.style_name = st.name.clone();
+        }
+        outline = outline.or(st.outline);
+        if num_id.is_none() {
+            num_id = st.num_id.clone();
+        }
+        ilvl = ilvl.or(st.ilvl);
+        if p.align.is_none() {
+            p.align = st.align.clone();
+        }
+        style_id = st.based_on.clone();
+        guard += 1;
+        if guard > 16 {
+            break;
+        }
+    }
+
+    // outlineLvl 9 表示正文级别
+    p.heading_level = outline
+        .filter(|l| *l < 9)
+        .map(|l| l + 1)
+        .or_else(|| p.style_name.as_deref().and_then(heading_from_style_name));
+
+    if let Some(id) = num_id.filter(|id| id != "0") {
+        let lvl = ilvl.unwrap_or(0).min(8);
+        p.numbering = ctx.next_label(&id, lvl);
+        if p.numbering.is_some() {
+            p.num_level = Some(lvl);
+        }
+    }
+}
+
+fn heading_from_style_name(name: &str) -> Option<u8> {
+    let lower = name.to_ascii_lowercase();
+    let rest = lower
+        .strip_prefix("heading")
+        .or_else(|| lower.strip_prefix("标题"))?;
+    let n: u8 = rest.trim().parse().ok()?;
+    (1..=9).contains(&n).then_some(n)
+}
+
+fn normalize_align(v: &str) -> String {
+    match v {
+        "both" | "distribute" => "justify",
+        "start" => "left",
+        "end" => "right",
+        other => other,
+    }
+    .to_string()
+}
+
+/// <w:b/> 表示开启；w:val="0"/"false" 表示显式关闭
+fn toggle_on(e: &BytesStart) -> bool {
+    !matches!(
+        attr(e, b"val").as_deref(),
+        Some("0") | Some("false") | Some("off")
+    )
+}
+
+fn attr(e: &BytesStart, key: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| local_name(a.key.as_ref()) == key)
+        .and_then(|a| a.unescape_value().ok().map(|v| v.to_string()))
+}
+
+#[derive(Debug, Clone, Default)]
+struct StyleDef {
+    name: Option<String>,
+    based_on: Option<String>,
+    outline: Option<u8>,
+    num_id: Option<String>,
+    ilvl: Option<u8>,
+    align: Option<String>,
+}
+
+fn parse_styles(xml: &str) -> Result<HashMap<String, StyleDef>> {
+    let mut reader = XmlReader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+    let mut out = HashMap::new();
+    let mut cur: Option<(String, StyleDef)> = None;
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+                let name = e.name().as_ref().to_vec();
+                let n = local_name(&name);
+                if n == b"style" {
+                    // 只关心段落样式
+                    let is_para = attr(&e, b"type").map(|t| t == "paragraph").unwrap_or(false);
+                    cur = match (is_para, attr(&e, b"styleId")) {
+                        (true, Some(id)) => Some((id, StyleDef::default())),
+                        _ => None,
+                    };
+                } else if let Some((_, st)) = cur.as_mut() {
+                    match n {
+                        b"name" => st.name = attr(&e, b"val"),
+                        b"basedOn" => st.based_on = attr(&e, b"val"),
+                        b"outlineLvl" => st.outline = attr(&e, b"val").and_then(|v| v.parse().ok()),
+                        b"numId" => st.num_id = attr(&e, b"val"),
+                        b"ilvl" => st.ilvl = attr(&e, b"val").and_then(|v| v.parse().ok()),
+                        b"jc" => st.align = attr(&e, b"val").map(|v| normalize_align(&v)),
+                        _ => {}
+                    }
+                }
+            }
+            Ok(Event::End(e)) => {
+                if local_name(e.name().as_ref()) == b"style" {
+                    if let Some((id, st)) = cur.take() {
+                        out.insert(id, st);
+                    }
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(anyhow!("styles XML解析失败: {e:?}")),
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(out)
+}
+
+#[derive(Debug, Clone, Default)]
+struct LevelDef {
+    start: u32,
+    fmt: String,
+    text: String,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Numbering {
+    /// abstractNumId -> 各级定义
+    abstracts: HashMap<String, HashMap<u8, LevelDef>>,
+    /// numId -> abstractNumId
+    nums: HashMap<String, String>,
+}
+
+fn parse_numbering(xml: &str) -> Result<Numbering> {
+    let mut reader = XmlReader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+    let mut out = Numbering::default();
+    let mut cur_abstract: Option<String> = None;
+    let mut cur_level: Option<(u8, LevelDef)> = None;
+    let mut cur_num: Option<String> = None;
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+                let name = e.name().as_ref().to_vec();
+                match local_name(&name) {
+                    b"abstractNum" => cur_abstract = attr(&e, b"abstractNumId"),
+                    b"lvl" if cur_abstract.is_some() => {
+                        let ilvl = attr(&e, b"ilvl").and_then(|v| v.parse().ok()).unwrap_or(0);
+                        cur_level = Some((
+                            ilvl,
+                            LevelDef {
+                                start: 1,
+                                fmt: "decimal".to_string(),
+                                text: String::new(),
+                            },
+                        ));
+                    }
+                    b"start" => {
+                        if let Some((_, lv)) = cur_level.as_mut() {
+                            lv.start = attr(&e, b"val").and_then(|v| v.parse().ok()).unwrap_or(1);
+                        }
+                    }
+                    b"numFmt" => {
+                        if let (Some((_, lv)), Some(v)) = (cur_level.as_mut(), attr(&e, b"val")) {
+                            lv.fmt = v;
+                        }
+                    }
+                    b"lvlText" => {
+                        if let (Some((_, lv)), Some(v)) = (cur_level.as_mut(), attr(&e, b"val")) {
+                            lv.text = v;
+                        }
+                    }
+                    b"num" => cur_num = attr(&e, b"numId"),
+                    b"abstractNumId" => {
+                        if let (Some(num), Some(v)) = (cur_num.as_ref(), attr(&e, b"val")) {
+                            out.nums.insert(num.clone(), v);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::End(e)) => match local_name(e.name().as_ref()) {
+                b"lvl" => {
+                    if let (Some(abs), Some((ilvl, lv))) = (cur_abstract.as_ref(), cur_level.take())
+                    {
+                        out.abstracts
+                            .entry(abs.clone())
+                            .or_default()
+                            .insert(ilvl, lv);
+                    }
+                }
+                b"abstractNum" => cur_abstract = None,
+                b"num" => cur_num = None,
+                _ => {}
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(anyhow!("numbering XML解析失败: {e:?}")),
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(out)
+}
+
+impl Ctx {
+    /// 推进该级计数并重置更深的级别，返回按 lvlText 拼好的编号
+    fn next_label(&mut self, num_id: &str, ilvl: u8) -> Option<String> {
+        let abs_id = self.numbering.nums.get(num_id)?;
+        let levels = self.numbering.abstracts.get(abs_id)?;
+        let def = levels.get(&ilvl)?;
+        if def.fmt == "none" {
+            return None;
+        }
+        let counters = self.counters.entry(abs_id.clone()).or_insert([None; 9]);
+        let i = ilvl as usize;
+        counters[i] = Some(counters[i].map(|c| c + 1).unwrap_or(def.start));
+        for c in counters.iter_mut().skip(i + 1) {
+            *c = None;
+        }
+        if def.fmt == "bullet" {
+            return Some(bullet_char(&def.text));
+        }
+
+        let mut label = def.text.clone();
+        for (k, lv) in levels.iter() {
+            let placeholder = format!("%{}", *k as usize + 1);
+            if !label.contains(&placeholder) {
+                continue;
+            }
+            let value = counters[*k as usize].unwrap_or(lv.start);
+            label = label.replace(&placeholder, &format_number(value, &lv.fmt));
+        }
+        Some(label)
+    }
+}
+
+/// Symbol/Wingdings 字体的私用区字符前端无法显示，统一换成实心圆点
+fn bullet_char(text: &str) -> String {
+    match text.chars().next() {
+        Some(c) if !('\u{E000}'..='\u{F8FF}').contains(&c) => c.to_string(),
+        _ => "•".to_string(),
+    }
+}
+
+fn format_number(n: u32, fmt: &str) -> String {
+    match fmt {
+        "decimalZero" => format!("{n:02}"),
+        "lowerLetter" => letters(n),
+        "upperLetter" => letters(n).to_uppercase(),
+        "lowerRoman" => roman(n).to_lowercase(),
+        "upperRoman" => roman(n),
+        "chineseCounting"
+        | "chineseCountingThousand"
+        | "chineseLegalSimplified"
+        | "ideographDigital"
+        | "japaneseCounting" => chinese_number(n),
+        "ideographTraditional" => {
+            const STEMS: [&str; 10] = ["甲", "乙", "丙", "丁", "戊", "己", "庚", "辛", "壬", "癸"];
+            STEMS[((n.max(1) - 1) % 10) as usize].to_string()
+        }
+        "decimalEnclosedCircle" | "decimalEnclosedCircleChinese" if (1..=20).contains(&n) => {
+            char::from_u32(0x2460 + n - 1)
+                .map(String::from)
+                .unwrap_or_default()
+        }
+        _ => n.to_string(),
+    }
+}
+
+/// 1 -> a, 26 -> z, 27 -> aa（Word 的重复字母写法）
+fn letters(n: u32) -> String {
+    let n = n.max(1) - 1;
+    let c = (b'a' + (n % 26) as u8) as char;
+    c.to_string().repeat((n / 26 + 1) as usize)
+}
+
+fn roman(mut n: u32) -> String {
+    const TABLE: [(u32, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut out = String::new();
+    for (v, s) in TABLE {
+        while n >= v {
+            out.push_str(s);
+            n -= v;
+        }
+    }
+    out
+}
+
+/// 公文编号常见到百以内：一、十、十一、二十一、一百零一
+fn chinese_number(n: u32) -> String {
+    const DIGITS: [&str; 10] = ["零", "一", "二", "三", "四", "五", "六", "七", "八", "九"];
+    if n < 10 {
+        return DIGITS[n as usize].to_string();
+    }
+    if n < 100 {
+        let tens = n / 10;
+        let ones = n % 10;
+        let mut s = String::new();
+        if tens > 1 {
+            s.push_str(DIGITS[tens as usize]);
+        }
+        s.push('十');
+        if ones > 0 {
+            s.push_str(DIGITS[ones as usize]);
+        }
+        return s;
+    }
+    if n < 1000 {
+        let hundreds = n / 100;
+        let rest = n % 100;
+        let mut s = format!("{}百", DIGITS[hundreds as usize]);
+        if rest == 0 {
+            return s;
+        }
+        if rest < 10 {
+            s.push('零');
+            s.push_str(DIGITS[rest as usize]);
+        } else if rest < 20 {
+            // 一百一十五
+            s.push('一');
+            s.push_str(&chinese_number(rest));
+        } else {
+            s.push_str(&chinese_number(rest));
+        }
+        return s;
+    }
+    n.to_string()
+}
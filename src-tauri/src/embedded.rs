@@ -0,0 +1,198 @@
+// 主 docx 内嵌对象（word/embeddings/）。
+// 直接嵌入的 xlsx/docx/pdf 原样取出；OLE 对象（oleObject*.bin）按内部流拆出真正的文件：
+// Package 流为 OOXML 包，CONTENTS 流多为 PDF，Ole10Native 为“包”对象（带原文件名），
+// 含 Workbook/WordDocument 等流的则本身就是旧版 Office 文件。
+
+use crate::file_type;
+use anyhow::{anyhow, Context, Result};
+use encoding_rs::GBK;
+use std::io::{Cursor, Read, Seek};
+use zip::ZipArchive;
+
+/// attachments.container_kind：ZIP 条目（含子 ZIP）与主 docx 内嵌对象
+pub const CONTAINER_ZIP: &str = "zip";
+pub const CONTAINER_MAIN_DOCX: &str = "main_docx";
+
+const EMBEDDINGS_DIR: &str = "word/embeddings/";
+/// 单个内嵌对象上限，超出的跳过
+const MAX_OBJECT_BYTES: u64 = 256 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct EmbeddedObject {
+    /// docx 包内路径，预览时据此重新取出
+    pub part_name: String,
+    /// 推断出的文件名（决定类型识别与缓存扩展名）
+    pub file_name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// 枚举主 docx 的内嵌对象；不是 docx（doc/ofd 等）时返回空
+pub fn enumerate(docx_bytes: &[u8]) -> Result<Vec<EmbeddedObject>> {
+    let Ok(mut zip) = ZipArchive::new(Cursor::new(docx_bytes)) else {
+        return Ok(vec![]);
+    };
+    let mut parts: Vec<String> = zip
+        .file_names()
+        .filter(|n| n.starts_with(EMBEDDINGS_DIR) && !n.ends_with('/'))
+        .map(|n| n.to_string())
+        .collect();
+    parts.sort();
+
+    let mut out = Vec::new();
+    for part_name in parts {
+        let raw = match read_part(&mut zip, &part_name) {
+            Ok(b) => b,
+            Err(e) => {
+                eprintln!("读取内嵌对象失败 {part_name}: {e:#}");
+                continue;
+            }
+        };
+        let (file_name, bytes) = unwrap_object(&part_name, raw);
+        out.push(EmbeddedObject {
+            part_name,
+            file_name,
+            bytes,
+        });
+    }
+    Ok(out)
+}
+
+/// 预览/缓存时按包内路径取出对象内容（与导入时拆包结果一致）
+pub fn read_payload(docx_bytes: &[u8], part_name: &str) -> Result<Vec<u8>> {
+    let mut zip = ZipArchive::new(Cursor::new(docx_bytes)).context("主docx不是有效的zip")?;
+    let raw = read_part(&mut zip, part_name)?;
+    Ok(unwrap_object(part_name, raw).1)
+}
+
+fn read_part<R: Read + Seek>(zip: &mut ZipArchive<R>, part_name: &str) -> Result<Vec<u8>> {
+    let f = zip
+        .by_name(part_name)
+        .with_context(|| format!("主docx内找不到: {part_name}"))?;
+    if f.size() > MAX_OBJECT_BYTES {
+        return Err(anyhow!("内嵌对象过大: {part_name}"));
+    }
+    let mut buf = Vec::new();
+    f.take(MAX_OBJECT_BYTES).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// 返回 (文件名, 内容)；拆不开的 OLE 对象原样保留
+fn unwrap_object(part_name: &str, raw: Vec<u8>) -> (String, Vec<u8>) {
+    let base = part_name.rsplit('/').next().unwrap_or(part_name);
+    let stem = base.rsplit_once('.').map(|(s, _)| s).unwrap_or(base);
+    if !raw.starts_with(&[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1]) {
+        return (base.to_string(), raw);
+    }
+    match unwrap_ole(&raw) {
+        Ok(Some((name, bytes))) => {
+            let name = name.unwrap_or_else(|| {
+                let ext = guess_extension(&bytes);
+                format!("{stem}.{ext}")
+            });
+            (name, bytes)
+        }
+        // OLE 本身就是 xls/doc/ppt
+        Ok(None) => {
+            let ext = ole_extension(&raw).unwrap_or("bin");
+            (format!("{stem}.{ext}"), raw)
+        }
+        Err(e) => {
+            eprintln!("解析OLE内嵌对象失败 {part_name}: {e:#}");
+            (base.to_string(), raw)
+        }
+    }
+}
+
+/// Some((原文件名, 内容))：对象包着另一个文件；None：OLE 本身就是文档
+fn unwrap_ole(raw: &[u8]) -> Result<Option<(Option<String>, Vec<u8>)>> {
+    let mut cfb = cfb::CompoundFile::open(Cursor::new(raw)).context("不是有效的OLE文件")?;
+    if cfb.is_stream("/Package") {
+        return Ok(Some((None, read_stream(&mut cfb, "/Package")?)));
+    }
+    if cfb.is_stream("/CONTENTS") {
+        return Ok(Some((None, read_stream(&mut cfb, "/CONTENTS")?)));
+    }
+    if cfb.is_stream("/\u{1}Ole10Native") {
+        let native = read_stream(&mut cfb, "/\u{1}Ole10Native")?;
+        let (label, data) = parse_ole10_native(&native)?;
+        return Ok(Some((label, data)));
+    }
+    Ok(None)
+}
+
+fn read_stream<F: Read + Seek>(cfb: &mut cfb::CompoundFile<F>, name: &str) -> Result<Vec<u8>> {
+    let mut s = cfb.open_stream(name)?;
+    let mut buf = Vec::new();
+    s.read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// Ole10Native：总长 u32 | 标志 u16 | 标签\0 | 源路径\0 | 8 字节保留 | 临时路径\0 | 数据长 u32 | 数据
+fn parse_ole10_native(b: &[u8]) -> Result<(Option<String>, Vec<u8>)> {
+    let mut pos = 6;
+    let label = read_cstr(b, &mut pos)?;
+    let src_path = read_cstr(b, &mut pos)?;
+    pos += 8;
+    let _temp_path = read_cstr(b, &mut pos)?;
+    let len = b
+        .get(pos..pos + 4)
+        .map(|s| u32::from_le_bytes([s[0], s[1], s[2], s[3]]) as usize)
+        .ok_or_else(|| anyhow!("Ole10Native 截断"))?;
+    pos += 4;
+    let data = b
+        .get(pos..pos + len)
+        .ok_or_else(|| anyhow!("Ole10Native 数据截断"))?
+        .to_vec();
+    // 标签通常就是文件名；为空时取源路径的文件名部分
+    let name = [label, src_path]
+        .into_iter()
+        .map(|s| {
+            s.rsplit(['/', '\\'])
+                .next()
+                .unwrap_or("")
+                .trim()
+                .to_string()
+        })
+        .find(|s| !s.is_empty());
+    Ok((name, data))
+}
+
+/// 以 \0 结尾的 ANSI 字符串，中文系统下按 GBK 解码
+fn read_cstr(b: &[u8], pos: &mut usize) -> Result<String> {
+    let rest = b.get(*pos..).ok_or_else(|| anyhow!("Ole10Native 截断"))?;
+    let end = rest
+        .iter()
+        .position(|c| *c == 0)
+        .ok_or_else(|| anyhow!("Ole10Native 字符串未结束"))?;
+    *pos += end + 1;
+    let (s, _, _) = GBK.decode(&rest[..end]);
+    Ok(s.to_string())
+}
+
+fn guess_extension(bytes: &[u8]) -> &'static str {
+    let head = &bytes[..bytes.len().min(file_type::SNIFF_BYTES)];
+    match file_type::detect("", head) {
+        file_type::PDF => "pdf",
+        file_type::DOCX => "docx",
+        file_type::EXCEL => "xlsx",
+        file_type::PPT => "pptx",
+        file_type::OFD => "ofd",
+        file_type::WORD if head.starts_with(b"{\\rtf") => "rtf",
+        file_type::WORD => ole_extension(bytes).unwrap_or("doc"),
+        _ => "bin",
+    }
+}
+
+/// 旧版 Office 文件：看 OLE 里有哪种主流
+fn ole_extension(raw: &[u8]) -> Option<&'static str> {
+    let cfb = cfb::CompoundFile::open(Cursor::new(raw)).ok()?;
+    if cfb.is_stream("/Workbook") || cfb.is_stream("/Book") {
+        Some("xls")
+    } else if cfb.is_stream("/WordDocument") {
+        Some("doc")
+    } else if cfb.is_stream("/PowerPoint Document") {
+        Some("ppt")
+    } else {
+        None
+    }
+}
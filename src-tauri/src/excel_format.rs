@@ -0,0 +1,651 @@
+// Excel 数字格式：把单元格数值按 numFmt 格式化成显示文本（常用子集）。
+// 支持分节（正;负;零）、千分位、小数位、百分比、科学计数、字面文本与日期时间格式；
+// 分数等少见格式退回常规格式。
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike};
+
+/// 内置格式编号 -> 格式串（中文区域设置下的表现）
+pub fn builtin_format(id: u32) -> Option<&'static str> {
+    Some(match id {
+        0 => "General",
+        1 => "0",
+        2 => "0.00",
+        3 => "#,##0",
+        4 => "#,##0.00",
+        5 | 6 => "¥#,##0;¥-#,##0",
+        7 | 8 => "¥#,##0.00;¥-#,##0.00",
+        9 => "0%",
+        10 => "0.00%",
+        11 => "0.00E+00",
+        12 => "# ?/?",
+        13 => "# ??/??",
+        14 => "yyyy/m/d",
+        15 => "d-mmm-yy",
+        16 => "d-mmm",
+        17 => "mmm-yy",
+        18 => "h:mm AM/PM",
+        19 => "h:mm:ss AM/PM",
+        20 => "h:mm",
+        21 => "h:mm:ss",
+        22 => "yyyy/m/d h:mm",
+        27 | 36 | 50 | 52 | 57 => "yyyy\"年\"m\"月\"",
+        28 | 29 | 51 | 53 | 54 | 58 => "m\"月\"d\"日\"",
+        30 => "m-d-yy",
+        31 => "yyyy\"年\"m\"月\"d\"日\"",
+        32 => "h\"时\"mm\"分\"",
+        33 => "h\"时\"mm\"分\"ss\"秒\"",
+        34 | 55 => "上午/下午h\"时\"mm\"分\"",
+        35 | 56 => "上午/下午h\"时\"mm\"分\"ss\"秒\"",
+        37 | 38 => "#,##0 ;(#,##0)",
+        39 | 40 => "#,##0.00;(#,##0.00)",
+        45 => "mm:ss",
+        46 => "[h]:mm:ss",
+        47 => "mm:ss.0",
+        48 => "##0.0E+0",
+        49 => "@",
+        _ => return None,
+    })
+}
+
+/// 格式串是否为日期/时间格式（去掉引号文本、转义字符与颜色/区域标记后仍含 y/m/d/h/s）
+pub fn is_date_format(code: &str) -> bool {
+    let section = split_sections(code).into_iter().next().unwrap_or_default();
+    if section.eq_ignore_ascii_case("general") {
+        return false;
+    }
+    if section.contains("上午/下午") {
+        return true;
+    }
+    let mut chars = section.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                for n in chars.by_ref() {
+                    if n == '"' {
+                        break;
+                    }
+                }
+            }
+            '\\' | '_' | '*' => {
+                chars.next();
+            }
+            '[' => {
+                let mut inner = String::new();
+                for n in chars.by_ref() {
+                    if n == ']' {
+                        break;
+                    }
+                    inner.push(n);
+                }
+                // [h] [mm] [ss] 为累计时长
+                let lower = inner.to_ascii_lowercase();
+                if !lower.is_empty() && lower.chars().all(|c| matches!(c, 'h' | 'm' | 's')) {
+                    return true;
+                }
+            }
+            'y' | 'Y' | 'm' | 'M' | 'd' | 'D' | 'h' | 'H' | 's' | 'S' => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// 数值按格式串显示；date1904 为工作簿的 1904 日期系统标记
+pub fn format_value(v: f64, code: &str, date1904: bool) -> String {
+    let sections = split_sections(code);
+    // 有负数节时负数按绝对值套用该节；只有一节时由数字格式自己带负号
+    let (section, v) = match sections.len() {
+        0 => return format_general(v),
+        1 => (sections[0].as_str(), v),
+        2 if v < 0.0 => (sections[1].as_str(), -v),
+        2 => (sections[0].as_str(), v),
+        _ if v < 0.0 => (sections[1].as_str(), -v),
+        _ if v == 0.0 => (sections[2].as_str(), v),
+        _ => (sections[0].as_str(), v),
+    };
+    let section = strip_brackets(section);
+    if section.trim().is_empty() {
+        // ;;; 之类用来隐藏数值
+        return String::new();
+    }
+    if section.eq_ignore_ascii_case("general") || section == "@" {
+        return format_general(v);
+    }
+    if is_date_format(&section) {
+        return format_date(v, &section, date1904).unwrap_or_else(|| format_general(v));
+    }
+    format_number(v, &section)
+}
+
+/// Excel “常规”格式：最多约 11 位有效字符，整数不带小数点
+pub fn format_general(v: f64) -> String {
+    if v == 0.0 {
+        return "0".to_string();
+    }
+    if !v.is_finite() {
+        return v.to_string();
+    }
+    let abs = v.abs();
+    if !(1e-9..1e11).contains(&abs) {
+        let s = format!("{:.5E}", v);
+        return tidy_exponent(&s);
+    }
+    if v.fract() == 0.0 {
+        return format!("{:.0}", v);
+    }
+    let int_digits = (abs.log10().floor() as i64 + 1).max(1) as usize;
+    let decimals = 10usize.saturating_sub(int_digits);
+    let s = format!("{:.*}", decimals, v);
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Excel 序列值转日期时间（1900 系统含闰年 bug：60 之前的序号要补回一天）
+pub fn serial_to_datetime(v: f64, date1904: bool) -> Option<NaiveDateTime> {
+    if !v.is_finite() || v < 0.0 || v > 2_958_466.0 {
+        return None;
+    }
+    let base = if date1904 {
+        NaiveDate::from_ymd_opt(1904, 1, 1)?
+    } else if v < 60.0 {
+        NaiveDate::from_ymd_opt(1899, 12, 31)?
+    } else {
+        NaiveDate::from_ymd_opt(1899, 12, 30)?
+    };
+    let days = v.floor() as i64;
+    let ms = ((v - v.floor()) * 86_400_000.0).round() as i64;
+    base.and_hms_opt(0, 0, 0)?
+        .checked_add_signed(Duration::days(days))?
+        .checked_add_signed(Duration::milliseconds(ms))
+}
+
+/// 日期值的 ISO 表示（无时间部分时只到日）
+pub fn serial_to_iso(v: f64, date1904: bool) -> Option<String> {
+    let dt = serial_to_datetime(v, date1904)?;
+    if dt.time().num_seconds_from_midnight() == 0 && dt.time().nanosecond() == 0 {
+        Some(dt.format("%Y-%m-%d").to_string())
+    } else {
+        Some(dt.format("%Y-%m-%dT%H:%M:%S").to_string())
+    }
+}
+
+/// 按 ; 分节（引号、方括号内的 ; 不算）
+fn split_sections(code: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut cur = String::new();
+    let mut in_quote = false;
+    let mut in_bracket = false;
+    let mut escaped = false;
+    for c in code.chars() {
+        if escaped {
+            cur.push(c);
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if !in_quote => {
+                escaped = true;
+                cur.push(c);
+            }
+            '"' => {
+                in_quote = !in_quote;
+                cur.push(c);
+            }
+            '[' if !in_quote => {
+                in_bracket = true;
+                cur.push(c);
+            }
+            ']' if !in_quote => {
+                in_bracket = false;
+                cur.push(c);
+            }
+            ';' if !in_quote && !in_bracket => out.push(std::mem::take(&mut cur)),
+            _ => cur.push(c),
+        }
+    }
+    out.push(cur);
+    out
+}
+
+/// 去掉颜色/条件标记；[$¥-804] 这类货币区域标记保留符号部分；[h]/[mm]/[ss] 保留
+fn strip_brackets(section: &str) -> String {
+    let mut out = String::new();
+    let mut chars = section.chars();
+    let mut in_quote = false;
+    while let Some(c) = chars.next() {
+        if c == '"' {
+            in_quote = !in_quote;
+            out.push(c);
+            continue;
+        }
+        if c != '[' || in_quote {
+            out.push(c);
+            continue;
+        }
+        let mut inner = String::new();
+        for n in chars.by_ref() {
+            if n == ']' {
+                break;
+            }
+            inner.push(n);
+        }
+        let lower = inner.to_ascii_lowercase();
+        if !lower.is_empty() && lower.chars().all(|c| matches!(c, 'h' | 'm' | 's')) {
+            out.push('[');
+            out.push_str(&inner);
+            out.push(']');
+        } else if let Some(rest) = inner.strip_prefix('$') {
+            let symbol = rest.split('-').next().unwrap_or("");
+            if !symbol.is_empty() {
+                out.push('"');
+                out.push_str(symbol);
+                out.push('"');
+            }
+        }
+    }
+    out
+}
+
+/// 普通数字格式：前缀字面文本 + 数字模式 + 后缀字面文本
+fn format_number(v: f64, section: &str) -> String {
+    let mut prefix = String::new();
+    let mut pattern = String::new();
+    let mut suffix = String::new();
+    let mut percent = 0;
+    let mut chars = section.chars().peekable();
+    while let Some(c) = chars.next() {
+        let literal: Option<String> = match c {
+            '"' => {
+                let mut s = String::new();
+                for n in chars.by_ref() {
+                    if n == '"' {
+                        break;
+                    }
+                    s.push(n);
+                }
+                Some(s)
+            }
+            '\\' => chars.next().map(|n| n.to_string()),
+            '_' => {
+                chars.next();
+                Some(" ".to_string())
+            }
+            '*' => {
+                chars.next();
+                Some(String::new())
+            }
+            '%' => {
+                percent += 1;
+                Some("%".to_string())
+            }
+            '0' | '#' | '?' | '.' => {
+                pattern.push(c);
+                None
+            }
+            ',' if !pattern.is_empty() => {
+                pattern.push(c);
+                None
+            }
+            'E' | 'e' if !pattern.is_empty() && matches!(chars.peek(), Some('+') | Some('-')) => {
+                pattern.push('E');
+                pattern.push(chars.next().unwrap_or('+'));
+                None
+            }
+            '/' if !pattern.is_empty() => {
+                // 分数格式不处理
+                return format_general(v);
+            }
+            _ => Some(c.to_string()),
+        };
+        if let Some(s) = literal {
+            if pattern.is_empty() {
+                prefix.push_str(&s);
+            } else {
+                suffix.push_str(&s);
+            }
+        }
+    }
+    if pattern.is_empty() {
+        return format!("{prefix}{suffix}");
+    }
+
+    let mut v = v * 100f64.powi(percent);
+    let negative = v < 0.0;
+    v = v.abs();
+
+    let body = if let Some((mantissa, exp)) = pattern.split_once('E') {
+        format_scientific(v, mantissa, exp)
+    } else {
+        let (int_pat, frac_pat) = match pattern.split_once('.') {
+            Some((i, f)) => (i.to_string(), Some(f.to_string())),
+            None => (pattern.clone(), None),
+        };
+        // 整数模式末尾的逗号表示按千缩放
+        let trailing_commas = int_pat.len() - int_pat.trim_end_matches(',').len();
+        let int_pat = int_pat.trim_end_matches(',');
+        v /= 1000f64.powi(trailing_commas as i32);
+        let grouping = int_pat.contains(',');
+        let min_int = int_pat.chars().filter(|c| *c == '0').count();
+        let frac_pat = frac_pat.map(|f| f.replace(',', ""));
+        let min_frac = frac_pat
+            .as_deref()
+            .map(|f| f.chars().filter(|c| *c == '0').count())
+            .unwrap_or(0);
+        let max_frac = frac_pat.as_deref().map(|f| f.len()).unwrap_or(0);
+
+        let rounded = format!("{:.*}", max_frac, v);
+        let (int_s, frac_s) = match rounded.split_once('.') {
+            Some((i, f)) => (i.to_string(), f.to_string()),
+            None => (rounded.clone(), String::new()),
+        };
+        let mut frac_s = frac_s;
+        while frac_s.len() > min_frac && frac_s.ends_with('0') {
+            frac_s.pop();
+        }
+        let mut int_s = if int_s == "0" && min_int == 0 {
+            String::new()
+        } else {
+            int_s
+        };
+        while int_s.len() < min_int {
+            int_s.insert(0, '0');
+        }
+        if grouping {
+            int_s = group_thousands(&int_s);
+        }
+        match frac_pat {
+            Some(_) => format!("{int_s}.{frac_s}"),
+            None => int_s,
+        }
+    };
+    let sign = if negative && body.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        "-"
+    } else {
+        ""
+    };
+    format!("{sign}{prefix}{body}{suffix}")
+}
+
+fn format_scientific(v: f64, mantissa: &str, exp: &str) -> String {
+    let decimals = mantissa.split_once('.').map(|(_, f)| f.len()).unwrap_or(0);
+    let exp_digits = exp.chars().filter(|c| *c == '0').count().max(1);
+    let s = format!("{:.*E}", decimals, v);
+    let (m, e) = s.split_once('E').unwrap_or((&s, "0"));
+    let e: i32 = e.parse().unwrap_or(0);
+    let sign = if e < 0 {
+        "-"
+    } else if exp.starts_with('+') {
+        "+"
+    } else {
+        ""
+    };
+    format!("{m}E{sign}{:0width$}", e.abs(), width = exp_digits)
+}
+
+fn tidy_exponent(s: &str) -> String {
+    let (m, e) = s.split_once('E').unwrap_or((s, "0"));
+    let m = if m.contains('.') {
+        m.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        m
+    };
+    let e: i32 = e.parse().unwrap_or(0);
+    let sign = if e < 0 { '-' } else { '+' };
+    format!("{m}E{sign}{:02}", e.abs())
+}
+
+fn group_thousands(int_s: &str) -> String {
+    let digits: Vec<char> = int_s.chars().collect();
+    let mut out = String::new();
+    for (i, c) in digits.iter().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.push(',');
+        }
+        out.push(*c);
+    }
+    out
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum DateToken {
+    Literal(String),
+    Year(usize),
+    /// 月或分钟，渲染前按上下文判定
+    M(usize),
+    Minute(usize),
+    Day(usize),
+    /// aaa / aaaa：中文星期
+    WeekdayCn(usize),
+    Hour(usize),
+    Second(usize),
+    /// ss 后的 .0 / .00
+    Fraction(usize),
+    Elapsed(char),
+    AmPm,
+    AmPmCn,
+}
+
+fn format_date(v: f64, section: &str, date1904: bool) -> Option<String> {
+    let tokens = tokenize_date(section);
+    let frac_digits = tokens
+        .iter()
+        .find_map(|t| match t {
+            DateToken::Fraction(n) => Some(*n),
+            _ => None,
+        })
+        .unwrap_or(0);
+    // 没有小数秒时按秒四舍五入，避免 23:59:59.9 显示成 23:59:59
+    let v = if frac_digits == 0 {
+        (v * 86_400.0).round() / 86_400.0
+    } else {
+        v
+    };
+    let dt = serial_to_datetime(v, date1904)?;
+    let twelve_hour = tokens
+        .iter()
+        .any(|t| matches!(t, DateToken::AmPm | DateToken::AmPmCn));
+
+    let mut out = String::new();
+    for t in &tokens {
+        match t {
+            DateToken::Literal(s) => out.push_str(s),
+            DateToken::Year(n) if *n <= 2 => out.push_str(&format!("{:02}", dt.year() % 100)),
+            DateToken::Year(_) => out.push_str(&format!("{:04}", dt.year())),
+            DateToken::M(n) => match n {
+                1 => out.push_str(&dt.month().to_string()),
+                2 => out.push_str(&format!("{:02}", dt.month())),
+                3 => out.push_str(&dt.format("%b").to_string()),
+                4 => out.push_str(&dt.format("%B").to_string()),
+                _ => out.push_str(&dt.format("%b").to_string()[..1]),
+            },
+            DateToken::Minute(n) => out.push_str(&pad(dt.minute() as i64, *n)),
+            DateToken::Day(n) => match n {
+                1 => out.push_str(&dt.day().to_string()),
+                2 => out.push_str(&format!("{:02}", dt.day())),
+                3 => out.push_str(&dt.format("%a").to_string()),
+                _ => out.push_str(&dt.format("%A").to_string()),
+            },
+            DateToken::WeekdayCn(n) => {
+                let names = ["一", "二", "三", "四", "五", "六", "日"];
+                let idx = dt.weekday().num_days_from_monday() as usize;
+                if *n >= 4 {
+                    out.push_str("星期");
+                }
+                out.push_str(names[idx]);
+            }
+            DateToken::Hour(n) => {
+                let mut h = dt.hour();
+                if twelve_hour {
+                    h %= 12;
+                    if h == 0 {
+                        h = 12;
+                    }
+                }
+                out.push_str(&pad(h as i64, *n));
+            }
+            DateToken::Second(n) => out.push_str(&pad(dt.second() as i64, *n)),
+            DateToken::Fraction(n) => {
+                let ms = dt.nanosecond() / 1_000_000;
+                let s = format!("{:03}", ms);
+                out.push('.');
+                out.push_str(&s[..(*n).min(3)]);
+            }
+            DateToken::Elapsed(unit) => {
+                let total_secs = (v * 86_400.0).round() as i64;
+                let n = match unit {
+                    'h' => total_secs / 3600,
+                    'm' => total_secs / 60,
+                    _ => total_secs,
+                };
+                out.push_str(&n.to_string());
+            }
+            DateToken::AmPm => out.push_str(if dt.hour() < 12 { "AM" } else { "PM" }),
+            DateToken::AmPmCn => out.push_str(if dt.hour() < 12 { "上午" } else { "下午" }),
+        }
+    }
+    Some(out)
+}
+
+fn pad(n: i64, width: usize) -> String {
+    if width >= 2 {
+        format!("{:02}", n)
+    } else {
+        n.to_string()
+    }
+}
+
+fn tokenize_date(section: &str) -> Vec<DateToken> {
+    let chars: Vec<char> = section.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let run = |i: usize, target: char| {
+        chars[i..]
+            .iter()
+            .take_while(|c| c.eq_ignore_ascii_case(&target))
+            .count()
+    };
+    while i < chars.len() {
+        let c = chars[i];
+        let rest: String = chars[i..].iter().collect();
+        if rest.starts_with("上午/下午") {
+            tokens.push(DateToken::AmPmCn);
+            i += 5;
+            continue;
+        }
+        let upper = rest.to_ascii_uppercase();
+        if upper.starts_with("AM/PM") {
+            tokens.push(DateToken::AmPm);
+            i += 5;
+            continue;
+        }
+        if upper.starts_with("A/P") {
+            tokens.push(DateToken::AmPm);
+            i += 3;
+            continue;
+        }
+        match c {
+            '"' => {
+                let mut s = String::new();
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    s.push(chars[i]);
+                    i += 1;
+                }
+                i += 1;
+                tokens.push(DateToken::Literal(s));
+            }
+            '\\' => {
+                if let Some(n) = chars.get(i + 1) {
+                    tokens.push(DateToken::Literal(n.to_string()));
+                }
+                i += 2;
+            }
+            '_' => {
+                tokens.push(DateToken::Literal(" ".to_string()));
+                i += 2;
+            }
+            '*' => i += 2,
+            '[' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|c| *c == ']')
+                    .map(|p| i + p)
+                    .unwrap_or(chars.len());
+                let unit = chars
+                    .get(i + 1)
+                    .copied()
+                    .unwrap_or('h')
+                    .to_ascii_lowercase();
+                tokens.push(DateToken::Elapsed(unit));
+                i = end + 1;
+            }
+            'y' | 'Y' | 'e' => {
+                let n = run(i, c);
+                tokens.push(DateToken::Year(n));
+                i += n;
+            }
+            'm' | 'M' => {
+                let n = run(i, 'm');
+                tokens.push(DateToken::M(n));
+                i += n;
+            }
+            'd' | 'D' => {
+                let n = run(i, 'd');
+                tokens.push(DateToken::Day(n));
+                i += n;
+            }
+            'a' if run(i, 'a') >= 3 => {
+                let n = run(i, 'a');
+                tokens.push(DateToken::WeekdayCn(n));
+                i += n;
+            }
+            'h' | 'H' => {
+                let n = run(i, 'h');
+                tokens.push(DateToken::Hour(n));
+                i += n;
+            }
+            's' | 'S' => {
+                let n = run(i, 's');
+                tokens.push(DateToken::Second(n));
+                i += n;
+                if chars.get(i) == Some(&'.') && chars.get(i + 1) == Some(&'0') {
+                    let zeros = chars[i + 1..].iter().take_while(|c| **c == '0').count();
+                    tokens.push(DateToken::Fraction(zeros));
+                    i += 1 + zeros;
+                }
+            }
+            _ => {
+                tokens.push(DateToken::Literal(c.to_string()));
+                i += 1;
+            }
+        }
+    }
+
+    // m 紧跟在 h 之后或紧挨在 s 之前时是分钟
+    for idx in 0..tokens.len() {
+        let DateToken::M(n) = tokens[idx] else {
+            continue;
+        };
+        if n > 2 {
+            continue;
+        }
+        let prev = tokens[..idx]
+            .iter()
+            .rev()
+            .find(|t| !matches!(t, DateToken::Literal(_)));
+        let next = tokens[idx + 1..]
+            .iter()
+            .find(|t| !matches!(t, DateToken::Literal(_)));
+        let after_hour = matches!(
+            prev,
+            Some(DateToken::Hour(_)) | Some(DateToken::Elapsed('h'))
+        );
+        let before_second = matches!(
+            next,
+            Some(DateToken::Second(_)) | Some(DateToken::Elapsed('s'))
+        );
+        if after_hour || before_second {
+            tokens[idx] = DateToken::Minute(n);
+        }
+    }
+    tokens
+}
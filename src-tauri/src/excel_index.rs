@@ -0,0 +1,545 @@
+// Excel 单元格磁盘索引：整本工作簿首次打开时解析一次，之后 sheet 信息、窗口读取与查找都直接读索引。
+// 目录 cache/<archive_id>/<file_id>/excel_index/：
+//   index.json      版本、源文件大小/修改时间、各 sheet 的尺寸与列宽/行高/合并/样式
+//   <n>.rows        每行一段 JSON：[[列, 单元格], ...]，只存非空或带样式的单元格
+//   <n>.offsets     (已存行数+1) 个 u64 小端，第 r 行数据为 [off[r], off[r+1])
+
+use crate::cache;
+use crate::db;
+use crate::excel_preview::{
+    self, CellKind, CellStyle, ExcelCell, ExcelCellsResp, MergedRange, SheetInfo,
+};
+use crate::library_root::LibraryRootState;
+use crate::xlsx_sheet::{SheetItem, StyleTable, XlsxBook};
+use anyhow::{anyhow, Context, Result};
+use calamine::{open_workbook_auto, Reader};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+use uuid::Uuid;
+use zip::ZipArchive;
+
+/// 索引格式变化时递增，旧索引自动重建
+const INDEX_VERSION: u32 = 1;
+/// 内存中最多保留的已打开索引数
+const MAX_OPEN_INDEXES: usize = 4;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ColSpec {
+    first: usize,
+    last: usize,
+    width: Option<f64>,
+    hidden: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SheetIndex {
+    name: String,
+    hidden: bool,
+    /// 按最后一个非空单元格计（绝对位置，与单元格坐标一致）
+    rows: usize,
+    cols: usize,
+    /// .rows 中存了多少行（只有样式的尾行也算）
+    stored_rows: usize,
+    styles: Vec<CellStyle>,
+    merges: Vec<MergedRange>,
+    col_specs: Vec<ColSpec>,
+    /// 行号 -> 自定义行高（像素）
+    row_heights: BTreeMap<usize, f64>,
+    hidden_rows: BTreeSet<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexMeta {
+    version: u32,
+    source_len: u64,
+    source_mtime: u64,
+    sheets: Vec<SheetIndex>,
+}
+
+#[derive(Debug)]
+pub(crate) struct ExcelIndex {
+    dir: PathBuf,
+    meta: IndexMeta,
+}
+
+static OPEN: Lazy<Mutex<VecDeque<Arc<ExcelIndex>>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+/// 同一时间只建一个索引，避免 info/cells 并发请求重复解析
+static BUILD_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+/// 建索引失败的 (目录, 大小, 修改时间)，同一文件不再反复尝试（伪装 xls 等走降级解析）
+static FAILED: Lazy<Mutex<HashSet<(PathBuf, u64, u64)>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// 取附件的单元格索引（没有则建）；建不了时返回 None，调用方走原有解析路径
+pub(crate) fn for_file(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    file_id: &str,
+) -> Option<Arc<ExcelIndex>> {
+    match open_for_file(app, state, file_id) {
+        Ok(index) => index,
+        Err(e) => {
+            eprintln!("Excel索引不可用 {file_id}: {e:#}");
+            None
+        }
+    }
+}
+
+fn open_for_file(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    file_id: &str,
+) -> Result<Option<Arc<ExcelIndex>>> {
+    let (root, conn) = db::open_conn(app, state)?;
+    let source = cache::ensure_cached_file(&root, &conn, file_id)?;
+    let archive_id: String = conn
+        .query_row(
+            "SELECT archive_id FROM attachments WHERE file_id=?",
+            [file_id],
+            |r| r.get(0),
+        )
+        .with_context(|| format!("找不到附件: {file_id}"))?;
+    let dir = root
+        .join("cache")
+        .join(&archive_id)
+        .join(file_id)
+        .join("excel_index");
+    ensure(&dir, &source)
+}
+
+fn source_stamp(source: &Path) -> Result<(u64, u64)> {
+    let md = fs::metadata(source).with_context(|| format!("读取文件失败: {}", source.display()))?;
+    let mtime = md
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Ok((md.len(), mtime))
+}
+
+fn ensure(dir: &Path, source: &Path) -> Result<Option<Arc<ExcelIndex>>> {
+    let (len, mtime) = source_stamp(source)?;
+    let fresh = |m: &IndexMeta| {
+        m.version == INDEX_VERSION && m.source_len == len && m.source_mtime == mtime
+    };
+    {
+        let mut open = OPEN.lock().unwrap();
+        if let Some(i) = open.iter().position(|x| x.dir == dir && fresh(&x.meta)) {
+            let hit = open.remove(i).unwrap();
+            open.push_front(hit.clone());
+            return Ok(Some(hit));
+        }
+    }
+    let failed_key = (dir.to_path_buf(), len, mtime);
+    if FAILED.lock().unwrap().contains(&failed_key) {
+        return Ok(None);
+    }
+
+    let _guard = BUILD_LOCK.lock().unwrap();
+    let meta = match load_meta(dir).filter(|m| fresh(m)) {
+        Some(meta) => meta,
+        None => match build(dir, source, len, mtime) {
+            Ok(meta) => meta,
+            Err(e) => {
+                eprintln!("建立Excel索引失败 {}: {e:#}", source.display());
+                FAILED.lock().unwrap().insert(failed_key);
+                return Ok(None);
+            }
+        },
+    };
+    let index = Arc::new(ExcelIndex {
+        dir: dir.to_path_buf(),
+        meta,
+    });
+    let mut open = OPEN.lock().unwrap();
+    open.retain(|x| x.dir != dir);
+    open.push_front(index.clone());
+    open.truncate(MAX_OPEN_INDEXES);
+    Ok(Some(index))
+}
+
+fn load_meta(dir: &Path) -> Option<IndexMeta> {
+    let bytes = fs::read(dir.join("index.json")).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// 先建在临时目录里，完整写好后再换上，半途失败不会留下残缺索引
+fn build(dir: &Path, source: &Path, len: u64, mtime: u64) -> Result<IndexMeta> {
+    let tmp = dir.with_file_name(format!("excel_index.tmp-{}", Uuid::new_v4()));
+    fs::create_dir_all(&tmp)?;
+    let built = build_sheets(&tmp, source).and_then(|sheets| {
+        let meta = IndexMeta {
+            version: INDEX_VERSION,
+            source_len: len,
+            source_mtime: mtime,
+            sheets,
+        };
+        fs::write(tmp.join("index.json"), serde_json::to_vec(&meta)?)?;
+        Ok(meta)
+    });
+    let meta = match built {
+        Ok(meta) => meta,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&tmp);
+            return Err(e);
+        }
+    };
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    fs::rename(&tmp, dir)?;
+    Ok(meta)
+}
+
+/// xlsx 流式读取（带格式与样式）；其他格式交给 calamine
+fn build_sheets(dir: &Path, source: &Path) -> Result<Vec<SheetIndex>> {
+    let xlsx = File::open(source)
+        .ok()
+        .and_then(|f| ZipArchive::new(f).ok())
+        .and_then(|mut zip| XlsxBook::open(&mut zip).ok().map(|book| (zip, book)));
+    match xlsx {
+        Some((mut zip, book)) => build_xlsx(dir, &mut zip, &book),
+        None => build_calamine(dir, source),
+    }
+}
+
+fn build_xlsx(dir: &Path, zip: &mut ZipArchive<File>, book: &XlsxBook) -> Result<Vec<SheetIndex>> {
+    let mut out = Vec::new();
+    for (n, sheet) in book.sheets.iter().enumerate() {
+        let mut w = RowWriter::create(dir, n)?;
+        let mut styles = StyleTable::default();
+        let mut col_specs = Vec::new();
+        let mut merges = Vec::new();
+        let mut row_heights = BTreeMap::new();
+        let mut hidden_rows = BTreeSet::new();
+        let mut err: Option<anyhow::Error> = None;
+        book.walk_sheet(
+            zip,
+            &sheet.name,
+            0..usize::MAX,
+            0..usize::MAX,
+            &mut |item| match item {
+                SheetItem::Cols {
+                    first,
+                    last,
+                    width,
+                    hidden,
+                } => col_specs.push(ColSpec {
+                    first,
+                    last,
+                    width,
+                    hidden,
+                }),
+                SheetItem::Row {
+                    row,
+                    height,
+                    hidden,
+                } => {
+                    if let Some(h) = height {
+                        row_heights.insert(row, h);
+                    }
+                    if hidden {
+                        hidden_rows.insert(row);
+                    }
+                }
+                SheetItem::Cell {
+                    row,
+                    col,
+                    mut cell,
+                    xf,
+                } => {
+                    if err.is_some() {
+                        return;
+                    }
+                    cell.style = styles.index(book, xf);
+                    if let Err(e) = w.push(row, col, cell) {
+                        err = Some(e);
+                    }
+                }
+                SheetItem::Merge(m) => merges.push(m),
+            },
+        )?;
+        if let Some(e) = err {
+            return Err(e);
+        }
+        let (rows, cols, stored_rows) = w.finish()?;
+        out.push(SheetIndex {
+            name: sheet.name.clone(),
+            hidden: sheet.hidden,
+            rows,
+            cols,
+            stored_rows,
+            styles: styles.styles,
+            merges,
+            col_specs,
+            row_heights,
+            hidden_rows,
+        });
+    }
+    Ok(out)
+}
+
+fn build_calamine(dir: &Path, source: &Path) -> Result<Vec<SheetIndex>> {
+    let mut workbook = open_workbook_auto(source).context("打开Excel失败")?;
+    let mut out = Vec::new();
+    for (n, name) in workbook.sheet_names().to_vec().into_iter().enumerate() {
+        let mut w = RowWriter::create(dir, n)?;
+        if let Ok(range) = workbook.worksheet_range(&name) {
+            let formulas = workbook.worksheet_formula(&name).ok();
+            if let Some((r0, c0)) = range.start() {
+                for (i, row) in range.rows().enumerate() {
+                    for (j, v) in row.iter().enumerate() {
+                        let pos = (r0 + i as u32, c0 + j as u32);
+                        let mut cell = excel_preview::data_to_cell(v);
+                        cell.formula = formulas
+                            .as_ref()
+                            .and_then(|f| f.get_value(pos))
+                            .filter(|f| !f.is_empty())
+                            .cloned();
+                        w.push(pos.0 as usize, pos.1 as usize, cell)?;
+                    }
+                }
+            }
+        }
+        let (rows, cols, stored_rows) = w.finish()?;
+        out.push(SheetIndex {
+            name,
+            hidden: false,
+            rows,
+            cols,
+            stored_rows,
+            styles: Vec::new(),
+            merges: Vec::new(),
+            col_specs: Vec::new(),
+            row_heights: BTreeMap::new(),
+            hidden_rows: BTreeSet::new(),
+        });
+    }
+    Ok(out)
+}
+
+fn rows_path(dir: &Path, n: usize) -> PathBuf {
+    dir.join(format!("{n}.rows"))
+}
+
+fn offsets_path(dir: &Path, n: usize) -> PathBuf {
+    dir.join(format!("{n}.offsets"))
+}
+
+/// 按行号递增写入单元格；跳过的行记为空行
+struct RowWriter {
+    out: BufWriter<File>,
+    offsets_path: PathBuf,
+    offsets: Vec<u64>,
+    cur_row: Option<usize>,
+    cur: Vec<(usize, ExcelCell)>,
+    rows: usize,
+    cols: usize,
+}
+
+impl RowWriter {
+    fn create(dir: &Path, n: usize) -> Result<Self> {
+        Ok(Self {
+            out: BufWriter::new(File::create(rows_path(dir, n))?),
+            offsets_path: offsets_path(dir, n),
+            offsets: vec![0],
+            cur_row: None,
+            cur: Vec::new(),
+            rows: 0,
+            cols: 0,
+        })
+    }
+
+    fn push(&mut self, row: usize, col: usize, cell: ExcelCell) -> Result<()> {
+        let has_value = cell.kind != CellKind::Empty || cell.formula.is_some();
+        if !has_value && cell.style.is_none() {
+            return Ok(());
+        }
+        if self.cur_row != Some(row) {
+            self.flush_row()?;
+            if row < self.offsets.len() - 1 {
+                return Err(anyhow!("sheet 行顺序异常: 第{}行", row + 1));
+            }
+            self.cur_row = Some(row);
+        }
+        if has_value {
+            self.rows = self.rows.max(row + 1);
+            self.cols = self.cols.max(col + 1);
+        }
+        self.cur.push((col, cell));
+        Ok(())
+    }
+
+    fn flush_row(&mut self) -> Result<()> {
+        let Some(row) = self.cur_row.take() else {
+            return Ok(());
+        };
+        let pos = *self.offsets.last().unwrap_or(&0);
+        // 中间没有单元格的行长度为 0
+        while self.offsets.len() - 1 < row {
+            self.offsets.push(pos);
+        }
+        let bytes = serde_json::to_vec(&self.cur)?;
+        self.out.write_all(&bytes)?;
+        self.offsets.push(pos + bytes.len() as u64);
+        self.cur.clear();
+        Ok(())
+    }
+
+    /// 返回 (行数, 列数, 已存行数)
+    fn finish(mut self) -> Result<(usize, usize, usize)> {
+        self.flush_row()?;
+        self.out.flush()?;
+        let mut bytes = Vec::with_capacity(self.offsets.len() * 8);
+        for off in &self.offsets {
+            bytes.extend_from_slice(&off.to_le_bytes());
+        }
+        fs::write(&self.offsets_path, bytes)?;
+        Ok((self.rows, self.cols, self.offsets.len() - 1))
+    }
+}
+
+impl ExcelIndex {
+    pub(crate) fn sheet_infos(&self) -> Vec<SheetInfo> {
+        self.meta
+            .sheets
+            .iter()
+            .map(|s| SheetInfo {
+                name: s.name.clone(),
+                rows: s.rows,
+                cols: s.cols,
+            })
+            .collect()
+    }
+
+    /// 第一个未隐藏的 sheet
+    pub(crate) fn default_sheet(&self) -> Option<String> {
+        self.meta
+            .sheets
+            .iter()
+            .find(|s| !s.hidden)
+            .or_else(|| self.meta.sheets.first())
+            .map(|s| s.name.clone())
+    }
+
+    fn sheet(&self, name: &str) -> Result<(usize, &SheetIndex)> {
+        self.meta
+            .sheets
+            .iter()
+            .enumerate()
+            .find(|(_, s)| s.name == name)
+            .ok_or_else(|| anyhow!("找不到sheet: {name}"))
+    }
+
+    /// offsets[from..=to]
+    fn read_offsets(&self, n: usize, from: usize, to: usize) -> Result<Vec<u64>> {
+        let mut f = File::open(offsets_path(&self.dir, n)).context("读取Excel索引失败")?;
+        f.seek(SeekFrom::Start(from as u64 * 8))?;
+        let mut bytes = vec![0u8; (to - from + 1) * 8];
+        f.read_exact(&mut bytes)?;
+        Ok(bytes
+            .chunks_exact(8)
+            .map(|b| u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+            .collect())
+    }
+
+    /// 读取 [rows) x [cols) 窗口：按行偏移直接定位，不重新解析工作簿
+    pub(crate) fn read_window(
+        &self,
+        sheet_name: &str,
+        rows: Range<usize>,
+        cols: Range<usize>,
+    ) -> Result<ExcelCellsResp> {
+        let (n, sheet) = self.sheet(sheet_name)?;
+        let blank = vec![vec![ExcelCell::default(); cols.len()]; rows.len()];
+        let mut resp = ExcelCellsResp::plain(rows.start, cols.start, blank);
+
+        let stored = rows.start.min(sheet.stored_rows)..rows.end.min(sheet.stored_rows);
+        if !stored.is_empty() {
+            let offsets = self.read_offsets(n, stored.start, stored.end)?;
+            let base = offsets[0];
+            let mut f = File::open(rows_path(&self.dir, n)).context("读取Excel索引失败")?;
+            f.seek(SeekFrom::Start(base))?;
+            let mut data = vec![0u8; (offsets[offsets.len() - 1] - base) as usize];
+            f.read_exact(&mut data)?;
+            for (i, r) in stored.enumerate() {
+                let a = (offsets[i] - base) as usize;
+                let b = (offsets[i + 1] - base) as usize;
+                if a == b {
+                    continue;
+                }
+                let cells: Vec<(usize, ExcelCell)> = serde_json::from_slice(&data[a..b])?;
+                for (c, cell) in cells {
+                    if cols.contains(&c) {
+                        resp.cells[r - rows.start][c - cols.start] = cell;
+                    }
+                }
+            }
+        }
+
+        // 样式表整张下发（单元格 style 为其下标）
+        resp.styles = sheet.styles.clone();
+        resp.merges = sheet
+            .merges
+            .iter()
+            .filter(|m| {
+                m.first_row < rows.end
+                    && m.last_row >= rows.start
+                    && m.first_col < cols.end
+                    && m.last_col >= cols.start
+            })
+            .cloned()
+            .collect();
+        for spec in &sheet.col_specs {
+            for c in spec.first.max(cols.start)..(spec.last + 1).min(cols.end) {
+                if spec.width.is_some() {
+                    resp.col_widths[c - cols.start] = spec.width;
+                }
+                if spec.hidden {
+                    resp.hidden_cols.push(c);
+                }
+            }
+        }
+        for (r, h) in sheet.row_heights.range(rows.clone()) {
+            resp.row_heights[r - rows.start] = Some(*h);
+        }
+        resp.hidden_rows = sheet.hidden_rows.range(rows).copied().collect();
+        Ok(resp)
+    }
+
+    /// 按行序遍历 sheet 中存下的单元格；回调返回 false 时停止
+    pub(crate) fn for_each_cell(
+        &self,
+        sheet_name: &str,
+        f: &mut dyn FnMut(usize, usize, &ExcelCell) -> bool,
+    ) -> Result<()> {
+        let (n, sheet) = self.sheet(sheet_name)?;
+        if sheet.stored_rows == 0 {
+            return Ok(());
+        }
+        let offsets = self.read_offsets(n, 0, sheet.stored_rows)?;
+        let mut reader = BufReader::new(File::open(rows_path(&self.dir, n))?);
+        let mut buf = Vec::new();
+        for r in 0..sheet.stored_rows {
+            let len = (offsets[r + 1] - offsets[r]) as usize;
+            if len == 0 {
+                continue;
+            }
+            buf.resize(len, 0);
+            reader.read_exact(&mut buf)?;
+            let cells: Vec<(usize, ExcelCell)> = serde_json::from_slice(&buf)?;
+            for (c, cell) in &cells {
+                if !f(r, *c, cell) {
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,10 +1,13 @@
 use crate::cache;
 use crate::db;
+use crate::excel_format;
+use crate::excel_index;
 use crate::library_root::resolve_library_root;
 use crate::library_root::LibraryRootState;
+use crate::text_preview;
+use crate::xlsx_sheet::{self, XlsxBook};
 use anyhow::{anyhow, Context, Result};
 use calamine::{open_workbook_auto, Data, Reader};
-use encoding_rs::GBK;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -41,7 +44,117 @@ pub struct ExcelCellsReq {
 pub struct ExcelCellsResp {
     pub row_start: usize,
     pub col_start: usize,
-    pub cells: Vec<Vec<String>>,
+    pub cells: Vec<Vec<ExcelCell>>,
+    /// 单元格 style 为该表下标；只含窗口内用到的非默认样式
+    pub styles: Vec<CellStyle>,
+    /// 与窗口相交的合并区域（绝对行列）
+    pub merges: Vec<MergedRange>,
+    /// 窗口内各列宽 / 各行高（像素），None 为默认
+    pub col_widths: Vec<Option<f64>>,
+    pub row_heights: Vec<Option<f64>>,
+    /// 窗口内隐藏的行 / 列（绝对行列）
+    pub hidden_rows: Vec<usize>,
+    pub hidden_cols: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CellKind {
+    #[default]
+    Empty,
+    String,
+    Number,
+    Bool,
+    Date,
+    Error,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExcelCell {
+    pub kind: CellKind,
+    /// 数字/布尔为 JSON 原生值，日期为 ISO 字符串，空单元格为 null
+    pub value: serde_json::Value,
+    /// 按单元格数字格式格式化后的显示文本
+    pub display: String,
+    /// 公式文本（不含开头的 =）
+    pub formula: Option<String>,
+    pub style: Option<usize>,
+}
+
+impl ExcelCell {
+    pub(crate) fn text(s: String) -> Self {
+        if s.is_empty() {
+            return Self::default();
+        }
+        Self {
+            kind: CellKind::String,
+            value: serde_json::Value::String(s.clone()),
+            display: s,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn bool(b: bool) -> Self {
+        Self {
+            kind: CellKind::Bool,
+            value: serde_json::Value::Bool(b),
+            display: if b { "TRUE" } else { "FALSE" }.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn error(code: String) -> Self {
+        Self {
+            kind: CellKind::Error,
+            value: serde_json::Value::String(code.clone()),
+            display: code,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CellStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strike: bool,
+    /// 十六进制 RGB，如 "FF0000"
+    pub font_color: Option<String>,
+    pub fill_color: Option<String>,
+    /// left / center / right / ...
+    pub h_align: Option<String>,
+    /// top / center / bottom
+    pub v_align: Option<String>,
+    pub wrap: bool,
+}
+
+/// 0 起、首尾均含
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergedRange {
+    pub first_row: usize,
+    pub first_col: usize,
+    pub last_row: usize,
+    pub last_col: usize,
+}
+
+impl ExcelCellsResp {
+    /// 只有值、没有格式信息的窗口（xls/ods/降级解析）
+    pub(crate) fn plain(row_start: usize, col_start: usize, cells: Vec<Vec<ExcelCell>>) -> Self {
+        let rows = cells.len();
+        let cols = cells.first().map(|r| r.len()).unwrap_or(0);
+        Self {
+            row_start,
+            col_start,
+            cells,
+            styles: Vec::new(),
+            merges: Vec::new(),
+            col_widths: vec![None; cols],
+            row_heights: vec![None; rows],
+            hidden_rows: Vec::new(),
+            hidden_cols: Vec::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -74,7 +187,7 @@ pub fn get_excel_sheet_info(
     get_excel_sheet_info_impl(&app, &state, &file_id).map_err(db::err_to_string)
 }
 
-fn get_excel_sheet_info_impl(
+pub(crate) fn get_excel_sheet_info_impl(
     app: &tauri::AppHandle,
     state: &LibraryRootState,
     file_id: &str,
@@ -90,13 +203,25 @@ fn get_excel_sheet_info_impl(
     {
         return Err(anyhow!("这是 macOS 资源文件（以 ._ 开头），可忽略"));
     }
+    // 有单元格索引时不再打开工作簿
+    if let Some(index) = excel_index::for_file(app, state, file_id) {
+        return Ok(ExcelSheetInfoResp {
+            file_id: file_id.to_string(),
+            default_sheet: index.default_sheet(),
+            sheets: index.sheet_infos(),
+        });
+    }
     match open_workbook_auto(path) {
         Ok(mut workbook) => {
             let sheet_names = workbook.sheet_names().to_vec();
             let mut sheets = Vec::new();
             for name in &sheet_names {
+                // 行列按绝对位置计（数据不从 A1 开始时也与单元格坐标一致）
                 let (rows, cols) = match workbook.worksheet_range(name) {
-                    Ok(range) => (range.height(), range.width()),
+                    Ok(range) => range
+                        .end()
+                        .map(|(r, c)| (r as usize + 1, c as usize + 1))
+                        .unwrap_or((0, 0)),
                     Err(_) => (0, 0),
                 };
                 sheets.push(SheetInfo {
@@ -176,7 +301,7 @@ pub fn get_excel_sheet_cells(
     get_excel_sheet_cells_impl(&app, &state, req).map_err(db::err_to_string)
 }
 
-fn get_excel_sheet_cells_impl(
+pub(crate) fn get_excel_sheet_cells_impl(
     app: &tauri::AppHandle,
     state: &LibraryRootState,
     req: ExcelCellsReq,
@@ -196,29 +321,53 @@ fn get_excel_sheet_cells_impl(
     {
         return Err(anyhow!("这是 macOS 资源文件（以 ._ 开头），可忽略"));
     }
+    if let Some(index) = excel_index::for_file(app, state, &req.file_id) {
+        return index.read_window(
+            &req.sheet_name,
+            req.row_start..req.row_end,
+            req.col_start..req.col_end,
+        );
+    }
+    let kind = sniff_kind(path).unwrap_or(FileKind::Unknown);
+    // xlsx 直读才有数字格式/合并/样式；ods 等其他 zip 表格交给 calamine
+    if kind == FileKind::Zip {
+        if let Ok(resp) = xlsx_window(
+            path,
+            &req.sheet_name,
+            req.row_start..req.row_end,
+            req.col_start..req.col_end,
+        ) {
+            return Ok(resp);
+        }
+    }
     match open_workbook_auto(path) {
         Ok(mut workbook) => {
             let range = workbook
                 .worksheet_range(&req.sheet_name)
                 .context("读取sheet失败")?;
+            let formulas = workbook.worksheet_formula(&req.sheet_name).ok();
 
             let mut cells = Vec::new();
             for r in req.row_start..req.row_end {
                 let mut row = Vec::new();
                 for c in req.col_start..req.col_end {
-                    let v = range.get((r, c)).unwrap_or(&Data::Empty);
-                    row.push(cell_to_string(v));
+                    let pos = (r as u32, c as u32);
+                    let mut cell = data_to_cell(range.get_value(pos).unwrap_or(&Data::Empty));
+                    cell.formula = formulas
+                        .as_ref()
+                        .and_then(|f| f.get_value(pos))
+                        .filter(|f| !f.is_empty())
+                        .cloned();
+                    row.push(cell);
                 }
                 cells.push(row);
             }
-
-            Ok(ExcelCellsResp {
-                row_start: req.row_start,
-                col_start: req.col_start,
-                cells,
-            })
+            Ok(ExcelCellsResp::plain(req.row_start, req.col_start, cells))
         }
         Err(e) => {
+            if kind == FileKind::Zip {
+                return Err(anyhow!("打开Excel失败: {e:#}"));
+            }
             let root = resolve_library_root(app, state)?;
             db::init_db(app, &root)?;
             let conn = rusqlite::Connection::open(root.join("db.sqlite"))?;
@@ -229,75 +378,155 @@ fn get_excel_sheet_cells_impl(
                     |r| r.get(0),
                 )
                 .with_context(|| format!("找不到附件: {}", req.file_id))?;
-            let kind = sniff_kind(path).unwrap_or(FileKind::Unknown);
-            match kind {
-                FileKind::Zip => {
-                    let cells = xlsx_read_cells_window(
-                        path,
-                        &req.sheet_name,
-                        req.row_start,
-                        req.row_end,
-                        req.col_start,
-                        req.col_end,
-                    )
-                    .with_context(|| format!("打开Excel失败: {e:#}"))?;
-                    Ok(ExcelCellsResp {
-                        row_start: req.row_start,
-                        col_start: req.col_start,
-                        cells,
-                    })
-                }
-                _ => {
-                    let fb = load_or_build_fallback(&root, &archive_id, &req.file_id, path)
-                        .with_context(|| format!("打开Excel失败: {e:#}"))?;
-                    let sheet = fb
-                        .sheets
-                        .iter()
-                        .find(|s| s.name == req.sheet_name)
-                        .ok_or_else(|| anyhow!("找不到sheet: {}", req.sheet_name))?;
-
-                    let mut cells = Vec::new();
-                    for r in req.row_start..req.row_end {
-                        let mut row = Vec::new();
-                        for c in req.col_start..req.col_end {
-                            let v = sheet
-                                .cells
-                                .get(r)
-                                .and_then(|rr| rr.get(c))
-                                .cloned()
-                                .unwrap_or_default();
-                            row.push(v);
-                        }
-                        cells.push(row);
-                    }
-                    Ok(ExcelCellsResp {
-                        row_start: req.row_start,
-                        col_start: req.col_start,
-                        cells,
-                    })
+            let fb = load_or_build_fallback(&root, &archive_id, &req.file_id, path)
+                .with_context(|| format!("打开Excel失败: {e:#}"))?;
+            let sheet = fb
+                .sheets
+                .iter()
+                .find(|s| s.name == req.sheet_name)
+                .ok_or_else(|| anyhow!("找不到sheet: {}", req.sheet_name))?;
+
+            let mut cells = Vec::new();
+            for r in req.row_start..req.row_end {
+                let mut row = Vec::new();
+                for c in req.col_start..req.col_end {
+                    let v = sheet
+                        .cells
+                        .get(r)
+                        .and_then(|rr| rr.get(c))
+                        .cloned()
+                        .unwrap_or_default();
+                    row.push(ExcelCell::text(v));
                 }
+                cells.push(row);
             }
+            Ok(ExcelCellsResp::plain(req.row_start, req.col_start, cells))
         }
     }
 }
 
-fn cell_to_string(v: &Data) -> String {
+fn xlsx_window(
+    path: &Path,
+    sheet_name: &str,
+    rows: std::ops::Range<usize>,
+    cols: std::ops::Range<usize>,
+) -> Result<ExcelCellsResp> {
+    let f = fs::File::open(path).with_context(|| format!("打开文件失败: {}", path.display()))?;
+    let mut zip = ZipArchive::new(f).context("打开xlsx(zip)失败")?;
+    let book = XlsxBook::open(&mut zip)?;
+    book.read_window(&mut zip, sheet_name, rows, cols)
+}
+
+/// 第一个 sheet 左上角的一小块（缩略图用），依次尝试 calamine、xlsx 直读、降级解析
+pub(crate) fn first_sheet_window(
+    root: &Path,
+    archive_id: &str,
+    file_id: &str,
+    path: &Path,
+    rows: usize,
+    cols: usize,
+) -> Result<(String, Vec<Vec<String>>)> {
+    let displays = |resp: ExcelCellsResp| -> Vec<Vec<String>> {
+        resp.cells
+            .into_iter()
+            .map(|row| row.into_iter().map(|c| c.display).collect())
+            .collect()
+    };
+    if sniff_kind(path).unwrap_or(FileKind::Unknown) == FileKind::Zip {
+        let first = fs::File::open(path)
+            .ok()
+            .and_then(|f| ZipArchive::new(f).ok())
+            .and_then(|mut zip| {
+                let book = XlsxBook::open(&mut zip).ok()?;
+                let name = book.sheets.iter().find(|s| !s.hidden)?.name.clone();
+                let resp = book.read_window(&mut zip, &name, 0..rows, 0..cols).ok()?;
+                Some((name, resp))
+            });
+        if let Some((name, resp)) = first {
+            return Ok((name, displays(resp)));
+        }
+    }
+    if let Ok(mut workbook) = open_workbook_auto(path) {
+        if let Some(name) = workbook.sheet_names().first().cloned() {
+            let range = workbook.worksheet_range(&name).context("读取sheet失败")?;
+            let cells = (0..rows)
+                .map(|r| {
+                    (0..cols)
+                        .map(|c| {
+                            data_to_cell(
+                                range
+                                    .get_value((r as u32, c as u32))
+                                    .unwrap_or(&Data::Empty),
+                            )
+                            .display
+                        })
+                        .collect()
+                })
+                .collect();
+            return Ok((name, cells));
+        }
+    }
+    let fb = load_or_build_fallback(root, archive_id, file_id, path)?;
+    let sheet = fb.sheets.first().ok_or_else(|| anyhow!("表格为空"))?;
+    let cells = (0..rows)
+        .map(|r| {
+            (0..cols)
+                .map(|c| {
+                    sheet
+                        .cells
+                        .get(r)
+                        .and_then(|rr| rr.get(c))
+                        .cloned()
+                        .unwrap_or_default()
+                })
+                .collect()
+        })
+        .collect();
+    Ok((sheet.name.clone(), cells))
+}
+
+/// calamine 的值没有单元格格式：日期按常见格式显示，数字按常规格式
+pub(crate) fn data_to_cell(v: &Data) -> ExcelCell {
     match v {
-        Data::Empty => String::new(),
-        Data::String(s) => s.to_string(),
-        Data::Float(f) => {
-            if f.fract() == 0.0 {
-                format!("{:.0}", f)
+        Data::Empty => ExcelCell::default(),
+        Data::String(s) => ExcelCell::text(s.to_string()),
+        Data::Float(f) => number_cell(*f),
+        Data::Int(i) => number_cell(*i as f64),
+        Data::Bool(b) => ExcelCell::bool(*b),
+        Data::DateTime(dt) => {
+            let serial = dt.as_f64();
+            let fmt = if dt.is_duration() {
+                "[h]:mm:ss"
+            } else if serial.fract() == 0.0 {
+                "yyyy-mm-dd"
             } else {
-                f.to_string()
+                "yyyy-mm-dd hh:mm:ss"
+            };
+            ExcelCell {
+                kind: CellKind::Date,
+                value: excel_format::serial_to_iso(serial, false)
+                    .map(serde_json::Value::String)
+                    .unwrap_or_else(|| serde_json::json!(serial)),
+                display: excel_format::format_value(serial, fmt, false),
+                ..Default::default()
             }
         }
-        Data::Int(i) => i.to_string(),
-        Data::Bool(b) => b.to_string(),
-        Data::DateTime(f) => f.to_string(),
-        Data::DateTimeIso(s) => s.to_string(),
-        Data::DurationIso(s) => s.to_string(),
-        Data::Error(e) => format!("错误:{e:?}"),
+        Data::DateTimeIso(s) | Data::DurationIso(s) => ExcelCell {
+            kind: CellKind::Date,
+            value: serde_json::Value::String(s.to_string()),
+            display: s.replace('T', " "),
+            ..Default::default()
+        },
+        Data::Error(e) => ExcelCell::error(e.to_string()),
+    }
+}
+
+fn number_cell(n: f64) -> ExcelCell {
+    ExcelCell {
+        kind: CellKind::Number,
+        value: serde_json::json!(n),
+        display: excel_format::format_general(n),
+        ..Default::default()
     }
 }
 
@@ -383,59 +612,28 @@ fn parse_excel_like_as_table(path: &Path) -> Result<FallbackWorkbook> {
 }
 
 fn decode_text_guess(bytes: &[u8]) -> String {
-    if let Ok(s) = std::str::from_utf8(bytes) {
-        return s.to_string();
-    }
-    let (decoded, _, had_errors) = GBK.decode(bytes);
-    if !had_errors {
-        return decoded.to_string();
-    }
-    String::from_utf8_lossy(bytes).to_string()
+    text_preview::decode(bytes).0
 }
 
 fn parse_delimited_table(text: &str) -> Result<FallbackSheet> {
-    let mut lines = Vec::new();
-    for l in text.lines() {
-        let l = l.trim_end_matches('\r');
-        if l.trim().is_empty() {
-            continue;
-        }
-        lines.push(l.to_string());
-        if lines.len() >= 5000 {
-            break;
-        }
-    }
-    if lines.is_empty() {
-        return Err(anyhow!("文件内容为空，无法预览"));
-    }
-
-    let sample = lines
-        .iter()
-        .take(40)
-        .map(|s| s.as_str())
-        .collect::<Vec<_>>()
-        .join("\n");
-    let tab = sample.matches('\t').count();
-    let comma = sample.matches(',').count();
-    let semi = sample.matches(';').count();
-    let delim = if tab >= comma && tab >= semi {
-        '\t'
-    } else if comma >= semi {
-        ','
-    } else {
-        ';'
-    };
-
+    let delim = text_preview::sniff_delimiter(text);
     let mut cells: Vec<Vec<String>> = Vec::new();
     let mut max_cols = 0usize;
-    for l in lines {
-        let row = l
-            .split(delim)
+    text_preview::parse_records(text, delim, &mut |_, row| {
+        if row.iter().all(|s| s.trim().is_empty()) {
+            return true;
+        }
+        let row = row
+            .into_iter()
             .take(200)
             .map(|s| s.trim().to_string())
             .collect::<Vec<_>>();
         max_cols = max_cols.max(row.len());
         cells.push(row);
+        cells.len() < 5000
+    });
+    if cells.is_empty() {
+        return Err(anyhow!("文件内容为空，无法预览"));
     }
     Ok(FallbackSheet {
         name: "Sheet1".to_string(),
@@ -516,33 +714,6 @@ fn xlsx_list_sheets(path: &Path) -> Result<Vec<XlsxSheetMeta>> {
     Ok(out)
 }
 
-fn xlsx_read_cells_window(
-    path: &Path,
-    sheet_name: &str,
-    row_start: usize,
-    row_end: usize,
-    col_start: usize,
-    col_end: usize,
-) -> Result<Vec<Vec<String>>> {
-    let f = fs::File::open(path).with_context(|| format!("打开文件失败: {}", path.display()))?;
-    let mut zip = ZipArchive::new(f).context("打开xlsx(zip)失败")?;
-
-    let workbook_xml =
-        read_zip_text(&mut zip, "xl/workbook.xml").context("xlsx缺少 xl/workbook.xml")?;
-    let rels_xml = read_zip_text(&mut zip, "xl/_rels/workbook.xml.rels").unwrap_or_default();
-    let rels = parse_rels_map(&rels_xml);
-    let sheets = parse_workbook_sheets(&workbook_xml, &rels)?;
-    let (_, sheet_path) = sheets
-        .iter()
-        .find(|(n, _)| n == sheet_name)
-        .cloned()
-        .ok_or_else(|| anyhow!("找不到sheet: {sheet_name}"))?;
-
-    let shared = read_shared_strings(&mut zip).unwrap_or_default();
-    let sheet_xml = read_zip_text(&mut zip, &sheet_path).context("读取sheet.xml失败")?;
-    parse_sheet_cells_window(&sheet_xml, &shared, row_start, row_end, col_start, col_end)
-}
-
 fn read_zip_text<R: Read + std::io::Seek>(zip: &mut ZipArchive<R>, name: &str) -> Result<String> {
     let mut f = zip.by_name(name)?;
     let mut s = String::new();
@@ -599,115 +770,6 @@ fn parse_sheet_dimension(sheet_xml: &str) -> Option<(usize, usize)> {
     let r = cap.get(1)?.as_str();
     let parts = r.split(':').collect::<Vec<_>>();
     let last = parts.last().copied().unwrap_or(r);
-    let (row, col) = parse_cell_ref(last)?;
+    let (row, col) = xlsx_sheet::parse_cell_ref(last)?;
     Some((row + 1, col + 1))
 }
-
-fn parse_cell_ref(s: &str) -> Option<(usize, usize)> {
-    // "BC12" => (11, 54)
-    let mut col: usize = 0;
-    let mut i = 0usize;
-    let bytes = s.as_bytes();
-    while i < bytes.len() {
-        let b = bytes[i];
-        if (b'A'..=b'Z').contains(&b) || (b'a'..=b'z').contains(&b) {
-            let v = (b.to_ascii_uppercase() - b'A' + 1) as usize;
-            col = col * 26 + v;
-            i += 1;
-            continue;
-        }
-        break;
-    }
-    if col == 0 {
-        return None;
-    }
-    let row_str = &s[i..];
-    let row: usize = row_str.parse().ok()?;
-    Some((row.saturating_sub(1), col.saturating_sub(1)))
-}
-
-fn read_shared_strings<R: Read + std::io::Seek>(zip: &mut ZipArchive<R>) -> Result<Vec<String>> {
-    let xml = read_zip_text(zip, "xl/sharedStrings.xml")?;
-    // <si><t>..</t></si> 或富文本 <r><t>..</t></r>
-    let re_si = Regex::new(r#"(?is)<si[^>]*>(.*?)</si>"#).unwrap();
-    let re_t = Regex::new(r#"(?is)<t[^>]*>(.*?)</t>"#).unwrap();
-    let re_tags = Regex::new(r"(?is)<[^>]+>").unwrap();
-    let mut out = Vec::new();
-    for si in re_si.captures_iter(&xml) {
-        let inner = si.get(1).map(|m| m.as_str()).unwrap_or("");
-        let mut s = String::new();
-        for tcap in re_t.captures_iter(inner) {
-            let t = tcap.get(1).map(|m| m.as_str()).unwrap_or("");
-            let mut v = re_tags.replace_all(t, "").to_string();
-            v = decode_basic_html_entities(&v);
-            s.push_str(&v);
-        }
-        out.push(s);
-    }
-    Ok(out)
-}
-
-fn parse_sheet_cells_window(
-    sheet_xml: &str,
-    shared: &[String],
-    row_start: usize,
-    row_end: usize,
-    col_start: usize,
-    col_end: usize,
-) -> Result<Vec<Vec<String>>> {
-    let rows = row_end.saturating_sub(row_start);
-    let cols = col_end.saturating_sub(col_start);
-    let mut out = vec![vec![String::new(); cols]; rows];
-
-    // 简化解析：用正则抓 <c r="A1" t="s"><v>0</v></c> 和 inlineStr
-    let re_cell = Regex::new(r#"(?is)<c\b([^>]*)>(.*?)</c>"#).unwrap();
-    let re_attr_r = Regex::new(r#"(?is)\sr="([^"]+)""#).unwrap();
-    let re_attr_t = Regex::new(r#"(?is)\st="([^"]+)""#).unwrap();
-    let re_v = Regex::new(r#"(?is)<v[^>]*>(.*?)</v>"#).unwrap();
-    let re_is_t = Regex::new(r#"(?is)<is[^>]*>.*?<t[^>]*>(.*?)</t>.*?</is>"#).unwrap();
-    let re_tags = Regex::new(r"(?is)<[^>]+>").unwrap();
-
-    for cap in re_cell.captures_iter(sheet_xml) {
-        let attrs = cap.get(1).map(|m| m.as_str()).unwrap_or("");
-        let inner = cap.get(2).map(|m| m.as_str()).unwrap_or("");
-        let rcap = re_attr_r.captures(attrs);
-        let Some(rcap) = rcap else { continue };
-        let r = rcap.get(1).unwrap().as_str();
-        let Some((rr, cc)) = parse_cell_ref(r) else {
-            continue;
-        };
-        if rr < row_start || rr >= row_end || cc < col_start || cc >= col_end {
-            continue;
-        }
-        let t = re_attr_t
-            .captures(attrs)
-            .and_then(|c| c.get(1))
-            .map(|m| m.as_str().to_string());
-
-        let mut value = String::new();
-        if t.as_deref() == Some("inlineStr") {
-            if let Some(ic) = re_is_t.captures(inner).and_then(|c| c.get(1)) {
-                value = re_tags.replace_all(ic.as_str(), "").to_string();
-                value = decode_basic_html_entities(&value);
-            }
-        } else if let Some(vc) = re_v.captures(inner).and_then(|c| c.get(1)) {
-            let raw = re_tags.replace_all(vc.as_str(), "").to_string();
-            if t.as_deref() == Some("s") {
-                if let Ok(idx) = raw.trim().parse::<usize>() {
-                    value = shared.get(idx).cloned().unwrap_or_default();
-                } else {
-                    value = raw;
-                }
-            } else {
-                value = raw;
-            }
-        }
-
-        let r0 = rr - row_start;
-        let c0 = cc - col_start;
-        if r0 < rows && c0 < cols {
-            out[r0][c0] = value;
-        }
-    }
-    Ok(out)
-}
@@ -0,0 +1,938 @@
+// Excel 整表操作：排序、筛选、跨 sheet 查找与导出。
+// 排序/筛选时只从单元格索引中抽取用到的列逐行判定，内存里只缓存结果行号序列；
+// 翻页时按行号从索引读取窗口。建不了索引的文件（降级解析的表格）每次请求整表读一次，不缓存。
+
+use crate::cache;
+use crate::db;
+use crate::excel_index::{self, ExcelIndex};
+use crate::excel_preview::{self, CellKind, ExcelCell, ExcelCellsReq, ExcelCellsResp, SheetInfo};
+use crate::library_root::LibraryRootState;
+use crate::xlsx_sheet;
+use anyhow::{anyhow, Context, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use once_cell::sync::Lazy;
+use pinyin::ToPinyin;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::Write;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::State;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// 内存中最多保留的排序/筛选结果数
+const MAX_CACHED_VIEWS: usize = 16;
+const DEFAULT_FIND_LIMIT: usize = 500;
+
+static EMPTY_CELL: ExcelCell = ExcelCell {
+    kind: CellKind::Empty,
+    value: serde_json::Value::Null,
+    display: String::new(),
+    formula: None,
+    style: None,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SortSpec {
+    pub col: usize,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterOp {
+    Contains,
+    NotContains,
+    Equals,
+    NotEquals,
+    StartsWith,
+    EndsWith,
+    /// 数值/日期比较；两边都不是数字或日期时不匹配
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    Empty,
+    NotEmpty,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilterSpec {
+    pub col: usize,
+    pub op: FilterOp,
+    #[serde(default)]
+    pub value: String,
+}
+
+/// 一张 sheet 的排序/筛选条件；多个筛选条件同时满足
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExcelView {
+    pub file_id: String,
+    pub sheet_name: String,
+    /// 顶部表头行数，不参与排序/筛选，始终保留在最前
+    #[serde(default)]
+    pub header_rows: usize,
+    #[serde(default)]
+    pub sort: Option<SortSpec>,
+    #[serde(default)]
+    pub filters: Vec<FilterSpec>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcelQueryReq {
+    #[serde(flatten)]
+    pub view: ExcelView,
+    /// 结果视图中的行范围（0 起）与原表列范围
+    pub row_start: usize,
+    pub row_end: usize,
+    pub col_start: usize,
+    pub col_end: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcelQueryResp {
+    /// 筛选后的总行数（含表头行）
+    pub total_rows: usize,
+    /// 窗口内各行在原表中的行号（0 起）
+    pub source_rows: Vec<usize>,
+    /// row_start 为结果视图中的位置，hidden_rows 为原表行号；排序后合并区域不再成立，merges 为空
+    pub window: ExcelCellsResp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcelFindHit {
+    pub sheet_name: String,
+    pub row: usize,
+    pub col: usize,
+    /// 如 "B12"
+    pub cell_ref: String,
+    pub display: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcelFindResp {
+    pub hits: Vec<ExcelFindHit>,
+    /// 命中数超过上限被截断
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    Xlsx,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcelExportReq {
+    #[serde(flatten)]
+    pub view: ExcelView,
+    pub format: ExportFormat,
+    /// 导出的列范围，缺省为整表
+    #[serde(default)]
+    pub col_start: Option<usize>,
+    #[serde(default)]
+    pub col_end: Option<usize>,
+    /// 缺省时弹出保存对话框
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+/// 排序/筛选结果的行号序列，键为 (预览文件路径, sheet, 条件) 的 JSON；最近使用的在前。
+/// 路径含库根目录，换库后自然失效
+static VIEWS: Lazy<Mutex<VecDeque<(String, Arc<Vec<usize>>)>>> =
+    Lazy::new(|| Mutex::new(VecDeque::new()));
+
+/// 一张 sheet 的数据来源
+enum SheetData {
+    /// 单元格索引：按需逐行读取，不把整表读进内存
+    Index(Arc<ExcelIndex>),
+    /// 建不了索引（降级解析的表格）时整表读一次，只在本次请求内使用
+    Table(ExcelCellsResp),
+}
+
+struct SheetSource {
+    info: SheetInfo,
+    data: SheetData,
+}
+
+impl SheetSource {
+    fn open(
+        app: &tauri::AppHandle,
+        state: &LibraryRootState,
+        file_id: &str,
+        name: &str,
+    ) -> Result<Self> {
+        let find = |sheets: Vec<SheetInfo>| {
+            sheets
+                .into_iter()
+                .find(|s| s.name == name)
+                .ok_or_else(|| anyhow!("找不到sheet: {name}"))
+        };
+        if let Some(index) = excel_index::for_file(app, state, file_id) {
+            return Ok(Self {
+                info: find(index.sheet_infos())?,
+                data: SheetData::Index(index),
+            });
+        }
+        let info = find(excel_preview::get_excel_sheet_info_impl(app, state, file_id)?.sheets)?;
+        let table = read_table(app, state, file_id, &info)?;
+        Ok(Self {
+            info,
+            data: SheetData::Table(table),
+        })
+    }
+
+    /// 按行序遍历单元格；回调返回 false 时停止
+    fn for_each_cell(&self, f: &mut dyn FnMut(usize, usize, &ExcelCell) -> bool) -> Result<()> {
+        match &self.data {
+            SheetData::Index(index) => index.for_each_cell(&self.info.name, f),
+            SheetData::Table(table) => {
+                for (r, row) in table.cells.iter().enumerate() {
+                    for (c, cell) in row.iter().enumerate() {
+                        if !f(r, c, cell) {
+                            return Ok(());
+                        }
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// 读取 [rows) x [cols) 窗口（绝对行列）
+    fn read_window(&self, rows: Range<usize>, cols: Range<usize>) -> Result<ExcelCellsResp> {
+        let table = match &self.data {
+            SheetData::Index(index) => return index.read_window(&self.info.name, rows, cols),
+            SheetData::Table(table) => table,
+        };
+        let cells = rows
+            .clone()
+            .map(|r| cols.clone().map(|c| cell_at(table, r, c).clone()).collect())
+            .collect();
+        let mut resp = ExcelCellsResp::plain(rows.start, cols.start, cells);
+        resp.styles = table.styles.clone();
+        resp.col_widths = cols
+            .clone()
+            .map(|c| table.col_widths.get(c).copied().flatten())
+            .collect();
+        resp.hidden_cols = table
+            .hidden_cols
+            .iter()
+            .copied()
+            .filter(|c| cols.contains(c))
+            .collect();
+        resp.row_heights = rows
+            .clone()
+            .map(|r| table.row_heights.get(r).copied().flatten())
+            .collect();
+        resp.hidden_rows = table
+            .hidden_rows
+            .iter()
+            .copied()
+            .filter(|r| rows.contains(r))
+            .collect();
+        Ok(resp)
+    }
+
+    /// 按结果行号取窗口：原表中连续的行合并成一次读取。
+    /// row_start 为结果视图中的位置；行高、隐藏行仍按原表行号（与批注一致）
+    fn read_rows(
+        &self,
+        row_start: usize,
+        source_rows: &[usize],
+        cols: Range<usize>,
+    ) -> Result<ExcelCellsResp> {
+        let mut window = ExcelCellsResp::plain(row_start, cols.start, Vec::new());
+        window.col_widths = vec![None; cols.len()];
+        let mut i = 0;
+        while i < source_rows.len() {
+            let first = source_rows[i];
+            let mut end = i + 1;
+            while end < source_rows.len() && source_rows[end] == first + (end - i) {
+                end += 1;
+            }
+            let part = self.read_window(first..first + (end - i), cols.clone())?;
+            if i == 0 {
+                // 样式表整张下发，各段相同
+                window.styles = part.styles;
+                window.col_widths = part.col_widths;
+                window.hidden_cols = part.hidden_cols;
+            }
+            window.cells.extend(part.cells);
+            window.row_heights.extend(part.row_heights);
+            window.hidden_rows.extend(part.hidden_rows);
+            i = end;
+        }
+        Ok(window)
+    }
+}
+
+/// 整表读取（行列均为绝对位置），只用于没有单元格索引的文件
+fn read_table(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    file_id: &str,
+    sheet: &SheetInfo,
+) -> Result<ExcelCellsResp> {
+    if sheet.rows == 0 || sheet.cols == 0 {
+        return Ok(ExcelCellsResp::plain(0, 0, Vec::new()));
+    }
+    excel_preview::get_excel_sheet_cells_impl(
+        app,
+        state,
+        ExcelCellsReq {
+            file_id: file_id.to_string(),
+            sheet_name: sheet.name.clone(),
+            row_start: 0,
+            row_end: sheet.rows,
+            col_start: 0,
+            col_end: sheet.cols,
+        },
+    )
+}
+
+/// 按条件取结果行号序列（先查缓存）
+fn load_view(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    view: &ExcelView,
+) -> Result<(SheetSource, Arc<Vec<usize>>)> {
+    let preview = cache::get_attachment_preview_path_impl(app, state, &view.file_id)
+        .context("获取Excel预览文件失败")?;
+    let source = SheetSource::open(app, state, &view.file_id, &view.sheet_name)?;
+    let key = serde_json::to_string(&(
+        &preview.path,
+        &view.sheet_name,
+        view.header_rows,
+        &view.sort,
+        &view.filters,
+    ))?;
+    {
+        let mut views = VIEWS.lock().unwrap();
+        if let Some(i) = views.iter().position(|(k, _)| *k == key) {
+            if let Some(hit) = views.remove(i) {
+                let rows = hit.1.clone();
+                views.push_front(hit);
+                return Ok((source, rows));
+            }
+        }
+    }
+
+    let rows = Arc::new(compute_view(
+        &source,
+        view.header_rows,
+        view.sort.as_ref(),
+        &view.filters,
+    )?);
+    let mut views = VIEWS.lock().unwrap();
+    views.push_front((key, rows.clone()));
+    views.truncate(MAX_CACHED_VIEWS);
+    Ok((source, rows))
+}
+
+fn cell_at(table: &ExcelCellsResp, row: usize, col: usize) -> &ExcelCell {
+    table
+        .cells
+        .get(row)
+        .and_then(|r| r.get(col))
+        .unwrap_or(&EMPTY_CELL)
+}
+
+/// 只抽取排序/筛选用到的列，逐行判定
+fn compute_view(
+    source: &SheetSource,
+    header_rows: usize,
+    sort: Option<&SortSpec>,
+    filters: &[FilterSpec],
+) -> Result<Vec<usize>> {
+    let total = source.info.rows;
+    if sort.is_none() && filters.is_empty() {
+        return Ok((0..total).collect());
+    }
+    let mut builder = ViewBuilder::new(header_rows.min(total), total, sort, filters);
+    source.for_each_cell(&mut |r, c, cell| builder.visit(r, c, cell))?;
+    Ok(builder.finish())
+}
+
+/// 流式收集一行里用到的单元格；没有存下单元格的行按空行判定
+struct ViewBuilder<'a> {
+    header: usize,
+    total: usize,
+    sort: Option<&'a SortSpec>,
+    filters: &'a [FilterSpec],
+    /// 用到的列（升序），cur 的下标与之对应
+    cols: Vec<usize>,
+    row: Option<usize>,
+    cur: Vec<Option<ExcelCell>>,
+    /// 下一个尚未判定的行
+    next: usize,
+    body: Vec<(SortKey, usize)>,
+}
+
+impl<'a> ViewBuilder<'a> {
+    fn new(
+        header: usize,
+        total: usize,
+        sort: Option<&'a SortSpec>,
+        filters: &'a [FilterSpec],
+    ) -> Self {
+        let mut cols: Vec<usize> = filters
+            .iter()
+            .map(|f| f.col)
+            .chain(sort.map(|s| s.col))
+            .collect();
+        cols.sort_unstable();
+        cols.dedup();
+        let cur = vec![None; cols.len()];
+        Self {
+            header,
+            total,
+            sort,
+            filters,
+            cols,
+            row: None,
+            cur,
+            next: header,
+            body: Vec::new(),
+        }
+    }
+
+    fn visit(&mut self, r: usize, c: usize, cell: &ExcelCell) -> bool {
+        if r < self.header {
+            return true;
+        }
+        if r >= self.total {
+            return false;
+        }
+        if self.row != Some(r) {
+            self.flush();
+            self.blank_until(r);
+            self.row = Some(r);
+        }
+        if let Ok(i) = self.cols.binary_search(&c) {
+            self.cur[i] = Some(cell.clone());
+        }
+        true
+    }
+
+    fn flush(&mut self) {
+        let Some(r) = self.row.take() else {
+            return;
+        };
+        let cells = std::mem::replace(&mut self.cur, vec![None; self.cols.len()]);
+        let cell = |col: usize| {
+            self.cols
+                .binary_search(&col)
+                .ok()
+                .and_then(|i| cells[i].as_ref())
+                .unwrap_or(&EMPTY_CELL)
+        };
+        if self.filters.iter().all(|f| matches(cell(f.col), f)) {
+            let key = self
+                .sort
+                .map(|s| sort_key(cell(s.col)))
+                .unwrap_or(SortKey::Empty);
+            self.body.push((key, r));
+        }
+        self.next = r + 1;
+    }
+
+    /// [next, end) 之间的行没有单元格，结论都一样
+    fn blank_until(&mut self, end: usize) {
+        if self.next < end && self.filters.iter().all(|f| matches(&EMPTY_CELL, f)) {
+            self.body
+                .extend((self.next..end).map(|r| (SortKey::Empty, r)));
+        }
+        self.next = self.next.max(end);
+    }
+
+    fn finish(mut self) -> Vec<usize> {
+        self.flush();
+        self.blank_until(self.total);
+        if let Some(sort) = self.sort {
+            // 稳定排序，键相同的保持原表顺序
+            self.body
+                .sort_by(|(a, _), (b, _)| compare_keys(a, b, sort.descending));
+        }
+        (0..self.header)
+            .chain(self.body.into_iter().map(|(_, r)| r))
+            .collect()
+    }
+}
+
+fn matches(cell: &ExcelCell, f: &FilterSpec) -> bool {
+    let text = cell.display.to_lowercase();
+    let needle = f.value.trim().to_lowercase();
+    match f.op {
+        FilterOp::Contains => text.contains(&needle),
+        FilterOp::NotContains => !text.contains(&needle),
+        FilterOp::Equals => equals(cell, &text, &needle),
+        FilterOp::NotEquals => !equals(cell, &text, &needle),
+        FilterOp::StartsWith => text.starts_with(&needle),
+        FilterOp::EndsWith => text.ends_with(&needle),
+        FilterOp::Gt => compare_to(cell, &f.value) == Some(Ordering::Greater),
+        FilterOp::Gte => matches!(
+            compare_to(cell, &f.value),
+            Some(Ordering::Greater | Ordering::Equal)
+        ),
+        FilterOp::Lt => compare_to(cell, &f.value) == Some(Ordering::Less),
+        FilterOp::Lte => matches!(
+            compare_to(cell, &f.value),
+            Some(Ordering::Less | Ordering::Equal)
+        ),
+        FilterOp::Empty => cell.display.trim().is_empty(),
+        FilterOp::NotEmpty => !cell.display.trim().is_empty(),
+    }
+}
+
+/// 显示文本相同，或数值/日期相等（"1,000" 与 1000、"2024/1/5" 与 2024-01-05）
+fn equals(cell: &ExcelCell, text: &str, needle: &str) -> bool {
+    text.trim() == needle || compare_to(cell, needle) == Some(Ordering::Equal)
+}
+
+fn compare_to(cell: &ExcelCell, target: &str) -> Option<Ordering> {
+    if let (Some(a), Some(b)) = (cell_number(cell), parse_number(target)) {
+        return a.partial_cmp(&b);
+    }
+    if let (Some(a), Some(b)) = (cell_datetime(cell), parse_datetime(target)) {
+        return Some(a.cmp(&b));
+    }
+    None
+}
+
+fn cell_number(cell: &ExcelCell) -> Option<f64> {
+    match cell.kind {
+        CellKind::Number => cell.value.as_f64(),
+        // 降级解析的表格全是文本
+        CellKind::String => parse_number(&cell.display),
+        _ => None,
+    }
+}
+
+fn cell_datetime(cell: &ExcelCell) -> Option<NaiveDateTime> {
+    match cell.kind {
+        CellKind::Date => cell.value.as_str().and_then(parse_datetime),
+        CellKind::String => parse_datetime(&cell.display),
+        _ => None,
+    }
+}
+
+fn parse_number(s: &str) -> Option<f64> {
+    let s = s.trim().replace(',', "");
+    if s.is_empty() {
+        return None;
+    }
+    if let Some(p) = s.strip_suffix('%') {
+        return p.trim().parse::<f64>().ok().map(|v| v / 100.0);
+    }
+    s.parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+/// 2024-01-05 / 2024/1/5 / 2024年1月5日 / 2024-01-05T08:30:00
+fn parse_datetime(s: &str) -> Option<NaiveDateTime> {
+    let s = s
+        .trim()
+        .replace(['/', '.', '年', '月'], "-")
+        .replace('日', "")
+        .replace('T', " ");
+    for fmt in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(&s, fmt) {
+            return Some(dt);
+        }
+    }
+    NaiveDate::parse_from_str(&s, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+}
+
+/// 与 Excel 一致：数字 < 日期 < 文本 < 布尔 < 错误，空单元格无论升降序都在最后
+enum SortKey {
+    Number(f64),
+    Date(NaiveDateTime),
+    Text(String),
+    Bool(bool),
+    Error(String),
+    Empty,
+}
+
+impl SortKey {
+    fn rank(&self) -> u8 {
+        match self {
+            SortKey::Number(_) => 0,
+            SortKey::Date(_) => 1,
+            SortKey::Text(_) => 2,
+            SortKey::Bool(_) => 3,
+            SortKey::Error(_) => 4,
+            SortKey::Empty => 5,
+        }
+    }
+}
+
+fn sort_key(cell: &ExcelCell) -> SortKey {
+    if cell.display.trim().is_empty() {
+        return SortKey::Empty;
+    }
+    match cell.kind {
+        CellKind::Bool => return SortKey::Bool(cell.value.as_bool().unwrap_or(false)),
+        CellKind::Error => return SortKey::Error(cell.display.clone()),
+        _ => {}
+    }
+    if let Some(n) = cell_number(cell) {
+        return SortKey::Number(n);
+    }
+    if let Some(dt) = cell_datetime(cell) {
+        return SortKey::Date(dt);
+    }
+    SortKey::Text(text_sort_key(&cell.display))
+}
+
+/// 中文按拼音排序（与中文版 Excel 默认一致），其余字符不区分大小写
+fn text_sort_key(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() * 2);
+    for c in s.trim().chars() {
+        match c.to_pinyin() {
+            Some(p) => {
+                out.push_str(p.plain());
+                // 音节之间留分隔，避免 "xian" 与 "xi an" 混排
+                out.push(' ');
+            }
+            None => out.extend(c.to_lowercase()),
+        }
+    }
+    out
+}
+
+fn compare_keys(a: &SortKey, b: &SortKey, descending: bool) -> Ordering {
+    let (ra, rb) = (a.rank(), b.rank());
+    if ra != rb {
+        // 空单元格始终在最后；其余类型的先后随升降序整体翻转
+        if matches!(a, SortKey::Empty) || matches!(b, SortKey::Empty) {
+            return ra.cmp(&rb);
+        }
+        return if descending { rb.cmp(&ra) } else { ra.cmp(&rb) };
+    }
+    let ord = match (a, b) {
+        (SortKey::Number(x), SortKey::Number(y)) => x.partial_cmp(y).unwrap_or(Ordering::Equal),
+        (SortKey::Date(x), SortKey::Date(y)) => x.cmp(y),
+        (SortKey::Text(x), SortKey::Text(y)) => x.cmp(y),
+        (SortKey::Bool(x), SortKey::Bool(y)) => x.cmp(y),
+        (SortKey::Error(x), SortKey::Error(y)) => x.cmp(y),
+        _ => Ordering::Equal,
+    };
+    if descending {
+        ord.reverse()
+    } else {
+        ord
+    }
+}
+
+#[tauri::command]
+pub fn query_excel_sheet(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    req: ExcelQueryReq,
+) -> Result<ExcelQueryResp, String> {
+    query_excel_sheet_impl(&app, &state, req).map_err(db::err_to_string)
+}
+
+fn query_excel_sheet_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    req: ExcelQueryReq,
+) -> Result<ExcelQueryResp> {
+    if req.row_end <= req.row_start || req.col_end <= req.col_start {
+        return Err(anyhow!("无效的范围"));
+    }
+    let (source, rows) = load_view(app, state, &req.view)?;
+    let source_rows: Vec<usize> = rows
+        .iter()
+        .skip(req.row_start)
+        .take(req.row_end - req.row_start)
+        .copied()
+        .collect();
+    let window = source.read_rows(req.row_start, &source_rows, req.col_start..req.col_end)?;
+
+    Ok(ExcelQueryResp {
+        total_rows: rows.len(),
+        source_rows,
+        window,
+    })
+}
+
+/// 在所有 sheet 中查找显示文本包含 query 的单元格（不区分大小写）
+#[tauri::command]
+pub fn find_in_excel(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    file_id: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<ExcelFindResp, String> {
+    find_in_excel_impl(&app, &state, &file_id, &query, limit).map_err(db::err_to_string)
+}
+
+fn find_in_excel_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    file_id: &str,
+    query: &str,
+    limit: Option<usize>,
+) -> Result<ExcelFindResp> {
+    let needle = query.trim().to_lowercase();
+    if needle.is_empty() {
+        return Ok(ExcelFindResp {
+            hits: Vec::new(),
+            truncated: false,
+        });
+    }
+    let mut finder = Finder {
+        needle,
+        limit: limit.unwrap_or(DEFAULT_FIND_LIMIT).max(1),
+        hits: Vec::new(),
+    };
+
+    // 有单元格索引时逐行扫描磁盘索引，不把整表读进内存
+    if let Some(index) = excel_index::for_file(app, state, file_id) {
+        for sheet in index.sheet_infos() {
+            index.for_each_cell(&sheet.name, &mut |r, c, cell| {
+                finder.visit(&sheet.name, r, c, cell)
+            })?;
+            if finder.full() {
+                break;
+            }
+        }
+        return Ok(finder.finish());
+    }
+
+    let info = excel_preview::get_excel_sheet_info_impl(app, state, file_id)?;
+    'sheets: for sheet in &info.sheets {
+        let table = match read_table(app, state, file_id, sheet) {
+            Ok(t) => t,
+            Err(e) => {
+                eprintln!("查找时读取sheet失败 {}: {e:#}", sheet.name);
+                continue;
+            }
+        };
+        for (r, row) in table.cells.iter().enumerate() {
+            for (c, cell) in row.iter().enumerate() {
+                if !finder.visit(&sheet.name, r, c, cell) {
+                    break 'sheets;
+                }
+            }
+        }
+    }
+    Ok(finder.finish())
+}
+
+struct Finder {
+    needle: String,
+    limit: usize,
+    hits: Vec<ExcelFindHit>,
+}
+
+impl Finder {
+    /// 多收一条用来判断是否截断；返回 false 表示不用再找
+    fn visit(&mut self, sheet_name: &str, row: usize, col: usize, cell: &ExcelCell) -> bool {
+        if self.full() {
+            return false;
+        }
+        if cell.display.to_lowercase().contains(&self.needle) {
+            self.hits.push(ExcelFindHit {
+                sheet_name: sheet_name.to_string(),
+                row,
+                col,
+                cell_ref: format!("{}{}", xlsx_sheet::col_name(col), row + 1),
+                display: cell.display.clone(),
+            });
+        }
+        !self.full()
+    }
+
+    fn full(&self) -> bool {
+        self.hits.len() > self.limit
+    }
+
+    fn finish(mut self) -> ExcelFindResp {
+        let truncated = self.full();
+        self.hits.truncate(self.limit);
+        ExcelFindResp {
+            hits: self.hits,
+            truncated,
+        }
+    }
+}
+
+/// 导出当前排序/筛选结果；返回导出的行数，取消保存时为 0
+#[tauri::command]
+pub fn export_excel_range(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    req: ExcelExportReq,
+) -> Result<usize, String> {
+    export_excel_range_impl(&app, &state, req).map_err(db::err_to_string)
+}
+
+fn export_excel_range_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    req: ExcelExportReq,
+) -> Result<usize> {
+    let (ext, filter_name) = match req.format {
+        ExportFormat::Csv => ("csv", "CSV 文件"),
+        ExportFormat::Xlsx => ("xlsx", "Excel 工作簿"),
+    };
+    let path = match req.path {
+        Some(p) => PathBuf::from(p),
+        None => match rfd::FileDialog::new()
+            .add_filter(filter_name, &[ext])
+            .set_file_name(format!("{}.{ext}", req.view.sheet_name))
+            .save_file()
+        {
+            Some(p) => p,
+            None => return Ok(0),
+        },
+    };
+
+    let (source, rows) = load_view(app, state, &req.view)?;
+    let width = source.info.cols;
+    let col_start = req.col_start.unwrap_or(0);
+    let col_end = req.col_end.unwrap_or(width).min(width).max(col_start);
+    let out = if col_end > col_start {
+        source.read_rows(0, &rows, col_start..col_end)?.cells
+    } else {
+        vec![Vec::new(); rows.len()]
+    };
+
+    match req.format {
+        ExportFormat::Csv => write_csv(&path, &out)?,
+        ExportFormat::Xlsx => write_xlsx(&path, &req.view.sheet_name, &out)?,
+    }
+    Ok(out.len())
+}
+
+/// UTF-8 带 BOM，Excel 直接打开中文不乱码；内容为显示文本
+fn write_csv(path: &Path, rows: &[Vec<ExcelCell>]) -> Result<()> {
+    let mut text = String::from('\u{FEFF}');
+    for row in rows {
+        let line = row
+            .iter()
+            .map(|c| csv_field(&c.display))
+            .collect::<Vec<_>>()
+            .join(",");
+        text.push_str(&line);
+        text.push_str("\r\n");
+    }
+    fs::write(path, text).with_context(|| format!("写入文件失败: {}", path.display()))
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// 最小 xlsx：单个 sheet，数字/布尔保留类型，其余写显示文本；不含公式与样式
+fn write_xlsx(path: &Path, sheet_name: &str, rows: &[Vec<ExcelCell>]) -> Result<()> {
+    let mut sheet = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>"#,
+    );
+    for (r, row) in rows.iter().enumerate() {
+        sheet.push_str(&format!(r#"<row r="{}">"#, r + 1));
+        for (c, cell) in row.iter().enumerate() {
+            let cell_ref = format!("{}{}", xlsx_sheet::col_name(c), r + 1);
+            match cell.kind {
+                CellKind::Empty => {}
+                CellKind::Number if cell.value.as_f64().is_some() => {
+                    let v = cell.value.as_f64().unwrap_or(0.0);
+                    sheet.push_str(&format!(r#"<c r="{cell_ref}"><v>{v}</v></c>"#));
+                }
+                CellKind::Bool => {
+                    let v = cell.value.as_bool().unwrap_or(false) as u8;
+                    sheet.push_str(&format!(r#"<c r="{cell_ref}" t="b"><v>{v}</v></c>"#));
+                }
+                _ if cell.display.is_empty() => {}
+                _ => sheet.push_str(&format!(
+                    r#"<c r="{cell_ref}" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+                    xml_escape(&cell.display)
+                )),
+            }
+        }
+        sheet.push_str("</row>");
+    }
+    sheet.push_str("</sheetData></worksheet>");
+
+    let workbook = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="{}" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+        xml_escape(&export_sheet_name(sheet_name))
+    );
+    let parts: [(&str, &str); 5] = [
+        (
+            "[Content_Types].xml",
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/></Types>"#,
+        ),
+        (
+            "_rels/.rels",
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#,
+        ),
+        ("xl/workbook.xml", &workbook),
+        (
+            "xl/_rels/workbook.xml.rels",
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/></Relationships>"#,
+        ),
+        ("xl/worksheets/sheet1.xml", &sheet),
+    ];
+
+    let f = fs::File::create(path).with_context(|| format!("写入文件失败: {}", path.display()))?;
+    let mut zip = ZipWriter::new(f);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, body) in parts {
+        zip.start_file(name, options)?;
+        zip.write_all(body.as_bytes())?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+/// sheet 名最长 31 字符，不能含 []:*?/\
+fn export_sheet_name(name: &str) -> String {
+    let s: String = name
+        .chars()
+        .filter(|c| !matches!(c, '[' | ']' | ':' | '*' | '?' | '/' | '\\'))
+        .take(31)
+        .collect();
+    if s.trim().is_empty() {
+        "Sheet1".to_string()
+    } else {
+        s
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\t' | '\n' | '\r' => out.push(ch),
+            // XML 1.0 不允许的控制字符
+            c if (c as u32) < 0x20 => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
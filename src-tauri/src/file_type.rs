@@ -0,0 +1,306 @@
+use crate::db;
+use anyhow::Result;
+use rusqlite::{params, Connection};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// 识别类型时读取的文件头长度（足够覆盖 ZIP 第一个条目名、ftyp 品牌等）
+pub const SNIFF_BYTES: usize = 4096;
+
+/// 分类规则变化时递增，init_db 据此对旧库重新分类
+const FILE_TYPE_VERSION: &str = "5";
+const META_FILE_TYPE_VERSION: &str = "file_type_version";
+
+// 附件 file_type 取值（前端按这些值选预览器与图标）
+pub const PDF: &str = "pdf";
+pub const WORD: &str = "word";
+pub const DOCX: &str = "docx_other";
+pub const WPS: &str = "wps";
+pub const EXCEL: &str = "excel";
+pub const PPT: &str = "ppt";
+pub const OFD: &str = "ofd";
+pub const CEB: &str = "ceb";
+pub const UOF: &str = "uof";
+pub const TEXT: &str = "text";
+pub const IMAGE: &str = "image";
+pub const AUDIO: &str = "audio";
+pub const VIDEO: &str = "video";
+pub const ZIP_CHILD: &str = "zip_child";
+pub const ARCHIVE: &str = "archive";
+pub const OTHER: &str = "other";
+
+/// 文件头 + 扩展名判断类型：内容能确定大类时以内容为准，
+/// 同一容器格式（ZIP/OLE）下再按扩展名或内部条目细分；识别不了内容时才只看扩展名。
+pub fn detect(name: &str, head: &[u8]) -> &'static str {
+    let ext = extension(name);
+    match sniff(head) {
+        Some(Sniffed::Zip) => zip_subtype(&ext, head),
+        Some(Sniffed::Ole) => ole_subtype(&ext),
+        Some(Sniffed::Type(t)) => t,
+        None => {
+            let by_name = from_extension(&ext);
+            // 声称是文本但内容明显是二进制的，不当文本处理
+            if by_name == TEXT && !head.is_empty() && !looks_like_text(head) {
+                OTHER
+            } else if ext.is_empty() && !head.is_empty() && looks_like_text(head) {
+                // 没有扩展名的纯文本（导出日志、数据文件等）
+                TEXT
+            } else {
+                by_name
+            }
+        }
+    }
+}
+
+/// 仅按扩展名判断（拿不到文件内容时使用）
+pub fn from_name(name: &str) -> &'static str {
+    from_extension(&extension(name))
+}
+
+pub(crate) fn extension(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    match base.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => ext.to_ascii_lowercase(),
+        _ => String::new(),
+    }
+}
+
+fn from_extension(ext: &str) -> &'static str {
+    match ext {
+        "pdf" => PDF,
+        "doc" | "dot" | "rtf" => WORD,
+        "docx" | "docm" | "dotx" => DOCX,
+        "wps" | "wpt" => WPS,
+        "xls" | "xlsx" | "xlsm" | "xlsb" | "xlt" | "xltx" | "et" | "ett" | "ods" => EXCEL,
+        "ppt" | "pptx" | "pps" | "ppsx" | "pot" | "potx" | "dps" | "dpt" | "odp" => PPT,
+        "ofd" => OFD,
+        "ceb" => CEB,
+        "uof" | "uot" | "uos" | "uop" => UOF,
+        "txt" | "text" | "csv" | "tsv" | "tab" | "md" | "log" | "json" | "xml" | "htm" | "html"
+        | "ini" => TEXT,
+        "png" | "jpg" | "jpeg" | "jpe" | "gif" | "bmp" | "tif" | "tiff" | "webp" | "heic"
+        | "heif" | "svg" => IMAGE,
+        "mp3" | "wav" | "wma" | "m4a" | "aac" | "flac" | "ogg" | "amr" => AUDIO,
+        "mp4" | "m4v" | "mov" | "avi" | "wmv" | "mkv" | "webm" | "flv" | "3gp" | "mpg" | "mpeg" => {
+            VIDEO
+        }
+        "zip" => ZIP_CHILD,
+        "rar" | "7z" | "gz" | "tgz" | "bz2" | "xz" | "tar" => ARCHIVE,
+        _ => OTHER,
+    }
+}
+
+enum Sniffed {
+    Zip,
+    Ole,
+    Type(&'static str),
+}
+
+fn sniff(h: &[u8]) -> Option<Sniffed> {
+    let at = |off: usize, sig: &[u8]| h.len() >= off + sig.len() && &h[off..off + sig.len()] == sig;
+
+    if at(0, b"PK\x03\x04") {
+        return Some(Sniffed::Zip);
+    }
+    if at(0, &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1]) {
+        return Some(Sniffed::Ole);
+    }
+    // UTF-16 BOM（FF FE / FE FF）会被当成 MP3 帧同步，交给扩展名与文本判断
+    if at(0, &[0xFF, 0xFE]) || at(0, &[0xFE, 0xFF]) {
+        return None;
+    }
+    let t = if at(0, b"%PDF-") {
+        PDF
+    } else if at(0, b"{\\rtf") {
+        WORD
+    } else if at(0, &[0xFF, 0xD8, 0xFF])
+        || at(0, b"\x89PNG\r\n\x1a\n")
+        || at(0, b"GIF87a")
+        || at(0, b"GIF89a")
+        || at(0, b"II*\0")
+        || at(0, b"MM\0*")
+        // BMP 文件头的两个保留字段为 0，用来排除以 "BM" 开头的文本
+        || (at(0, b"BM") && at(6, &[0, 0, 0, 0]))
+        || (at(0, b"RIFF") && at(8, b"WEBP"))
+    {
+        IMAGE
+    } else if at(4, b"ftyp") {
+        ftyp_type(h)
+    } else if at(0, b"ID3")
+        || mpeg_audio_frame(h)
+        || at(0, b"fLaC")
+        || at(0, b"OggS")
+        || (at(0, b"RIFF") && at(8, b"WAVE"))
+        || at(0, b"#!AMR")
+    {
+        AUDIO
+    } else if (at(0, b"RIFF") && at(8, b"AVI "))
+        || at(0, &[0x1A, 0x45, 0xDF, 0xA3])
+        || at(0, b"FLV\x01")
+        || at(0, &[0x00, 0x00, 0x01, 0xBA])
+    {
+        VIDEO
+    } else if at(0, &[0x30, 0x26, 0xB2, 0x75, 0x8E, 0x66, 0xCF, 0x11]) {
+        // ASF 容器：wma 与 wmv 共用，无法从文件头区分
+        return None;
+    } else if at(0, b"Rar!\x1A\x07")
+        || at(0, &[0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C])
+        || at(0, &[0x1F, 0x8B])
+        || at(0, b"BZh")
+        || at(0, &[0xFD, b'7', b'z', b'X', b'Z', 0x00])
+    {
+        ARCHIVE
+    } else {
+        return None;
+    };
+    Some(Sniffed::Type(t))
+}
+
+/// MPEG 音频帧头：帧同步 + 合法的 layer、码率与采样率索引
+fn mpeg_audio_frame(h: &[u8]) -> bool {
+    h.len() >= 3
+        && h[0] == 0xFF
+        && (h[1] & 0xE0) == 0xE0
+        && (h[1] & 0x06) != 0
+        && (h[2] >> 4) != 0x0F
+        && ((h[2] >> 2) & 0x03) != 0x03
+}
+
+/// ISO BMFF：按主品牌区分图片（HEIF）、音频（M4A）与视频
+fn ftyp_type(h: &[u8]) -> &'static str {
+    let brand = h.get(8..12).unwrap_or_default();
+    match brand {
+        b"heic" | b"heix" | b"heim" | b"heis" | b"mif1" | b"msf1" | b"avif" => IMAGE,
+        b"M4A " | b"M4B " | b"F4A " => AUDIO,
+        _ => VIDEO,
+    }
+}
+
+/// ZIP 容器：扩展名能说明的按扩展名；否则看第一个条目名（OOXML/OFD 都有固定目录结构）。
+/// 只有 .zip 才当作待展开的子 ZIP，其余认不出的（jar/apk/epub/odt 等）归为压缩包
+fn zip_subtype(ext: &str, head: &[u8]) -> &'static str {
+    match from_extension(ext) {
+        t @ (DOCX | EXCEL | PPT | OFD | UOF | ZIP_CHILD) => return t,
+        // 扩展名写成 .doc/.wps 但实为 OOXML 的情况很常见
+        WORD | WPS => {
+            if contains(head, b"word/") || contains(head, b"[Content_Types].xml") {
+                return DOCX;
+            }
+        }
+        _ => {}
+    }
+    if contains(head, b"word/") {
+        DOCX
+    } else if contains(head, b"xl/") {
+        EXCEL
+    } else if contains(head, b"ppt/") {
+        PPT
+    } else if contains(head, b"OFD.xml") || contains(head, b"Doc_0/") {
+        OFD
+    } else if contains(head, b"uof:") {
+        UOF
+    } else {
+        ARCHIVE
+    }
+}
+
+/// OLE 复合文档：Word/Excel/PowerPoint 与 WPS 旧格式共用，只能靠扩展名细分
+fn ole_subtype(ext: &str) -> &'static str {
+    match from_extension(ext) {
+        t @ (WORD | WPS | EXCEL | PPT) => t,
+        // 扩展名丢失时多数是 Word 文档；其他扩展名（如 .msg）不猜
+        _ if ext.is_empty() => WORD,
+        _ => OTHER,
+    }
+}
+
+fn contains(hay: &[u8], needle: &[u8]) -> bool {
+    hay.windows(needle.len()).any(|w| w == needle)
+}
+
+/// 没有 NUL 且能按 UTF-8 或 GBK 解出来的视为文本
+fn looks_like_text(head: &[u8]) -> bool {
+    if head.contains(&0) {
+        // UTF-16 带 BOM 的文本
+        return head.starts_with(&[0xFF, 0xFE]) || head.starts_with(&[0xFE, 0xFF]);
+    }
+    // 头部截断可能切在多字节字符中间：只有末尾不完整也算合法 UTF-8
+    let valid = match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    };
+    valid || !encoding_rs::GBK.decode_without_bom_handling(head).1
+}
+
+/// 规则升级后对已有附件重新分类：已解压到缓存的按内容判断，其余按文件名
+pub fn ensure_reclassified(conn: &Connection, root: &Path) -> Result<()> {
+    if !reclassify_pending(conn)? {
+        return Ok(());
+    }
+    let mut updates = Vec::new();
+    {
+        let mut stmt = conn.prepare(
+            // 主文内嵌对象的 virtual_path 是 oleObject*.bin，类型以导入时拆包结果为准
+            "SELECT a.file_id, a.display_name, a.virtual_path, a.file_type, a.cached_path,
+                    EXISTS(SELECT 1 FROM attachments c
+                           WHERE c.archive_id=a.archive_id AND c.container_virtual_path=a.virtual_path
+                             AND c.container_kind='zip')
+             FROM attachments a
+             WHERE a.container_kind<>'main_docx'",
+        )?;
+        let rows = stmt.query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+                r.get::<_, String>(3)?,
+                r.get::<_, Option<String>>(4)?,
+                r.get::<_, bool>(5)?,
+            ))
+        })?;
+        for row in rows {
+            let (file_id, display_name, virtual_path, old, cached_path, expanded) = row?;
+            let head = cached_path
+                .map(|rel| read_head(&root.join(rel)))
+                .unwrap_or_default();
+            // 子ZIP内附件的 display_name 带 [xxx.zip]/ 前缀，扩展名以 virtual_path 为准
+            let name = if extension(&virtual_path).is_empty() {
+                display_name
+            } else {
+                virtual_path
+            };
+            let new = if head.is_empty() {
+                from_name(&name)
+            } else {
+                detect(&name, &head)
+            };
+            // 已展开过的子ZIP保持原类型，避免与导入时的展开结果不一致
+            if new != old && !(old == ZIP_CHILD && expanded) {
+                updates.push((file_id, new));
+            }
+        }
+    }
+    let tx = conn.unchecked_transaction()?;
+    for (file_id, file_type) in updates {
+        tx.execute(
+            "UPDATE attachments SET file_type=? WHERE file_id=?",
+            params![file_type, file_id],
+        )?;
+    }
+    db::set_meta_value(&tx, META_FILE_TYPE_VERSION, FILE_TYPE_VERSION)?;
+    tx.commit()?;
+    Ok(())
+}
+
+pub(crate) fn reclassify_pending(conn: &Connection) -> Result<bool> {
+    Ok(db::get_meta_value(conn, META_FILE_TYPE_VERSION)?.as_deref() != Some(FILE_TYPE_VERSION))
+}
+
+fn read_head(path: &Path) -> Vec<u8> {
+    let mut head = Vec::new();
+    if let Ok(f) = fs::File::open(path) {
+        let _ = f.take(SNIFF_BYTES as u64).read_to_end(&mut head);
+    }
+    head
+}
@@ -0,0 +1,648 @@
+use crate::search::{self, ArchiveFilter, Range, SearchResult};
+use anyhow::Result;
+use pinyin::ToPinyinMulti;
+use rusqlite::{params, Connection};
+use std::collections::HashSet;
+
+// 全拼音节表：用于把连续输入的拼音（如 zhangsan）切分成音节
+const PINYIN_SYLLABLES: &str = "a ai an ang ao \
+ba bai ban bang bao bei ben beng bi bian biao bie bin bing bo bu \
+ca cai can cang cao ce cen ceng cha chai chan chang chao che chen cheng chi chong chou chu chua chuai chuan chuang chui chun chuo ci cong cou cu cuan cui cun cuo \
+da dai dan dang dao de dei den deng di dia dian diao die ding diu dong dou du duan dui dun duo \
+e ei en eng er \
+fa fan fang fei fen feng fo fou fu \
+ga gai gan gang gao ge gei gen geng gong gou gu gua guai guan guang gui gun guo \
+ha hai han hang hao he hei hen heng hong hou hu hua huai huan huang hui hun huo \
+ji jia jian jiang jiao jie jin jing jiong jiu ju juan jue jun \
+ka kai kan kang kao ke kei ken keng kong kou ku kua kuai kuan kuang kui kun kuo \
+la lai lan lang lao le lei leng li lia lian liang liao lie lin ling liu lo long lou lu luan lun luo lv lve \
+ma mai man mang mao me mei men meng mi mian miao mie min ming miu mo mou mu \
+na nai nan nang nao ne nei nen neng ni nian niang niao nie nin ning niu nong nou nu nuan nun nuo nv nve \
+o ou \
+pa pai pan pang pao pei pen peng pi pian piao pie pin ping po pou pu \
+qi qia qian qiang qiao qie qin qing qiong qiu qu quan que qun \
+ran rang rao re ren reng ri rong rou ru rua ruan rui run ruo \
+sa sai san sang sao se sen seng sha shai shan shang shao she shei shen sheng shi shou shu shua shuai shuan shuang shui shun shuo si song sou su suan sui sun suo \
+ta tai tan tang tao te tei teng ti tian tiao tie ting tong tou tu tuan tui tun tuo \
+wa wai wan wang wei wen weng wo wu \
+xi xia xian xiang xiao xie xin xing xiong xiu xu xuan xue xun \
+ya yan yang yao ye yi yin ying yo yong you yu yuan yue yun \
+za zai zan zang zao ze zei zen zeng zha zhai zhan zhang zhao zhe zhei zhen zheng zhi zhong zhou zhu zhua zhuai zhuan zhuang zhui zhun zhuo zi zong zou zu zuan zui zun zuo";
+
+/// 最长的音节（zhuang/chuang/shuang）
+const MAX_SYLLABLE_LEN: usize = 6;
+/// 拼音打错一个字母时最多尝试的变体数，长查询的变体数随长度线性增长
+const MAX_EDIT_VARIANTS: usize = 600;
+
+const KIND_MAIN_DOC_FIELD: &str = "main_doc_field";
+const KIND_ATTACHMENT_NAME: &str = "attachment_name";
+
+/// 进入拼音索引的主文字段（正文太长，不做拼音）
+const INDEXED_FIELDS: [&str; 2] = ["instruction_no", "title"];
+
+fn syllables() -> HashSet<&'static str> {
+    PINYIN_SYLLABLES.split_whitespace().collect()
+}
+
+fn char_readings(c: char) -> Vec<String> {
+    match c.to_pinyin_multi() {
+        Some(multi) => {
+            let mut out: Vec<String> = multi
+                .into_iter()
+                .map(|p| p.plain().replace('ü', "v"))
+                .collect();
+            out.dedup();
+            out
+        }
+        None => vec![],
+    }
+}
+
+/// 文本 -> (全拼列, 首字母列)。汉字按音节拆开，拉丁字母/数字按词保留（小写）。
+pub fn build_pinyin_text(text: &str) -> (String, String) {
+    let mut full = Vec::new();
+    let mut initials = Vec::new();
+    let mut word = String::new();
+    let flush = |word: &mut String, full: &mut Vec<String>, initials: &mut Vec<String>| {
+        if !word.is_empty() {
+            full.push(word.clone());
+            initials.push(word.clone());
+            word.clear();
+        }
+    };
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            word.push(c.to_ascii_lowercase());
+            continue;
+        }
+        flush(&mut word, &mut full, &mut initials);
+        if let Some(first) = char_readings(c).into_iter().next() {
+            if let Some(i) = first.chars().next() {
+                initials.push(i.to_string());
+            }
+            full.push(first);
+        }
+    }
+    flush(&mut word, &mut full, &mut initials);
+    (full.join(" "), initials.join(" "))
+}
+
+pub fn index_main_doc_fields(
+    conn: &Connection,
+    archive_id: &str,
+    instruction_no: &str,
+    title: &str,
+) -> Result<()> {
+    conn.execute(
+        "DELETE FROM pinyin_fts WHERE archive_id=? AND kind=?",
+        params![archive_id, KIND_MAIN_DOC_FIELD],
+    )?;
+    let mut stmt = conn.prepare(
+        "INSERT INTO pinyin_fts(archive_id,kind,ref_id,full_text,initials_text,source_text) VALUES(?,?,?,?,?,?)",
+    )?;
+    for (name, text) in INDEXED_FIELDS.iter().zip([instruction_no, title]) {
+        let (full, initials) = build_pinyin_text(text);
+        stmt.execute(params![
+            archive_id,
+            KIND_MAIN_DOC_FIELD,
+            name,
+            full,
+            initials,
+            text
+        ])?;
+    }
+    Ok(())
+}
+
+pub fn index_attachment_name(
+    conn: &Connection,
+    archive_id: &str,
+    file_id: &str,
+    display_name: &str,
+) -> Result<()> {
+    let (full, initials) = build_pinyin_text(display_name);
+    conn.execute(
+        "INSERT INTO pinyin_fts(archive_id,kind,ref_id,full_text,initials_text,source_text) VALUES(?,?,?,?,?,?)",
+        params![
+            archive_id,
+            KIND_ATTACHMENT_NAME,
+            file_id,
+            full,
+            initials,
+            display_name
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn delete_archive(conn: &Connection, archive_id: &str) -> Result<()> {
+    conn.execute("DELETE FROM pinyin_fts WHERE archive_id=?", [archive_id])?;
+    Ok(())
+}
+
+/// 旧库没有拼音索引：行数对不上时整体重建（数据量只有标题/编号/附件名，开销可控）
+pub fn ensure_pinyin_fts_synced(conn: &Connection) -> Result<()> {
+    let main_cnt: i64 = conn.query_row("SELECT COUNT(1) FROM main_doc", [], |r| r.get(0))?;
+    let att_cnt: i64 = conn.query_row("SELECT COUNT(1) FROM attachments", [], |r| r.get(0))?;
+    let fts_cnt: i64 = conn
+        .query_row("SELECT COUNT(1) FROM pinyin_fts", [], |r| r.get(0))
+        .unwrap_or(0);
+    let expected = main_cnt * INDEXED_FIELDS.len() as i64 + att_cnt;
+    if expected != fts_cnt {
+        rebuild_pinyin_fts(conn)?;
+    }
+    Ok(())
+}
+
+pub fn rebuild_pinyin_fts(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM pinyin_fts", [])?;
+    let mut rows = Vec::new();
+    {
+        let mut stmt = conn.prepare("SELECT archive_id, instruction_no, title FROM main_doc")?;
+        let it = stmt.query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+            ))
+        })?;
+        for row in it {
+            rows.push(row?);
+        }
+    }
+    for (archive_id, instruction_no, title) in rows {
+        index_main_doc_fields(conn, &archive_id, &instruction_no, &title)?;
+    }
+    let mut rows = Vec::new();
+    {
+        let mut stmt = conn.prepare("SELECT archive_id, file_id, display_name FROM attachments")?;
+        let it = stmt.query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+            ))
+        })?;
+        for row in it {
+            rows.push(row?);
+        }
+    }
+    for (archive_id, file_id, display_name) in rows {
+        index_attachment_name(conn, &archive_id, &file_id, &display_name)?;
+    }
+    Ok(())
+}
+
+/// 把连续拼音切分成音节；最后一个音节允许是前缀（边输入边搜）。
+/// 从后往前按位置记下能切完时第一个音节的结束位置，每个位置只算一次；同一位置优先最长音节
+fn segment_pinyin(input: &str, table: &HashSet<&'static str>) -> Option<Vec<String>> {
+    if input.is_empty() || !input.chars().all(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
+    let n = input.len();
+    let mut next: Vec<Option<usize>> = vec![None; n + 1];
+    next[n] = Some(n);
+    for i in (0..n).rev() {
+        next[i] = (1..=(n - i).min(MAX_SYLLABLE_LEN))
+            .rev()
+            .map(|len| i + len)
+            .find(|&j| table.contains(&input[i..j]) && next[j].is_some())
+            // 末尾不完整音节
+            .or_else(|| {
+                (n - i <= MAX_SYLLABLE_LEN && table.iter().any(|t| t.starts_with(&input[i..])))
+                    .then_some(n)
+            });
+    }
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < n {
+        let j = next[i]?;
+        out.push(input[i..j].to_string());
+        i = j;
+    }
+    Some(out)
+}
+
+fn edit_distance_le1(a: &str, b: &str) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > 1 {
+        return false;
+    }
+    let (mut i, mut j, mut edits) = (0usize, 0usize, 0usize);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            i += 1;
+            j += 1;
+            continue;
+        }
+        edits += 1;
+        if edits > 1 {
+            return false;
+        }
+        // 相邻换位也算一次编辑
+        if i + 1 < a.len() && j + 1 < b.len() && a[i] == b[j + 1] && a[i + 1] == b[j] {
+            i += 2;
+            j += 2;
+        } else if a.len() > b.len() {
+            i += 1;
+        } else if a.len() < b.len() {
+            j += 1;
+        } else {
+            i += 1;
+            j += 1;
+        }
+    }
+    edits + (a.len() - i) + (b.len() - j) <= 1
+}
+
+/// 编辑距离为1的变体，按删除、换位、替换、插入的顺序惰性生成，最多取 cap 个
+fn edit1_variants(s: &str, cap: usize) -> Vec<String> {
+    let chars: Vec<char> = s.chars().collect();
+    let chars = &chars;
+    let n = chars.len();
+    let deletes = (0..n).map(|i| {
+        let mut v = chars.clone();
+        v.remove(i);
+        v
+    });
+    let swaps = (0..n.saturating_sub(1)).map(|i| {
+        let mut v = chars.clone();
+        v.swap(i, i + 1);
+        v
+    });
+    let replaces = (0..n).flat_map(|i| {
+        ('a'..='z').map(move |c| {
+            let mut v = chars.clone();
+            v[i] = c;
+            v
+        })
+    });
+    let inserts = (0..=n).flat_map(|i| {
+        ('a'..='z').map(move |c| {
+            let mut v = chars.clone();
+            v.insert(i, c);
+            v
+        })
+    });
+    let mut seen = HashSet::new();
+    deletes
+        .chain(swaps)
+        .chain(replaces)
+        .chain(inserts)
+        .map(|v| v.into_iter().collect::<String>())
+        .filter(|v| v != s && seen.insert(v.clone()))
+        .take(cap)
+        .collect()
+}
+
+/// 模糊检索计划：FTS 表达式 + 用于高亮回映射的拼音串
+struct FuzzyPlan {
+    match_query: String,
+    targets: Vec<String>,
+}
+
+fn phrase(column: &str, tokens: &[String], prefix: bool) -> String {
+    let body = tokens.join(" ").replace('"', "\"\"");
+    if prefix {
+        format!("{column} : \"{body}\" *")
+    } else {
+        format!("{column} : \"{body}\"")
+    }
+}
+
+fn build_fuzzy_plan(conn: &Connection, query: &str) -> Result<FuzzyPlan> {
+    let q = query.trim();
+    let mut clauses = Vec::new();
+    let mut targets = Vec::new();
+    if q.is_empty() {
+        return Ok(FuzzyPlan {
+            match_query: String::new(),
+            targets,
+        });
+    }
+
+    let has_cjk = q.chars().any(|c| !char_readings(c).is_empty());
+    if has_cjk {
+        // 汉字查询：转成音节短语，同音字（错别字）也能命中
+        let (full, _) = build_pinyin_text(q);
+        let tokens: Vec<String> = full.split_whitespace().map(|s| s.to_string()).collect();
+        if !tokens.is_empty() {
+            clauses.push(phrase("full_text", &tokens, false));
+            targets.push(tokens.concat());
+        }
+    } else {
+        let table = syllables();
+        let qn: String = q
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        if qn.is_empty() {
+            return Ok(FuzzyPlan {
+                match_query: String::new(),
+                targets,
+            });
+        }
+        targets.push(qn.clone());
+
+        match segment_pinyin(&qn, &table) {
+            Some(tokens) => clauses.push(phrase("full_text", &tokens, true)),
+            None => {
+                // 可能是拼音打错一个字母：在编辑距离1的变体里找可切分的
+                if qn.len() >= 3 && qn.chars().all(|c| c.is_ascii_lowercase()) {
+                    let mut accepted = 0usize;
+                    for v in edit1_variants(&qn, MAX_EDIT_VARIANTS) {
+                        if let Some(tokens) = segment_pinyin(&v, &table) {
+                            if tokens.len() < 2 {
+                                continue;
+                            }
+                            clauses.push(phrase("full_text", &tokens, false));
+                            targets.push(v);
+                            accepted += 1;
+                            if accepted >= 12 {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // 首字母缩写（如 aqjd）
+        if qn.len() <= 10 && qn.chars().all(|c| c.is_ascii_lowercase()) {
+            let letters: Vec<String> = qn.chars().map(|c| c.to_string()).collect();
+            clauses.push(phrase("initials_text", &letters, false));
+        }
+
+        // 拉丁单词拼写错误：在词表里找编辑距离为1的词
+        if qn.len() >= 4 {
+            let mut stmt = conn
+                .prepare("SELECT term FROM pinyin_fts_vocab WHERE length(term) BETWEEN ? AND ?")?;
+            let rows = stmt
+                .query_map(params![(qn.len() - 1) as i64, (qn.len() + 1) as i64], |r| {
+                    r.get::<_, String>(0)
+                })?;
+            let mut found = 0usize;
+            for row in rows {
+                let term = row?;
+                if term == qn || !edit_distance_le1(&term, &qn) {
+                    continue;
+                }
+                clauses.push(phrase("full_text", &[term.clone()], false));
+                targets.push(term);
+                found += 1;
+                if found >= 20 {
+                    break;
+                }
+            }
+            clauses.push(phrase("full_text", &[qn.clone()], false));
+        }
+    }
+
+    Ok(FuzzyPlan {
+        match_query: clauses.join(" OR "),
+        targets,
+    })
+}
+
+pub fn query_fuzzy(
+    conn: &Connection,
+    query: &str,
+    limit: usize,
+    allowed_archives: &Option<HashSet<String>>,
+    want_types: &Option<HashSet<String>>,
+) -> Result<Vec<SearchResult>> {
+    let plan = build_fuzzy_plan(conn, query)?;
+    if plan.match_query.is_empty() {
+        return Ok(vec![]);
+    }
+    // 档案范围在 SQL 里过滤，否则 LIMIT 先截断会漏掉范围内的命中
+    let (archive_clause, archive_params) =
+        match search::archive_in_clause("pinyin_fts.archive_id", allowed_archives)? {
+            ArchiveFilter::All => (String::new(), vec![]),
+            ArchiveFilter::Empty => return Ok(vec![]),
+            ArchiveFilter::In(sql, params) => (sql, params),
+        };
+    let sql = format!(
+        "SELECT pinyin_fts.archive_id, pinyin_fts.kind, pinyin_fts.ref_id, pinyin_fts.source_text, a.file_type
+        FROM pinyin_fts
+        JOIN main_doc m ON m.archive_id = pinyin_fts.archive_id
+        LEFT JOIN attachments a ON a.file_id = pinyin_fts.ref_id
+        WHERE pinyin_fts MATCH ? {archive_clause}
+        ORDER BY COALESCE(m.issued_at_ts, 0) DESC, pinyin_fts.archive_id ASC
+        LIMIT ?"
+    );
+    let mut bind: Vec<rusqlite::types::Value> = Vec::new();
+    bind.push(rusqlite::types::Value::from(plan.match_query.clone()));
+    bind.extend(archive_params);
+    bind.push(rusqlite::types::Value::from(limit as i64));
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(bind), |r| {
+        Ok((
+            r.get::<_, String>(0)?,
+            r.get::<_, String>(1)?,
+            r.get::<_, String>(2)?,
+            r.get::<_, String>(3)?,
+            r.get::<_, Option<String>>(4)?,
+        ))
+    })?;
+
+    let mut out = Vec::new();
+    for row in rows {
+        let (archive_id, kind, ref_id, source_text, file_type) = row?;
+        let highlights = pinyin_highlights_utf16(&source_text, &plan.targets);
+        if kind == KIND_MAIN_DOC_FIELD {
+            if let Some(want) = want_types {
+                if !want.contains("docx_main") {
+                    continue;
+                }
+            }
+            out.push(SearchResult::MainDocField {
+                archive_id,
+                field_name: ref_id,
+                source_text,
+                highlights,
+                best_block_id: None,
+                best_block_highlights: None,
+                truncated: false,
+            });
+        } else {
+            if let Some(want) = want_types {
+                if !file_type.map(|t| want.contains(&t)).unwrap_or(false) {
+                    continue;
+                }
+            }
+            out.push(SearchResult::AttachmentName {
+                archive_id,
+                file_id: ref_id,
+                display_name: source_text,
+                highlights,
+            });
+        }
+    }
+    Ok(out)
+}
+
+struct CharUnit {
+    utf16_start: usize,
+    utf16_end: usize,
+    readings: Vec<String>,
+}
+
+fn char_units(text: &str) -> Vec<CharUnit> {
+    let mut out = Vec::new();
+    let mut pos = 0usize;
+    for c in text.chars() {
+        let len = c.len_utf16();
+        let readings = if c.is_ascii_alphanumeric() {
+            vec![c.to_ascii_lowercase().to_string()]
+        } else {
+            char_readings(c)
+        };
+        out.push(CharUnit {
+            utf16_start: pos,
+            utf16_end: pos + len,
+            readings,
+        });
+        pos += len;
+    }
+    out
+}
+
+/// 从 units[ui] 开始消费 target[ti..]，每个字可用全拼或首字母；返回匹配结束的 unit 下标
+fn match_units(units: &[CharUnit], ui: usize, target: &str, ti: usize) -> Option<usize> {
+    if ti >= target.len() {
+        return Some(ui);
+    }
+    let unit = units.get(ui)?;
+    if unit.readings.is_empty() {
+        return None;
+    }
+    let rest = &target[ti..];
+    for r in &unit.readings {
+        if rest.starts_with(r.as_str()) {
+            if let Some(end) = match_units(units, ui + 1, target, ti + r.len()) {
+                return Some(end);
+            }
+        } else if r.starts_with(rest) {
+            // 查询末尾只输入了半个音节
+            return Some(ui + 1);
+        }
+    }
+    for r in &unit.readings {
+        if r.len() > 1 && rest.as_bytes()[0] == r.as_bytes()[0] {
+            if let Some(end) = match_units(units, ui + 1, target, ti + 1) {
+                return Some(end);
+            }
+        }
+    }
+    None
+}
+
+/// 把拼音命中映射回原文字符的 UTF-16 区间
+fn pinyin_highlights_utf16(text: &str, targets: &[String]) -> Vec<Range> {
+    let units = char_units(text);
+    let mut ranges = Vec::new();
+    for target in targets {
+        if target.is_empty() {
+            continue;
+        }
+        let mut ui = 0usize;
+        while ui < units.len() {
+            match match_units(&units, ui, target, 0) {
+                Some(end) if end > ui => {
+                    ranges.push(Range {
+                        start: units[ui].utf16_start,
+                        end: units[end - 1].utf16_end,
+                    });
+                    ui = end;
+                }
+                _ => ui += 1,
+            }
+        }
+    }
+    crate::search::normalize_ranges(ranges, 20)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(s: &str) -> Option<Vec<String>> {
+        segment_pinyin(s, &syllables())
+    }
+
+    #[test]
+    fn segment_prefers_longest_syllable() {
+        assert_eq!(seg("zhangsan").unwrap(), ["zhang", "san"]);
+        assert_eq!(seg("xian").unwrap(), ["xian"]);
+        // fang + u 切不完时退回 fan + gu
+        assert_eq!(seg("fangu").unwrap(), ["fan", "gu"]);
+    }
+
+    #[test]
+    fn segment_allows_trailing_prefix() {
+        assert_eq!(seg("zhangs").unwrap(), ["zhang", "s"]);
+        assert_eq!(seg("zh").unwrap(), ["zh"]);
+    }
+
+    #[test]
+    fn segment_rejects_invalid_input() {
+        assert_eq!(seg(""), None);
+        assert_eq!(seg("Zhang"), None);
+        assert_eq!(seg("zhang3"), None);
+        assert_eq!(seg("vvv"), None);
+    }
+
+    #[test]
+    fn segment_ambiguous_long_query() {
+        // 每个 xian 都能切成 xi + an，回溯实现在这里是指数级
+        let ok = format!("{}q", "xian".repeat(40));
+        let tokens = seg(&ok).unwrap();
+        assert_eq!(tokens.len(), 41);
+        assert!(tokens[..40].iter().all(|t| t == "xian"));
+        assert_eq!(tokens[40], "q");
+
+        // 结尾的 vv 怎么切都不成立（nv 是音节，单个 v 不是）
+        let bad = format!("{}vv", "xian".repeat(40));
+        assert_eq!(seg(&bad), None);
+    }
+
+    #[test]
+    fn edit_variants_are_unique_and_exclude_input() {
+        let v = edit1_variants("ab", usize::MAX);
+        assert_eq!(v.len(), 129);
+        assert!(!v.contains(&"ab".to_string()));
+        let unique: HashSet<_> = v.iter().collect();
+        assert_eq!(unique.len(), v.len());
+        for s in ["a", "b", "ba", "bb", "aab", "abz"] {
+            assert!(v.contains(&s.to_string()), "{s}");
+        }
+    }
+
+    #[test]
+    fn edit_variants_respect_cap_in_order() {
+        assert_eq!(
+            edit1_variants("abcdef", 5),
+            ["bcdef", "acdef", "abdef", "abcef", "abcdf"]
+        );
+    }
+
+    #[test]
+    fn edit_variants_recover_swapped_letters() {
+        let table = syllables();
+        let fixed = edit1_variants("zhnagsan", MAX_EDIT_VARIANTS)
+            .into_iter()
+            .find(|v| segment_pinyin(v, &table).is_some_and(|t| t.len() >= 2));
+        assert_eq!(fixed.as_deref(), Some("zhangsan"));
+    }
+
+    #[test]
+    fn edit_distance_le1_cases() {
+        assert!(edit_distance_le1("zhang", "zhnag"));
+        assert!(edit_distance_le1("zhang", "zhan"));
+        assert!(edit_distance_le1("zhang", "zhangs"));
+        assert!(!edit_distance_le1("zhang", "zhnga"));
+    }
+}
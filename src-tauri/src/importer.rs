@@ -1,8 +1,22 @@
+use crate::attachment_text::{self, AttachmentBlock};
 use crate::db;
+use crate::doc_legacy;
 use crate::docx;
+use crate::docx_images;
+use crate::docx_revisions;
+use crate::docx_rich;
+use crate::embedded;
+use crate::file_type;
+use crate::fuzzy;
 use crate::library_root::{resolve_library_root, LibraryRootState};
+use crate::media_meta::{self, MediaMeta};
+use crate::ocr;
+use crate::ofd;
+use crate::outline;
 use crate::progress;
+use crate::saved_searches;
 use crate::search;
+use crate::similar;
 use anyhow::{anyhow, Context, Result};
 use chrono::{Datelike, FixedOffset, NaiveDate, TimeZone};
 use encoding_rs::GBK;
@@ -182,6 +196,21 @@ fn import_zips_impl(
         }
     }
 
+    // 保存的搜索：只在本次新导入的档案内复跑，失败只记录不影响导入结果
+    let new_ids = archives
+        .iter()
+        .map(|a: &db::ArchiveRow| a.archive_id.clone())
+        .collect::<Vec<_>>();
+    if let Err(e) = saved_searches::run_for_new_archives(app, &conn, &new_ids) {
+        eprintln!("保存的搜索复跑失败: {e:#}");
+    }
+    // OCR 在后台跑，不拖慢导入
+    match ocr::enqueue_archives(&conn, &new_ids) {
+        Ok(n) if n > 0 => ocr::spawn_worker(app, root),
+        Ok(_) => {}
+        Err(e) => eprintln!("OCR 排队失败: {e:#}"),
+    }
+
     // 用同一口径的 total/current 标记完成，保证前端进度条能走满
     let total_steps = total.saturating_mul(IMPORT_STEPS_PER_ZIP).max(1);
     progress::emit(
@@ -221,33 +250,21 @@ fn reparse_main_doc_impl(
     db::init_db(app, &root)?;
     let mut conn = Connection::open(root.join("db.sqlite"))?;
 
-    let (original_name, stored_path): (String, String) = conn
-        .query_row(
-            "SELECT original_name, stored_path FROM archives WHERE archive_id=?",
-            [archive_id],
-            |r| Ok((r.get(0)?, r.get(1)?)),
-        )
-        .with_context(|| format!("找不到档案: {}", archive_id))?;
-
-    let stored_abs = root.join(&stored_path);
-    if !stored_abs.exists() {
-        return Err(anyhow!("ZIP不存在: {}", stored_abs.display()));
-    }
-
     progress::emit(
         app,
         progress::ProgressEvent::new("reparse", 0, 3, "扫描ZIP", "识别主docx"),
     );
-    let mut zip = ZipArchive::new(fs::File::open(&stored_abs)?)?;
-    let main_docx_name = identify_main_docx(&original_name, &mut zip)?;
-    let main_docx_bytes = read_zip_entry_bytes(&mut zip, &main_docx_name)
-        .with_context(|| format!("读取主docx失败: {main_docx_name}"))?;
+    let (main_docx_name, main_docx_bytes) = read_main_document(&root, &conn, archive_id)?;
+    // 富文本模型缓存依赖主文内容，重新解析后作废
+    docx_rich::invalidate(&root, archive_id);
 
     progress::emit(
         app,
         progress::ProgressEvent::new("reparse", 1, 3, "解析主docx", "抽取字段与段落"),
     );
-    let parsed = docx::parse_main_docx(&main_docx_bytes)?;
+    let parsed = parse_main_document(&main_docx_name, &main_docx_bytes)?;
+    // 目录与图片索引共用一次富文本解析；.doc/.wps/.ofd 主文没有富文本模型
+    let rich_blocks = docx_rich::parse_rich_docx(&main_docx_bytes).unwrap_or_default();
 
     progress::emit(
         app,
@@ -323,6 +340,11 @@ fn reparse_main_doc_impl(
             stmt.execute(params![archive_id, name, search_text, text])?;
         }
     }
+    fuzzy::index_main_doc_fields(&tx, archive_id, &parsed.instruction_no, &parsed.title)?;
+    similar::index_archive(&tx, archive_id)?;
+    outline::index_archive(&tx, archive_id, &rich_blocks, &parsed.blocks)?;
+    docx_revisions::index_archive(&tx, archive_id, &parsed.revisions)?;
+    docx_images::index_archive(&tx, archive_id, &rich_blocks)?;
 
     tx.execute(
         "UPDATE archives SET status='completed', error=NULL WHERE archive_id=?",
@@ -491,7 +513,9 @@ fn import_one_zip(
             .with_context(|| format!("读取主docx失败: {main_docx_name}"))?;
 
         emit_import_progress(app, zip_idx, zip_total, 4, "解析主docx", "抽取字段与段落");
-        let parsed = docx::parse_main_docx(&main_docx_bytes)?;
+        let parsed = parse_main_document(&main_docx_name, &main_docx_bytes)?;
+        // 目录与图片索引共用一次富文本解析；.doc/.wps/.ofd 主文没有富文本模型
+        let rich_blocks = docx_rich::parse_rich_docx(&main_docx_bytes).unwrap_or_default();
         let issued_at_ts = db::parse_issued_at_to_ts(&parsed.issued_at).unwrap_or(0);
 
         // 写 main_doc + blocks + FTS + attachments 采用一个事务，避免中途失败留下半数据
@@ -540,10 +564,16 @@ fn import_one_zip(
                 stmt.execute(params![archive_id, name, search_text, text])?;
             }
         }
+        fuzzy::index_main_doc_fields(&tx, &archive_id, &parsed.instruction_no, &parsed.title)?;
+        similar::index_archive(&tx, &archive_id)?;
+        outline::index_archive(&tx, &archive_id, &rich_blocks, &parsed.blocks)?;
+        docx_revisions::index_archive(&tx, &archive_id, &parsed.revisions)?;
+        docx_images::index_archive(&tx, &archive_id, &rich_blocks)?;
 
-        // 附件枚举（主 ZIP + 一层子 ZIP）
+        // 附件枚举（主 ZIP + 一层子 ZIP + 主文内嵌对象）
         emit_import_progress(app, zip_idx, zip_total, 5, "枚举附件", "主ZIP/子ZIP");
-        let attachments = enumerate_attachments(&stored_abs, &main_docx_name)?;
+        let mut attachments = enumerate_attachments(&stored_abs, &main_docx_name)?;
+        attachments.extend(enumerate_embedded(&main_docx_name, &main_docx_bytes)?);
         write_attachments_tx(&tx, &archive_id, attachments)?;
 
         tx.execute(
@@ -582,16 +612,32 @@ fn identify_main_docx<R: Read + Seek>(
     zip: &mut ZipArchive<R>,
 ) -> Result<String> {
     let mut docx_entries = Vec::new(); // (internal_name, decoded_name)
+    let mut doc_entries = Vec::new(); // .doc/.wps，仅在没有 docx 时使用
+    let mut ofd_entries = Vec::new(); // .ofd，前两类都没有时使用
     for i in 0..zip.len() {
         let f = zip.by_index(i)?;
         let internal = f.name().to_string();
         let decoded = decode_zip_filename(f.name_raw(), &internal);
-        if decoded.to_ascii_lowercase().ends_with(".docx") {
+        let lower = decoded.to_ascii_lowercase();
+        if should_skip_zip_entry(&decoded, &internal) {
+            continue;
+        }
+        if lower.ends_with(".docx") {
             docx_entries.push((internal, decoded));
+        } else if lower.ends_with(".doc") || lower.ends_with(".wps") {
+            doc_entries.push((internal, decoded));
+        } else if lower.ends_with(".ofd") {
+            ofd_entries.push((internal, decoded));
         }
     }
     if docx_entries.is_empty() {
-        return Err(anyhow!("ZIP内未找到docx"));
+        docx_entries = doc_entries;
+    }
+    if docx_entries.is_empty() {
+        docx_entries = ofd_entries;
+    }
+    if docx_entries.is_empty() {
+        return Err(anyhow!("ZIP内未找到docx/doc/wps/ofd"));
     }
 
     let zip_stem = Path::new(zip_filename)
@@ -625,6 +671,43 @@ fn identify_main_docx<R: Read + Seek>(
     Ok(docx_entries[0].0.clone())
 }
 
+/// 从已入库的 ZIP 中重新找出主文，返回 (ZIP 内条目名, 内容)
+pub(crate) fn read_main_document(
+    root: &Path,
+    conn: &Connection,
+    archive_id: &str,
+) -> Result<(String, Vec<u8>)> {
+    let (original_name, stored_path): (String, String) = conn
+        .query_row(
+            "SELECT original_name, stored_path FROM archives WHERE archive_id=?",
+            [archive_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .with_context(|| format!("找不到档案: {}", archive_id))?;
+
+    let stored_abs = root.join(&stored_path);
+    if !stored_abs.exists() {
+        return Err(anyhow!("ZIP不存在: {}", stored_abs.display()));
+    }
+    let mut zip = ZipArchive::new(fs::File::open(&stored_abs)?)?;
+    let main_docx_name = identify_main_docx(&original_name, &mut zip)?;
+    let main_docx_bytes = read_zip_entry_bytes(&mut zip, &main_docx_name)
+        .with_context(|| format!("读取主docx失败: {main_docx_name}"))?;
+    Ok((main_docx_name, main_docx_bytes))
+}
+
+/// 按内容区分 docx、.doc/.wps 与 .ofd（改过扩展名的文件也能解析）
+fn parse_main_document(entry_name: &str, bytes: &[u8]) -> Result<docx::MainDocParsed> {
+    if doc_legacy::is_ole(bytes) {
+        return doc_legacy::parse_main_doc(bytes)
+            .with_context(|| format!("解析主doc失败: {entry_name}"));
+    }
+    if ofd::is_ofd(bytes) {
+        return ofd::parse_main_ofd(bytes).with_context(|| format!("解析主ofd失败: {entry_name}"));
+    }
+    docx::parse_main_docx(bytes)
+}
+
 fn read_zip_entry_bytes<R: Read + Seek>(
     zip: &mut ZipArchive<R>,
     entry_name: &str,
@@ -654,39 +737,22 @@ struct AttachmentToInsert {
     source_depth: i64,
     container_virtual_path: Option<String>,
     virtual_path: String,
+    /// embedded::CONTAINER_*
+    container_kind: &'static str,
     size_bytes: Option<i64>,
+    meta: Option<MediaMeta>,
+    /// doc/wps/ofd 附件的正文段落
+    text_blocks: Option<Vec<AttachmentBlock>>,
 }
 
-fn file_type_from_name(name: &str) -> String {
-    let lower = name.to_ascii_lowercase();
-    if lower.ends_with(".pdf") {
-        return "pdf".to_string();
-    }
-    if lower.ends_with(".xlsx") || lower.ends_with(".xls") {
-        return "excel".to_string();
-    }
-    if lower.ends_with(".png")
-        || lower.ends_with(".jpg")
-        || lower.ends_with(".jpeg")
-        || lower.ends_with(".gif")
-        || lower.ends_with(".bmp")
-    {
-        return "image".to_string();
-    }
-    if lower.ends_with(".mp4")
-        || lower.ends_with(".mov")
-        || lower.ends_with(".avi")
-        || lower.ends_with(".wmv")
-    {
-        return "video".to_string();
-    }
-    if lower.ends_with(".docx") {
-        return "docx_other".to_string();
-    }
-    if lower.ends_with(".zip") {
-        return "zip_child".to_string();
-    }
-    "other".to_string()
+/// 读出文件头用于类型识别；读失败（损坏条目）时按空头处理，退回扩展名判断
+fn read_head<R: Read>(r: &mut R) -> Vec<u8> {
+    let mut head = Vec::new();
+    let _ = r
+        .by_ref()
+        .take(file_type::SNIFF_BYTES as u64)
+        .read_to_end(&mut head);
+    head
 }
 
 fn basename(path: &str) -> String {
@@ -720,7 +786,7 @@ fn enumerate_attachments(zip_abs: &Path, main_docx_name: &str) -> Result<Vec<Att
     // 先枚举主 ZIP
     let mut child_zips = Vec::new(); // (internal_virtual_path, decoded_basename, size)
     for i in 0..zip.len() {
-        let f = zip.by_index(i)?;
+        let mut f = zip.by_index(i)?;
         let internal = f.name().to_string();
         if internal.ends_with('/') {
             continue;
@@ -733,15 +799,20 @@ fn enumerate_attachments(zip_abs: &Path, main_docx_name: &str) -> Result<Vec<Att
         if lower.ends_with(".ds_store") {
             continue;
         }
-        if lower.ends_with(".docx") && internal == main_docx_name {
+        if internal == main_docx_name {
             continue;
         }
         let display_name = basename(&decoded);
-        let ty = file_type_from_name(&decoded);
-        if ty == "zip_child" {
+        let head = read_head(&mut f);
+        let ty = file_type::detect(&decoded, &head).to_string();
+        if ty == file_type::ZIP_CHILD {
             child_zips.push((internal.clone(), display_name.clone(), f.size() as i64));
         }
 
+        let size = f.size() as i64;
+        let meta = media_meta::extract(&ty, &decoded, &mut head.as_slice().chain(&mut f));
+        let text_blocks = attachment_text::extract(&ty, f.size(), &head, &mut f);
+
         // 记录主ZIP附件（包括子zip本体）
         let container_virtual_path = None;
         let file_id = stable_file_id("__ARCHIVE_ID__", 0, &container_virtual_path, &internal); // 占位，后面修复
@@ -752,7 +823,10 @@ fn enumerate_attachments(zip_abs: &Path, main_docx_name: &str) -> Result<Vec<Att
             source_depth: 0,
             container_virtual_path,
             virtual_path: internal,
-            size_bytes: Some(f.size() as i64),
+            container_kind: embedded::CONTAINER_ZIP,
+            size_bytes: Some(size),
+            meta,
+            text_blocks,
         });
     }
 
@@ -761,7 +835,7 @@ fn enumerate_attachments(zip_abs: &Path, main_docx_name: &str) -> Result<Vec<Att
         let child_bytes = read_zip_entry_bytes(&mut zip, &child_internal_path)?;
         let mut nested = ZipArchive::new(std::io::Cursor::new(child_bytes))?;
         for i in 0..nested.len() {
-            let f = nested.by_index(i)?;
+            let mut f = nested.by_index(i)?;
             let internal = f.name().to_string();
             if internal.ends_with('/') {
                 continue;
@@ -772,10 +846,14 @@ fn enumerate_attachments(zip_abs: &Path, main_docx_name: &str) -> Result<Vec<Att
             }
             let file_basename = basename(&decoded);
             let display_name = format!("[{}]/{}", child_display, file_basename);
-            let ty = file_type_from_name(&decoded);
-            if ty == "zip_child" {
+            let head = read_head(&mut f);
+            let ty = file_type::detect(&decoded, &head).to_string();
+            if ty == file_type::ZIP_CHILD {
                 // 深度限制为2，子zip内的zip不展开，但可作为普通附件名记录
             }
+            let size = f.size() as i64;
+            let meta = media_meta::extract(&ty, &decoded, &mut head.as_slice().chain(&mut f));
+            let text_blocks = attachment_text::extract(&ty, f.size(), &head, &mut f);
             let container_virtual_path = Some(child_internal_path.clone());
             let file_id = stable_file_id("__ARCHIVE_ID__", 1, &container_virtual_path, &internal); // 占位，后面修复
             out.push(AttachmentToInsert {
@@ -785,7 +863,10 @@ fn enumerate_attachments(zip_abs: &Path, main_docx_name: &str) -> Result<Vec<Att
                 source_depth: 1,
                 container_virtual_path,
                 virtual_path: internal,
-                size_bytes: Some(f.size() as i64),
+                container_kind: embedded::CONTAINER_ZIP,
+                size_bytes: Some(size),
+                meta,
+                text_blocks,
             });
         }
     }
@@ -793,6 +874,37 @@ fn enumerate_attachments(zip_abs: &Path, main_docx_name: &str) -> Result<Vec<Att
     Ok(out)
 }
 
+/// 主 docx 内嵌对象作为虚拟附件：容器为主文本身，virtual_path 为包内路径
+fn enumerate_embedded(
+    main_docx_name: &str,
+    main_docx_bytes: &[u8],
+) -> Result<Vec<AttachmentToInsert>> {
+    let mut out = Vec::new();
+    let main_display = basename(main_docx_name);
+    for obj in embedded::enumerate(main_docx_bytes)? {
+        let head = &obj.bytes[..obj.bytes.len().min(file_type::SNIFF_BYTES)];
+        let ty = file_type::detect(&obj.file_name, head).to_string();
+        let size = obj.bytes.len() as u64;
+        let meta = media_meta::extract(&ty, &obj.file_name, &mut obj.bytes.as_slice());
+        let text_blocks = attachment_text::extract(&ty, size, head, &mut &obj.bytes[head.len()..]);
+        let container_virtual_path = Some(main_docx_name.to_string());
+        let file_id = stable_file_id("__ARCHIVE_ID__", 1, &container_virtual_path, &obj.part_name); // 占位，后面修复
+        out.push(AttachmentToInsert {
+            file_id,
+            display_name: format!("[{}]/{}", main_display, obj.file_name),
+            file_type: ty,
+            source_depth: 1,
+            container_virtual_path,
+            virtual_path: obj.part_name,
+            container_kind: embedded::CONTAINER_MAIN_DOCX,
+            size_bytes: Some(size as i64),
+            meta,
+            text_blocks,
+        });
+    }
+    Ok(out)
+}
+
 fn should_skip_zip_entry(decoded: &str, internal: &str) -> bool {
     let d = decoded.replace('\\', "/").to_ascii_lowercase();
     let i = internal.replace('\\', "/").to_ascii_lowercase();
@@ -839,8 +951,8 @@ fn write_attachments_tx(
 
     {
         let mut stmt = tx.prepare(
-            "INSERT INTO attachments(file_id,archive_id,display_name,file_type,source_depth,container_virtual_path,virtual_path,cached_path,size_bytes)
-             VALUES(?,?,?,?,?,?,?,?,?)",
+            "INSERT INTO attachments(file_id,archive_id,display_name,file_type,source_depth,container_virtual_path,virtual_path,cached_path,size_bytes,container_kind)
+             VALUES(?,?,?,?,?,?,?,?,?,?)",
         )?;
         let mut stmt_fts = tx.prepare(
             "INSERT INTO attachments_fts(archive_id,file_id,search_text,display_name) VALUES(?,?,?,?)",
@@ -855,10 +967,18 @@ fn write_attachments_tx(
                 a.container_virtual_path,
                 a.virtual_path,
                 Option::<String>::None,
-                a.size_bytes
+                a.size_bytes,
+                a.container_kind
             ])?;
             let search_text = search::build_search_text(&a.display_name);
             stmt_fts.execute(params![archive_id, a.file_id, search_text, a.display_name])?;
+            fuzzy::index_attachment_name(tx, archive_id, &a.file_id, &a.display_name)?;
+            if let Some(meta) = &a.meta {
+                media_meta::insert(tx, archive_id, &a.file_id, meta)?;
+            }
+            if let Some(blocks) = &a.text_blocks {
+                attachment_text::insert(tx, archive_id, &a.file_id, blocks)?;
+            }
         }
     }
     Ok(())
@@ -6,6 +6,7 @@ use std::sync::Mutex;
 use tauri::Manager;
 use tauri::State;
 
+use crate::backfill;
 use crate::db;
 use crate::progress;
 
@@ -142,6 +143,7 @@ pub fn set_library_root(
     .map_err(db::err_to_string)?;
 
     *state.root.lock().unwrap() = Some(new_root.clone());
+    backfill::spawn_worker(&app, &new_root);
     if let Err(e) = save_app_config(
         &app,
         &AppConfig {
@@ -4,14 +4,41 @@
 )]
 
 mod annotations;
+mod archive_search;
+mod attachment_text;
+mod backfill;
 mod cache;
 mod db;
+mod doc_legacy;
 mod docx;
+mod docx_images;
+mod docx_revisions;
+mod docx_rich;
+mod embedded;
+mod excel_format;
+mod excel_index;
 mod excel_preview;
+mod excel_query;
+mod file_type;
+mod fuzzy;
 mod importer;
 mod library_root;
+mod media_meta;
+mod ocr;
+mod ofd;
+mod outline;
+mod pattern_search;
 mod progress;
+mod saved_searches;
 mod search;
+mod search_history;
+mod similar;
+mod snippet;
+mod synonyms;
+mod text_preview;
+mod thumbnail;
+mod user_dict;
+mod xlsx_sheet;
 
 use tauri::Manager;
 
@@ -25,6 +52,10 @@ fn main() {
             // 初始化运行时选择的库目录
             let state: tauri::State<library_root::LibraryRootState> = app.state();
             let root = library_root::resolve_library_root(&handle, &state)?;
+            // 继续上次未完成的 OCR 队列（未启用时立即退出）
+            ocr::spawn_worker(&handle, &root);
+            // 旧库缺少的索引在后台补建，不拖慢启动
+            backfill::spawn_worker(&handle, &root);
             *state.root.lock().unwrap() = Some(root);
             Ok(())
         })
@@ -39,6 +70,31 @@ fn main() {
             importer::reparse_main_doc,
             search::search,
             search::search_paged,
+            search::get_search_result_text,
+            archive_search::search_in_archive,
+            similar::find_similar_archives,
+            search_history::list_search_history,
+            search_history::clear_search_history,
+            search_history::delete_search_history,
+            search_history::pin_search_history,
+            search_history::suggest_queries,
+            saved_searches::list_saved_searches,
+            saved_searches::save_search,
+            saved_searches::delete_saved_search,
+            saved_searches::list_notifications,
+            saved_searches::mark_notifications_read,
+            saved_searches::clear_notifications,
+            search::rebuild_search_index,
+            synonyms::list_synonym_groups,
+            synonyms::save_synonym_group,
+            synonyms::delete_synonym_group,
+            synonyms::import_synonyms,
+            synonyms::export_synonyms,
+            synonyms::get_synonym_settings,
+            synonyms::set_synonym_index_expansion,
+            user_dict::list_user_dict_words,
+            user_dict::add_user_dict_word,
+            user_dict::remove_user_dict_word,
             db::list_archives,
             db::list_topics_by_date,
             db::get_archive_detail,
@@ -46,12 +102,29 @@ fn main() {
             db::update_archive_title,
             db::delete_archive,
             docx::get_docx_blocks,
+            docx::get_docx_supplement_blocks,
+            docx_rich::get_docx_rich_blocks,
+            outline::get_docx_outline,
+            docx_revisions::list_docx_revisions,
+            docx_revisions::get_docx_block_versions,
+            docx_images::list_docx_images,
+            docx_images::get_docx_image,
             docx::get_docx_attachment_preview,
             cache::get_attachment_preview_path,
             cache::cleanup_cache,
             cache::cleanup_archive_cache,
             excel_preview::get_excel_sheet_info,
             excel_preview::get_excel_sheet_cells,
+            excel_query::query_excel_sheet,
+            excel_query::find_in_excel,
+            excel_query::export_excel_range,
+            ocr::get_ocr_status,
+            ocr::set_ocr_enabled,
+            ocr::retry_failed_ocr,
+            ocr::get_ocr_blocks,
+            thumbnail::get_thumbnail,
+            attachment_text::get_attachment_blocks,
+            text_preview::get_text_preview,
             annotations::create_annotation,
             annotations::list_annotations,
             annotations::delete_annotation,
@@ -0,0 +1,807 @@
+use crate::cache;
+use crate::db;
+use anyhow::Result;
+use chrono::{NaiveDateTime, TimeZone};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+const META_MEDIA_META_VERSION: &str = "media_meta_version";
+const MEDIA_META_VERSION: &str = "1";
+
+/// 图片只读文件头部：EXIF 必须在前 64KB 的 APP1 段内，PNG 的 eXIf 一般也在前部
+const IMAGE_HEAD_BYTES: u64 = 512 * 1024;
+/// moov 超过这个大小视为异常文件，不解析
+const MAX_MOOV_BYTES: u64 = 64 * 1024 * 1024;
+/// MP4 时间从 1904-01-01 起算
+const MP4_EPOCH_OFFSET: i64 = 2_082_844_800;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MediaMeta {
+    /// 拍摄/创建时间（本地时间，YYYY-MM-DD HH:MM:SS）
+    pub captured_at: Option<String>,
+    pub captured_at_ts: Option<i64>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub gps_lat: Option<f64>,
+    pub gps_lon: Option<f64>,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub duration_ms: Option<i64>,
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+}
+
+impl MediaMeta {
+    fn is_empty(&self) -> bool {
+        self.captured_at_ts.is_none()
+            && self.camera_make.is_none()
+            && self.camera_model.is_none()
+            && self.gps_lat.is_none()
+            && self.width.is_none()
+            && self.duration_ms.is_none()
+            && self.video_codec.is_none()
+            && self.audio_codec.is_none()
+    }
+}
+
+/// 拍摄时间/时长筛选（搜索与档案列表共用）
+#[derive(Debug, Clone, Default)]
+pub struct MediaFilter {
+    pub captured_from: Option<i64>,
+    pub captured_to: Option<i64>,
+    pub min_duration_ms: Option<i64>,
+    pub max_duration_ms: Option<i64>,
+}
+
+impl MediaFilter {
+    pub fn is_active(&self) -> bool {
+        self.captured_from.is_some()
+            || self.captured_to.is_some()
+            || self.min_duration_ms.is_some()
+            || self.max_duration_ms.is_some()
+    }
+
+    /// 生成 attachment_meta 上的条件（不含 WHERE/AND 前缀）
+    pub fn sql(&self, alias: &str) -> (String, Vec<rusqlite::types::Value>) {
+        let mut conds = Vec::new();
+        let mut params = Vec::new();
+        if self.captured_from.is_some() || self.captured_to.is_some() {
+            conds.push(format!("{alias}.captured_at_ts BETWEEN ? AND ?"));
+            params.push(self.captured_from.unwrap_or(i64::MIN).into());
+            params.push(self.captured_to.unwrap_or(i64::MAX).into());
+        }
+        if self.min_duration_ms.is_some() || self.max_duration_ms.is_some() {
+            conds.push(format!("{alias}.duration_ms BETWEEN ? AND ?"));
+            params.push(self.min_duration_ms.unwrap_or(0).into());
+            params.push(self.max_duration_ms.unwrap_or(i64::MAX).into());
+        }
+        if conds.is_empty() {
+            return ("1=1".to_string(), params);
+        }
+        (conds.join(" AND "), params)
+    }
+}
+
+/// 从附件内容提取元数据；失败或不支持的格式返回 None，不影响导入
+pub fn extract<R: Read>(file_type: &str, name: &str, r: &mut R) -> Option<MediaMeta> {
+    let lower = name.to_ascii_lowercase();
+    let meta = match file_type {
+        "image" => {
+            let mut head = Vec::new();
+            r.by_ref()
+                .take(IMAGE_HEAD_BYTES)
+                .read_to_end(&mut head)
+                .ok()?;
+            if head.starts_with(&[0xFF, 0xD8]) {
+                parse_jpeg(&head)
+            } else if head.starts_with(b"\x89PNG\r\n\x1a\n") {
+                parse_png(&head)
+            } else {
+                None
+            }
+        }
+        "video" if lower.ends_with(".mp4") || lower.ends_with(".mov") => parse_mp4(r),
+        _ => None,
+    }?;
+    (!meta.is_empty()).then_some(meta)
+}
+
+pub fn insert(conn: &Connection, archive_id: &str, file_id: &str, m: &MediaMeta) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO attachment_meta(file_id,archive_id,captured_at,captured_at_ts,camera_make,camera_model,gps_lat,gps_lon,width,height,duration_ms,video_codec,audio_codec)
+         VALUES(?,?,?,?,?,?,?,?,?,?,?,?,?)",
+        params![
+            file_id,
+            archive_id,
+            m.captured_at,
+            m.captured_at_ts,
+            m.camera_make,
+            m.camera_model,
+            m.gps_lat,
+            m.gps_lon,
+            m.width,
+            m.height,
+            m.duration_ms,
+            m.video_codec,
+            m.audio_codec
+        ],
+    )?;
+    Ok(())
+}
+
+pub(crate) fn backfill_pending(conn: &Connection) -> Result<bool> {
+    Ok(db::get_meta_value(conn, META_MEDIA_META_VERSION)?.as_deref() != Some(MEDIA_META_VERSION))
+}
+
+/// 旧库首次打开时为已导入的图片/视频补建元数据，包括重新分类后才成为图片/视频的附件
+/// （只做一次，读不到的附件跳过）；须在 file_type::ensure_reclassified 之后调用
+pub fn ensure_backfilled(conn: &Connection, root: &Path) -> Result<()> {
+    if !backfill_pending(conn)? {
+        return Ok(());
+    }
+    let mut todo = Vec::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT a.file_id, a.archive_id, a.file_type, a.display_name, a.virtual_path
+             FROM attachments a
+             WHERE a.file_type IN ('image','video')
+               AND NOT EXISTS (SELECT 1 FROM attachment_meta m WHERE m.file_id=a.file_id)",
+        )?;
+        let rows = stmt.query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+                r.get::<_, String>(3)?,
+                r.get::<_, String>(4)?,
+            ))
+        })?;
+        for row in rows {
+            todo.push(row?);
+        }
+    }
+    for (file_id, archive_id, file_type, display_name, virtual_path) in todo {
+        // 主文内嵌对象的 virtual_path 没有原扩展名，此时以 display_name 为准
+        let lower = virtual_path.to_ascii_lowercase();
+        let name = if lower.ends_with(".mp4") || lower.ends_with(".mov") {
+            virtual_path
+        } else {
+            display_name
+        };
+        let meta = cache::ensure_cached_file(root, conn, &file_id)
+            .and_then(|p| Ok(fs::File::open(p)?))
+            .map(|mut f| extract(&file_type, &name, &mut f));
+        match meta {
+            Ok(Some(meta)) => insert(conn, &archive_id, &file_id, &meta)?,
+            Ok(None) => {}
+            Err(e) => eprintln!("附件元数据补建失败 {file_id}: {e:#}"),
+        }
+    }
+    db::set_meta_value(conn, META_MEDIA_META_VERSION, MEDIA_META_VERSION)?;
+    Ok(())
+}
+
+pub fn load_for_archive(conn: &Connection, archive_id: &str) -> Result<HashMap<String, MediaMeta>> {
+    let mut stmt = conn.prepare(
+        "SELECT file_id,captured_at,captured_at_ts,camera_make,camera_model,gps_lat,gps_lon,width,height,duration_ms,video_codec,audio_codec
+         FROM attachment_meta WHERE archive_id=?",
+    )?;
+    let rows = stmt.query_map([archive_id], |r| {
+        Ok((
+            r.get::<_, String>(0)?,
+            MediaMeta {
+                captured_at: r.get(1)?,
+                captured_at_ts: r.get(2)?,
+                camera_make: r.get(3)?,
+                camera_model: r.get(4)?,
+                gps_lat: r.get(5)?,
+                gps_lon: r.get(6)?,
+                width: r.get(7)?,
+                height: r.get(8)?,
+                duration_ms: r.get(9)?,
+                video_codec: r.get(10)?,
+                audio_codec: r.get(11)?,
+            },
+        ))
+    })?;
+    let mut out = HashMap::new();
+    for row in rows {
+        let (file_id, meta) = row?;
+        out.insert(file_id, meta);
+    }
+    Ok(out)
+}
+
+fn set_captured(meta: &mut MediaMeta, local: NaiveDateTime) {
+    meta.captured_at = Some(local.format("%Y-%m-%d %H:%M:%S").to_string());
+    meta.captured_at_ts = db::tz_offset()
+        .from_local_datetime(&local)
+        .single()
+        .map(|dt| dt.timestamp());
+}
+
+// ---------------- JPEG / PNG / EXIF ----------------
+
+fn parse_jpeg(data: &[u8]) -> Option<MediaMeta> {
+    let mut meta = MediaMeta::default();
+    let mut i = 2;
+    while i + 4 <= data.len() {
+        if data[i] != 0xFF {
+            break;
+        }
+        let marker = data[i + 1];
+        // 填充字节
+        if marker == 0xFF {
+            i += 1;
+            continue;
+        }
+        // SOS 之后是图像数据
+        if marker == 0xDA || marker == 0xD9 {
+            break;
+        }
+        let len = u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+        let seg_end = (i + 2 + len).min(data.len());
+        let seg = &data[(i + 4).min(seg_end)..seg_end];
+        match marker {
+            0xE1 if seg.starts_with(b"Exif\0\0") => parse_tiff(&seg[6..], &mut meta),
+            // SOFn（C4/C8/CC 不是帧头）
+            0xC0..=0xCF if marker != 0xC4 && marker != 0xC8 && marker != 0xCC => {
+                if seg.len() >= 5 {
+                    meta.height = Some(u16::from_be_bytes([seg[1], seg[2]]) as i64);
+                    meta.width = Some(u16::from_be_bytes([seg[3], seg[4]]) as i64);
+                }
+            }
+            _ => {}
+        }
+        i = seg_end;
+    }
+    Some(meta)
+}
+
+fn parse_png(data: &[u8]) -> Option<MediaMeta> {
+    let mut meta = MediaMeta::default();
+    let mut i = 8;
+    while i + 8 <= data.len() {
+        let len = u32::from_be_bytes(data[i..i + 4].try_into().ok()?) as usize;
+        let ty = &data[i + 4..i + 8];
+        let body_end = i.saturating_add(8).saturating_add(len).min(data.len());
+        let body = &data[i + 8..body_end];
+        match ty {
+            b"IHDR" if body.len() >= 8 => {
+                meta.width = Some(u32::from_be_bytes(body[0..4].try_into().ok()?) as i64);
+                meta.height = Some(u32::from_be_bytes(body[4..8].try_into().ok()?) as i64);
+            }
+            b"eXIf" => parse_tiff(body, &mut meta),
+            b"IEND" => break,
+            _ => {}
+        }
+        // 长度 + 类型 + 数据 + CRC
+        i = i.saturating_add(12).saturating_add(len);
+    }
+    Some(meta)
+}
+
+struct Tiff<'a> {
+    data: &'a [u8],
+    le: bool,
+}
+
+impl<'a> Tiff<'a> {
+    fn u16(&self, off: usize) -> Option<u16> {
+        let b: [u8; 2] = self.data.get(off..off + 2)?.try_into().ok()?;
+        Some(if self.le {
+            u16::from_le_bytes(b)
+        } else {
+            u16::from_be_bytes(b)
+        })
+    }
+
+    fn u32(&self, off: usize) -> Option<u32> {
+        let b: [u8; 4] = self.data.get(off..off + 4)?.try_into().ok()?;
+        Some(if self.le {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        })
+    }
+
+    /// 返回 (tag, type, count, 值所在偏移)
+    fn entries(&self, ifd: usize) -> Vec<(u16, u16, u32, usize)> {
+        let mut out = Vec::new();
+        let Some(n) = self.u16(ifd) else {
+            return out;
+        };
+        for k in 0..n as usize {
+            let e = ifd + 2 + k * 12;
+            let (Some(tag), Some(ty), Some(count)) =
+                (self.u16(e), self.u16(e + 2), self.u32(e + 4))
+            else {
+                break;
+            };
+            let unit = match ty {
+                1 | 2 | 6 | 7 => 1,
+                3 | 8 => 2,
+                4 | 9 | 11 => 4,
+                5 | 10 | 12 => 8,
+                _ => continue,
+            };
+            let size = unit * count as usize;
+            let off = if size <= 4 {
+                e + 8
+            } else {
+                match self.u32(e + 8) {
+                    Some(v) => v as usize,
+                    None => continue,
+                }
+            };
+            out.push((tag, ty, count, off));
+        }
+        out
+    }
+
+    fn ascii(&self, off: usize, count: u32) -> Option<String> {
+        let raw = self.data.get(off..off + count as usize)?;
+        let s = String::from_utf8_lossy(raw)
+            .trim_end_matches('\0')
+            .trim()
+            .to_string();
+        (!s.is_empty()).then_some(s)
+    }
+
+    fn rational(&self, off: usize) -> Option<f64> {
+        let n = self.u32(off)? as f64;
+        let d = self.u32(off + 4)? as f64;
+        (d != 0.0).then_some(n / d)
+    }
+
+    fn int(&self, ty: u16, off: usize) -> Option<i64> {
+        match ty {
+            3 => self.u16(off).map(|v| v as i64),
+            4 => self.u32(off).map(|v| v as i64),
+            _ => None,
+        }
+    }
+}
+
+fn parse_tiff(data: &[u8], meta: &mut MediaMeta) {
+    let le = match data.get(0..2) {
+        Some(b"II") => true,
+        Some(b"MM") => false,
+        _ => return,
+    };
+    let t = Tiff { data, le };
+    let Some(ifd0) = t.u32(4) else {
+        return;
+    };
+    let mut exif_ifd = None;
+    let mut gps_ifd = None;
+    let mut datetime = None;
+    for (tag, ty, count, off) in t.entries(ifd0 as usize) {
+        match tag {
+            0x010F => meta.camera_make = t.ascii(off, count),
+            0x0110 => meta.camera_model = t.ascii(off, count),
+            0x0132 => datetime = t.ascii(off, count),
+            0x8769 => exif_ifd = t.int(ty, off),
+            0x8825 => gps_ifd = t.int(ty, off),
+            _ => {}
+        }
+    }
+    let mut original = None;
+    if let Some(ifd) = exif_ifd {
+        for (tag, ty, count, off) in t.entries(ifd as usize) {
+            match tag {
+                0x9003 => original = t.ascii(off, count),
+                0xA002 => meta.width = meta.width.or(t.int(ty, off)),
+                0xA003 => meta.height = meta.height.or(t.int(ty, off)),
+                _ => {}
+            }
+        }
+    }
+    // 优先 DateTimeOriginal（拍摄时间），其次 DateTime（修改时间）
+    if let Some(s) = original.or(datetime) {
+        if let Ok(local) = NaiveDateTime::parse_from_str(&s, "%Y:%m:%d %H:%M:%S") {
+            set_captured(meta, local);
+        }
+    }
+    if let Some(ifd) = gps_ifd {
+        let mut lat_ref = None;
+        let mut lon_ref = None;
+        let mut lat = None;
+        let mut lon = None;
+        for (tag, _ty, count, off) in t.entries(ifd as usize) {
+            match tag {
+                1 => lat_ref = t.ascii(off, count),
+                2 => lat = dms(&t, off),
+                3 => lon_ref = t.ascii(off, count),
+                4 => lon = dms(&t, off),
+                _ => {}
+            }
+        }
+        if let (Some(lat), Some(lon)) = (lat, lon) {
+            let sign = |r: &Option<String>, neg: &str| {
+                if r.as_deref() == Some(neg) {
+                    -1.0
+                } else {
+                    1.0
+                }
+            };
+            meta.gps_lat = Some(lat * sign(&lat_ref, "S"));
+            meta.gps_lon = Some(lon * sign(&lon_ref, "W"));
+        }
+    }
+}
+
+/// 度/分/秒三个 RATIONAL 转十进制度
+fn dms(t: &Tiff, off: usize) -> Option<f64> {
+    let d = t.rational(off)?;
+    let m = t.rational(off + 8)?;
+    let s = t.rational(off + 16)?;
+    Some(d + m / 60.0 + s / 3600.0)
+}
+
+// ---------------- MP4 / MOV ----------------
+
+/// 顺序读取顶层 box，跳过 mdat 等大块数据，只把 moov 读入内存
+fn parse_mp4<R: Read>(r: &mut R) -> Option<MediaMeta> {
+    loop {
+        let mut hdr = [0u8; 8];
+        r.read_exact(&mut hdr).ok()?;
+        let size32 = u32::from_be_bytes(hdr[0..4].try_into().ok()?) as u64;
+        let ty = [hdr[4], hdr[5], hdr[6], hdr[7]];
+        let (body_len, to_end) = match size32 {
+            0 => (0, true),
+            1 => {
+                let mut ext = [0u8; 8];
+                r.read_exact(&mut ext).ok()?;
+                (u64::from_be_bytes(ext).checked_sub(16)?, false)
+            }
+            n => (n.checked_sub(8)?, false),
+        };
+        if &ty == b"moov" {
+            let mut body = Vec::new();
+            if to_end {
+                r.by_ref()
+                    .take(MAX_MOOV_BYTES)
+                    .read_to_end(&mut body)
+                    .ok()?;
+            } else {
+                if body_len > MAX_MOOV_BYTES {
+                    return None;
+                }
+                r.by_ref().take(body_len).read_to_end(&mut body).ok()?;
+            }
+            let mut meta = MediaMeta::default();
+            parse_moov(&body, &mut meta);
+            return Some(meta);
+        }
+        if to_end {
+            return None;
+        }
+        io::copy(&mut r.by_ref().take(body_len), &mut io::sink()).ok()?;
+    }
+}
+
+/// 遍历一层子 box：回调 (类型, 内容)
+fn each_box<'a>(data: &'a [u8], mut f: impl FnMut(&[u8; 4], &'a [u8])) {
+    let mut i = 0;
+    while i + 8 <= data.len() {
+        let size = u32::from_be_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]) as usize;
+        let ty = [data[i + 4], data[i + 5], data[i + 6], data[i + 7]];
+        let (hdr, size) = match size {
+            0 => (8, data.len() - i),
+            1 => {
+                let Some(b) = data.get(i + 8..i + 16) else {
+                    return;
+                };
+                // 损坏文件的 largesize 可能超出 usize 或让 i+size 溢出
+                let Ok(size) = usize::try_from(u64::from_be_bytes(b.try_into().unwrap())) else {
+                    return;
+                };
+                (16, size)
+            }
+            n => (8, n),
+        };
+        let Some(end) = i.checked_add(size) else {
+            return;
+        };
+        if size < hdr || end > data.len() {
+            return;
+        }
+        f(&ty, &data[i + hdr..end]);
+        i = end;
+    }
+}
+
+fn be_u32(d: &[u8], off: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(d.get(off..off + 4)?.try_into().ok()?))
+}
+
+fn be_u64(d: &[u8], off: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(d.get(off..off + 8)?.try_into().ok()?))
+}
+
+fn parse_moov(moov: &[u8], meta: &mut MediaMeta) {
+    each_box(moov, |ty, body| match ty {
+        b"mvhd" => parse_mvhd(body, meta),
+        b"trak" => parse_trak(body, meta),
+        _ => {}
+    });
+}
+
+fn parse_mvhd(body: &[u8], meta: &mut MediaMeta) {
+    let version = body.first().copied().unwrap_or(0);
+    let (created, timescale, duration) = if version == 1 {
+        (be_u64(body, 4), be_u32(body, 20), be_u64(body, 24))
+    } else {
+        (
+            be_u32(body, 4).map(|v| v as u64),
+            be_u32(body, 12),
+            be_u32(body, 16).map(|v| v as u64),
+        )
+    };
+    if let (Some(ts), Some(d)) = (timescale, duration) {
+        if ts > 0 {
+            meta.duration_ms = Some((d as u128 * 1000 / ts as u128) as i64);
+        }
+    }
+    // 创建时间为 UTC；未写入时为 0
+    if let Some(c) = created.filter(|c| *c as i64 > MP4_EPOCH_OFFSET) {
+        if let Some(utc) = chrono::DateTime::from_timestamp(c as i64 - MP4_EPOCH_OFFSET, 0) {
+            set_captured(meta, utc.with_timezone(&db::tz_offset()).naive_local());
+        }
+    }
+}
+
+fn parse_trak(trak: &[u8], meta: &mut MediaMeta) {
+    let mut size = None;
+    let mut handler = None;
+    let mut codec = None;
+    each_box(trak, |ty, body| match ty {
+        b"tkhd" => {
+            // 宽高为 16.16 定点数，位于 tkhd 末尾
+            if body.len() >= 8 {
+                let n = body.len();
+                let w = be_u32(body, n - 8).unwrap_or(0) >> 16;
+                let h = be_u32(body, n - 4).unwrap_or(0) >> 16;
+                size = Some((w as i64, h as i64));
+            }
+        }
+        b"mdia" => each_box(body, |ty, body| match ty {
+            b"hdlr" => handler = body.get(8..12).map(|b| b.to_vec()),
+            b"minf" => each_box(body, |ty, body| {
+                if ty == b"stbl" {
+                    each_box(body, |ty, body| {
+                        // stsd: version/flags(4) + entry_count(4) + 首个条目 size(4) + format(4)
+                        if ty == b"stsd" {
+                            codec = body
+                                .get(12..16)
+                                .map(|b| String::from_utf8_lossy(b).trim().to_string());
+                        }
+                    })
+                }
+            }),
+            _ => {}
+        }),
+        _ => {}
+    });
+    match handler.as_deref() {
+        Some(b"vide") => {
+            if meta.video_codec.is_none() {
+                meta.video_codec = codec;
+                if let Some((w, h)) = size.filter(|(w, h)| *w > 0 && *h > 0) {
+                    meta.width = Some(w);
+                    meta.height = Some(h);
+                }
+            }
+        }
+        Some(b"soun") => {
+            if meta.audio_codec.is_none() {
+                meta.audio_codec = codec;
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1700000000 = 2023-11-14 22:13:20 UTC
+    const CREATED_UNIX: u32 = 1_700_000_000;
+
+    fn bx(ty: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut out = ((8 + body.len()) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(ty);
+        out.extend_from_slice(body);
+        out
+    }
+
+    /// size 字段为 1，后跟 64 位长度
+    fn large_bx(ty: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut out = 1u32.to_be_bytes().to_vec();
+        out.extend_from_slice(ty);
+        out.extend_from_slice(&((16 + body.len()) as u64).to_be_bytes());
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn mvhd(timescale: u32, duration: u32) -> Vec<u8> {
+        let mut body = vec![0u8; 4];
+        body.extend_from_slice(&(CREATED_UNIX + MP4_EPOCH_OFFSET as u32).to_be_bytes());
+        body.extend_from_slice(&0u32.to_be_bytes());
+        body.extend_from_slice(&timescale.to_be_bytes());
+        body.extend_from_slice(&duration.to_be_bytes());
+        body.resize(100, 0);
+        bx(b"mvhd", &body)
+    }
+
+    fn video_trak(codec: &[u8; 4], w: u32, h: u32) -> Vec<u8> {
+        let mut tkhd = vec![0u8; 76];
+        tkhd.extend_from_slice(&(w << 16).to_be_bytes());
+        tkhd.extend_from_slice(&(h << 16).to_be_bytes());
+        let mut hdlr = vec![0u8; 8];
+        hdlr.extend_from_slice(b"vide");
+        hdlr.resize(24, 0);
+        let mut stsd = vec![0u8; 4];
+        stsd.extend_from_slice(&1u32.to_be_bytes());
+        stsd.extend_from_slice(&16u32.to_be_bytes());
+        stsd.extend_from_slice(codec);
+        let stbl = bx(b"stbl", &bx(b"stsd", &stsd));
+        let minf = bx(b"minf", &stbl);
+        let mdia = bx(b"mdia", &[bx(b"hdlr", &hdlr), minf].concat());
+        bx(b"trak", &[bx(b"tkhd", &tkhd), mdia].concat())
+    }
+
+    fn ftyp() -> Vec<u8> {
+        bx(b"ftyp", b"isom\0\0\0\0isommp41")
+    }
+
+    fn mp4(bytes: &[u8]) -> Option<MediaMeta> {
+        extract("video", "clip.MP4", &mut &bytes[..])
+    }
+
+    #[test]
+    fn mp4_reads_moov() {
+        let moov = bx(
+            b"moov",
+            &[mvhd(600, 6000), video_trak(b"avc1", 1920, 1080)].concat(),
+        );
+        let meta = mp4(&[ftyp(), moov].concat()).unwrap();
+        assert_eq!(meta.duration_ms, Some(10_000));
+        assert_eq!(meta.captured_at.as_deref(), Some("2023-11-15 06:13:20"));
+        assert_eq!(meta.captured_at_ts, Some(CREATED_UNIX as i64));
+        assert_eq!(meta.video_codec.as_deref(), Some("avc1"));
+        assert_eq!((meta.width, meta.height), (Some(1920), Some(1080)));
+    }
+
+    #[test]
+    fn mp4_moov_after_mdat() {
+        let mdat = bx(b"mdat", &[0xAB; 4096]);
+        let moov = bx(b"moov", &mvhd(1000, 2500));
+        let meta = mp4(&[ftyp(), mdat, moov].concat()).unwrap();
+        assert_eq!(meta.duration_ms, Some(2500));
+    }
+
+    #[test]
+    fn mp4_largesize_boxes() {
+        let mdat = large_bx(b"mdat", &[0u8; 100]);
+        let moov = large_bx(b"moov", &mvhd(90_000, 180_000));
+        let meta = mp4(&[ftyp(), mdat, moov].concat()).unwrap();
+        assert_eq!(meta.duration_ms, Some(2000));
+    }
+
+    #[test]
+    fn mp4_truncated_inputs() {
+        let full = [ftyp(), bx(b"moov", &mvhd(600, 6000))].concat();
+        // 截在 moov 内容中间、box 头中间，都只返回 None
+        for cut in [full.len() - 20, ftyp().len() + 4, 3] {
+            assert!(mp4(&full[..cut]).is_none(), "cut at {cut}");
+        }
+        // mvhd 完整但内容太短
+        assert!(mp4(&[ftyp(), bx(b"moov", &bx(b"mvhd", &[0u8; 10]))].concat()).is_none());
+        // 没有 moov
+        assert!(mp4(&[ftyp(), bx(b"mdat", &[0u8; 16])].concat()).is_none());
+    }
+
+    #[test]
+    fn each_box_stops_on_bad_sizes() {
+        let mut huge = 1u32.to_be_bytes().to_vec();
+        huge.extend_from_slice(b"free");
+        huge.extend_from_slice(&u64::MAX.to_be_bytes());
+        let tiny = [0, 0, 0, 4, b'f', b'r', b'e', b'e'];
+        for data in [huge, tiny.to_vec()] {
+            let mut seen = 0;
+            each_box(&data, |_, _| seen += 1);
+            assert_eq!(seen, 0);
+        }
+    }
+
+    /// 小端 TIFF：IFD0 放 Make/DateTime，可选 Exif 子 IFD 放 DateTimeOriginal
+    fn exif_tiff(make: &str, datetime: &str, original: Option<&str>) -> Vec<u8> {
+        let ifd0_count = if original.is_some() { 3 } else { 2 };
+        let exif_off = 8 + 2 + 12 * ifd0_count + 4;
+        let mut data_off = exif_off + if original.is_some() { 18 } else { 0 };
+        let mut data = Vec::new();
+        let mut ascii = |s: &str| {
+            let off = data_off as u32;
+            data.extend_from_slice(s.as_bytes());
+            data.push(0);
+            data_off += s.len() + 1;
+            ((s.len() + 1) as u32, off)
+        };
+        let mut ifd0 = vec![(0x010Fu16, 2u16, ascii(make)), (0x0132, 2, ascii(datetime))];
+        let exif = original.map(ascii);
+        if exif.is_some() {
+            ifd0.push((0x8769, 4, (1, exif_off as u32)));
+        }
+
+        let mut out = b"II".to_vec();
+        out.extend_from_slice(&42u16.to_le_bytes());
+        out.extend_from_slice(&8u32.to_le_bytes());
+        let write_ifd = |out: &mut Vec<u8>, entries: &[(u16, u16, (u32, u32))]| {
+            out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+            for (tag, ty, (count, value)) in entries {
+                out.extend_from_slice(&tag.to_le_bytes());
+                out.extend_from_slice(&ty.to_le_bytes());
+                out.extend_from_slice(&count.to_le_bytes());
+                out.extend_from_slice(&value.to_le_bytes());
+            }
+            out.extend_from_slice(&0u32.to_le_bytes());
+        };
+        write_ifd(&mut out, &ifd0);
+        if let Some(e) = exif {
+            write_ifd(&mut out, &[(0x9003, 2, e)]);
+        }
+        out.extend_from_slice(&data);
+        out
+    }
+
+    fn jpeg_with_exif(tiff: &[u8]) -> Vec<u8> {
+        let mut out = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        out.extend_from_slice(&((2 + 6 + tiff.len()) as u16).to_be_bytes());
+        out.extend_from_slice(b"Exif\0\0");
+        out.extend_from_slice(tiff);
+        out.extend_from_slice(&[0xFF, 0xDA, 0, 2]);
+        out
+    }
+
+    fn image(bytes: &[u8]) -> Option<MediaMeta> {
+        extract("image", "photo.jpg", &mut &bytes[..])
+    }
+
+    #[test]
+    fn exif_prefers_date_time_original() {
+        let tiff = exif_tiff("Canon", "2022:01:02 03:04:05", Some("2021:05:06 07:08:09"));
+        let meta = image(&jpeg_with_exif(&tiff)).unwrap();
+        assert_eq!(meta.camera_make.as_deref(), Some("Canon"));
+        assert_eq!(meta.captured_at.as_deref(), Some("2021-05-06 07:08:09"));
+        let expected = db::tz_offset()
+            .with_ymd_and_hms(2021, 5, 6, 7, 8, 9)
+            .unwrap()
+            .timestamp();
+        assert_eq!(meta.captured_at_ts, Some(expected));
+    }
+
+    #[test]
+    fn exif_falls_back_to_date_time() {
+        let tiff = exif_tiff("Canon", "2022:01:02 03:04:05", None);
+        let meta = image(&jpeg_with_exif(&tiff)).unwrap();
+        assert_eq!(meta.captured_at.as_deref(), Some("2022-01-02 03:04:05"));
+    }
+
+    #[test]
+    fn exif_ignores_invalid_date() {
+        let tiff = exif_tiff("Canon", "0000:00:00 00:00:00", None);
+        let meta = image(&jpeg_with_exif(&tiff)).unwrap();
+        assert_eq!(meta.captured_at, None);
+        assert_eq!(meta.captured_at_ts, None);
+        // 截断的 EXIF 不崩溃
+        let cut = jpeg_with_exif(&tiff[..20]);
+        assert!(image(&cut).map(|m| m.captured_at.is_none()).unwrap_or(true));
+    }
+}
@@ -0,0 +1,634 @@
+use crate::cache;
+use crate::db;
+use crate::library_root::LibraryRootState;
+use crate::progress;
+use crate::saved_searches;
+use crate::search::{self, ArchiveFilter, Range};
+use anyhow::{anyhow, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::State;
+
+const META_OCR_ENABLED: &str = "ocr_enabled";
+/// 只对这些附件类型做 OCR（PDF 只处理纯图片扫描件）
+const OCR_FILE_TYPES: [&str; 2] = ["image", "pdf"];
+/// 置信度过低的行多为噪点，不入索引
+const MIN_LINE_CONFIDENCE: i32 = 30;
+/// 中文模型必需，英文模型有则一起用
+const CHI_MODEL: &str = "chi_sim";
+const ENG_MODEL: &str = "eng";
+
+/// 同一时间只跑一个后台 OCR 线程
+static WORKER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OcrRegion {
+    pub x: i64,
+    pub y: i64,
+    pub w: i64,
+    pub h: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OcrBlock {
+    pub file_id: String,
+    /// 图片为 1；PDF 为第几张扫描页
+    pub page: i64,
+    pub block_idx: i64,
+    pub text: String,
+    pub region: OcrRegion,
+    pub confidence: i64,
+    pub image_width: i64,
+    pub image_height: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OcrStatus {
+    /// 编译时带 OCR 引擎（ocr feature）且找到中文识别模型
+    pub available: bool,
+    pub enabled: bool,
+    pub pending: i64,
+    pub running: i64,
+    pub done: i64,
+    pub failed: i64,
+    pub skipped: i64,
+}
+
+/// 一张图片的识别结果（行级）
+pub(crate) struct OcrPage {
+    pub width: i64,
+    pub height: i64,
+    pub lines: Vec<OcrLine>,
+}
+
+pub(crate) struct OcrLine {
+    pub text: String,
+    pub region: OcrRegion,
+    pub confidence: i32,
+}
+
+fn is_enabled(conn: &Connection) -> Result<bool> {
+    Ok(db::get_meta_value(conn, META_OCR_ENABLED)?.as_deref() == Some("1"))
+}
+
+/// 导入后把新档案的图片/PDF 附件加入 OCR 队列；未启用时什么都不做
+pub fn enqueue_archives(conn: &Connection, archive_ids: &[String]) -> Result<usize> {
+    if archive_ids.is_empty() || !is_enabled(conn)? {
+        return Ok(0);
+    }
+    let mut n = 0;
+    for archive_id in archive_ids {
+        n += conn.execute(
+            "INSERT OR IGNORE INTO ocr_jobs(file_id, archive_id, status, error, updated_at)
+             SELECT file_id, archive_id, 'pending', NULL, ? FROM attachments
+             WHERE archive_id=? AND file_type IN ('image','pdf')",
+            params![chrono::Utc::now().timestamp(), archive_id],
+        )?;
+    }
+    Ok(n)
+}
+
+fn enqueue_all(conn: &Connection) -> Result<usize> {
+    Ok(conn.execute(
+        "INSERT OR IGNORE INTO ocr_jobs(file_id, archive_id, status, error, updated_at)
+         SELECT file_id, archive_id, 'pending', NULL, ? FROM attachments
+         WHERE file_type IN ('image','pdf')",
+        [chrono::Utc::now().timestamp()],
+    )?)
+}
+
+/// 启动后台 OCR 线程处理队列；已有线程在跑、未启用或找不到模型时直接返回
+pub fn spawn_worker(app: &tauri::AppHandle, root: &Path) {
+    let Some(tessdata) = tessdata_dir(app, root) else {
+        return;
+    };
+    if WORKER_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    let root = root.to_path_buf();
+    std::thread::spawn(move || {
+        if let Err(e) = run_worker(&app, &root, &tessdata) {
+            eprintln!("OCR 后台任务失败: {e:#}");
+        }
+        WORKER_RUNNING.store(false, Ordering::SeqCst);
+    });
+}
+
+fn run_worker(app: &tauri::AppHandle, root: &Path, tessdata: &Path) -> Result<()> {
+    let conn = db::open_conn_at(root)?;
+    // 上次异常退出时遗留的 running 重新排队
+    conn.execute(
+        "UPDATE ocr_jobs SET status='pending' WHERE status='running'",
+        [],
+    )?;
+    let mut processed = 0usize;
+    // 本轮有新识别结果、尚未复跑保存的搜索的档案
+    let mut recognized = HashSet::new();
+    loop {
+        if !is_enabled(&conn)? {
+            break;
+        }
+        let next: Option<(String, String)> = conn
+            .query_row(
+                "SELECT file_id, archive_id FROM ocr_jobs WHERE status='pending'
+                 ORDER BY updated_at ASC, file_id ASC LIMIT 1",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .optional()?;
+        let Some((file_id, archive_id)) = next else {
+            break;
+        };
+        let pending: i64 = conn.query_row(
+            "SELECT COUNT(1) FROM ocr_jobs WHERE status='pending'",
+            [],
+            |r| r.get(0),
+        )?;
+        progress::emit(
+            app,
+            progress::ProgressEvent::new(
+                "ocr",
+                processed,
+                processed + pending as usize,
+                "文字识别",
+                &format!("正在识别附件 {file_id}"),
+            ),
+        );
+        set_job_status(&conn, &file_id, "running", None)?;
+        match process_file(root, &conn, tessdata, &archive_id, &file_id) {
+            Ok(true) => {
+                set_job_status(&conn, &file_id, "done", None)?;
+                recognized.insert(archive_id.clone());
+            }
+            Ok(false) => set_job_status(&conn, &file_id, "skipped", None)?,
+            Err(e) => {
+                eprintln!("OCR 失败: {file_id}: {e:#}");
+                set_job_status(&conn, &file_id, "failed", Some(&format!("{e:#}")))?;
+            }
+        }
+        processed += 1;
+        // 档案的附件全部识别完后，保存的搜索才能命中 OCR 文字
+        let remaining: i64 = conn.query_row(
+            "SELECT COUNT(1) FROM ocr_jobs WHERE archive_id=? AND status IN ('pending','running')",
+            [&archive_id],
+            |r| r.get(0),
+        )?;
+        if remaining == 0 && recognized.remove(&archive_id) {
+            rerun_saved_searches(app, &conn, &[archive_id]);
+        }
+    }
+    // 中途关闭 OCR 时，已识别的部分也复跑一次
+    if !recognized.is_empty() {
+        let ids = recognized.into_iter().collect::<Vec<_>>();
+        rerun_saved_searches(app, &conn, &ids);
+    }
+    if processed > 0 {
+        progress::emit(
+            app,
+            progress::ProgressEvent::complete("ocr", &format!("文字识别完成：{processed} 个附件")),
+        );
+    }
+    Ok(())
+}
+
+fn rerun_saved_searches(app: &tauri::AppHandle, conn: &Connection, archive_ids: &[String]) {
+    if let Err(e) = saved_searches::run_for_new_archives(app, conn, archive_ids) {
+        eprintln!("保存的搜索复跑失败: {e:#}");
+    }
+}
+
+fn set_job_status(
+    conn: &Connection,
+    file_id: &str,
+    status: &str,
+    error: Option<&str>,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE ocr_jobs SET status=?, error=?, updated_at=? WHERE file_id=?",
+        params![status, error, chrono::Utc::now().timestamp(), file_id],
+    )?;
+    Ok(())
+}
+
+/// 返回 false 表示无需 OCR（如带文字层的 PDF）
+fn process_file(
+    root: &Path,
+    conn: &Connection,
+    tessdata: &Path,
+    archive_id: &str,
+    file_id: &str,
+) -> Result<bool> {
+    let file_type: String = conn.query_row(
+        "SELECT file_type FROM attachments WHERE file_id=?",
+        [file_id],
+        |r| r.get(0),
+    )?;
+    let path = cache::ensure_cached_file(root, conn, file_id)?;
+    let bytes = fs::read(&path)?;
+    let images = match file_type.as_str() {
+        "image" => vec![bytes],
+        "pdf" => {
+            let images = pdf_scanned_images(&bytes);
+            if images.is_empty() {
+                return Ok(false);
+            }
+            images
+        }
+        _ => return Ok(false),
+    };
+
+    let mut pages = Vec::new();
+    for img in &images {
+        pages.push(engine::recognize(tessdata, img)?);
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM ocr_blocks WHERE file_id=?", [file_id])?;
+    tx.execute("DELETE FROM ocr_fts WHERE file_id=?", [file_id])?;
+    {
+        let mut ins = tx.prepare(
+            "INSERT INTO ocr_blocks(file_id, archive_id, page, block_idx, text, x, y, w, h, confidence, image_width, image_height)
+             VALUES(?,?,?,?,?,?,?,?,?,?,?,?)",
+        )?;
+        let mut fts = tx.prepare(
+            "INSERT INTO ocr_fts(archive_id, file_id, page, block_idx, search_text, source_text)
+             VALUES(?,?,?,?,?,?)",
+        )?;
+        for (pi, page) in pages.iter().enumerate() {
+            let page_no = pi as i64 + 1;
+            let mut idx = 0i64;
+            for line in &page.lines {
+                let text = line.text.trim();
+                if text.is_empty() || line.confidence < MIN_LINE_CONFIDENCE {
+                    continue;
+                }
+                ins.execute(params![
+                    file_id,
+                    archive_id,
+                    page_no,
+                    idx,
+                    text,
+                    line.region.x,
+                    line.region.y,
+                    line.region.w,
+                    line.region.h,
+                    line.confidence as i64,
+                    page.width,
+                    page.height
+                ])?;
+                fts.execute(params![
+                    archive_id,
+                    file_id,
+                    page_no,
+                    idx,
+                    search::build_search_text(text),
+                    text
+                ])?;
+                idx += 1;
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(true)
+}
+
+/// 打包的 tessdata 优先，其次库目录下 ocr/tessdata；只认带中文模型的目录。
+/// 未带 OCR 引擎编译时始终为 None
+fn tessdata_dir(app: &tauri::AppHandle, root: &Path) -> Option<PathBuf> {
+    use tauri::Manager;
+    if !cfg!(feature = "ocr") {
+        return None;
+    }
+    let bundled = app.path().resource_dir().ok().map(|d| d.join("tessdata"));
+    let local = Some(root.join("ocr").join("tessdata"));
+    [bundled, local]
+        .into_iter()
+        .flatten()
+        .find(|p| model_path(p, CHI_MODEL).is_file())
+}
+
+fn model_path(tessdata: &Path, lang: &str) -> PathBuf {
+    tessdata.join(format!("{lang}.traineddata"))
+}
+
+/// 从没有文字层的 PDF 中取出 DCTDecode（JPEG）图片，按出现顺序视为扫描页。
+/// 只做字节级扫描，不解析交叉引用；有字体资源的 PDF 视为文字 PDF，跳过。
+pub(crate) fn pdf_scanned_images(bytes: &[u8]) -> Vec<Vec<u8>> {
+    if find(bytes, b"/Font", 0).is_some() {
+        return vec![];
+    }
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while let Some(i) = find(bytes, b"/DCTDecode", pos) {
+        pos = i + 1;
+        let Some(s) = find(bytes, b"stream", i) else {
+            break;
+        };
+        let mut start = s + b"stream".len();
+        while start < bytes.len() && (bytes[start] == b'\r' || bytes[start] == b'\n') {
+            start += 1;
+        }
+        if !bytes[start..].starts_with(&[0xFF, 0xD8, 0xFF]) {
+            continue;
+        }
+        let Some(end) = find(bytes, b"endstream", start) else {
+            break;
+        };
+        let mut e = end;
+        while e > start && (bytes[e - 1] == b'\r' || bytes[e - 1] == b'\n') {
+            e -= 1;
+        }
+        out.push(bytes[start..e].to_vec());
+        pos = end;
+    }
+    out
+}
+
+fn find(hay: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    if from >= hay.len() {
+        return None;
+    }
+    hay[from..]
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|p| p + from)
+}
+
+/// 高亮区间按字符比例换算成行框内的子区域（行内字宽近似均匀）
+pub(crate) fn highlight_regions(
+    text: &str,
+    region: &OcrRegion,
+    highlights: &[Range],
+) -> Vec<OcrRegion> {
+    let total = text.encode_utf16().count().max(1) as i64;
+    highlights
+        .iter()
+        .map(|h| {
+            let x0 = region.x + region.w * h.start as i64 / total;
+            let x1 = region.x + region.w * h.end as i64 / total;
+            OcrRegion {
+                x: x0,
+                y: region.y,
+                w: (x1 - x0).max(1),
+                h: region.h,
+            }
+        })
+        .collect()
+}
+
+#[cfg(feature = "ocr")]
+mod engine {
+    use super::{model_path, OcrLine, OcrPage, OcrRegion, CHI_MODEL, ENG_MODEL};
+    use anyhow::{anyhow, Result};
+    use leptess::{capi, LepTess};
+    use std::path::Path;
+
+    pub fn recognize(tessdata: &Path, image: &[u8]) -> Result<OcrPage> {
+        let langs = if model_path(tessdata, ENG_MODEL).is_file() {
+            format!("{CHI_MODEL}+{ENG_MODEL}")
+        } else {
+            CHI_MODEL.to_string()
+        };
+        let datapath = tessdata.to_string_lossy();
+        let mut lt = LepTess::new(Some(datapath.as_ref()), &langs)
+            .map_err(|e| anyhow!("初始化 Tesseract 失败: {e}"))?;
+        lt.set_image_from_mem(image)
+            .map_err(|e| anyhow!("读取图片失败: {e}"))?;
+        let (width, height) = lt.get_image_dimensions().unwrap_or((0, 0));
+        let mut lines = Vec::new();
+        if let Some(boxes) = lt.get_component_boxes(capi::TessPageIteratorLevel_RIL_TEXTLINE, true)
+        {
+            for b in &boxes {
+                lt.set_rectangle(b);
+                let text = lt.get_utf8_text().unwrap_or_default();
+                let g = b.as_ref();
+                lines.push(OcrLine {
+                    // 中文识别结果字间常带空格
+                    text: text.split_whitespace().collect::<Vec<_>>().join(" "),
+                    region: OcrRegion {
+                        x: g.x as i64,
+                        y: g.y as i64,
+                        w: g.w as i64,
+                        h: g.h as i64,
+                    },
+                    confidence: lt.mean_text_conf(),
+                });
+            }
+        }
+        Ok(OcrPage {
+            width: width as i64,
+            height: height as i64,
+            lines,
+        })
+    }
+}
+
+#[cfg(not(feature = "ocr"))]
+mod engine {
+    use super::OcrPage;
+    use anyhow::{anyhow, Result};
+    use std::path::Path;
+
+    pub fn recognize(_tessdata: &Path, _image: &[u8]) -> Result<OcrPage> {
+        Err(anyhow!("当前版本未包含 OCR 引擎"))
+    }
+}
+
+/// 搜索：OCR 行文本命中，按附件类型过滤
+pub(crate) fn query_ocr(
+    conn: &Connection,
+    match_query: &str,
+    limit: usize,
+    allowed_archives: &Option<HashSet<String>>,
+    want_types: &Option<HashSet<String>>,
+) -> Result<Vec<search::SearchResult>> {
+    let mut types: Vec<&str> = OCR_FILE_TYPES.to_vec();
+    if let Some(want) = want_types {
+        types.retain(|t| want.contains(*t));
+        if types.is_empty() {
+            return Ok(vec![]);
+        }
+    }
+    let (archive_clause, archive_params) =
+        match search::archive_in_clause("ocr_fts.archive_id", allowed_archives)? {
+            ArchiveFilter::All => (String::new(), vec![]),
+            ArchiveFilter::Empty => return Ok(vec![]),
+            ArchiveFilter::In(sql, params) => (sql, params),
+        };
+    let type_clause = format!(
+        " AND a.file_type IN ({})",
+        types.iter().map(|_| "?").collect::<Vec<_>>().join(",")
+    );
+    let sql = format!(
+        "SELECT b.archive_id, b.file_id, a.display_name, b.page, b.block_idx, b.text,
+                b.x, b.y, b.w, b.h, b.image_width, b.image_height
+         FROM ocr_fts
+         JOIN ocr_blocks b ON b.file_id=ocr_fts.file_id AND b.page=ocr_fts.page AND b.block_idx=ocr_fts.block_idx
+         JOIN attachments a ON a.file_id=b.file_id
+         JOIN main_doc m ON m.archive_id=b.archive_id
+         WHERE ocr_fts MATCH ? {archive_clause} {type_clause}
+         ORDER BY COALESCE(m.issued_at_ts, 0) DESC, b.archive_id ASC, b.file_id ASC, b.page ASC, b.block_idx ASC
+         LIMIT ?"
+    );
+    let mut bind: Vec<rusqlite::types::Value> = Vec::new();
+    bind.push(rusqlite::types::Value::from(match_query.to_string()));
+    bind.extend(archive_params);
+    bind.extend(
+        types
+            .iter()
+            .map(|t| rusqlite::types::Value::from(t.to_string())),
+    );
+    bind.push(rusqlite::types::Value::from(limit as i64));
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(bind), |r| {
+        Ok(search::SearchResult::OcrText {
+            archive_id: r.get(0)?,
+            file_id: r.get(1)?,
+            display_name: r.get(2)?,
+            page: r.get(3)?,
+            block_idx: r.get(4)?,
+            text: r.get(5)?,
+            region: OcrRegion {
+                x: r.get(6)?,
+                y: r.get(7)?,
+                w: r.get(8)?,
+                h: r.get(9)?,
+            },
+            image_width: r.get(10)?,
+            image_height: r.get(11)?,
+            highlights: vec![],
+            highlight_regions: vec![],
+        })
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+pub(crate) fn delete_archive(conn: &Connection, archive_id: &str) -> Result<()> {
+    conn.execute("DELETE FROM ocr_fts WHERE archive_id=?", [archive_id])?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_ocr_status(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+) -> Result<OcrStatus, String> {
+    let (root, conn) = db::open_conn(&app, &state).map_err(db::err_to_string)?;
+    load_status(&app, &root, &conn).map_err(db::err_to_string)
+}
+
+fn load_status(app: &tauri::AppHandle, root: &Path, conn: &Connection) -> Result<OcrStatus> {
+    let count = |status: &str| -> Result<i64> {
+        Ok(conn.query_row(
+            "SELECT COUNT(1) FROM ocr_jobs WHERE status=?",
+            [status],
+            |r| r.get(0),
+        )?)
+    };
+    Ok(OcrStatus {
+        available: tessdata_dir(app, root).is_some(),
+        enabled: is_enabled(conn)?,
+        pending: count("pending")?,
+        running: count("running")?,
+        done: count("done")?,
+        failed: count("failed")?,
+        skipped: count("skipped")?,
+    })
+}
+
+#[tauri::command]
+pub fn set_ocr_enabled(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    enabled: bool,
+) -> Result<OcrStatus, String> {
+    set_ocr_enabled_impl(&app, &state, enabled).map_err(db::err_to_string)
+}
+
+fn set_ocr_enabled_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    enabled: bool,
+) -> Result<OcrStatus> {
+    let (root, conn) = db::open_conn(app, state)?;
+    if enabled && tessdata_dir(app, &root).is_none() {
+        return Err(anyhow!(
+            "OCR 不可用：当前版本未包含 OCR 引擎，或找不到中文识别模型 {CHI_MODEL}.traineddata（库目录下 ocr/tessdata）"
+        ));
+    }
+    db::set_meta_value(&conn, META_OCR_ENABLED, if enabled { "1" } else { "0" })?;
+    if enabled {
+        // 首次开启：把库里已有的图片/PDF 全部排队
+        enqueue_all(&conn)?;
+        spawn_worker(app, &root);
+    }
+    load_status(app, &root, &conn)
+}
+
+#[tauri::command]
+pub fn retry_failed_ocr(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+) -> Result<usize, String> {
+    let (root, conn) = db::open_conn(&app, &state).map_err(db::err_to_string)?;
+    let n = conn
+        .execute(
+            "UPDATE ocr_jobs SET status='pending', error=NULL WHERE status='failed'",
+            [],
+        )
+        .map_err(|e| db::err_to_string(anyhow!(e)))?;
+    spawn_worker(&app, &root);
+    Ok(n)
+}
+
+#[tauri::command]
+pub fn get_ocr_blocks(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    file_id: String,
+) -> Result<Vec<OcrBlock>, String> {
+    get_ocr_blocks_impl(&app, &state, &file_id).map_err(db::err_to_string)
+}
+
+fn get_ocr_blocks_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    file_id: &str,
+) -> Result<Vec<OcrBlock>> {
+    let (_root, conn) = db::open_conn(app, state)?;
+    let mut stmt = conn.prepare(
+        "SELECT file_id, page, block_idx, text, x, y, w, h, confidence, image_width, image_height
+         FROM ocr_blocks WHERE file_id=? ORDER BY page, block_idx",
+    )?;
+    let rows = stmt.query_map([file_id], |r| {
+        Ok(OcrBlock {
+            file_id: r.get(0)?,
+            page: r.get(1)?,
+            block_idx: r.get(2)?,
+            text: r.get(3)?,
+            region: OcrRegion {
+                x: r.get(4)?,
+                y: r.get(5)?,
+                w: r.get(6)?,
+                h: r.get(7)?,
+            },
+            confidence: r.get(8)?,
+            image_width: r.get(9)?,
+            image_height: r.get(10)?,
+        })
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
@@ -0,0 +1,235 @@
+// OFD（GB/T 33190 版式文档）文字抽取：OFD.xml -> DocRoot(Document.xml) -> 各页 Content.xml，
+// 收集 TextObject/TextCode 的文字与坐标，按行拼成文本。不解析模板页、注释层与字形。
+
+use crate::docx::{self, MainDocParsed};
+use anyhow::{anyhow, Context, Result};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader as XmlReader;
+use std::io::{Cursor, Read, Seek};
+use zip::ZipArchive;
+
+/// 纵坐标相差小于该值（毫米）的文字视为同一行
+const LINE_TOLERANCE: f64 = 1.5;
+
+#[derive(Debug, Clone)]
+struct TextFragment {
+    x: f64,
+    y: f64,
+    text: String,
+}
+
+pub fn is_ofd(bytes: &[u8]) -> bool {
+    if !bytes.starts_with(b"PK\x03\x04") {
+        return false;
+    }
+    ZipArchive::new(Cursor::new(bytes))
+        .map(|mut z| z.by_name("OFD.xml").is_ok())
+        .unwrap_or(false)
+}
+
+/// OFD 主文：每个文字行作为一个段落，其余规则与 docx 相同
+pub fn parse_main_ofd(bytes: &[u8]) -> Result<MainDocParsed> {
+    let paragraphs = extract_pages(bytes)?.into_iter().flatten().collect();
+    docx::parse_main_paragraphs(paragraphs)
+}
+
+/// 按页返回文字行（页序与 Document.xml 中 Pages 顺序一致）
+pub fn extract_pages(bytes: &[u8]) -> Result<Vec<Vec<String>>> {
+    let mut zip = ZipArchive::new(Cursor::new(bytes)).context("ofd不是有效的zip")?;
+    let ofd_xml = read_text(&mut zip, "OFD.xml").context("ofd缺少 OFD.xml")?;
+    let doc_root =
+        first_element_text(&ofd_xml, b"DocRoot")?.ok_or_else(|| anyhow!("OFD.xml 缺少 DocRoot"))?;
+    let doc_path = resolve_path("", &doc_root);
+    let doc_xml = read_text(&mut zip, &doc_path).with_context(|| format!("ofd缺少 {doc_path}"))?;
+    let doc_dir = parent_dir(&doc_path);
+
+    let mut pages = Vec::new();
+    for base_loc in page_locations(&doc_xml)? {
+        let page_path = resolve_path(&doc_dir, &base_loc);
+        // 个别页面缺失时跳过该页，不影响其余页
+        let Ok(content) = read_text(&mut zip, &page_path) else {
+            pages.push(vec![]);
+            continue;
+        };
+        pages.push(group_lines(page_fragments(&content)?));
+    }
+    Ok(pages)
+}
+
+fn read_text<R: Read + Seek>(zip: &mut ZipArchive<R>, name: &str) -> Result<String> {
+    let mut f = zip.by_name(name)?;
+    let mut s = String::new();
+    f.read_to_string(&mut s)?;
+    Ok(s)
+}
+
+fn local_name(name: &[u8]) -> &[u8] {
+    match name.iter().rposition(|b| *b == b':') {
+        Some(i) => &name[i + 1..],
+        None => name,
+    }
+}
+
+fn attr(e: &BytesStart<'_>, key: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| local_name(a.key.as_ref()) == key)
+        .and_then(|a| a.unescape_value().ok().map(|v| v.to_string()))
+}
+
+fn first_element_text(xml: &str, tag: &[u8]) -> Result<Option<String>> {
+    let mut reader = XmlReader::from_str(xml);
+    let mut buf = Vec::new();
+    let mut inside = false;
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) if local_name(e.name().as_ref()) == tag => inside = true,
+            Ok(Event::Text(t)) if inside => {
+                let s = t.unescape()?.trim().to_string();
+                if !s.is_empty() {
+                    return Ok(Some(s));
+                }
+            }
+            Ok(Event::End(e)) if local_name(e.name().as_ref()) == tag => inside = false,
+            Ok(Event::Eof) => return Ok(None),
+            Err(e) => return Err(anyhow!("XML解析失败: {e:?}")),
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+/// Document.xml 中 Pages/Page 的 BaseLoc
+fn page_locations(doc_xml: &str) -> Result<Vec<String>> {
+    let mut reader = XmlReader::from_str(doc_xml);
+    let mut buf = Vec::new();
+    let mut out = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e))
+                if local_name(e.name().as_ref()) == b"Page" =>
+            {
+                if let Some(loc) = attr(&e, b"BaseLoc") {
+                    out.push(loc);
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(anyhow!("XML解析失败: {e:?}")),
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(out)
+}
+
+fn page_fragments(content_xml: &str) -> Result<Vec<TextFragment>> {
+    let mut reader = XmlReader::from_str(content_xml);
+    reader.config_mut().trim_text(false);
+    let mut buf = Vec::new();
+    let mut out = Vec::new();
+    // 当前 TextObject 的 Boundary 原点；TextCode 坐标相对于它
+    let mut origin = (0.0, 0.0);
+    let mut code: Option<TextFragment> = None;
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => match local_name(e.name().as_ref()) {
+                b"TextObject" => {
+                    origin = attr(&e, b"Boundary")
+                        .map(|b| parse_origin(&b))
+                        .unwrap_or((0.0, 0.0));
+                }
+                b"TextCode" => {
+                    let num = |k: &[u8]| {
+                        attr(&e, k)
+                            .and_then(|v| v.trim().parse::<f64>().ok())
+                            .unwrap_or(0.0)
+                    };
+                    code = Some(TextFragment {
+                        x: origin.0 + num(b"X"),
+                        y: origin.1 + num(b"Y"),
+                        text: String::new(),
+                    });
+                }
+                _ => {}
+            },
+            Ok(Event::Text(t)) => {
+                if let Some(c) = code.as_mut() {
+                    c.text.push_str(&t.unescape()?);
+                }
+            }
+            Ok(Event::End(e)) if local_name(e.name().as_ref()) == b"TextCode" => {
+                if let Some(c) = code.take() {
+                    if !c.text.trim().is_empty() {
+                        out.push(c);
+                    }
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(anyhow!("XML解析失败: {e:?}")),
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(out)
+}
+
+/// Boundary="x y w h"
+fn parse_origin(boundary: &str) -> (f64, f64) {
+    let mut it = boundary
+        .split_whitespace()
+        .map(|v| v.parse::<f64>().unwrap_or(0.0));
+    (it.next().unwrap_or(0.0), it.next().unwrap_or(0.0))
+}
+
+/// 先按纵坐标分行，行内按横坐标拼接
+fn group_lines(mut frags: Vec<TextFragment>) -> Vec<String> {
+    frags.sort_by(|a, b| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+    let mut lines: Vec<(f64, Vec<TextFragment>)> = Vec::new();
+    for f in frags {
+        match lines.last_mut() {
+            Some((y, line)) if (f.y - *y).abs() < LINE_TOLERANCE => line.push(f),
+            _ => lines.push((f.y, vec![f])),
+        }
+    }
+    lines
+        .into_iter()
+        .map(|(_, mut line)| {
+            line.sort_by(|a, b| a.x.total_cmp(&b.x));
+            line.into_iter()
+                .map(|f| f.text)
+                .collect::<String>()
+                .replace('\u{00A0}', " ")
+                .replace('\u{3000}', " ")
+        })
+        .collect()
+}
+
+fn parent_dir(path: &str) -> String {
+    match path.rfind('/') {
+        Some(i) => path[..i].to_string(),
+        None => String::new(),
+    }
+}
+
+/// OFD 内路径：以 / 开头为包内绝对路径，否则相对于当前文件所在目录
+fn resolve_path(base_dir: &str, loc: &str) -> String {
+    let loc = loc.replace('\\', "/");
+    let joined = if let Some(abs) = loc.strip_prefix('/') {
+        abs.to_string()
+    } else if base_dir.is_empty() {
+        loc
+    } else {
+        format!("{base_dir}/{loc}")
+    };
+    let mut parts: Vec<&str> = Vec::new();
+    for p in joined.split('/') {
+        match p {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            p => parts.push(p),
+        }
+    }
+    parts.join("/")
+}
@@ -0,0 +1,313 @@
+use crate::db;
+use crate::docx::{self, DocxBlock};
+use crate::docx_rich::{self, RichBlock};
+use crate::importer;
+use crate::library_root::LibraryRootState;
+use crate::search::SearchResult;
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use tauri::State;
+
+/// 旧库补建目录的版本标记
+const META_OUTLINE_VERSION: &str = "docx_outline_version";
+const OUTLINE_VERSION: &str = "1";
+/// 按编号识别的标题，标题部分超过该长度视为正文段落
+const MAX_PATTERN_TITLE_CHARS: usize = 40;
+/// 样式标题过长时截断显示
+const MAX_TITLE_CHARS: usize = 60;
+
+const CN_NUM: &str = "一二三四五六七八九十百零〇";
+
+/// 公文常见层级编号，按惯用的从大到小排列；实际层级按文档中出现的种类压缩
+static SECTION_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+    [
+        format!(r"^第[{CN_NUM}\d]+[章编篇部]"),
+        format!(r"^第[{CN_NUM}\d]+节"),
+        format!(r"^第[{CN_NUM}\d]+条"),
+        format!(r"^[{CN_NUM}]+[、．.]"),
+        format!(r"^[（(][{CN_NUM}]+[）)]"),
+        r"^\d{1,3}(?:[、．]|\.(?:[^\d]|$))".to_string(),
+        r"^[（(]\d{1,3}[）)]".to_string(),
+    ]
+    .iter()
+    .map(|p| Regex::new(p).expect("valid regex"))
+    .collect()
+});
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutlineEntry {
+    pub block_id: String,
+    /// 从 1 开始
+    pub level: i64,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutlineNode {
+    pub block_id: String,
+    pub level: i64,
+    pub title: String,
+    pub children: Vec<OutlineNode>,
+}
+
+/// 从主文段落推导目录：docx 标题样式/大纲级别优先，其余按章节编号识别；
+/// rich_blocks 为主文富文本模型（.doc/.wps/.ofd 主文为空）
+pub fn build(rich_blocks: &[RichBlock], blocks: &[DocxBlock]) -> Vec<OutlineEntry> {
+    // 自动编号不在段落文本里，需要从富文本模型补回来才能按编号识别
+    let mut rich: HashMap<&str, (Option<u8>, Option<&str>)> = HashMap::new();
+    for b in rich_blocks {
+        if let RichBlock::Paragraph(p) = b {
+            rich.insert(
+                p.block_id.as_str(),
+                (p.heading_level, p.numbering.as_deref()),
+            );
+        }
+    }
+
+    // (block_id, 排序键, 标题)；样式标题键 1~9，编号标题键 10 起
+    let mut candidates = Vec::new();
+    for b in blocks
+        .iter()
+        .filter(|b| b.block_id.starts_with(docx::BODY_PREFIX))
+    {
+        let (heading_level, numbering) = rich
+            .get(b.block_id.as_str())
+            .copied()
+            .unwrap_or((None, None));
+        let text = match numbering {
+            Some(n) => format!("{n}{}", b.text.trim()),
+            None => b.text.trim().to_string(),
+        };
+        if text.is_empty() {
+            continue;
+        }
+        if let Some(level) = heading_level {
+            candidates.push((b.block_id.clone(), level as usize, truncate(&text)));
+            continue;
+        }
+        if let Some(kind) = SECTION_PATTERNS.iter().position(|re| re.is_match(&text)) {
+            let title = pattern_title(&text);
+            if title.chars().count() <= MAX_PATTERN_TITLE_CHARS {
+                candidates.push((b.block_id.clone(), 10 + kind, title));
+            }
+        }
+    }
+
+    let ranks: Vec<usize> = candidates
+        .iter()
+        .map(|c| c.1)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    candidates
+        .into_iter()
+        .map(|(block_id, key, title)| OutlineEntry {
+            block_id,
+            level: ranks.iter().position(|r| *r == key).unwrap_or(0) as i64 + 1,
+            title,
+        })
+        .collect()
+}
+
+/// “一、总体要求。……”只取到第一个句读或冒号
+fn pattern_title(text: &str) -> String {
+    let end = text
+        .char_indices()
+        .find(|(_, c)| {
+            matches!(
+                *c,
+                '。' | '；' | ';' | '！' | '!' | '？' | '?' | '：' | ':' | '\n'
+            )
+        })
+        .map(|(i, _)| i)
+        .unwrap_or(text.len());
+    text[..end].trim().to_string()
+}
+
+fn truncate(text: &str) -> String {
+    let line = text.lines().next().unwrap_or("").trim();
+    if line.chars().count() <= MAX_TITLE_CHARS {
+        return line.to_string();
+    }
+    let mut s: String = line.chars().take(MAX_TITLE_CHARS).collect();
+    s.push('…');
+    s
+}
+
+/// 重建单个档案的目录（导入/重解析后调用）
+pub fn index_archive(
+    conn: &Connection,
+    archive_id: &str,
+    rich_blocks: &[RichBlock],
+    blocks: &[DocxBlock],
+) -> Result<()> {
+    conn.execute("DELETE FROM docx_outline WHERE archive_id=?", [archive_id])?;
+    let mut stmt = conn.prepare(
+        "INSERT INTO docx_outline(archive_id,ord,block_id,level,title) VALUES(?,?,?,?,?)",
+    )?;
+    for (i, e) in build(rich_blocks, blocks).into_iter().enumerate() {
+        stmt.execute(params![archive_id, i as i64, e.block_id, e.level, e.title])?;
+    }
+    Ok(())
+}
+
+pub(crate) fn backfill_pending(conn: &Connection) -> Result<bool> {
+    Ok(db::get_meta_value(conn, META_OUTLINE_VERSION)?.as_deref() != Some(OUTLINE_VERSION))
+}
+
+/// 旧库首次打开时为已导入档案补建目录（只做一次，出错的档案跳过）
+pub fn ensure_backfilled(conn: &Connection, root: &Path) -> Result<()> {
+    if !backfill_pending(conn)? {
+        return Ok(());
+    }
+    let mut todo = Vec::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT m.archive_id FROM main_doc m
+             WHERE NOT EXISTS (SELECT 1 FROM docx_outline o WHERE o.archive_id=m.archive_id)",
+        )?;
+        let rows = stmt.query_map([], |r| r.get::<_, String>(0))?;
+        for row in rows {
+            todo.push(row?);
+        }
+    }
+    for archive_id in todo {
+        let bytes = match importer::read_main_document(root, conn, &archive_id) {
+            Ok((_, bytes)) => bytes,
+            Err(e) => {
+                eprintln!("目录补建失败 {archive_id}: {e:#}");
+                continue;
+            }
+        };
+        let rich = docx_rich::parse_rich_docx(&bytes).unwrap_or_default();
+        let done = load_blocks(conn, &archive_id).and_then(|blocks| {
+            let tx = conn.unchecked_transaction()?;
+            index_archive(&tx, &archive_id, &rich, &blocks)?;
+            tx.commit()?;
+            Ok(())
+        });
+        if let Err(e) = done {
+            eprintln!("目录补建失败 {archive_id}: {e:#}");
+        }
+    }
+    db::set_meta_value(conn, META_OUTLINE_VERSION, OUTLINE_VERSION)?;
+    Ok(())
+}
+
+fn load_blocks(conn: &Connection, archive_id: &str) -> Result<Vec<DocxBlock>> {
+    let mut stmt =
+        conn.prepare("SELECT block_id,text FROM docx_blocks WHERE archive_id=? ORDER BY block_id")?;
+    let rows = stmt.query_map([archive_id], |r| {
+        Ok(DocxBlock {
+            block_id: r.get(0)?,
+            text: r.get(1)?,
+        })
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+fn load_entries(conn: &Connection, archive_id: &str) -> Result<Vec<OutlineEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT block_id, level, title FROM docx_outline WHERE archive_id=? ORDER BY ord",
+    )?;
+    let rows = stmt.query_map([archive_id], |r| {
+        Ok(OutlineEntry {
+            block_id: r.get(0)?,
+            level: r.get(1)?,
+            title: r.get(2)?,
+        })
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+#[tauri::command]
+pub fn get_docx_outline(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    archive_id: String,
+) -> Result<Vec<OutlineNode>, String> {
+    get_docx_outline_impl(&app, &state, &archive_id).map_err(db::err_to_string)
+}
+
+fn get_docx_outline_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    archive_id: &str,
+) -> Result<Vec<OutlineNode>> {
+    let (_root, conn) = db::open_conn(app, state)?;
+    Ok(nest(load_entries(&conn, archive_id)?))
+}
+
+/// 扁平目录 -> 树；层级跳跃（1 级下直接 3 级）时挂在最近的上级下
+fn nest(entries: Vec<OutlineEntry>) -> Vec<OutlineNode> {
+    let mut roots: Vec<OutlineNode> = Vec::new();
+    // 当前路径上各节点所在的 children 下标
+    let mut path: Vec<(i64, usize)> = Vec::new();
+    for e in entries {
+        while path.last().map(|(lvl, _)| *lvl >= e.level).unwrap_or(false) {
+            path.pop();
+        }
+        let node = OutlineNode {
+            block_id: e.block_id,
+            level: e.level,
+            title: e.title,
+            children: Vec::new(),
+        };
+        let mut siblings = &mut roots;
+        for (_, idx) in &path {
+            siblings = &mut siblings[*idx].children;
+        }
+        siblings.push(node);
+        path.push((e.level, siblings.len() - 1));
+    }
+    roots
+}
+
+/// 段落所在章节的标题路径（含段落本身为标题的情况）
+fn section_path(entries: &[OutlineEntry], block_id: &str) -> Vec<String> {
+    let mut path: Vec<&OutlineEntry> = Vec::new();
+    // block_id 定长编号，字符串序即文档序
+    for e in entries
+        .iter()
+        .take_while(|e| e.block_id.as_str() <= block_id)
+    {
+        while path.last().map(|p| p.level >= e.level).unwrap_or(false) {
+            path.pop();
+        }
+        path.push(e);
+    }
+    path.into_iter().map(|e| e.title.clone()).collect()
+}
+
+/// 搜索结果：为正文段落命中补上章节路径（只处理当前页）
+pub(crate) fn fill_section_paths(conn: &Connection, items: &mut [SearchResult]) -> Result<()> {
+    let mut cache: HashMap<String, Vec<OutlineEntry>> = HashMap::new();
+    for item in items.iter_mut() {
+        if let SearchResult::DocxBlock {
+            archive_id,
+            block_id,
+            section_path: path,
+            ..
+        } = item
+        {
+            if !cache.contains_key(archive_id.as_str()) {
+                cache.insert(archive_id.clone(), load_entries(conn, archive_id)?);
+            }
+            *path = section_path(&cache[archive_id.as_str()], block_id);
+        }
+    }
+    Ok(())
+}
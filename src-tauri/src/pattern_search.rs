@@ -0,0 +1,498 @@
+use crate::search::{self, ArchiveFilter, Range, SearchResult};
+use anyhow::{anyhow, Result};
+use regex::{Regex, RegexBuilder};
+use rusqlite::Connection;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+const DEFAULT_BUDGET_MS: u64 = 3000;
+const MAX_BUDGET_MS: u64 = 30_000;
+/// 每条结果最多返回的高亮区间
+const MAX_HIGHLIGHTS: usize = 64;
+/// 预过滤最多使用的二元组数量
+const MAX_PREFILTER_GRAMS: usize = 4;
+/// 编译后正则的体积上限，防止病态表达式占满内存
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternMode {
+    Regex,
+    Wildcard,
+}
+
+/// None 表示普通 FTS 模式
+pub fn parse_mode(mode: Option<&str>) -> Result<Option<PatternMode>> {
+    match mode.map(|m| m.trim()).unwrap_or("") {
+        "" | "fts" => Ok(None),
+        "regex" => Ok(Some(PatternMode::Regex)),
+        "wildcard" => Ok(Some(PatternMode::Wildcard)),
+        other => Err(anyhow!("不支持的搜索模式: {other}")),
+    }
+}
+
+pub struct PatternHits {
+    pub docx: Vec<SearchResult>,
+    pub fields: Vec<SearchResult>,
+    pub attachments: Vec<SearchResult>,
+    pub timed_out: bool,
+}
+
+struct Scan {
+    re: Regex,
+    deadline: Instant,
+    timed_out: bool,
+}
+
+impl Scan {
+    fn expired(&mut self) -> bool {
+        if !self.timed_out && Instant::now() >= self.deadline {
+            self.timed_out = true;
+        }
+        self.timed_out
+    }
+
+    fn ranges(&self, text: &str) -> Vec<Range> {
+        let mut out = Vec::new();
+        // 字节偏移 -> UTF-16 偏移：按匹配顺序增量换算
+        let mut byte_pos = 0usize;
+        let mut utf16_pos = 0usize;
+        for m in self.re.find_iter(text) {
+            if m.start() == m.end() {
+                continue;
+            }
+            utf16_pos += text[byte_pos..m.start()].encode_utf16().count();
+            let start = utf16_pos;
+            utf16_pos += m.as_str().encode_utf16().count();
+            byte_pos = m.end();
+            out.push(Range {
+                start,
+                end: utf16_pos,
+            });
+            if out.len() >= MAX_HIGHLIGHTS {
+                break;
+            }
+        }
+        out
+    }
+}
+
+/// 在 docx 段落、主文字段和附件名的原文上做正则/通配符匹配
+pub fn run(
+    conn: &Connection,
+    mode: PatternMode,
+    pattern: &str,
+    limit: usize,
+    allowed_archives: &Option<HashSet<String>>,
+    want_types: &Option<HashSet<String>>,
+    budget_ms: Option<u64>,
+) -> Result<PatternHits> {
+    let (re_src, literals) = match mode {
+        PatternMode::Regex => (pattern.to_string(), regex_literals(pattern)),
+        PatternMode::Wildcard => wildcard_to_regex(pattern.trim()),
+    };
+    let re = RegexBuilder::new(&re_src)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| anyhow!("正则表达式无效: {e}"))?;
+    let budget = budget_ms
+        .unwrap_or(DEFAULT_BUDGET_MS)
+        .clamp(100, MAX_BUDGET_MS);
+    let mut scan = Scan {
+        re,
+        deadline: Instant::now() + Duration::from_millis(budget),
+        timed_out: false,
+    };
+    let prefilter = prefilter_query(&literals);
+
+    let mut hits = PatternHits {
+        docx: vec![],
+        fields: vec![],
+        attachments: vec![],
+        timed_out: false,
+    };
+    let (archive_clause, archive_params) =
+        match search::archive_in_clause("m.archive_id", allowed_archives)? {
+            ArchiveFilter::All => (String::new(), vec![]),
+            ArchiveFilter::Empty => return Ok(hits),
+            ArchiveFilter::In(sql, params) => (sql, params),
+        };
+    let want_docx = want_types
+        .as_ref()
+        .map(|w| w.contains("docx_main"))
+        .unwrap_or(true);
+
+    if want_docx {
+        hits.docx = scan_docx_blocks(
+            conn,
+            &mut scan,
+            prefilter.as_deref(),
+            &archive_clause,
+            &archive_params,
+            limit,
+        )?;
+        let docx_archives: HashSet<String> = hits
+            .docx
+            .iter()
+            .map(|r| search::result_archive_id(r).to_string())
+            .collect();
+        hits.fields = scan_main_doc_fields(
+            conn,
+            &mut scan,
+            prefilter.as_deref(),
+            &archive_clause,
+            &archive_params,
+            &docx_archives,
+            limit,
+        )?;
+    }
+    hits.attachments = scan_attachment_names(
+        conn,
+        &mut scan,
+        prefilter.as_deref(),
+        &archive_clause,
+        &archive_params,
+        want_types,
+        limit,
+    )?;
+    hits.timed_out = scan.timed_out;
+    Ok(hits)
+}
+
+fn scan_docx_blocks(
+    conn: &Connection,
+    scan: &mut Scan,
+    prefilter: Option<&str>,
+    archive_clause: &str,
+    archive_params: &[rusqlite::types::Value],
+    limit: usize,
+) -> Result<Vec<SearchResult>> {
+    let mut out = Vec::new();
+    if scan.expired() {
+        return Ok(out);
+    }
+    let prefilter_clause = if prefilter.is_some() {
+        " AND (b.archive_id, b.block_id) IN (
+             SELECT archive_id, block_id FROM docx_blocks_fts WHERE docx_blocks_fts MATCH ?
+           )"
+    } else {
+        ""
+    };
+    let sql = format!(
+        "SELECT b.archive_id, b.block_id, b.text
+         FROM docx_blocks b
+         JOIN main_doc m ON m.archive_id = b.archive_id
+         WHERE 1=1 {prefilter_clause} {archive_clause}
+         ORDER BY COALESCE(m.issued_at_ts, 0) DESC, b.archive_id ASC, b.block_id ASC"
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let mut rows = stmt.query(rusqlite::params_from_iter(bind(prefilter, archive_params)))?;
+    while let Some(r) = rows.next()? {
+        if scan.expired() {
+            break;
+        }
+        let block_text: String = r.get(2)?;
+        let highlights = scan.ranges(&block_text);
+        if highlights.is_empty() {
+            continue;
+        }
+        out.push(SearchResult::DocxBlock {
+            archive_id: r.get(0)?,
+            block_id: r.get(1)?,
+            block_text,
+            highlights,
+            truncated: false,
+            section_path: vec![],
+        });
+        if out.len() >= limit {
+            break;
+        }
+    }
+    Ok(out)
+}
+
+fn scan_main_doc_fields(
+    conn: &Connection,
+    scan: &mut Scan,
+    prefilter: Option<&str>,
+    archive_clause: &str,
+    archive_params: &[rusqlite::types::Value],
+    docx_archives: &HashSet<String>,
+    limit: usize,
+) -> Result<Vec<SearchResult>> {
+    let mut out = Vec::new();
+    if scan.expired() {
+        return Ok(out);
+    }
+    let prefilter_clause = if prefilter.is_some() {
+        " AND f.main_doc_fts MATCH ?"
+    } else {
+        ""
+    };
+    let sql = format!(
+        "SELECT f.archive_id, f.field_name, f.source_text
+         FROM main_doc_fts f
+         JOIN main_doc m ON m.archive_id = f.archive_id
+         WHERE 1=1 {prefilter_clause} {archive_clause}
+         ORDER BY COALESCE(m.issued_at_ts, 0) DESC, f.archive_id ASC"
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let mut rows = stmt.query(rusqlite::params_from_iter(bind(prefilter, archive_params)))?;
+    while let Some(r) = rows.next()? {
+        if scan.expired() {
+            break;
+        }
+        let archive_id: String = r.get(0)?;
+        let field_name: String = r.get(1)?;
+        // content 由 docx 段落拼成：段落已命中时不再重复返回整段正文
+        if field_name == "content" && docx_archives.contains(&archive_id) {
+            continue;
+        }
+        let source_text: String = r.get(2)?;
+        let highlights = scan.ranges(&source_text);
+        if highlights.is_empty() {
+            continue;
+        }
+        out.push(SearchResult::MainDocField {
+            archive_id,
+            field_name,
+            source_text,
+            highlights,
+            best_block_id: None,
+            best_block_highlights: None,
+            truncated: false,
+        });
+        if out.len() >= limit {
+            break;
+        }
+    }
+    Ok(out)
+}
+
+fn scan_attachment_names(
+    conn: &Connection,
+    scan: &mut Scan,
+    prefilter: Option<&str>,
+    archive_clause: &str,
+    archive_params: &[rusqlite::types::Value],
+    want_types: &Option<HashSet<String>>,
+    limit: usize,
+) -> Result<Vec<SearchResult>> {
+    let mut out = Vec::new();
+    if scan.expired() {
+        return Ok(out);
+    }
+    let mut type_clause = String::new();
+    let mut type_params: Vec<rusqlite::types::Value> = Vec::new();
+    if let Some(want) = want_types {
+        let attachment_types: Vec<String> =
+            want.iter().filter(|t| *t != "docx_main").cloned().collect();
+        if attachment_types.is_empty() {
+            return Ok(out);
+        }
+        type_clause = format!(
+            " AND a.file_type IN ({})",
+            attachment_types
+                .iter()
+                .map(|_| "?")
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        type_params = attachment_types
+            .into_iter()
+            .map(rusqlite::types::Value::from)
+            .collect();
+    }
+    let prefilter_clause = if prefilter.is_some() {
+        " AND a.file_id IN (SELECT file_id FROM attachments_fts WHERE attachments_fts MATCH ?)"
+    } else {
+        ""
+    };
+    let sql = format!(
+        "SELECT a.archive_id, a.file_id, a.display_name
+         FROM attachments a
+         JOIN main_doc m ON m.archive_id = a.archive_id
+         WHERE 1=1 {prefilter_clause} {archive_clause} {type_clause}
+         ORDER BY COALESCE(m.issued_at_ts, 0) DESC, a.archive_id ASC, a.file_id ASC"
+    );
+    let mut params = bind(prefilter, archive_params);
+    params.extend(type_params);
+    let mut stmt = conn.prepare(&sql)?;
+    let mut rows = stmt.query(rusqlite::params_from_iter(params))?;
+    while let Some(r) = rows.next()? {
+        if scan.expired() {
+            break;
+        }
+        let display_name: String = r.get(2)?;
+        let highlights = scan.ranges(&display_name);
+        if highlights.is_empty() {
+            continue;
+        }
+        out.push(SearchResult::AttachmentName {
+            archive_id: r.get(0)?,
+            file_id: r.get(1)?,
+            display_name,
+            highlights,
+        });
+        if out.len() >= limit {
+            break;
+        }
+    }
+    Ok(out)
+}
+
+fn bind(
+    prefilter: Option<&str>,
+    archive_params: &[rusqlite::types::Value],
+) -> Vec<rusqlite::types::Value> {
+    let mut out = Vec::new();
+    if let Some(q) = prefilter {
+        out.push(rusqlite::types::Value::from(q.to_string()));
+    }
+    out.extend(archive_params.iter().cloned());
+    out
+}
+
+/// 用必须出现的字面量生成 FTS 预过滤条件。
+/// 索引的 search_text 含全文字符二元组，因此字面量中的字母数字二元组一定能命中；
+/// 取最长字面量里的若干二元组做 AND，保证不漏结果。
+fn prefilter_query(literals: &[String]) -> Option<String> {
+    let longest = literals.iter().max_by_key(|l| l.chars().count())?;
+    let chars: Vec<char> = longest.chars().collect();
+    let mut grams: Vec<String> = Vec::new();
+    for w in chars.windows(2) {
+        if w.iter().all(|c| c.is_alphanumeric()) {
+            let g = w.iter().collect::<String>().to_lowercase();
+            if !grams.contains(&g) {
+                grams.push(g);
+            }
+        }
+    }
+    if grams.is_empty() {
+        return None;
+    }
+    // 均匀挑选，避免只用开头几个
+    let step = grams.len().div_ceil(MAX_PREFILTER_GRAMS).max(1);
+    let picked = grams
+        .iter()
+        .step_by(step)
+        .map(|g| format!("\"{}\"", g.replace('"', "\"\"")))
+        .collect::<Vec<_>>();
+    Some(picked.join(" AND "))
+}
+
+/// 通配符转正则：* 匹配任意非空白串，? 匹配单个字符，其余按字面量
+fn wildcard_to_regex(pattern: &str) -> (String, Vec<String>) {
+    let mut re = String::new();
+    let mut literals = Vec::new();
+    let mut cur = String::new();
+    for c in pattern.chars() {
+        match c {
+            '*' | '?' => {
+                if !cur.is_empty() {
+                    re.push_str(&regex::escape(&cur));
+                    literals.push(std::mem::take(&mut cur));
+                }
+                re.push_str(if c == '*' { r"\S*" } else { "." });
+            }
+            _ => cur.push(c),
+        }
+    }
+    if !cur.is_empty() {
+        re.push_str(&regex::escape(&cur));
+        literals.push(cur);
+    }
+    (re, literals)
+}
+
+/// 粗略提取正则中一定会出现的字面量片段（只看顶层、无分支的部分）。
+/// 拿不准时返回空，退化为全表扫描。
+fn regex_literals(pattern: &str) -> Vec<String> {
+    let body = pattern.strip_prefix("(?i)").unwrap_or(pattern);
+    if body.contains('|') || body.contains("(?") {
+        return vec![];
+    }
+    let chars: Vec<char> = body.chars().collect();
+    let mut runs: Vec<String> = Vec::new();
+    let mut cur: Vec<char> = Vec::new();
+    let mut depth = 0i32;
+    let flush = |cur: &mut Vec<char>, runs: &mut Vec<String>| {
+        if !cur.is_empty() {
+            runs.push(cur.drain(..).collect());
+        }
+    };
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' => {
+                let Some(&n) = chars.get(i + 1) else {
+                    break;
+                };
+                if n.is_ascii_punctuation() {
+                    if depth == 0 {
+                        cur.push(n);
+                    }
+                    i += 2;
+                    continue;
+                }
+                // \d \w \b \p{..} \x.. 等：不是字面量
+                flush(&mut cur, &mut runs);
+                i += 2;
+                if chars.get(i) == Some(&'{') {
+                    while i < chars.len() && chars[i] != '}' {
+                        i += 1;
+                    }
+                    i += 1;
+                } else if n == 'x' {
+                    i += 2;
+                } else if n == 'p' || n == 'P' {
+                    i += 1;
+                }
+                continue;
+            }
+            '?' | '*' | '{' => {
+                // 前一个字符可出现零次
+                cur.pop();
+                flush(&mut cur, &mut runs);
+                if c == '{' {
+                    while i < chars.len() && chars[i] != '}' {
+                        i += 1;
+                    }
+                }
+            }
+            '+' | '.' | '^' | '$' => flush(&mut cur, &mut runs),
+            '[' => {
+                flush(&mut cur, &mut runs);
+                i += 1;
+                if chars.get(i) == Some(&'^') {
+                    i += 1;
+                }
+                if chars.get(i) == Some(&']') {
+                    i += 1;
+                }
+                while i < chars.len() && chars[i] != ']' {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            }
+            '(' => {
+                flush(&mut cur, &mut runs);
+                depth += 1;
+            }
+            ')' => {
+                flush(&mut cur, &mut runs);
+                depth -= 1;
+            }
+            _ => {
+                if depth == 0 {
+                    cur.push(c);
+                }
+            }
+        }
+        i += 1;
+    }
+    flush(&mut cur, &mut runs);
+    runs
+}
@@ -0,0 +1,418 @@
+use crate::db;
+use crate::library_root::LibraryRootState;
+use crate::pattern_search;
+use crate::search::{self, SearchFilters, SearchRequest, SearchResult};
+use anyhow::{anyhow, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{Emitter, State};
+use uuid::Uuid;
+
+/// 每个档案在通知里保留的命中样例数量
+const SAMPLE_PER_ARCHIVE: usize = 3;
+/// 复跑单个保存搜索时的最大结果数（只针对新导入档案，足够覆盖）
+const RERUN_LIMIT: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub search_id: String,
+    pub name: String,
+    pub query: String,
+    pub filters: Option<SearchFilters>,
+    pub sort: Option<String>,
+    pub fuzzy: bool,
+    /// regex/wildcard；为空即全文检索
+    pub mode: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveSearchReq {
+    /// 为空则新建
+    pub search_id: Option<String>,
+    pub name: String,
+    pub query: String,
+    pub filters: Option<SearchFilters>,
+    pub sort: Option<String>,
+    pub fuzzy: Option<bool>,
+    pub mode: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationItem {
+    pub notification_id: String,
+    pub search_id: String,
+    pub search_name: String,
+    pub query: String,
+    pub archive_id: String,
+    pub archive_title: Option<String>,
+    pub hit_count: i64,
+    pub samples: Vec<SearchResult>,
+    pub created_at: i64,
+    pub read_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListNotificationsReq {
+    pub unread_only: Option<bool>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedSearchAlert {
+    pub search_id: String,
+    pub search_name: String,
+    pub archive_ids: Vec<String>,
+    pub hit_count: usize,
+}
+
+fn now_ts() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+fn row_to_saved_search(r: &rusqlite::Row<'_>) -> rusqlite::Result<SavedSearch> {
+    let filters_json: String = r.get(3)?;
+    Ok(SavedSearch {
+        search_id: r.get(0)?,
+        name: r.get(1)?,
+        query: r.get(2)?,
+        filters: serde_json::from_str(&filters_json).ok().flatten(),
+        sort: r.get(4)?,
+        fuzzy: r.get::<_, i64>(5)? != 0,
+        mode: r.get(6)?,
+        created_at: r.get(7)?,
+        updated_at: r.get(8)?,
+    })
+}
+
+const SAVED_SEARCH_COLUMNS: &str =
+    "search_id,name,query,filters_json,sort,fuzzy,mode,created_at,updated_at";
+
+fn load_saved_searches(conn: &Connection) -> Result<Vec<SavedSearch>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {SAVED_SEARCH_COLUMNS} FROM saved_searches ORDER BY updated_at DESC"
+    ))?;
+    let rows = stmt.query_map([], row_to_saved_search)?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+#[tauri::command]
+pub fn list_saved_searches(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+) -> Result<Vec<SavedSearch>, String> {
+    let (_root, conn) = db::open_conn(&app, &state).map_err(db::err_to_string)?;
+    load_saved_searches(&conn).map_err(db::err_to_string)
+}
+
+#[tauri::command]
+pub fn save_search(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    req: SaveSearchReq,
+) -> Result<SavedSearch, String> {
+    save_search_impl(&app, &state, req).map_err(db::err_to_string)
+}
+
+fn save_search_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    req: SaveSearchReq,
+) -> Result<SavedSearch> {
+    let query = req.query.trim().to_string();
+    if query.is_empty() {
+        return Err(anyhow!("搜索内容不能为空"));
+    }
+    let name = if req.name.trim().is_empty() {
+        query.clone()
+    } else {
+        req.name.trim().to_string()
+    };
+    // 保存的搜索用于新导入档案的复跑，archive_ids 限定没有意义
+    let filters = req.filters.map(|mut f| {
+        f.archive_ids = None;
+        f
+    });
+    let filters_json = serde_json::to_string(&filters)?;
+    let fuzzy = req.fuzzy.unwrap_or(false);
+    // 复跑时才解析会让错误静默吞掉，保存时先校验；fts 与空值统一存 NULL
+    let mode = match pattern_search::parse_mode(req.mode.as_deref())? {
+        Some(_) => req.mode.map(|m| m.trim().to_string()),
+        None => None,
+    };
+    let (_root, conn) = db::open_conn(app, state)?;
+    let now = now_ts();
+
+    let search_id = match req.search_id.filter(|s| !s.trim().is_empty()) {
+        Some(id) => {
+            let changed = conn.execute(
+                "UPDATE saved_searches SET name=?, query=?, filters_json=?, sort=?, fuzzy=?, mode=?, updated_at=? WHERE search_id=?",
+                params![name, query, filters_json, req.sort, fuzzy as i64, mode, now, id],
+            )?;
+            if changed == 0 {
+                return Err(anyhow!("找不到保存的搜索: {id}"));
+            }
+            id
+        }
+        None => {
+            let id = Uuid::new_v4().to_string();
+            conn.execute(
+                "INSERT INTO saved_searches(search_id,name,query,filters_json,sort,fuzzy,mode,created_at,updated_at)
+                 VALUES(?,?,?,?,?,?,?,?,?)",
+                params![id, name, query, filters_json, req.sort, fuzzy as i64, mode, now, now],
+            )?;
+            id
+        }
+    };
+
+    conn.query_row(
+        &format!("SELECT {SAVED_SEARCH_COLUMNS} FROM saved_searches WHERE search_id=?"),
+        [search_id.as_str()],
+        row_to_saved_search,
+    )
+    .map_err(|e| anyhow!(e))
+}
+
+#[tauri::command]
+pub fn delete_saved_search(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    search_id: String,
+) -> Result<(), String> {
+    delete_saved_search_impl(&app, &state, &search_id).map_err(db::err_to_string)
+}
+
+fn delete_saved_search_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    search_id: &str,
+) -> Result<()> {
+    let (_root, mut conn) = db::open_conn(app, state)?;
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM notifications WHERE search_id=?", [search_id])?;
+    tx.execute("DELETE FROM saved_searches WHERE search_id=?", [search_id])?;
+    tx.commit()?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_notifications(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    req: Option<ListNotificationsReq>,
+) -> Result<Vec<NotificationItem>, String> {
+    list_notifications_impl(&app, &state, req).map_err(db::err_to_string)
+}
+
+fn list_notifications_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    req: Option<ListNotificationsReq>,
+) -> Result<Vec<NotificationItem>> {
+    let (_root, conn) = db::open_conn(app, state)?;
+    let req = req.unwrap_or(ListNotificationsReq {
+        unread_only: None,
+        limit: None,
+        offset: None,
+    });
+    let limit = req.limit.unwrap_or(100).min(500) as i64;
+    let offset = req.offset.unwrap_or(0) as i64;
+    let unread_clause = if req.unread_only.unwrap_or(false) {
+        "WHERE n.read_at IS NULL"
+    } else {
+        ""
+    };
+    let sql = format!(
+        "SELECT n.notification_id, n.search_id, s.name, s.query, n.archive_id, m.title,
+                n.hit_count, n.sample_json, n.created_at, n.read_at
+         FROM notifications n
+         JOIN saved_searches s ON s.search_id=n.search_id
+         LEFT JOIN main_doc m ON m.archive_id=n.archive_id
+         {unread_clause}
+         ORDER BY n.created_at DESC, n.notification_id ASC
+         LIMIT ? OFFSET ?"
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(params![limit, offset], |r| {
+        let sample_json: String = r.get(7)?;
+        Ok(NotificationItem {
+            notification_id: r.get(0)?,
+            search_id: r.get(1)?,
+            search_name: r.get(2)?,
+            query: r.get(3)?,
+            archive_id: r.get(4)?,
+            archive_title: r.get(5)?,
+            hit_count: r.get(6)?,
+            samples: serde_json::from_str(&sample_json).unwrap_or_default(),
+            created_at: r.get(8)?,
+            read_at: r.get(9)?,
+        })
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+#[tauri::command]
+pub fn mark_notifications_read(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    notification_ids: Option<Vec<String>>,
+) -> Result<usize, String> {
+    mark_notifications_read_impl(&app, &state, notification_ids).map_err(db::err_to_string)
+}
+
+fn mark_notifications_read_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    notification_ids: Option<Vec<String>>,
+) -> Result<usize> {
+    let (_root, conn) = db::open_conn(app, state)?;
+    let now = now_ts();
+    // 不传 id 时全部标记为已读
+    let Some(ids) = notification_ids else {
+        return Ok(conn.execute(
+            "UPDATE notifications SET read_at=? WHERE read_at IS NULL",
+            [now],
+        )?);
+    };
+    let mut stmt = conn.prepare(
+        "UPDATE notifications SET read_at=? WHERE notification_id=? AND read_at IS NULL",
+    )?;
+    let mut changed = 0usize;
+    for id in ids {
+        changed += stmt.execute(params![now, id])?;
+    }
+    Ok(changed)
+}
+
+#[tauri::command]
+pub fn clear_notifications(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+) -> Result<(), String> {
+    let (_root, conn) = db::open_conn(&app, &state).map_err(db::err_to_string)?;
+    conn.execute("DELETE FROM notifications", [])
+        .map_err(|e| db::err_to_string(anyhow!(e)))?;
+    Ok(())
+}
+
+/// 导入完成或档案 OCR 完成后，把所有保存的搜索限定在这些档案内复跑，命中则记录通知并发出事件；
+/// 同一搜索在同一档案只通知一次
+pub fn run_for_new_archives(
+    app: &tauri::AppHandle,
+    conn: &Connection,
+    archive_ids: &[String],
+) -> Result<usize> {
+    if archive_ids.is_empty() {
+        return Ok(0);
+    }
+    let saved = load_saved_searches(conn)?;
+    let mut total_notifications = 0usize;
+    for s in saved {
+        let mut filters = s.filters.clone().unwrap_or(SearchFilters {
+            date_from: None,
+            date_to: None,
+            file_types: None,
+            archive_ids: None,
+            captured_from: None,
+            captured_to: None,
+            min_duration_ms: None,
+            max_duration_ms: None,
+        });
+        filters.archive_ids = Some(archive_ids.to_vec());
+        let req = SearchRequest {
+            query: s.query.clone(),
+            filters: Some(filters),
+            limit: Some(RERUN_LIMIT),
+            offset: Some(0),
+            fuzzy: Some(s.fuzzy),
+            sort: s.sort.clone(),
+            snippet: None,
+            mode: s.mode.clone(),
+            time_budget_ms: None,
+        };
+        let resp = match search::run_search(conn, req) {
+            Ok(r) => r,
+            Err(e) => {
+                // 单个保存搜索失败不影响其他
+                eprintln!("保存的搜索复跑失败: {}: {e:#}", s.name);
+                continue;
+            }
+        };
+        if resp.items.is_empty() {
+            continue;
+        }
+
+        // 按档案聚合
+        let mut by_archive: Vec<(String, Vec<SearchResult>)> = Vec::new();
+        let mut pos: HashMap<String, usize> = HashMap::new();
+        for item in resp.items {
+            let archive_id = search::result_archive_id(&item).to_string();
+            let idx = *pos.entry(archive_id.clone()).or_insert_with(|| {
+                by_archive.push((archive_id.clone(), Vec::new()));
+                by_archive.len() - 1
+            });
+            by_archive[idx].1.push(item);
+        }
+
+        let now = now_ts();
+        let mut hit_count = 0usize;
+        let mut hit_archives = Vec::new();
+        for (archive_id, items) in by_archive {
+            let exists: Option<String> = conn
+                .query_row(
+                    "SELECT notification_id FROM notifications WHERE search_id=? AND archive_id=?",
+                    params![s.search_id, archive_id],
+                    |r| r.get(0),
+                )
+                .optional()?;
+            if exists.is_some() {
+                continue;
+            }
+            let samples = items
+                .iter()
+                .take(SAMPLE_PER_ARCHIVE)
+                .cloned()
+                .collect::<Vec<_>>();
+            conn.execute(
+                "INSERT INTO notifications(notification_id,search_id,archive_id,hit_count,sample_json,created_at,read_at)
+                 VALUES(?,?,?,?,?,?,NULL)",
+                params![
+                    Uuid::new_v4().to_string(),
+                    s.search_id,
+                    archive_id,
+                    items.len() as i64,
+                    serde_json::to_string(&samples)?,
+                    now
+                ],
+            )?;
+            hit_count += items.len();
+            hit_archives.push(archive_id);
+        }
+        if hit_archives.is_empty() {
+            continue;
+        }
+        total_notifications += hit_archives.len();
+        // 失败不阻断主流程
+        let _ = app.emit(
+            "saved_search_alert",
+            &SavedSearchAlert {
+                search_id: s.search_id.clone(),
+                search_name: s.name.clone(),
+                archive_ids: hit_archives,
+                hit_count,
+            },
+        );
+    }
+    Ok(total_notifications)
+}
@@ -1,21 +1,83 @@
+use crate::attachment_text;
 use crate::db;
+use crate::fuzzy;
 use crate::library_root::{resolve_library_root, LibraryRootState};
-use anyhow::Result;
+use crate::media_meta::MediaFilter;
+use crate::ocr::{self, OcrRegion};
+use crate::outline;
+use crate::pattern_search;
+use crate::progress;
+use crate::search_history;
+use crate::similar;
+use crate::snippet::{self, SnippetOptions};
+use crate::synonyms;
+use crate::user_dict;
+use anyhow::{anyhow, Result};
 use jieba_rs::Jieba;
 use once_cell::sync::Lazy;
 use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 use tauri::State;
 
-static JIEBA: Lazy<Jieba> = Lazy::new(Jieba::new);
+/// 分词器：默认词典 + 当前库的用户词典（切换库时重新加载）
+struct Tokenizer {
+    root: Option<PathBuf>,
+    jieba: Jieba,
+}
+
+static JIEBA: Lazy<RwLock<Tokenizer>> = Lazy::new(|| {
+    RwLock::new(Tokenizer {
+        root: None,
+        jieba: Jieba::new(),
+    })
+});
+
+pub fn ensure_tokenizer_loaded(root: &Path) -> Result<()> {
+    if JIEBA.read().unwrap().root.as_deref() == Some(root) {
+        return Ok(());
+    }
+    reload_tokenizer(root)
+}
+
+pub fn reload_tokenizer(root: &Path) -> Result<()> {
+    // jieba 不支持删词：每次从默认词典重新构建
+    let mut jieba = Jieba::new();
+    for w in user_dict::read_user_dict(root)? {
+        jieba.add_word(&w.word, Some(w.freq), w.pos.as_deref());
+    }
+    let mut t = JIEBA.write().unwrap();
+    t.root = Some(root.to_path_buf());
+    t.jieba = jieba;
+    Ok(())
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchFilters {
     pub date_from: Option<i64>,
     pub date_to: Option<i64>,
     pub file_types: Option<Vec<String>>,
+    /// 仅在这些档案内搜索（保存的搜索对新导入档案复跑时使用）
+    pub archive_ids: Option<Vec<String>>,
+    /// 图片拍摄时间 / 视频时长：设置后只返回符合条件的附件结果
+    pub captured_from: Option<i64>,
+    pub captured_to: Option<i64>,
+    pub min_duration_ms: Option<i64>,
+    pub max_duration_ms: Option<i64>,
+}
+
+impl SearchFilters {
+    pub(crate) fn media(&self) -> MediaFilter {
+        MediaFilter {
+            captured_from: self.captured_from,
+            captured_to: self.captured_to,
+            min_duration_ms: self.min_duration_ms,
+            max_duration_ms: self.max_duration_ms,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +86,16 @@ pub struct SearchRequest {
     pub filters: Option<SearchFilters>,
     pub limit: Option<usize>,
     pub offset: Option<usize>,
+    /// 模糊模式：额外匹配拼音（全拼/首字母）、同音字与单字母拼写错误
+    pub fuzzy: Option<bool>,
+    /// 排序：issued_desc（默认）| issued_asc | relevance
+    pub sort: Option<String>,
+    /// 长文本只返回命中附近的片段（默认开启）；完整文本用 get_search_result_text 获取
+    pub snippet: Option<SnippetOptions>,
+    /// 匹配方式：fts（默认）| regex | wildcard（* 任意非空白串，? 单字符）
+    pub mode: Option<String>,
+    /// regex/wildcard 扫描的时间预算（毫秒），超时返回已找到的结果
+    pub time_budget_ms: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +110,9 @@ pub struct SearchPagedResponse {
     pub has_more: bool,
     pub offset: usize,
     pub limit: usize,
+    /// regex/wildcard 扫描超出时间预算，结果可能不完整
+    #[serde(default)]
+    pub timed_out: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,6 +124,12 @@ pub enum SearchResult {
         block_id: String,
         block_text: String,
         highlights: Vec<Range>,
+        /// block_text 是否为片段
+        #[serde(default)]
+        truncated: bool,
+        /// 所在章节的标题路径，由外到内
+        #[serde(default)]
+        section_path: Vec<String>,
     },
     #[serde(rename = "main_doc_field")]
     MainDocField {
@@ -58,6 +139,9 @@ pub enum SearchResult {
         highlights: Vec<Range>,
         best_block_id: Option<String>,
         best_block_highlights: Option<Vec<Range>>,
+        /// source_text 是否为片段
+        #[serde(default)]
+        truncated: bool,
     },
     #[serde(rename = "attachment_name")]
     AttachmentName {
@@ -75,6 +159,40 @@ pub enum SearchResult {
         locator: Value,
         content: String,
         highlights: Vec<Range>,
+        /// content 是否为片段
+        #[serde(default)]
+        truncated: bool,
+    },
+    /// 图片/扫描 PDF 的 OCR 行；region 为行框，highlight_regions 为命中在图上的近似位置
+    #[serde(rename = "ocr_text")]
+    OcrText {
+        archive_id: String,
+        file_id: String,
+        display_name: String,
+        page: i64,
+        block_idx: i64,
+        text: String,
+        highlights: Vec<Range>,
+        region: OcrRegion,
+        highlight_regions: Vec<OcrRegion>,
+        image_width: i64,
+        image_height: i64,
+    },
+    /// doc/wps/ofd/文本附件正文段落；page 仅 OFD 有，行号仅文本附件有
+    #[serde(rename = "attachment_block")]
+    AttachmentBlock {
+        archive_id: String,
+        file_id: String,
+        display_name: String,
+        block_id: String,
+        page: Option<i64>,
+        line_start: Option<i64>,
+        line_end: Option<i64>,
+        text: String,
+        highlights: Vec<Range>,
+        /// text 是否为片段
+        #[serde(default)]
+        truncated: bool,
     },
 }
 
@@ -92,12 +210,19 @@ pub fn build_search_text(text: &str) -> String {
     parts.extend(jieba_tokens(t));
     parts.extend(char_ngrams(t, 2));
     parts.extend(char_ngrams(t, 3));
+    // 索引时同义词扩展：把同组词条的分词结果一并写入 search_text（source_text 不变）
+    for syn in synonyms::expand_for_index(t) {
+        parts.extend(jieba_tokens(&syn));
+    }
     parts.retain(|s| !s.trim().is_empty());
     parts.join(" ")
 }
 
-fn jieba_tokens(text: &str) -> Vec<String> {
+pub(crate) fn jieba_tokens(text: &str) -> Vec<String> {
     JIEBA
+        .read()
+        .unwrap()
+        .jieba
         .cut(text, false)
         .into_iter()
         .map(|s| s.trim().to_string())
@@ -132,6 +257,12 @@ fn build_match_query(query: &str) -> String {
     tokens.extend(jieba_tokens(q));
     tokens.extend(char_ngrams(q, 2));
     tokens.extend(char_ngrams(q, 3));
+    // 查询时同义词扩展（缩写 <-> 全称）
+    for syn in synonyms::expand_query(q) {
+        tokens.extend(jieba_tokens(&syn));
+        tokens.extend(char_ngrams(&syn, 2));
+        tokens.extend(char_ngrams(&syn, 3));
+    }
     tokens.retain(|s| !s.trim().is_empty());
     tokens.sort();
     tokens.dedup();
@@ -173,32 +304,65 @@ fn search_paged_impl(
     let root = resolve_library_root(app, state)?;
     db::init_db(app, &root)?;
     let conn = Connection::open(root.join("db.sqlite"))?;
+    let is_first_page = req.offset.unwrap_or(0) == 0;
+    let query = req.query.clone();
+    let filters = req.filters.clone();
+    let resp = run_search(&conn, req)?;
+    // 只记录第一页，翻页不重复写历史；写失败不影响搜索结果
+    if is_first_page {
+        if let Err(e) = search_history::record(
+            &conn,
+            &query,
+            filters.as_ref(),
+            resp.items.len(),
+            resp.has_more,
+        ) {
+            eprintln!("记录搜索历史失败: {e:#}");
+        }
+    }
+    Ok(resp)
+}
 
+pub(crate) fn run_search(conn: &Connection, req: SearchRequest) -> Result<SearchPagedResponse> {
     let limit = req.limit.unwrap_or(50).min(200);
     let offset = req.offset.unwrap_or(0).min(20_000);
+    let pattern_mode = pattern_search::parse_mode(req.mode.as_deref())?;
     let match_query = build_match_query(&req.query);
-    if match_query.is_empty() {
+    if match_query.is_empty() && (pattern_mode.is_none() || req.query.trim().is_empty()) {
         return Ok(SearchPagedResponse {
             items: vec![],
             has_more: false,
             offset,
             limit,
+            timed_out: false,
         });
     }
 
-    let filters = req.filters.unwrap_or(SearchFilters {
+    let filters = req.filters.clone().unwrap_or(SearchFilters {
         date_from: None,
         date_to: None,
         file_types: None,
+        archive_ids: None,
+        captured_from: None,
+        captured_to: None,
+        min_duration_ms: None,
+        max_duration_ms: None,
     });
 
-    let allowed_archives = filter_archives_by_date(&conn, filters.date_from, filters.date_to)?;
-    let allowed_archives_set: Option<HashSet<String>> =
+    let allowed_archives = filter_archives_by_date(conn, filters.date_from, filters.date_to)?;
+    let mut allowed_archives_set: Option<HashSet<String>> =
         if filters.date_from.is_some() || filters.date_to.is_some() {
             Some(allowed_archives.into_iter().collect())
         } else {
             None
         };
+    if let Some(ids) = &filters.archive_ids {
+        let ids: HashSet<String> = ids.iter().cloned().collect();
+        allowed_archives_set = Some(match allowed_archives_set {
+            Some(set) => set.intersection(&ids).cloned().collect(),
+            None => ids,
+        });
+    }
 
     let want_types: Option<HashSet<String>> = filters
         .file_types
@@ -208,18 +372,50 @@ fn search_paged_impl(
     let need = offset.saturating_add(limit).saturating_add(1);
     let fetch = (need.saturating_mul(4)).min(5000).max(200);
 
-    let mut results_docx = query_docx_blocks(&conn, &match_query, fetch, &allowed_archives_set)?;
+    // regex/wildcard：直接在原文上匹配，能提取字面量时先用 FTS 缩小范围
+    if let Some(mode) = pattern_mode {
+        let hits = pattern_search::run(
+            conn,
+            mode,
+            &req.query,
+            fetch,
+            &allowed_archives_set,
+            &want_types,
+            req.time_budget_ms,
+        )?;
+        let mut out = Vec::new();
+        out.extend(hits.docx);
+        out.extend(hits.fields);
+        out.extend(hits.attachments);
+        return sort_and_page(conn, out, &req, offset, limit, hits.timed_out);
+    }
+
+    let mut results_docx = query_docx_blocks(conn, &match_query, fetch, &allowed_archives_set)?;
     let mut results_field =
-        query_main_doc_fields(&conn, &match_query, fetch, &allowed_archives_set)?;
+        query_main_doc_fields(conn, &match_query, fetch, &allowed_archives_set)?;
     let mut results_attach = query_attachment_names(
-        &conn,
+        conn,
         &match_query,
         fetch,
         &allowed_archives_set,
         &want_types,
     )?;
     let mut results_anno = query_annotations(
-        &conn,
+        conn,
+        &match_query,
+        fetch,
+        &allowed_archives_set,
+        &want_types,
+    )?;
+    let mut results_ocr = ocr::query_ocr(
+        conn,
+        &match_query,
+        fetch,
+        &allowed_archives_set,
+        &want_types,
+    )?;
+    let mut results_attach_text = attachment_text::query_attachment_blocks(
+        conn,
         &match_query,
         fetch,
         &allowed_archives_set,
@@ -257,6 +453,27 @@ fn search_paged_impl(
             *highlights = compute_highlights_utf16(content, &req.query);
         }
     }
+    for r in results_ocr.iter_mut() {
+        if let SearchResult::OcrText {
+            text,
+            highlights,
+            region,
+            highlight_regions,
+            ..
+        } = r
+        {
+            *highlights = compute_highlights_utf16(text, &req.query);
+            *highlight_regions = ocr::highlight_regions(text, region, highlights);
+        }
+    }
+    for r in results_attach_text.iter_mut() {
+        if let SearchResult::AttachmentBlock {
+            text, highlights, ..
+        } = r
+        {
+            *highlights = compute_highlights_utf16(text, &req.query);
+        }
+    }
 
     // main_doc_field：计算高亮，并对 content 计算 best_block_id
     let mut content_block_map: HashMap<String, Vec<String>> = HashMap::new();
@@ -299,6 +516,7 @@ fn search_paged_impl(
         tokens.extend(jieba_tokens(req.query.trim()));
         tokens.extend(char_ngrams(req.query.trim(), 2));
         tokens.extend(char_ngrams(req.query.trim(), 3));
+        tokens.extend(synonyms::expand_query(&req.query));
         tokens.retain(|s| !s.trim().is_empty());
         tokens.sort();
         tokens.dedup();
@@ -314,6 +532,7 @@ fn search_paged_impl(
             highlights,
             best_block_id,
             best_block_highlights,
+            ..
         } = &mut r
         {
             *highlights = compute_highlights_utf16(source_text, &req.query);
@@ -333,7 +552,7 @@ fn search_paged_impl(
 
                     // best_block_id：在 content_block_ids 中选择最相关段落
                     if let Some((best_id, best_text)) =
-                        pick_best_content_block(&conn, archive_id, content_ids, &query_tokens)?
+                        pick_best_content_block(conn, archive_id, content_ids, &query_tokens)?
                     {
                         *best_block_id = Some(best_id.clone());
                         *best_block_highlights =
@@ -348,6 +567,57 @@ fn search_paged_impl(
     }
     results_field = filtered_field_results;
 
+    // 模糊模式：拼音命中补充到字段/附件名结果中（已精确命中的不重复）
+    if req.fuzzy.unwrap_or(false) {
+        let fuzzy_results =
+            fuzzy::query_fuzzy(conn, &req.query, fetch, &allowed_archives_set, &want_types)?;
+        let mut seen_fields: HashSet<(String, String)> = HashSet::new();
+        for r in &results_field {
+            if let SearchResult::MainDocField {
+                archive_id,
+                field_name,
+                ..
+            } = r
+            {
+                seen_fields.insert((archive_id.clone(), field_name.clone()));
+            }
+        }
+        let mut seen_files: HashSet<String> = HashSet::new();
+        for r in &results_attach {
+            if let SearchResult::AttachmentName { file_id, .. } = r {
+                seen_files.insert(file_id.clone());
+            }
+        }
+        for r in fuzzy_results {
+            match &r {
+                SearchResult::MainDocField {
+                    archive_id,
+                    field_name,
+                    highlights,
+                    ..
+                } => {
+                    if highlights.is_empty()
+                        || !seen_fields.insert((archive_id.clone(), field_name.clone()))
+                    {
+                        continue;
+                    }
+                    results_field.push(r);
+                }
+                SearchResult::AttachmentName {
+                    file_id,
+                    highlights,
+                    ..
+                } => {
+                    if highlights.is_empty() || !seen_files.insert(file_id.clone()) {
+                        continue;
+                    }
+                    results_attach.push(r);
+                }
+                _ => {}
+            }
+        }
+    }
+
     // 类型过滤：docx_main / main_doc_field 属于 docx_main，附件按 file_type 过滤已在 SQL 内做；这里再做总过滤
     if let Some(want) = want_types.clone() {
         let want_docx = want.contains("docx_main");
@@ -360,12 +630,38 @@ fn search_paged_impl(
     out.extend(results_docx);
     out.extend(results_field);
     out.extend(results_anno);
+    out.extend(results_attach_text);
+    out.extend(results_ocr);
     out.extend(results_attach);
 
-    let archive_sort_keys = load_archive_sort_keys(&conn)?;
+    sort_and_page(conn, out, &req, offset, limit, false)
+}
+
+fn sort_and_page(
+    conn: &Connection,
+    mut out: Vec<SearchResult>,
+    req: &SearchRequest,
+    offset: usize,
+    limit: usize,
+    timed_out: bool,
+) -> Result<SearchPagedResponse> {
+    if let Some(media) = req.filters.as_ref().map(|f| f.media()) {
+        if media.is_active() {
+            retain_media_matches(conn, &mut out, &media)?;
+        }
+    }
+    let archive_sort_keys = load_archive_sort_keys(conn)?;
 
     // 先按下发时间倒序，再在同一档案内按结果类型和高亮强度稳定排序。
+    let sort = req.sort.as_deref().unwrap_or("issued_desc");
     out.sort_by(|a, b| {
+        if sort == "relevance" {
+            let sa = highlight_score(a);
+            let sb = highlight_score(b);
+            if sa != sb {
+                return sb.cmp(&sa);
+            }
+        }
         let sa = archive_sort_keys
             .get(result_archive_id(a))
             .cloned()
@@ -379,6 +675,9 @@ fn search_paged_impl(
                 issued_at_ts: i64::MIN,
             });
         if sa.issued_at_ts != sb.issued_at_ts {
+            if sort == "issued_asc" {
+                return sa.issued_at_ts.cmp(&sb.issued_at_ts);
+            }
             return sb.issued_at_ts.cmp(&sa.issued_at_ts);
         }
         let ka = kind_rank(a);
@@ -404,31 +703,257 @@ fn search_paged_impl(
     });
 
     let has_more = out.len() > offset.saturating_add(limit);
-    let items = out.into_iter().skip(offset).take(limit).collect::<Vec<_>>();
+    let mut items = out.into_iter().skip(offset).take(limit).collect::<Vec<_>>();
+
+    // 只对返回的这一页生成片段，highlights 同步改到片段坐标
+    let snippet_opts = req.snippet.clone().unwrap_or_default();
+    if snippet_opts.enabled() {
+        for item in items.iter_mut() {
+            apply_snippet(item, &snippet_opts);
+        }
+    }
+    outline::fill_section_paths(conn, &mut items)?;
 
     Ok(SearchPagedResponse {
         items,
         has_more,
         offset,
         limit,
+        timed_out,
     })
 }
 
+#[tauri::command]
+pub async fn rebuild_search_index(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+) -> Result<String, String> {
+    // 全库重建较慢：放到阻塞线程池执行
+    let root = resolve_library_root(&app, &state).map_err(db::err_to_string)?;
+    let app2 = app.clone();
+    tauri::async_runtime::spawn_blocking(move || rebuild_search_index_impl(&app2, &root))
+        .await
+        .map_err(|e| db::err_to_string(anyhow!(e).context("重建索引线程失败")))?
+        .map_err(db::err_to_string)
+}
+
+fn rebuild_search_index_impl(app: &tauri::AppHandle, root: &Path) -> Result<String> {
+    db::init_db(app, root)?;
+    let mut conn = Connection::open(root.join("db.sqlite"))?;
+    // (FTS表, 原文列)
+    let tables = [
+        ("docx_blocks_fts", "source_text"),
+        ("main_doc_fts", "source_text"),
+        ("attachments_fts", "display_name"),
+        ("annotations_fts", "source_text"),
+        ("ocr_fts", "source_text"),
+        ("attachment_blocks_fts", "source_text"),
+    ];
+    let total = tables.len();
+    for (idx, (table, source_col)) in tables.iter().enumerate() {
+        progress::emit(
+            app,
+            progress::ProgressEvent::new(
+                "rebuild_index",
+                idx,
+                total,
+                "重建索引",
+                &format!("正在重建 {table}"),
+            ),
+        );
+        let tx = conn.transaction()?;
+        rebuild_fts_search_text(&tx, table, source_col)?;
+        tx.commit()?;
+    }
+    // 词典变化同样影响相似档案词表
+    let tx = conn.transaction()?;
+    similar::rebuild_archive_terms(&tx)?;
+    tx.commit()?;
+    db::set_meta_value(&conn, db::META_SEARCH_INDEX_STALE, "0")?;
+    progress::emit(
+        app,
+        progress::ProgressEvent::complete("rebuild_index", "搜索索引重建完成"),
+    );
+    Ok("搜索索引重建完成".to_string())
+}
+
+fn rebuild_fts_search_text(conn: &Connection, table: &str, source_col: &str) -> Result<()> {
+    let mut rows = Vec::new();
+    {
+        let mut stmt = conn.prepare(&format!("SELECT rowid, {source_col} FROM {table}"))?;
+        let it = stmt.query_map([], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?)))?;
+        for row in it {
+            rows.push(row?);
+        }
+    }
+    let mut upd = conn.prepare(&format!("UPDATE {table} SET search_text=? WHERE rowid=?"))?;
+    for (rowid, text) in rows {
+        upd.execute(params![build_search_text(&text), rowid])?;
+    }
+    Ok(())
+}
+
+fn apply_snippet(item: &mut SearchResult, opts: &SnippetOptions) {
+    let (text, highlights, truncated) = match item {
+        SearchResult::DocxBlock {
+            block_text,
+            highlights,
+            truncated,
+            ..
+        } => (block_text, highlights, truncated),
+        SearchResult::MainDocField {
+            source_text,
+            highlights,
+            truncated,
+            ..
+        } => (source_text, highlights, truncated),
+        SearchResult::Annotation {
+            content,
+            highlights,
+            truncated,
+            ..
+        } => (content, highlights, truncated),
+        SearchResult::AttachmentBlock {
+            text,
+            highlights,
+            truncated,
+            ..
+        } => (text, highlights, truncated),
+        // 附件名、OCR 行本身很短，不截断
+        SearchResult::AttachmentName { .. } | SearchResult::OcrText { .. } => return,
+    };
+    if let Some((snip, rebased)) = snippet::make_snippet(text, highlights, opts) {
+        *text = snip;
+        *highlights = rebased;
+        *truncated = true;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultTextReq {
+    /// docx_block | main_doc_field | annotation | attachment_block
+    pub kind: String,
+    pub archive_id: String,
+    /// block_id / field_name / annotation_id
+    pub ref_id: String,
+    /// attachment_block 需要：block_id 只在附件内唯一
+    #[serde(default)]
+    pub file_id: Option<String>,
+    /// 传入时按该查询重新计算完整文本上的 highlights
+    pub query: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultTextResp {
+    pub text: String,
+    pub highlights: Vec<Range>,
+}
+
+#[tauri::command]
+pub fn get_search_result_text(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    req: ResultTextReq,
+) -> Result<ResultTextResp, String> {
+    get_search_result_text_impl(&app, &state, req).map_err(db::err_to_string)
+}
+
+fn get_search_result_text_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    req: ResultTextReq,
+) -> Result<ResultTextResp> {
+    let (_root, conn) = db::open_conn(app, state)?;
+    let (sql, bind): (String, Vec<&str>) = match req.kind.as_str() {
+        "docx_block" => (
+            "SELECT text FROM docx_blocks WHERE archive_id=? AND block_id=?".to_string(),
+            vec![req.archive_id.as_str(), req.ref_id.as_str()],
+        ),
+        "main_doc_field" => {
+            // 字段名只允许白名单，避免拼接任意列名
+            let col = match req.ref_id.as_str() {
+                "instruction_no" | "title" | "issued_at" | "content" => req.ref_id.as_str(),
+                other => return Err(anyhow!("未知字段: {other}")),
+            };
+            (
+                format!("SELECT {col} FROM main_doc WHERE archive_id=?"),
+                vec![req.archive_id.as_str()],
+            )
+        }
+        "annotation" => (
+            "SELECT content FROM annotations WHERE archive_id=? AND annotation_id=?".to_string(),
+            vec![req.archive_id.as_str(), req.ref_id.as_str()],
+        ),
+        "attachment_block" => {
+            let file_id = req
+                .file_id
+                .as_deref()
+                .ok_or_else(|| anyhow!("attachment_block 缺少 file_id"))?;
+            (
+                "SELECT text FROM attachment_blocks WHERE archive_id=? AND block_id=? AND file_id=?"
+                    .to_string(),
+                vec![req.archive_id.as_str(), req.ref_id.as_str(), file_id],
+            )
+        }
+        other => return Err(anyhow!("不支持的结果类型: {other}")),
+    };
+    let text: String = conn
+        .query_row(&sql, rusqlite::params_from_iter(bind), |r| r.get(0))
+        .optional()?
+        .ok_or_else(|| anyhow!("找不到结果文本: {} {}", req.kind, req.ref_id))?;
+    let highlights = req
+        .query
+        .as_deref()
+        .map(|q| compute_highlights_utf16(&text, q))
+        .unwrap_or_default();
+    Ok(ResultTextResp { text, highlights })
+}
+
+/// 拍摄时间/时长只对图片、视频附件有意义：其余结果一律去掉
+fn retain_media_matches(
+    conn: &Connection,
+    out: &mut Vec<SearchResult>,
+    media: &MediaFilter,
+) -> Result<()> {
+    let (cond, params) = media.sql("am");
+    let mut stmt = conn.prepare(&format!(
+        "SELECT am.file_id FROM attachment_meta am WHERE {cond}"
+    ))?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(params), |r| {
+        r.get::<_, String>(0)
+    })?;
+    let mut allowed = HashSet::new();
+    for row in rows {
+        allowed.insert(row?);
+    }
+    out.retain(|r| match r {
+        SearchResult::AttachmentName { file_id, .. } | SearchResult::OcrText { file_id, .. } => {
+            allowed.contains(file_id)
+        }
+        _ => false,
+    });
+    Ok(())
+}
+
 fn kind_rank(r: &SearchResult) -> i32 {
     match r {
         SearchResult::DocxBlock { .. } => 0,
         SearchResult::MainDocField { .. } => 1,
         SearchResult::Annotation { .. } => 2,
-        SearchResult::AttachmentName { .. } => 3,
+        SearchResult::AttachmentBlock { .. } => 3,
+        SearchResult::OcrText { .. } => 4,
+        SearchResult::AttachmentName { .. } => 5,
     }
 }
 
-fn result_archive_id(r: &SearchResult) -> &str {
+pub(crate) fn result_archive_id(r: &SearchResult) -> &str {
     match r {
         SearchResult::DocxBlock { archive_id, .. } => archive_id,
         SearchResult::MainDocField { archive_id, .. } => archive_id,
         SearchResult::AttachmentName { archive_id, .. } => archive_id,
         SearchResult::Annotation { archive_id, .. } => archive_id,
+        SearchResult::OcrText { archive_id, .. } => archive_id,
+        SearchResult::AttachmentBlock { archive_id, .. } => archive_id,
     }
 }
 
@@ -438,6 +963,8 @@ fn highlight_score(r: &SearchResult) -> usize {
         SearchResult::MainDocField { highlights, .. } => highlights,
         SearchResult::Annotation { highlights, .. } => highlights,
         SearchResult::AttachmentName { highlights, .. } => highlights,
+        SearchResult::OcrText { highlights, .. } => highlights,
+        SearchResult::AttachmentBlock { highlights, .. } => highlights,
     };
     hs.iter().map(|x| x.end.saturating_sub(x.start)).sum()
 }
@@ -492,6 +1019,31 @@ fn filter_archives_by_date(
     Ok(out)
 }
 
+/// allowed_archives 过滤下推到 SQL 的结果
+pub(crate) enum ArchiveFilter {
+    /// 不限档案
+    All,
+    /// 集合为空，不可能有结果
+    Empty,
+    /// 追加到 WHERE 后的片段及其参数
+    In(String, Vec<rusqlite::types::Value>),
+}
+
+/// 集合整体作为一个 JSON 参数传入，档案再多也不会超过 SQLite 的变量个数上限
+pub(crate) fn archive_in_clause(
+    column: &str,
+    allowed_archives: &Option<HashSet<String>>,
+) -> Result<ArchiveFilter> {
+    Ok(match allowed_archives {
+        None => ArchiveFilter::All,
+        Some(set) if set.is_empty() => ArchiveFilter::Empty,
+        Some(set) => ArchiveFilter::In(
+            format!(" AND {column} IN (SELECT value FROM json_each(?))"),
+            vec![rusqlite::types::Value::from(serde_json::to_string(set)?)],
+        ),
+    })
+}
+
 fn query_docx_blocks(
     conn: &Connection,
     match_query: &str,
@@ -499,14 +1051,26 @@ fn query_docx_blocks(
     allowed_archives: &Option<HashSet<String>>,
 ) -> Result<Vec<SearchResult>> {
     let mut out = Vec::new();
-    let sql = "SELECT docx_blocks_fts.archive_id, docx_blocks_fts.block_id, docx_blocks_fts.source_text
+    let (archive_clause, archive_params) =
+        match archive_in_clause("docx_blocks_fts.archive_id", allowed_archives)? {
+            ArchiveFilter::All => (String::new(), vec![]),
+            ArchiveFilter::Empty => return Ok(vec![]),
+            ArchiveFilter::In(sql, params) => (sql, params),
+        };
+    let sql = format!(
+        "SELECT docx_blocks_fts.archive_id, docx_blocks_fts.block_id, docx_blocks_fts.source_text
         FROM docx_blocks_fts
         JOIN main_doc m ON m.archive_id = docx_blocks_fts.archive_id
-        WHERE docx_blocks_fts MATCH ?
+        WHERE docx_blocks_fts MATCH ? {archive_clause}
         ORDER BY COALESCE(m.issued_at_ts, 0) DESC, docx_blocks_fts.archive_id ASC, docx_blocks_fts.block_id ASC
-        LIMIT ?";
-    let mut stmt = conn.prepare(sql)?;
-    let rows = stmt.query_map(params![match_query, limit as i64], |r| {
+        LIMIT ?"
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let mut bind: Vec<rusqlite::types::Value> = Vec::new();
+    bind.push(rusqlite::types::Value::from(match_query.to_string()));
+    bind.extend(archive_params);
+    bind.push(rusqlite::types::Value::from(limit as i64));
+    let rows = stmt.query_map(rusqlite::params_from_iter(bind), |r| {
         Ok((
             r.get::<_, String>(0)?,
             r.get::<_, String>(1)?,
@@ -525,6 +1089,8 @@ fn query_docx_blocks(
             block_id,
             block_text,
             highlights: vec![],
+            truncated: false,
+            section_path: vec![],
         });
         if out.len() >= limit {
             break;
@@ -540,14 +1106,26 @@ fn query_main_doc_fields(
     allowed_archives: &Option<HashSet<String>>,
 ) -> Result<Vec<SearchResult>> {
     let mut out = Vec::new();
-    let sql = "SELECT main_doc_fts.archive_id, main_doc_fts.field_name, main_doc_fts.source_text
+    let (archive_clause, archive_params) =
+        match archive_in_clause("main_doc_fts.archive_id", allowed_archives)? {
+            ArchiveFilter::All => (String::new(), vec![]),
+            ArchiveFilter::Empty => return Ok(vec![]),
+            ArchiveFilter::In(sql, params) => (sql, params),
+        };
+    let sql = format!(
+        "SELECT main_doc_fts.archive_id, main_doc_fts.field_name, main_doc_fts.source_text
         FROM main_doc_fts
         JOIN main_doc m ON m.archive_id = main_doc_fts.archive_id
-        WHERE main_doc_fts MATCH ?
+        WHERE main_doc_fts MATCH ? {archive_clause}
         ORDER BY COALESCE(m.issued_at_ts, 0) DESC, main_doc_fts.archive_id ASC
-        LIMIT ?";
-    let mut stmt = conn.prepare(sql)?;
-    let rows = stmt.query_map(params![match_query, limit as i64], |r| {
+        LIMIT ?"
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let mut bind: Vec<rusqlite::types::Value> = Vec::new();
+    bind.push(rusqlite::types::Value::from(match_query.to_string()));
+    bind.extend(archive_params);
+    bind.push(rusqlite::types::Value::from(limit as i64));
+    let rows = stmt.query_map(rusqlite::params_from_iter(bind), |r| {
         Ok((
             r.get::<_, String>(0)?,
             r.get::<_, String>(1)?,
@@ -568,6 +1146,7 @@ fn query_main_doc_fields(
             highlights: vec![],
             best_block_id: None,
             best_block_highlights: None,
+            truncated: false,
         });
         if out.len() >= limit {
             break;
@@ -606,18 +1185,12 @@ fn query_attachment_names(
     }
 
     // allowed_archives 过滤
-    let mut archive_clause = String::new();
-    let mut archive_params: Vec<String> = Vec::new();
-    if let Some(set) = allowed_archives {
-        if set.is_empty() {
-            return Ok(vec![]);
-        }
-        archive_clause = format!(
-            " AND a.archive_id IN ({})",
-            set.iter().map(|_| "?").collect::<Vec<_>>().join(",")
-        );
-        archive_params = set.iter().cloned().collect();
-    }
+    let (archive_clause, archive_params) =
+        match archive_in_clause("a.archive_id", allowed_archives)? {
+            ArchiveFilter::All => (String::new(), vec![]),
+            ArchiveFilter::Empty => return Ok(vec![]),
+            ArchiveFilter::In(sql, params) => (sql, params),
+        };
 
     let sql = format!(
         "SELECT a.archive_id, a.file_id, attachments_fts.display_name
@@ -634,9 +1207,7 @@ fn query_attachment_names(
     for t in type_params {
         bind.push(rusqlite::types::Value::from(t));
     }
-    for a in archive_params {
-        bind.push(rusqlite::types::Value::from(a));
-    }
+    bind.extend(archive_params);
     bind.push(rusqlite::types::Value::from(limit as i64));
 
     let rows = stmt.query_map(rusqlite::params_from_iter(bind), |r| {
@@ -675,18 +1246,12 @@ fn query_annotations(
     }
 
     // allowed_archives 过滤
-    let mut archive_clause = String::new();
-    let mut archive_params: Vec<String> = Vec::new();
-    if let Some(set) = allowed_archives {
-        if set.is_empty() {
-            return Ok(vec![]);
-        }
-        archive_clause = format!(
-            " AND a.archive_id IN ({})",
-            set.iter().map(|_| "?").collect::<Vec<_>>().join(",")
-        );
-        archive_params = set.iter().cloned().collect();
-    }
+    let (archive_clause, archive_params) =
+        match archive_in_clause("a.archive_id", allowed_archives)? {
+            ArchiveFilter::All => (String::new(), vec![]),
+            ArchiveFilter::Empty => return Ok(vec![]),
+            ArchiveFilter::In(sql, params) => (sql, params),
+        };
 
     let sql = format!(
         "SELECT a.archive_id, a.annotation_id, a.target_kind, a.target_ref, a.locator_json, a.content
@@ -700,9 +1265,7 @@ fn query_annotations(
     let mut stmt = conn.prepare(&sql)?;
     let mut bind: Vec<rusqlite::types::Value> = Vec::new();
     bind.push(rusqlite::types::Value::from(match_query.to_string()));
-    for a in archive_params {
-        bind.push(rusqlite::types::Value::from(a));
-    }
+    bind.extend(archive_params);
     bind.push(rusqlite::types::Value::from(limit as i64));
     let rows = stmt.query_map(rusqlite::params_from_iter(bind), |r| {
         let locator_json: String = r.get(4)?;
@@ -728,6 +1291,7 @@ fn query_annotations(
             locator,
             content,
             highlights: vec![],
+            truncated: false,
         });
         if out.len() >= limit {
             break;
@@ -737,6 +1301,11 @@ fn query_annotations(
 }
 
 fn compute_highlights_utf16(text: &str, query: &str) -> Vec<Range> {
+    find_highlights_utf16(text, query, 20)
+}
+
+/// 与搜索结果相同的高亮规则，max 为最多返回的区间数
+pub(crate) fn find_highlights_utf16(text: &str, query: &str, max: usize) -> Vec<Range> {
     let q = query.trim();
     if q.is_empty() || text.is_empty() {
         return vec![];
@@ -751,6 +1320,8 @@ fn compute_highlights_utf16(text: &str, query: &str) -> Vec<Range> {
     needles.extend(jieba_tokens(q));
     needles.extend(char_ngrams(q, 2));
     needles.extend(char_ngrams(q, 3));
+    // 同义词只按整词高亮，保证标出的是原文中实际出现的写法
+    needles.extend(synonyms::expand_query(q));
     needles.retain(|s| !s.trim().is_empty());
     needles.sort();
     needles.dedup();
@@ -769,7 +1340,7 @@ fn compute_highlights_utf16(text: &str, query: &str) -> Vec<Range> {
             }
         }
     }
-    normalize_ranges(ranges, 20)
+    normalize_ranges(ranges, max)
 }
 
 fn byte_to_utf16(text: &str, byte_idx: usize) -> Option<usize> {
@@ -798,7 +1369,7 @@ fn byte_to_utf16(text: &str, byte_idx: usize) -> Option<usize> {
     }
 }
 
-fn normalize_ranges(mut ranges: Vec<Range>, max: usize) -> Vec<Range> {
+pub(crate) fn normalize_ranges(mut ranges: Vec<Range>, max: usize) -> Vec<Range> {
     if ranges.is_empty() {
         return vec![];
     }
@@ -0,0 +1,281 @@
+use crate::db;
+use crate::library_root::LibraryRootState;
+use crate::search::SearchFilters;
+use anyhow::{anyhow, Result};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tauri::State;
+use uuid::Uuid;
+
+/// 未置顶历史最多保留条数，超出按时间淘汰
+const MAX_HISTORY: i64 = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHistoryItem {
+    pub history_id: String,
+    pub query: String,
+    pub filters: Option<SearchFilters>,
+    pub hit_count: i64,
+    /// 第一页之后还有结果（hit_count 只是下限）
+    pub has_more: bool,
+    pub search_count: i64,
+    pub searched_at: i64,
+    pub pinned: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuerySuggestion {
+    pub text: String,
+    /// history | title | vocab
+    pub source: String,
+    pub score: i64,
+}
+
+/// 记录一次搜索；相同查询+筛选条件合并为一条，只更新时间、命中数和次数
+pub fn record(
+    conn: &Connection,
+    query: &str,
+    filters: Option<&SearchFilters>,
+    hit_count: usize,
+    has_more: bool,
+) -> Result<()> {
+    let q = query.trim();
+    if q.is_empty() {
+        return Ok(());
+    }
+    let filters_json = serde_json::to_string(&filters)?;
+    let now = chrono::Utc::now().timestamp();
+    conn.execute(
+        "INSERT INTO search_history(history_id,query,filters_json,hit_count,has_more,search_count,searched_at,pinned)
+         VALUES(?,?,?,?,?,1,?,0)
+         ON CONFLICT(query, filters_json) DO UPDATE SET
+           hit_count=excluded.hit_count,
+           has_more=excluded.has_more,
+           search_count=search_history.search_count+1,
+           searched_at=excluded.searched_at",
+        params![
+            Uuid::new_v4().to_string(),
+            q,
+            filters_json,
+            hit_count as i64,
+            has_more as i64,
+            now
+        ],
+    )?;
+    conn.execute(
+        "DELETE FROM search_history WHERE pinned=0 AND history_id NOT IN (
+           SELECT history_id FROM search_history WHERE pinned=0 ORDER BY searched_at DESC LIMIT ?
+         )",
+        [MAX_HISTORY],
+    )?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_search_history(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHistoryItem>, String> {
+    list_search_history_impl(&app, &state, limit).map_err(db::err_to_string)
+}
+
+fn list_search_history_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHistoryItem>> {
+    let (_root, conn) = db::open_conn(app, state)?;
+    let limit = limit.unwrap_or(50).min(MAX_HISTORY as usize) as i64;
+    let mut stmt = conn.prepare(
+        "SELECT history_id,query,filters_json,hit_count,has_more,search_count,searched_at,pinned
+         FROM search_history
+         ORDER BY pinned DESC, searched_at DESC
+         LIMIT ?",
+    )?;
+    let rows = stmt.query_map([limit], |r| {
+        let filters_json: String = r.get(2)?;
+        Ok(SearchHistoryItem {
+            history_id: r.get(0)?,
+            query: r.get(1)?,
+            filters: serde_json::from_str(&filters_json).ok().flatten(),
+            hit_count: r.get(3)?,
+            has_more: r.get::<_, i64>(4)? != 0,
+            search_count: r.get(5)?,
+            searched_at: r.get(6)?,
+            pinned: r.get::<_, i64>(7)? != 0,
+        })
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+#[tauri::command]
+pub fn clear_search_history(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    keep_pinned: Option<bool>,
+) -> Result<usize, String> {
+    let (_root, conn) = db::open_conn(&app, &state).map_err(db::err_to_string)?;
+    let sql = if keep_pinned.unwrap_or(true) {
+        "DELETE FROM search_history WHERE pinned=0"
+    } else {
+        "DELETE FROM search_history"
+    };
+    conn.execute(sql, [])
+        .map_err(|e| db::err_to_string(anyhow!(e)))
+}
+
+#[tauri::command]
+pub fn delete_search_history(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    history_id: String,
+) -> Result<(), String> {
+    let (_root, conn) = db::open_conn(&app, &state).map_err(db::err_to_string)?;
+    conn.execute(
+        "DELETE FROM search_history WHERE history_id=?",
+        [history_id.as_str()],
+    )
+    .map_err(|e| db::err_to_string(anyhow!(e)))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn pin_search_history(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    history_id: String,
+    pinned: bool,
+) -> Result<(), String> {
+    let (_root, conn) = db::open_conn(&app, &state).map_err(db::err_to_string)?;
+    let changed = conn
+        .execute(
+            "UPDATE search_history SET pinned=? WHERE history_id=?",
+            params![pinned as i64, history_id],
+        )
+        .map_err(|e| db::err_to_string(anyhow!(e)))?;
+    if changed == 0 {
+        return Err(format!("找不到搜索历史: {history_id}"));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn suggest_queries(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    prefix: String,
+    limit: Option<usize>,
+) -> Result<Vec<QuerySuggestion>, String> {
+    suggest_queries_impl(&app, &state, &prefix, limit).map_err(db::err_to_string)
+}
+
+fn suggest_queries_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    prefix: &str,
+    limit: Option<usize>,
+) -> Result<Vec<QuerySuggestion>> {
+    let (_root, conn) = db::open_conn(app, state)?;
+    let limit = limit.unwrap_or(10).clamp(1, 50);
+    let p = prefix.trim();
+    if p.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut out: Vec<QuerySuggestion> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut push = |out: &mut Vec<QuerySuggestion>, text: String, source: &str, score: i64| {
+        let t = text.trim().to_string();
+        if t.is_empty() || t == p || !seen.insert(t.clone()) {
+            return;
+        }
+        out.push(QuerySuggestion {
+            text: t,
+            source: source.to_string(),
+            score,
+        });
+    };
+
+    let like = format!("{}%", escape_like(p));
+
+    // 1) 历史：置顶优先，其次按次数与时间
+    {
+        let mut stmt = conn.prepare(
+            "SELECT query, MAX(pinned), SUM(search_count), MAX(searched_at)
+             FROM search_history
+             WHERE query LIKE ? ESCAPE '\\'
+             GROUP BY query
+             ORDER BY MAX(pinned) DESC, SUM(search_count) DESC, MAX(searched_at) DESC
+             LIMIT ?",
+        )?;
+        let rows = stmt.query_map(params![like, limit as i64], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, i64>(1)?,
+                r.get::<_, i64>(2)?,
+            ))
+        })?;
+        for row in rows {
+            let (q, pinned, cnt) = row?;
+            push(&mut out, q, "history", 1_000_000 * pinned + cnt);
+        }
+    }
+
+    // 2) 文档标题：前缀匹配优先，再补包含匹配
+    if out.len() < limit {
+        let contains = format!("%{}%", escape_like(p));
+        let mut stmt = conn.prepare(
+            "SELECT title, CASE WHEN title LIKE ?1 ESCAPE '\\' THEN 1 ELSE 0 END AS prefix_hit
+             FROM main_doc
+             WHERE title LIKE ?2 ESCAPE '\\'
+             ORDER BY prefix_hit DESC, COALESCE(issued_at_ts, 0) DESC
+             LIMIT ?3",
+        )?;
+        let rows = stmt.query_map(params![like, contains, limit as i64], |r| {
+            Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?))
+        })?;
+        for row in rows {
+            let (title, prefix_hit) = row?;
+            push(&mut out, title, "title", prefix_hit);
+        }
+    }
+
+    // 3) 索引词表：取 jieba 分出的档案词（archive_terms），按出现档案数排序；
+    //    FTS 词表里是二/三元切片，不适合直接给用户看
+    if out.len() < limit {
+        let lower = p.to_lowercase();
+        let upper = format!("{lower}{}", char::MAX);
+        let mut stmt = conn.prepare(
+            "SELECT term, COUNT(1) AS doc FROM archive_terms
+             WHERE term >= ? AND term < ?
+             GROUP BY term
+             ORDER BY doc DESC, term ASC
+             LIMIT ?",
+        )?;
+        let rows = stmt.query_map(params![lower, upper, limit as i64], |r| {
+            Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?))
+        })?;
+        for row in rows {
+            let (term, doc) = row?;
+            if out.len() >= limit {
+                break;
+            }
+            push(&mut out, term, "vocab", doc);
+        }
+    }
+
+    out.truncate(limit);
+    Ok(out)
+}
+
+fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
@@ -0,0 +1,262 @@
+use crate::db;
+use crate::library_root::LibraryRootState;
+use crate::search;
+use anyhow::{anyhow, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::State;
+
+/// 每个档案只保留词频最高的若干词，控制表规模
+const MAX_TERMS_PER_ARCHIVE: usize = 300;
+/// 标题词的词频放大倍数
+const TITLE_WEIGHT: i64 = 3;
+/// 先按点积粗排，再对前 N 倍候选算精确余弦
+const CANDIDATE_FACTOR: usize = 5;
+const SHARED_TERMS: usize = 8;
+/// 词表计算方式变化时递增，init_db 据此对旧库重建
+const META_TERMS_VERSION: &str = "archive_terms_version";
+const TERMS_VERSION: &str = "1";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarArchive {
+    pub archive_id: String,
+    pub instruction_no: String,
+    pub title: String,
+    pub issued_at: String,
+    pub score: f64,
+    /// 贡献最大的共有词
+    pub shared_terms: Vec<String>,
+}
+
+/// 重建单个档案的词表（导入/重解析/改标题后调用）
+pub fn index_archive(conn: &Connection, archive_id: &str) -> Result<()> {
+    conn.execute("DELETE FROM archive_terms WHERE archive_id=?", [archive_id])?;
+    let Some((title, content)) = conn
+        .query_row(
+            "SELECT title, content FROM main_doc WHERE archive_id=?",
+            [archive_id],
+            |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)),
+        )
+        .optional()?
+    else {
+        return Ok(());
+    };
+
+    let mut tf: HashMap<String, i64> = HashMap::new();
+    for t in terms_of(&title) {
+        *tf.entry(t).or_default() += TITLE_WEIGHT;
+    }
+    // content 即正文段落拼接，不再重复统计 docx_blocks
+    for t in terms_of(&content) {
+        *tf.entry(t).or_default() += 1;
+    }
+
+    let mut terms = tf.into_iter().collect::<Vec<_>>();
+    terms.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    terms.truncate(MAX_TERMS_PER_ARCHIVE);
+    let mut stmt = conn.prepare("INSERT INTO archive_terms(archive_id, term, tf) VALUES(?,?,?)")?;
+    for (term, n) in terms {
+        stmt.execute(params![archive_id, term, n])?;
+    }
+    Ok(())
+}
+
+pub(crate) fn terms_pending(conn: &Connection) -> Result<bool> {
+    Ok(db::get_meta_value(conn, META_TERMS_VERSION)?.as_deref() != Some(TERMS_VERSION))
+}
+
+/// 旧库首次打开（或词表计算方式变化）时重建全部词表，只做一次
+pub fn ensure_archive_terms_synced(conn: &Connection) -> Result<()> {
+    if !terms_pending(conn)? {
+        return Ok(());
+    }
+    let tx = conn.unchecked_transaction()?;
+    rebuild_archive_terms(&tx)?;
+    db::set_meta_value(&tx, META_TERMS_VERSION, TERMS_VERSION)?;
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn rebuild_archive_terms(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM archive_terms", [])?;
+    let mut archive_ids = Vec::new();
+    {
+        let mut stmt = conn.prepare("SELECT archive_id FROM main_doc")?;
+        let rows = stmt.query_map([], |r| r.get::<_, String>(0))?;
+        for row in rows {
+            archive_ids.push(row?);
+        }
+    }
+    for archive_id in archive_ids {
+        index_archive(conn, &archive_id)?;
+    }
+    Ok(())
+}
+
+fn terms_of(text: &str) -> Vec<String> {
+    search::jieba_tokens(text)
+        .into_iter()
+        .map(|t| t.to_lowercase())
+        .filter(|t| is_meaningful(t))
+        .collect()
+}
+
+/// 单字、纯数字和标点对相似度没有意义
+fn is_meaningful(term: &str) -> bool {
+    term.chars().count() >= 2
+        && term.chars().any(|c| c.is_alphabetic())
+        && !term.chars().any(|c| c.is_whitespace())
+}
+
+#[tauri::command]
+pub fn find_similar_archives(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    archive_id: String,
+    limit: Option<usize>,
+) -> Result<Vec<SimilarArchive>, String> {
+    find_similar_archives_impl(&app, &state, &archive_id, limit).map_err(db::err_to_string)
+}
+
+fn find_similar_archives_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    archive_id: &str,
+    limit: Option<usize>,
+) -> Result<Vec<SimilarArchive>> {
+    let (_root, conn) = db::open_conn(app, state)?;
+    let limit = limit.unwrap_or(10).clamp(1, 50);
+
+    let exists: Option<i64> = conn
+        .query_row(
+            "SELECT 1 FROM main_doc WHERE archive_id=?",
+            [archive_id],
+            |r| r.get(0),
+        )
+        .optional()?;
+    if exists.is_none() {
+        return Err(anyhow!("档案 {archive_id} 没有 main_doc 记录"));
+    }
+    let target = load_terms(&conn, archive_id)?;
+    if target.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let n_docs: i64 = conn.query_row(
+        "SELECT COUNT(DISTINCT archive_id) FROM archive_terms",
+        [],
+        |r| r.get(0),
+    )?;
+    let mut df_cache: HashMap<String, i64> = HashMap::new();
+    let mut idf = |conn: &Connection, term: &str| -> Result<f64> {
+        let df = match df_cache.get(term) {
+            Some(v) => *v,
+            None => {
+                let v: i64 = conn.query_row(
+                    "SELECT COUNT(1) FROM archive_terms WHERE term=?",
+                    [term],
+                    |r| r.get(0),
+                )?;
+                df_cache.insert(term.to_string(), v);
+                v
+            }
+        };
+        Ok((1.0 + n_docs as f64 / df.max(1) as f64).ln())
+    };
+
+    // 目标向量
+    let mut target_w: HashMap<String, f64> = HashMap::new();
+    for (term, tf) in &target {
+        let w = weight(*tf) * idf(&conn, term)?;
+        target_w.insert(term.clone(), w);
+    }
+    let target_norm = norm(target_w.values());
+    if target_norm == 0.0 {
+        return Ok(vec![]);
+    }
+
+    // 倒排累加点积：只有共享词的档案才可能相似
+    let mut dots: HashMap<String, f64> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT archive_id, tf FROM archive_terms WHERE term=? AND archive_id<>?")?;
+        for (term, tw) in &target_w {
+            let rows = stmt.query_map(params![term, archive_id], |r| {
+                Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?))
+            })?;
+            for row in rows {
+                let (other, tf) = row?;
+                *dots.entry(other).or_default() += tw * weight(tf) * idf(&conn, term)?;
+            }
+        }
+    }
+    let mut candidates = dots.into_iter().collect::<Vec<_>>();
+    candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+    candidates.truncate(limit * CANDIDATE_FACTOR);
+
+    let mut out = Vec::new();
+    for (other, dot) in candidates {
+        let other_terms = load_terms(&conn, &other)?;
+        let mut other_w = HashMap::new();
+        for (term, tf) in &other_terms {
+            other_w.insert(term.clone(), weight(*tf) * idf(&conn, term)?);
+        }
+        let other_norm = norm(other_w.values());
+        if other_norm == 0.0 {
+            continue;
+        }
+        let mut shared = other_w
+            .iter()
+            .filter_map(|(t, w)| target_w.get(t).map(|tw| (t.clone(), tw * w)))
+            .collect::<Vec<_>>();
+        shared.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let (instruction_no, title, issued_at) = conn.query_row(
+            "SELECT instruction_no, title, issued_at FROM main_doc WHERE archive_id=?",
+            [&other],
+            |r| {
+                Ok((
+                    r.get::<_, String>(0)?,
+                    r.get::<_, String>(1)?,
+                    r.get::<_, String>(2)?,
+                ))
+            },
+        )?;
+        out.push(SimilarArchive {
+            archive_id: other,
+            instruction_no,
+            title,
+            issued_at,
+            score: dot / (target_norm * other_norm),
+            shared_terms: shared
+                .into_iter()
+                .take(SHARED_TERMS)
+                .map(|(t, _)| t)
+                .collect(),
+        });
+    }
+    out.sort_by(|a, b| b.score.total_cmp(&a.score));
+    out.truncate(limit);
+    Ok(out)
+}
+
+fn load_terms(conn: &Connection, archive_id: &str) -> Result<Vec<(String, i64)>> {
+    let mut stmt = conn.prepare("SELECT term, tf FROM archive_terms WHERE archive_id=?")?;
+    let rows = stmt.query_map([archive_id], |r| {
+        Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?))
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+/// 对数词频，避免长文档里高频词一家独大
+fn weight(tf: i64) -> f64 {
+    1.0 + (tf.max(1) as f64).ln()
+}
+
+fn norm<'a>(ws: impl Iterator<Item = &'a f64>) -> f64 {
+    ws.map(|w| w * w).sum::<f64>().sqrt()
+}
@@ -0,0 +1,119 @@
+use crate::search::Range;
+use serde::{Deserialize, Serialize};
+
+const ELLIPSIS: &str = "…";
+const DEFAULT_CONTEXT_CHARS: usize = 60;
+const DEFAULT_MAX_SNIPPETS: usize = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SnippetOptions {
+    /// 关闭后返回完整文本（旧行为）
+    pub disabled: Option<bool>,
+    /// 命中前后各保留的字符数（UTF-16 计）
+    pub context_chars: Option<usize>,
+    /// 最多保留的片段数
+    pub max_snippets: Option<usize>,
+}
+
+impl SnippetOptions {
+    fn context(&self) -> usize {
+        self.context_chars
+            .unwrap_or(DEFAULT_CONTEXT_CHARS)
+            .clamp(4, 2000)
+    }
+
+    fn max(&self) -> usize {
+        self.max_snippets
+            .unwrap_or(DEFAULT_MAX_SNIPPETS)
+            .clamp(1, 20)
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.disabled.unwrap_or(false)
+    }
+}
+
+/// 围绕命中生成上下文片段：多个窗口用省略号连接，highlights 重新定位到片段内。
+/// 文本不长时返回 None（保持原文）。
+pub fn make_snippet(
+    text: &str,
+    highlights: &[Range],
+    opts: &SnippetOptions,
+) -> Option<(String, Vec<Range>)> {
+    let units: Vec<u16> = text.encode_utf16().collect();
+    let total = units.len();
+    let context = opts.context();
+    let max_snippets = opts.max();
+    // 短文本不截断：整段不超过一个“满窗口”的长度
+    if total <= context * 2 * max_snippets.max(2) {
+        return None;
+    }
+
+    let mut hs = highlights
+        .iter()
+        .filter(|h| h.start < h.end && h.end <= total)
+        .cloned()
+        .collect::<Vec<_>>();
+    hs.sort_by(|a, b| (a.start, a.end).cmp(&(b.start, b.end)));
+
+    // 计算窗口并合并重叠
+    let mut windows: Vec<(usize, usize)> = Vec::new();
+    if hs.is_empty() {
+        windows.push((0, (context * 2).min(total)));
+    } else {
+        for h in &hs {
+            let s = h.start.saturating_sub(context);
+            let e = (h.end + context).min(total);
+            if let Some(last) = windows.last_mut() {
+                if s <= last.1 {
+                    last.1 = last.1.max(e);
+                    continue;
+                }
+            }
+            if windows.len() >= max_snippets {
+                break;
+            }
+            windows.push((s, e));
+        }
+    }
+
+    let mut out = String::new();
+    let mut out_len = 0usize; // UTF-16 长度
+    let mut rebased = Vec::new();
+    for (s, e) in windows {
+        let (s, e) = snap_to_char_boundary(&units, s, e);
+        if s > 0 && !out.ends_with(ELLIPSIS) {
+            out.push_str(ELLIPSIS);
+            out_len += ELLIPSIS.encode_utf16().count();
+        }
+        for h in &hs {
+            if h.end <= s || h.start >= e {
+                continue;
+            }
+            let hs0 = h.start.max(s) - s + out_len;
+            let he0 = h.end.min(e) - s + out_len;
+            rebased.push(Range {
+                start: hs0,
+                end: he0,
+            });
+        }
+        out.push_str(&String::from_utf16_lossy(&units[s..e]));
+        out_len += e - s;
+        if e < total {
+            out.push_str(ELLIPSIS);
+            out_len += ELLIPSIS.encode_utf16().count();
+        }
+    }
+    Some((out, rebased))
+}
+
+fn snap_to_char_boundary(units: &[u16], mut s: usize, mut e: usize) -> (usize, usize) {
+    // 避免把代理对拆开
+    if s > 0 && s < units.len() && (0xDC00..=0xDFFF).contains(&units[s]) {
+        s -= 1;
+    }
+    if e > 0 && e < units.len() && (0xD800..=0xDBFF).contains(&units[e - 1]) {
+        e += 1;
+    }
+    (s, e.min(units.len()))
+}
@@ -0,0 +1,380 @@
+use crate::db;
+use crate::library_root::LibraryRootState;
+use anyhow::{anyhow, Context, Result};
+use once_cell::sync::Lazy;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tauri::State;
+use uuid::Uuid;
+
+const META_INDEX_TIME: &str = "synonyms_index_time";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SynonymGroup {
+    pub group_id: String,
+    pub terms: Vec<String>,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveSynonymGroupReq {
+    pub group_id: Option<String>,
+    pub terms: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SynonymSettings {
+    pub index_time_expansion: bool,
+    pub index_stale: bool,
+    pub group_count: usize,
+}
+
+/// 当前库的同义词表（内存副本）：搜索/建索引都是热路径，不每次查库
+#[derive(Default)]
+struct SynonymDict {
+    root: Option<PathBuf>,
+    groups: Vec<Vec<String>>,
+    index_time: bool,
+}
+
+static SYNONYMS: Lazy<RwLock<SynonymDict>> = Lazy::new(|| RwLock::new(SynonymDict::default()));
+
+pub fn ensure_loaded(root: &Path, conn: &Connection) -> Result<()> {
+    {
+        let dict = SYNONYMS.read().unwrap();
+        if dict.root.as_deref() == Some(root) {
+            return Ok(());
+        }
+    }
+    reload(root, conn)
+}
+
+fn reload(root: &Path, conn: &Connection) -> Result<()> {
+    let groups = load_groups(conn)?
+        .into_iter()
+        .map(|g| g.terms)
+        .collect::<Vec<_>>();
+    let index_time = db::get_meta_value(conn, META_INDEX_TIME)?.as_deref() == Some("1");
+    let mut dict = SYNONYMS.write().unwrap();
+    dict.root = Some(root.to_path_buf());
+    dict.groups = groups;
+    dict.index_time = index_time;
+    Ok(())
+}
+
+/// 查询扩展：查询中出现某组任一词条时，返回同组的其余词条
+pub fn expand_query(query: &str) -> Vec<String> {
+    let q = query.trim();
+    if q.is_empty() {
+        return vec![];
+    }
+    let dict = SYNONYMS.read().unwrap();
+    collect_expansions(&dict.groups, q)
+}
+
+/// 建索引扩展：仅在开启“索引时扩展”后生效
+pub fn expand_for_index(text: &str) -> Vec<String> {
+    let dict = SYNONYMS.read().unwrap();
+    if !dict.index_time {
+        return vec![];
+    }
+    collect_expansions(&dict.groups, text)
+}
+
+fn collect_expansions(groups: &[Vec<String>], text: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    for g in groups {
+        if g.iter().any(|t| !t.is_empty() && text.contains(t.as_str())) {
+            for t in g {
+                if !text.contains(t.as_str()) && !out.contains(t) {
+                    out.push(t.clone());
+                }
+            }
+        }
+    }
+    out
+}
+
+fn normalize_terms(terms: Vec<String>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for t in terms {
+        let t = t.trim().to_string();
+        if !t.is_empty() && !out.contains(&t) {
+            out.push(t);
+        }
+    }
+    out
+}
+
+fn split_line_terms(line: &str) -> Vec<String> {
+    line.split(|c: char| {
+        c == ','
+            || c == '，'
+            || c == '='
+            || c == '|'
+            || c == '、'
+            || c == ';'
+            || c == '；'
+            || c.is_whitespace()
+    })
+    .map(|s| s.to_string())
+    .collect()
+}
+
+fn load_groups(conn: &Connection) -> Result<Vec<SynonymGroup>> {
+    let mut stmt = conn.prepare(
+        "SELECT group_id, terms_json, updated_at FROM synonym_groups ORDER BY updated_at DESC, group_id ASC",
+    )?;
+    let rows = stmt.query_map([], |r| {
+        let terms_json: String = r.get(1)?;
+        Ok(SynonymGroup {
+            group_id: r.get(0)?,
+            terms: serde_json::from_str(&terms_json).unwrap_or_default(),
+            updated_at: r.get(2)?,
+        })
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
+fn mark_index_stale_if_needed(conn: &Connection) -> Result<()> {
+    if db::get_meta_value(conn, META_INDEX_TIME)?.as_deref() == Some("1") {
+        db::set_meta_value(conn, db::META_SEARCH_INDEX_STALE, "1")?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_synonym_groups(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+) -> Result<Vec<SynonymGroup>, String> {
+    let (_root, conn) = db::open_conn(&app, &state).map_err(db::err_to_string)?;
+    load_groups(&conn).map_err(db::err_to_string)
+}
+
+#[tauri::command]
+pub fn save_synonym_group(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    req: SaveSynonymGroupReq,
+) -> Result<SynonymGroup, String> {
+    save_synonym_group_impl(&app, &state, req).map_err(db::err_to_string)
+}
+
+fn save_synonym_group_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    req: SaveSynonymGroupReq,
+) -> Result<SynonymGroup> {
+    let terms = normalize_terms(req.terms);
+    if terms.len() < 2 {
+        return Err(anyhow!("同义词组至少需要两个词条"));
+    }
+    let (root, conn) = db::open_conn(app, state)?;
+    let now = chrono::Utc::now().timestamp();
+    let group_id = req
+        .group_id
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    conn.execute(
+        "INSERT INTO synonym_groups(group_id,terms_json,updated_at) VALUES(?,?,?)
+         ON CONFLICT(group_id) DO UPDATE SET terms_json=excluded.terms_json, updated_at=excluded.updated_at",
+        params![group_id, serde_json::to_string(&terms)?, now],
+    )?;
+    mark_index_stale_if_needed(&conn)?;
+    reload(&root, &conn)?;
+    Ok(SynonymGroup {
+        group_id,
+        terms,
+        updated_at: now,
+    })
+}
+
+#[tauri::command]
+pub fn delete_synonym_group(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    group_id: String,
+) -> Result<(), String> {
+    delete_synonym_group_impl(&app, &state, &group_id).map_err(db::err_to_string)
+}
+
+fn delete_synonym_group_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    group_id: &str,
+) -> Result<()> {
+    let (root, conn) = db::open_conn(app, state)?;
+    conn.execute("DELETE FROM synonym_groups WHERE group_id=?", [group_id])?;
+    mark_index_stale_if_needed(&conn)?;
+    reload(&root, &conn)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn import_synonyms(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    path: Option<String>,
+    replace: Option<bool>,
+) -> Result<usize, String> {
+    import_synonyms_impl(&app, &state, path, replace.unwrap_or(false)).map_err(db::err_to_string)
+}
+
+fn import_synonyms_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    path: Option<String>,
+    replace: bool,
+) -> Result<usize> {
+    let path = match path {
+        Some(p) => PathBuf::from(p),
+        None => match rfd::FileDialog::new()
+            .add_filter("同义词表", &["txt", "csv"])
+            .pick_file()
+        {
+            Some(p) => p,
+            None => return Ok(0),
+        },
+    };
+    let bytes = fs::read(&path).with_context(|| format!("读取文件失败: {}", path.display()))?;
+    let text = match String::from_utf8(bytes.clone()) {
+        Ok(s) => s,
+        Err(_) => encoding_rs::GBK.decode(&bytes).0.to_string(),
+    };
+
+    // 每行一组，词条之间用逗号/等号/竖线/顿号/空白分隔；# 开头为注释
+    let mut groups = Vec::new();
+    for line in text.lines() {
+        let line = line.trim().trim_start_matches('\u{FEFF}');
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let terms = normalize_terms(split_line_terms(line));
+        if terms.len() >= 2 {
+            groups.push(terms);
+        }
+    }
+
+    let (root, mut conn) = db::open_conn(app, state)?;
+    let now = chrono::Utc::now().timestamp();
+    let tx = conn.transaction()?;
+    if replace {
+        tx.execute("DELETE FROM synonym_groups", [])?;
+    }
+    {
+        let mut stmt =
+            tx.prepare("INSERT INTO synonym_groups(group_id,terms_json,updated_at) VALUES(?,?,?)")?;
+        for terms in &groups {
+            stmt.execute(params![
+                Uuid::new_v4().to_string(),
+                serde_json::to_string(terms)?,
+                now
+            ])?;
+        }
+    }
+    tx.commit()?;
+    mark_index_stale_if_needed(&conn)?;
+    reload(&root, &conn)?;
+    Ok(groups.len())
+}
+
+#[tauri::command]
+pub fn export_synonyms(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    path: Option<String>,
+) -> Result<usize, String> {
+    export_synonyms_impl(&app, &state, path).map_err(db::err_to_string)
+}
+
+fn export_synonyms_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    path: Option<String>,
+) -> Result<usize> {
+    let path = match path {
+        Some(p) => PathBuf::from(p),
+        None => match rfd::FileDialog::new()
+            .add_filter("同义词表", &["txt"])
+            .set_file_name("synonyms.txt")
+            .save_file()
+        {
+            Some(p) => p,
+            None => return Ok(0),
+        },
+    };
+    let (_root, conn) = db::open_conn(app, state)?;
+    let groups = load_groups(&conn)?;
+    let mut text = String::from("# ArchiveVault 同义词表：每行一组，逗号分隔\n");
+    for g in &groups {
+        text.push_str(&g.terms.join(","));
+        text.push('\n');
+    }
+    fs::write(&path, text).with_context(|| format!("写入文件失败: {}", path.display()))?;
+    Ok(groups.len())
+}
+
+#[tauri::command]
+pub fn get_synonym_settings(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+) -> Result<SynonymSettings, String> {
+    get_synonym_settings_impl(&app, &state).map_err(db::err_to_string)
+}
+
+fn get_synonym_settings_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+) -> Result<SynonymSettings> {
+    let (_root, conn) = db::open_conn(app, state)?;
+    let group_count: i64 =
+        conn.query_row("SELECT COUNT(1) FROM synonym_groups", [], |r| r.get(0))?;
+    Ok(SynonymSettings {
+        index_time_expansion: db::get_meta_value(&conn, META_INDEX_TIME)?.as_deref() == Some("1"),
+        index_stale: db::get_meta_value(&conn, db::META_SEARCH_INDEX_STALE)?.as_deref()
+            == Some("1"),
+        group_count: group_count as usize,
+    })
+}
+
+#[tauri::command]
+pub fn set_synonym_index_expansion(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    enabled: bool,
+) -> Result<SynonymSettings, String> {
+    set_synonym_index_expansion_impl(&app, &state, enabled).map_err(db::err_to_string)?;
+    get_synonym_settings_impl(&app, &state).map_err(db::err_to_string)
+}
+
+fn set_synonym_index_expansion_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    enabled: bool,
+) -> Result<()> {
+    let (root, conn) = db::open_conn(app, state)?;
+    let prev = db::get_meta_value(&conn, META_INDEX_TIME)?;
+    let value = if enabled { "1" } else { "0" };
+    if prev.as_deref().unwrap_or("0") != value {
+        db::set_meta_value(&conn, META_INDEX_TIME, value)?;
+        // 开关变化后已有索引与设置不一致，需要重建
+        let has_groups: Option<String> = conn
+            .query_row("SELECT group_id FROM synonym_groups LIMIT 1", [], |r| {
+                r.get(0)
+            })
+            .optional()?;
+        if has_groups.is_some() {
+            db::set_meta_value(&conn, db::META_SEARCH_INDEX_STALE, "1")?;
+        }
+    }
+    reload(&root, &conn)?;
+    Ok(())
+}
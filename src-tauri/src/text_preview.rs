@@ -0,0 +1,297 @@
+use crate::cache;
+use crate::db;
+use crate::file_type;
+use crate::library_root::LibraryRootState;
+use anyhow::{Context, Result};
+use encoding_rs::{Encoding, GBK, UTF_8};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use tauri::State;
+
+/// 预览最多读取的字节数；更大的文件只看开头
+const MAX_PREVIEW_BYTES: u64 = 16 * 1024 * 1024;
+/// 每页默认条数与上限（text 模式按行，table 模式按记录）
+const DEFAULT_PAGE_SIZE: usize = 500;
+const MAX_PAGE_SIZE: usize = 5000;
+/// table 模式每条记录最多保留的列数
+const MAX_TABLE_COLS: usize = 200;
+/// 建索引时每块最多的行数与字节数
+const CHUNK_LINES: usize = 20;
+const CHUNK_BYTES: usize = 4096;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextRow {
+    /// 记录起始行号（从 1 开始）；引号内换行会让一条记录跨多行
+    pub line: usize,
+    pub cells: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextPreviewResp {
+    pub file_id: String,
+    /// UTF-8 / UTF-16LE / UTF-16BE / GBK
+    pub encoding: String,
+    /// text：按行展示；table：按分隔符拆成单元格
+    pub mode: String,
+    /// table 模式使用的分隔符
+    pub delimiter: Option<String>,
+    /// 本页第一条的序号（从 0 开始）
+    pub offset: usize,
+    /// 总行数（text）或总记录数（table）
+    pub total: usize,
+    /// text 模式本页各行，行号为 offset+i+1
+    pub lines: Vec<String>,
+    pub rows: Vec<TextRow>,
+    /// table 模式本页最大列数
+    pub cols: usize,
+    /// 文件超过预览上限，只读了开头
+    pub truncated: bool,
+}
+
+/// 按 BOM → UTF-8 → GBK 的顺序解码，都不合法时按 UTF-8 容错；返回文本与编码名
+pub(crate) fn decode(bytes: &[u8]) -> (String, &'static str) {
+    if let Some((enc, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, _) = enc.decode_without_bom_handling(&bytes[bom_len..]);
+        return (text.into_owned(), enc.name());
+    }
+    if let Ok(s) = std::str::from_utf8(bytes) {
+        return (s.to_string(), UTF_8.name());
+    }
+    let (text, had_errors) = GBK.decode_without_bom_handling(bytes);
+    if !had_errors {
+        return (text.into_owned(), GBK.name());
+    }
+    (String::from_utf8_lossy(bytes).into_owned(), UTF_8.name())
+}
+
+/// 按前 40 行里各分隔符出现次数猜：制表符 > 逗号 > 分号
+pub(crate) fn sniff_delimiter(text: &str) -> char {
+    let sample = text.lines().take(40).collect::<Vec<_>>().join("\n");
+    let tab = sample.matches('\t').count();
+    let comma = sample.matches(',').count();
+    let semi = sample.matches(';').count();
+    if tab >= comma && tab >= semi {
+        '\t'
+    } else if comma >= semi {
+        ','
+    } else {
+        ';'
+    }
+}
+
+/// 按 CSV 规则拆记录：引号内的分隔符与换行不拆，"" 转义为 "。
+/// on 收到记录起始行号（从 1 开始）与各字段，返回 false 时停止
+pub(crate) fn parse_records(
+    text: &str,
+    delim: char,
+    on: &mut dyn FnMut(usize, Vec<String>) -> bool,
+) {
+    let mut line = 1;
+    let mut start_line = 1;
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                '\n' => {
+                    line += 1;
+                    field.push('\n');
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            // 只有字段开头的引号才起引用作用，字段中间的按原样保留
+            '"' if field.is_empty() => quoted = true,
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                if !on(start_line, std::mem::take(&mut row)) {
+                    return;
+                }
+                line += 1;
+                start_line = line;
+            }
+            c if c == delim => row.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        on(start_line, row);
+    }
+}
+
+/// 建索引用：按行切块，每块不超过 CHUNK_LINES 行或约 CHUNK_BYTES 字节；返回 (起始行, 结束行, 文本)
+pub(crate) fn chunk_lines(text: &str) -> Vec<(usize, usize, String)> {
+    let mut out = Vec::new();
+    let mut buf = String::new();
+    let mut start = 1;
+    let mut n = 0;
+    for (i, line) in text.lines().enumerate() {
+        if n == 0 {
+            start = i + 1;
+        } else {
+            buf.push('\n');
+        }
+        buf.push_str(line);
+        n += 1;
+        if n >= CHUNK_LINES || buf.len() >= CHUNK_BYTES {
+            // 全是空行的块不建索引
+            if !buf.trim().is_empty() {
+                out.push((start, start + n - 1, std::mem::take(&mut buf)));
+            }
+            buf.clear();
+            n = 0;
+        }
+    }
+    if n > 0 && !buf.trim().is_empty() {
+        out.push((start, start + n - 1, buf));
+    }
+    out
+}
+
+/// 读取预览内容；超过上限时截到最后一个完整行，避免切在多字节字符中间
+fn read_limited(path: &Path) -> Result<(Vec<u8>, bool)> {
+    let f = fs::File::open(path).with_context(|| format!("读取文件失败: {}", path.display()))?;
+    let len = f.metadata()?.len();
+    let mut bytes = Vec::new();
+    f.take(MAX_PREVIEW_BYTES).read_to_end(&mut bytes)?;
+    let truncated = len > MAX_PREVIEW_BYTES;
+    if truncated {
+        match Encoding::for_bom(&bytes) {
+            // UTF-16 的换行符字节可能出现在其他字符里，只保证按双字节对齐
+            Some((enc, _)) if enc != UTF_8 => bytes.truncate(bytes.len() & !1),
+            _ => {
+                if let Some(p) = bytes.iter().rposition(|b| *b == b'\n') {
+                    bytes.truncate(p + 1);
+                }
+            }
+        }
+    }
+    Ok((bytes, truncated))
+}
+
+/// 文本/表格预览：csv/tsv 默认按表格拆分，其余按行；line 给定时返回包含该行的一页
+#[tauri::command]
+pub fn get_text_preview(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    file_id: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    line: Option<usize>,
+    as_table: Option<bool>,
+) -> Result<TextPreviewResp, String> {
+    get_text_preview_impl(&app, &state, &file_id, offset, limit, line, as_table)
+        .map_err(db::err_to_string)
+}
+
+fn get_text_preview_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    file_id: &str,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    line: Option<usize>,
+    as_table: Option<bool>,
+) -> Result<TextPreviewResp> {
+    let (root, conn) = db::open_conn(app, state)?;
+    let display_name: String = conn
+        .query_row(
+            "SELECT display_name FROM attachments WHERE file_id=?",
+            [file_id],
+            |r| r.get(0),
+        )
+        .with_context(|| format!("找不到附件: {file_id}"))?;
+    let path = cache::ensure_cached_file(&root, &conn, file_id)?;
+    let (bytes, truncated) = read_limited(&path)?;
+    let (text, encoding) = decode(&bytes);
+    let ext = file_type::extension(&display_name);
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let page_of = |idx: usize| idx / limit * limit;
+
+    let mut resp = TextPreviewResp {
+        file_id: file_id.to_string(),
+        encoding: encoding.to_string(),
+        mode: "text".to_string(),
+        delimiter: None,
+        offset: 0,
+        total: 0,
+        lines: vec![],
+        rows: vec![],
+        cols: 0,
+        truncated,
+    };
+
+    if !as_table.unwrap_or(matches!(ext.as_str(), "csv" | "tsv" | "tab")) {
+        let lines = text.lines().collect::<Vec<_>>();
+        resp.total = lines.len();
+        resp.offset = match line {
+            Some(l) => page_of(l.max(1) - 1),
+            None => offset.unwrap_or(0),
+        }
+        .min(page_of(resp.total.saturating_sub(1)));
+        resp.lines = lines
+            .iter()
+            .skip(resp.offset)
+            .take(limit)
+            .map(|s| s.to_string())
+            .collect();
+        return Ok(resp);
+    }
+
+    let delim = if ext == "tsv" || ext == "tab" {
+        '\t'
+    } else {
+        sniff_delimiter(&text)
+    };
+    resp.mode = "table".to_string();
+    resp.delimiter = Some(delim.to_string());
+    // 空行不算记录；先定位目标行所在的记录序号
+    let is_blank = |cells: &[String]| cells.len() == 1 && cells[0].trim().is_empty();
+    let start = match line {
+        Some(target) => {
+            let mut idx = 0usize;
+            let mut seen = 0usize;
+            parse_records(&text, delim, &mut |l, cells| {
+                if l > target {
+                    return false;
+                }
+                if !is_blank(&cells) {
+                    idx = seen;
+                    seen += 1;
+                }
+                true
+            });
+            page_of(idx)
+        }
+        None => offset.unwrap_or(0),
+    };
+    let mut total = 0usize;
+    parse_records(&text, delim, &mut |l, mut cells| {
+        if is_blank(&cells) {
+            return true;
+        }
+        if total >= start && total < start + limit {
+            cells.truncate(MAX_TABLE_COLS);
+            resp.cols = resp.cols.max(cells.len());
+            resp.rows.push(TextRow { line: l, cells });
+        }
+        total += 1;
+        true
+    });
+    resp.total = total;
+    resp.offset = start;
+    Ok(resp)
+}
@@ -0,0 +1,494 @@
+use crate::attachment_text;
+use crate::cache;
+use crate::db;
+use crate::docx;
+use crate::excel_preview;
+use crate::library_root::{resolve_library_root, LibraryRootState};
+use crate::ocr;
+use anyhow::{anyhow, Context, Result};
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, Rgb, RgbImage};
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+/// 缩略图只按这几档生成，请求尺寸向上取整，避免同一文件缓存太多份
+const SIZE_BUCKETS: [u32; 3] = [128, 256, 512];
+const JPEG_QUALITY: u8 = 80;
+/// 表格缩略图取第一个 sheet 左上角的范围
+const SHEET_ROWS: usize = 14;
+const SHEET_COLS: usize = 6;
+/// docx 缩略图最多排版的行数（A4 比例下一页大约 40 行）
+const DOC_LINES: usize = 40;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThumbnailResp {
+    pub file_id: String,
+    /// 实际生成的尺寸档（长边像素）
+    pub size: u32,
+    /// ready：path 可直接显示；unsupported：该类型无法生成，前端自行降级
+    pub status: String,
+    pub path: Option<String>,
+    pub mime: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ThumbKind {
+    Image,
+    Pdf,
+    Excel,
+    Docx,
+    /// doc/wps/ofd：用抽取出的正文排版
+    Text,
+}
+
+#[tauri::command]
+pub async fn get_thumbnail(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    file_id: String,
+    size: Option<u32>,
+) -> Result<ThumbnailResp, String> {
+    // 首次生成需要解码图片/解析文档：放到阻塞线程池，避免列表滚动时卡住 UI
+    let root = resolve_library_root(&app, &state).map_err(db::err_to_string)?;
+    let app2 = app.clone();
+    tauri::async_runtime::spawn_blocking(move || get_thumbnail_impl(&app2, &root, &file_id, size))
+        .await
+        .map_err(|e| db::err_to_string(anyhow!(e).context("缩略图线程失败")))?
+        .map_err(db::err_to_string)
+}
+
+fn get_thumbnail_impl(
+    app: &tauri::AppHandle,
+    root: &Path,
+    file_id: &str,
+    size: Option<u32>,
+) -> Result<ThumbnailResp> {
+    db::init_db(app, root)?;
+    let conn = db::open_conn_at(root)?;
+    let size = bucket(size);
+
+    let (archive_id, file_type, display_name) = conn
+        .query_row(
+            "SELECT archive_id, file_type, display_name FROM attachments WHERE file_id=?",
+            [file_id],
+            |r| {
+                Ok((
+                    r.get::<_, String>(0)?,
+                    r.get::<_, String>(1)?,
+                    r.get::<_, String>(2)?,
+                ))
+            },
+        )
+        .optional()?
+        .ok_or_else(|| anyhow!("找不到附件: {file_id}"))?;
+
+    let unsupported = ThumbnailResp {
+        file_id: file_id.to_string(),
+        size,
+        status: "unsupported".to_string(),
+        path: None,
+        mime: None,
+    };
+    let Some(kind) = thumb_kind(&file_type, &display_name) else {
+        return Ok(unsupported);
+    };
+
+    let dir = root.join("cache").join(&archive_id).join(file_id);
+    for ext in ["jpg", "svg"] {
+        let p = dir.join(format!("thumb.{size}.{ext}"));
+        if p.exists() {
+            return Ok(ready(file_id, size, &p, ext));
+        }
+    }
+
+    let src = cache::ensure_cached_file(root, &conn, file_id)?;
+    let rendered = match kind {
+        ThumbKind::Image => {
+            let bytes =
+                fs::read(&src).with_context(|| format!("读取图片失败: {}", src.display()))?;
+            // HEIC、SVG 等解码不了的图片交给前端降级显示
+            match image::load_from_memory(&bytes) {
+                Ok(img) => Some(("jpg", encode_jpeg(&img, size)?)),
+                Err(_) => None,
+            }
+        }
+        ThumbKind::Pdf => {
+            let bytes =
+                fs::read(&src).with_context(|| format!("读取PDF失败: {}", src.display()))?;
+            match pdf_first_page(app, root, &bytes, size)? {
+                Some(img) => Some(("jpg", encode_jpeg(&img, size)?)),
+                None => {
+                    // 渲染不了的文字 PDF 给一张占位页；不按正式缩略图缓存，装上 PDFium 后即可重新生成
+                    fs::create_dir_all(&dir)?;
+                    let out = dir.join(format!("thumb.{size}.placeholder.svg"));
+                    if !out.exists() {
+                        fs::write(&out, placeholder_svg("PDF", size))?;
+                    }
+                    return Ok(ready(file_id, size, &out, "svg"));
+                }
+            }
+        }
+        ThumbKind::Excel => {
+            let (_sheet, cells) = excel_preview::first_sheet_window(
+                root,
+                &archive_id,
+                file_id,
+                &src,
+                SHEET_ROWS,
+                SHEET_COLS,
+            )?;
+            Some(("svg", sheet_svg(&cells, size).into_bytes()))
+        }
+        ThumbKind::Docx => {
+            let bytes =
+                fs::read(&src).with_context(|| format!("读取docx失败: {}", src.display()))?;
+            let xml = docx::read_docx_document_xml(&bytes)?;
+            let paragraphs =
+                docx::extract_paragraph_texts_ignore_tables_with_pagebreak(&xml, true)?;
+            Some(("svg", page_svg(&first_page(&paragraphs), size).into_bytes()))
+        }
+        ThumbKind::Text => {
+            let bytes =
+                fs::read(&src).with_context(|| format!("读取文档失败: {}", src.display()))?;
+            // OFD 只取第一页；doc/wps/文本没有分页信息，排满为止（文本块按行展开）
+            let lines = attachment_text::extract_blocks(&file_type, &bytes)?
+                .into_iter()
+                .filter(|b| b.page.unwrap_or(1) == 1)
+                .flat_map(|b| b.text.split('\n').map(str::to_string).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            Some(("svg", page_svg(&lines, size).into_bytes()))
+        }
+    };
+    let Some((ext, data)) = rendered else {
+        return Ok(unsupported);
+    };
+
+    // 先写临时文件再改名：并发请求同一缩略图时不会读到半个文件
+    fs::create_dir_all(&dir)?;
+    let out = dir.join(format!("thumb.{size}.{ext}"));
+    let tmp = dir.join(format!("thumb.{size}.{ext}.{}.tmp", uuid::Uuid::new_v4()));
+    fs::write(&tmp, data)?;
+    fs::rename(&tmp, &out)?;
+    Ok(ready(file_id, size, &out, ext))
+}
+
+fn ready(file_id: &str, size: u32, path: &Path, ext: &str) -> ThumbnailResp {
+    ThumbnailResp {
+        file_id: file_id.to_string(),
+        size,
+        status: "ready".to_string(),
+        path: Some(path.to_string_lossy().to_string()),
+        mime: Some(
+            match ext {
+                "svg" => "image/svg+xml",
+                _ => "image/jpeg",
+            }
+            .to_string(),
+        ),
+    }
+}
+
+fn bucket(size: Option<u32>) -> u32 {
+    let want = size.unwrap_or(SIZE_BUCKETS[1]);
+    SIZE_BUCKETS
+        .iter()
+        .copied()
+        .find(|b| *b >= want)
+        .unwrap_or(SIZE_BUCKETS[SIZE_BUCKETS.len() - 1])
+}
+
+fn thumb_kind(file_type: &str, display_name: &str) -> Option<ThumbKind> {
+    let lower = display_name.to_ascii_lowercase();
+    match file_type {
+        "image" => Some(ThumbKind::Image),
+        "pdf" => Some(ThumbKind::Pdf),
+        "excel" => Some(ThumbKind::Excel),
+        _ if lower.ends_with(".docx") => Some(ThumbKind::Docx),
+        t if attachment_text::is_text_type(t) => Some(ThumbKind::Text),
+        _ => None,
+    }
+}
+
+/// 缩放到长边不超过 size，透明区域铺白底后编码成 JPEG
+fn encode_jpeg(img: &DynamicImage, size: u32) -> Result<Vec<u8>> {
+    let small = if img.width() > size || img.height() > size {
+        img.thumbnail(size, size)
+    } else {
+        img.clone()
+    };
+    let rgba = small.to_rgba8();
+    let rgb = RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let a = a as u32;
+        let mix = |c: u8| ((c as u32 * a + 255 * (255 - a)) / 255) as u8;
+        Rgb([mix(r), mix(g), mix(b)])
+    });
+    let mut out = Vec::new();
+    JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY)
+        .encode_image(&rgb)
+        .context("编码缩略图失败")?;
+    Ok(out)
+}
+
+/// 用 PDFium 渲染第一页（需 pdfium feature）；渲染不了时退回扫描件 PDF 的第一张整页图片，文字 PDF 返回 None
+fn pdf_first_page(
+    app: &tauri::AppHandle,
+    root: &Path,
+    bytes: &[u8],
+    size: u32,
+) -> Result<Option<DynamicImage>> {
+    if let Some(img) = pdf_engine::render_first_page(&pdfium_dirs(app, root), bytes, size)? {
+        return Ok(Some(img));
+    }
+    match ocr::pdf_scanned_images(bytes).into_iter().next() {
+        Some(jpeg) => Ok(Some(
+            image::load_from_memory(&jpeg).context("解码PDF扫描页失败")?,
+        )),
+        None => Ok(None),
+    }
+}
+
+/// 打包的 pdfium 动态库优先，其次库目录下 pdfium/
+fn pdfium_dirs(app: &tauri::AppHandle, root: &Path) -> Vec<PathBuf> {
+    use tauri::Manager;
+    let bundled = app.path().resource_dir().ok().map(|d| d.join("pdfium"));
+    let local = Some(root.join("pdfium"));
+    [bundled, local]
+        .into_iter()
+        .flatten()
+        .filter(|p| p.is_dir())
+        .collect()
+}
+
+#[cfg(feature = "pdfium")]
+mod pdf_engine {
+    use anyhow::{anyhow, Result};
+    use image::DynamicImage;
+    use pdfium_render::prelude::*;
+    use std::path::PathBuf;
+
+    pub fn render_first_page(
+        lib_dirs: &[PathBuf],
+        bytes: &[u8],
+        size: u32,
+    ) -> Result<Option<DynamicImage>> {
+        let bindings = lib_dirs
+            .iter()
+            .find_map(|d| {
+                Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path(d)).ok()
+            })
+            .map(Ok)
+            .unwrap_or_else(Pdfium::bind_to_system_library);
+        // 找不到动态库不算错误，交给扫描件降级
+        let Ok(bindings) = bindings else {
+            return Ok(None);
+        };
+        let pdfium = Pdfium::new(bindings);
+        let doc = pdfium
+            .load_pdf_from_byte_slice(bytes, None)
+            .map_err(|e| anyhow!("打开PDF失败: {e:?}"))?;
+        let page = doc
+            .pages()
+            .first()
+            .map_err(|e| anyhow!("PDF没有页面: {e:?}"))?;
+        let config = PdfRenderConfig::new()
+            .set_target_width(size as i32)
+            .set_maximum_height(size as i32);
+        let bitmap = page
+            .render_with_config(&config)
+            .map_err(|e| anyhow!("渲染PDF失败: {e:?}"))?;
+        Ok(Some(bitmap.as_image()))
+    }
+}
+
+#[cfg(not(feature = "pdfium"))]
+mod pdf_engine {
+    use anyhow::Result;
+    use image::DynamicImage;
+    use std::path::PathBuf;
+
+    pub fn render_first_page(
+        _lib_dirs: &[PathBuf],
+        _bytes: &[u8],
+        _size: u32,
+    ) -> Result<Option<DynamicImage>> {
+        Ok(None)
+    }
+}
+
+/// 取到第一个分页符为止（分页符由段落提取时标成 \u{000C}）
+fn first_page(paragraphs: &[String]) -> Vec<String> {
+    let mut out = Vec::new();
+    for p in paragraphs {
+        match p.split_once('\u{000C}') {
+            Some((head, _)) => {
+                if !head.trim().is_empty() {
+                    out.push(head.to_string());
+                }
+                break;
+            }
+            None => out.push(p.clone()),
+        }
+    }
+    out
+}
+
+/// 按 A4 比例把首页段落排成文字行（不处理字体样式，只求版面近似）
+fn page_svg(paragraphs: &[String], size: u32) -> String {
+    const W: f64 = 210.0;
+    const H: f64 = 297.0;
+    const MARGIN: f64 = 18.0;
+    const LINE_H: f64 = 6.4;
+    const FONT: f64 = 4.2;
+    // 每行可容纳的“半角宽度”
+    let cap = ((W - 2.0 * MARGIN) / (FONT / 2.0)) as usize;
+
+    let mut lines = Vec::new();
+    'outer: for p in paragraphs {
+        let p = p.trim();
+        if p.is_empty() {
+            lines.push(String::new());
+        }
+        let mut cur = String::new();
+        let mut width = 0usize;
+        for ch in p.chars() {
+            let w = if ch.is_ascii() { 1 } else { 2 };
+            if width + w > cap {
+                lines.push(std::mem::take(&mut cur));
+                width = 0;
+                if lines.len() >= DOC_LINES {
+                    break 'outer;
+                }
+            }
+            cur.push(ch);
+            width += w;
+        }
+        if !cur.is_empty() {
+            lines.push(cur);
+        }
+        if lines.len() >= DOC_LINES {
+            break;
+        }
+    }
+    lines.truncate(DOC_LINES);
+
+    let (pw, ph) = fit(W, H, size);
+    let mut svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{pw}" height="{ph}" viewBox="0 0 {W} {H}"><rect width="{W}" height="{H}" fill="#ffffff"/><g font-family="sans-serif" font-size="{FONT}" fill="#333333">"##
+    );
+    for (i, line) in lines.iter().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        let y = MARGIN + LINE_H * (i as f64 + 1.0);
+        svg.push_str(&format!(
+            r#"<text x="{MARGIN}" y="{y:.1}">{}</text>"#,
+            xml_escape(line)
+        ));
+    }
+    svg.push_str("</g></svg>");
+    svg
+}
+
+/// 空白 A4 页加类型标签，用于无法渲染内容的文档
+fn placeholder_svg(label: &str, size: u32) -> String {
+    const W: f64 = 210.0;
+    const H: f64 = 297.0;
+    let (pw, ph) = fit(W, H, size);
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{pw}" height="{ph}" viewBox="0 0 {W} {H}"><rect width="{W}" height="{H}" fill="#ffffff"/><rect x="55" y="118" width="100" height="40" rx="6" fill="#d93025"/><text x="105" y="146" font-family="sans-serif" font-size="24" font-weight="bold" fill="#ffffff" text-anchor="middle">{}</text></svg>"##,
+        xml_escape(label)
+    )
+}
+
+/// 表格左上角画成网格，首行加底色当作表头
+fn sheet_svg(cells: &[Vec<String>], size: u32) -> String {
+    const COL_W: f64 = 40.0;
+    const ROW_H: f64 = 12.0;
+    const FONT: f64 = 7.0;
+    let rows = cells.len().max(1);
+    let cols = cells.iter().map(|r| r.len()).max().unwrap_or(0).max(1);
+    let w = COL_W * cols as f64;
+    let h = ROW_H * rows as f64;
+    let (pw, ph) = fit(w, h, size);
+    // 每格可容纳的“半角宽度”
+    let cap = ((COL_W - 4.0) / (FONT / 2.0)) as usize;
+
+    let mut svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{pw}" height="{ph}" viewBox="0 0 {w} {h}"><rect width="{w}" height="{h}" fill="#ffffff"/><rect width="{w}" height="{ROW_H}" fill="#eef2f7"/><g stroke="#d0d7de" stroke-width="0.5">"##
+    );
+    for r in 0..=rows {
+        let y = ROW_H * r as f64;
+        svg.push_str(&format!(r#"<line x1="0" y1="{y}" x2="{w}" y2="{y}"/>"#));
+    }
+    for c in 0..=cols {
+        let x = COL_W * c as f64;
+        svg.push_str(&format!(r#"<line x1="{x}" y1="0" x2="{x}" y2="{h}"/>"#));
+    }
+    svg.push_str(&format!(
+        r##"</g><g font-family="sans-serif" font-size="{FONT}" fill="#333333">"##
+    ));
+    for (r, row) in cells.iter().enumerate() {
+        for (c, v) in row.iter().enumerate() {
+            let v = v.trim();
+            if v.is_empty() {
+                continue;
+            }
+            let x = COL_W * c as f64 + 2.0;
+            let y = ROW_H * r as f64 + ROW_H - 3.5;
+            svg.push_str(&format!(
+                r#"<text x="{x}" y="{y}">{}</text>"#,
+                xml_escape(&clip(v, cap))
+            ));
+        }
+    }
+    svg.push_str("</g></svg>");
+    svg
+}
+
+/// 长边缩放到 size，保持比例
+fn fit(w: f64, h: f64, size: u32) -> (u32, u32) {
+    let scale = size as f64 / w.max(h);
+    (
+        ((w * scale).round() as u32).max(1),
+        ((h * scale).round() as u32).max(1),
+    )
+}
+
+fn clip(s: &str, cap: usize) -> String {
+    let mut out = String::new();
+    let mut width = 0usize;
+    for ch in s.chars() {
+        if ch == '\n' || ch == '\r' {
+            break;
+        }
+        let w = if ch.is_ascii() { 1 } else { 2 };
+        if width + w > cap {
+            out.push('…');
+            break;
+        }
+        out.push(ch);
+        width += w;
+    }
+    out
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\t' => out.push(' '),
+            // XML 1.0 不允许的控制字符
+            c if (c as u32) < 0x20 => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
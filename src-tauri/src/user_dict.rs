@@ -0,0 +1,190 @@
+use crate::db;
+use crate::library_root::LibraryRootState;
+use crate::search;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+const USER_DICT_FILE: &str = "user_dict.txt";
+const DEFAULT_FREQ: usize = 2000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDictWord {
+    pub word: String,
+    pub freq: usize,
+    pub pos: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDictResp {
+    pub words: Vec<UserDictWord>,
+    /// 词典变化后旧的 search_text 仍按旧分词结果建立，需要重建索引
+    pub needs_rebuild: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddUserDictWordReq {
+    pub word: String,
+    pub freq: Option<usize>,
+    pub pos: Option<String>,
+}
+
+/// 每个库一份词典：<库目录>/index/user_dict.txt（jieba 格式：词 词频 词性）
+pub fn user_dict_path(root: &Path) -> PathBuf {
+    root.join("index").join(USER_DICT_FILE)
+}
+
+pub fn read_user_dict(root: &Path) -> Result<Vec<UserDictWord>> {
+    let p = user_dict_path(root);
+    if !p.exists() {
+        return Ok(vec![]);
+    }
+    let text =
+        fs::read_to_string(&p).with_context(|| format!("读取用户词典失败: {}", p.display()))?;
+    let mut out: Vec<UserDictWord> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim().trim_start_matches('\u{FEFF}');
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        let Some(word) = parts.next() else {
+            continue;
+        };
+        let freq = parts
+            .next()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_FREQ);
+        let pos = parts.next().map(|s| s.to_string());
+        out.retain(|w| w.word != word);
+        out.push(UserDictWord {
+            word: word.to_string(),
+            freq,
+            pos,
+        });
+    }
+    Ok(out)
+}
+
+fn write_user_dict(root: &Path, words: &[UserDictWord]) -> Result<()> {
+    let p = user_dict_path(root);
+    fs::create_dir_all(p.parent().unwrap())?;
+    let mut text = String::new();
+    for w in words {
+        text.push_str(&w.word);
+        text.push(' ');
+        text.push_str(&w.freq.to_string());
+        if let Some(pos) = &w.pos {
+            text.push(' ');
+            text.push_str(pos);
+        }
+        text.push('\n');
+    }
+    fs::write(&p, text).with_context(|| format!("写入用户词典失败: {}", p.display()))?;
+    Ok(())
+}
+
+fn needs_rebuild(root: &Path) -> Result<bool> {
+    let conn = rusqlite::Connection::open(root.join("db.sqlite"))?;
+    Ok(db::get_meta_value(&conn, db::META_SEARCH_INDEX_STALE)?.as_deref() == Some("1"))
+}
+
+fn after_change(root: &Path) -> Result<UserDictResp> {
+    let conn = rusqlite::Connection::open(root.join("db.sqlite"))?;
+    db::set_meta_value(&conn, db::META_SEARCH_INDEX_STALE, "1")?;
+    search::reload_tokenizer(root)?;
+    Ok(UserDictResp {
+        words: read_user_dict(root)?,
+        needs_rebuild: true,
+    })
+}
+
+#[tauri::command]
+pub fn list_user_dict_words(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+) -> Result<UserDictResp, String> {
+    list_user_dict_words_impl(&app, &state).map_err(db::err_to_string)
+}
+
+fn list_user_dict_words_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+) -> Result<UserDictResp> {
+    let (root, _conn) = db::open_conn(app, state)?;
+    Ok(UserDictResp {
+        words: read_user_dict(&root)?,
+        needs_rebuild: needs_rebuild(&root)?,
+    })
+}
+
+#[tauri::command]
+pub fn add_user_dict_word(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    req: AddUserDictWordReq,
+) -> Result<UserDictResp, String> {
+    add_user_dict_word_impl(&app, &state, req).map_err(db::err_to_string)
+}
+
+fn add_user_dict_word_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    req: AddUserDictWordReq,
+) -> Result<UserDictResp> {
+    let word = req.word.trim().to_string();
+    if word.is_empty() {
+        return Err(anyhow!("词条不能为空"));
+    }
+    if word.chars().any(|c| c.is_whitespace()) {
+        return Err(anyhow!("词条不能包含空白字符"));
+    }
+    let pos = req
+        .pos
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty());
+    if pos
+        .as_deref()
+        .map(|p| p.contains(char::is_whitespace))
+        .unwrap_or(false)
+    {
+        return Err(anyhow!("词性不能包含空白字符"));
+    }
+    let (root, _conn) = db::open_conn(app, state)?;
+    let mut words = read_user_dict(&root)?;
+    words.retain(|w| w.word != word);
+    words.push(UserDictWord {
+        word,
+        freq: req.freq.unwrap_or(DEFAULT_FREQ).max(1),
+        pos,
+    });
+    write_user_dict(&root, &words)?;
+    after_change(&root)
+}
+
+#[tauri::command]
+pub fn remove_user_dict_word(
+    app: tauri::AppHandle,
+    state: State<'_, LibraryRootState>,
+    word: String,
+) -> Result<UserDictResp, String> {
+    remove_user_dict_word_impl(&app, &state, &word).map_err(db::err_to_string)
+}
+
+fn remove_user_dict_word_impl(
+    app: &tauri::AppHandle,
+    state: &LibraryRootState,
+    word: &str,
+) -> Result<UserDictResp> {
+    let (root, _conn) = db::open_conn(app, state)?;
+    let mut words = read_user_dict(&root)?;
+    let before = words.len();
+    words.retain(|w| w.word != word.trim());
+    if words.len() == before {
+        return Err(anyhow!("词典中没有该词条: {word}"));
+    }
+    write_user_dict(&root, &words)?;
+    after_change(&root)
+}
@@ -0,0 +1,833 @@
+// xlsx 直读：calamine 只给值，这里补上数字格式、公式、合并区域、行列尺寸/隐藏与基础样式。
+// sheet.xml 流式读取，只构造请求窗口内的单元格；窗口之后的行整行跳过，合并区域在 sheetData 之后仍需读到。
+
+use crate::docx;
+use crate::excel_format;
+use crate::excel_preview::{CellKind, CellStyle, ExcelCell, ExcelCellsResp, MergedRange};
+use anyhow::{anyhow, Context, Result};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::QName;
+use quick_xml::Reader as XmlReader;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Seek};
+use std::ops::Range;
+use zip::ZipArchive;
+
+/// 列宽（字符数）-> 像素，按默认字体最大数字宽 7px
+const PX_PER_CHAR: f64 = 7.0;
+/// 行高磅 -> 像素（96dpi）
+const PX_PER_PT: f64 = 96.0 / 72.0;
+
+#[derive(Debug, Clone)]
+pub struct XlsxSheetRef {
+    pub name: String,
+    /// 包内路径，如 xl/worksheets/sheet1.xml
+    pub path: String,
+    pub hidden: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct CellXf {
+    num_fmt: Option<String>,
+    style: CellStyle,
+}
+
+#[derive(Debug, Default)]
+pub struct XlsxBook {
+    pub sheets: Vec<XlsxSheetRef>,
+    pub date1904: bool,
+    shared: Vec<String>,
+    xfs: Vec<CellXf>,
+}
+
+impl XlsxBook {
+    /// 读取工作簿结构、共享字符串与样式表；不是 xlsx（如 ods）时报错
+    pub fn open<R: Read + Seek>(zip: &mut ZipArchive<R>) -> Result<Self> {
+        let workbook_xml =
+            read_zip_text(zip, "xl/workbook.xml").context("xlsx缺少 xl/workbook.xml")?;
+        let rels = read_zip_text(zip, "xl/_rels/workbook.xml.rels")
+            .ok()
+            .map(|x| docx::parse_docx_relationships(&x))
+            .transpose()?
+            .unwrap_or_default();
+        let (sheets, date1904) = parse_workbook(&workbook_xml, &rels)?;
+        let shared = match read_zip_text(zip, "xl/sharedStrings.xml") {
+            Ok(x) => parse_shared_strings(&x)?,
+            Err(_) => Vec::new(),
+        };
+        let xfs = match read_zip_text(zip, "xl/styles.xml") {
+            Ok(x) => parse_styles(&x)?,
+            Err(_) => Vec::new(),
+        };
+        Ok(Self {
+            sheets,
+            date1904,
+            shared,
+            xfs,
+        })
+    }
+
+    pub fn sheet(&self, name: &str) -> Result<&XlsxSheetRef> {
+        self.sheets
+            .iter()
+            .find(|s| s.name == name)
+            .ok_or_else(|| anyhow!("找不到sheet: {name}"))
+    }
+
+    /// 读取 [rows) x [cols) 窗口（0 起、行列均为绝对位置）
+    pub fn read_window<R: Read + Seek>(
+        &self,
+        zip: &mut ZipArchive<R>,
+        sheet_name: &str,
+        rows: Range<usize>,
+        cols: Range<usize>,
+    ) -> Result<ExcelCellsResp> {
+        let blank = vec![vec![ExcelCell::default(); cols.len()]; rows.len()];
+        let mut resp = ExcelCellsResp::plain(rows.start, cols.start, blank);
+        let mut styles = StyleTable::default();
+        self.walk_sheet(
+            zip,
+            sheet_name,
+            rows.clone(),
+            cols.clone(),
+            &mut |item| match item {
+                SheetItem::Cols {
+                    first,
+                    last,
+                    width,
+                    hidden,
+                } => {
+                    for c in first.max(cols.start)..(last + 1).min(cols.end) {
+                        if width.is_some() {
+                            resp.col_widths[c - cols.start] = width;
+                        }
+                        if hidden {
+                            resp.hidden_cols.push(c);
+                        }
+                    }
+                }
+                SheetItem::Row {
+                    row,
+                    height,
+                    hidden,
+                } => {
+                    if rows.contains(&row) {
+                        resp.row_heights[row - rows.start] = height;
+                        if hidden {
+                            resp.hidden_rows.push(row);
+                        }
+                    }
+                }
+                SheetItem::Cell {
+                    row,
+                    col,
+                    mut cell,
+                    xf,
+                } => {
+                    cell.style = styles.index(self, xf);
+                    resp.cells[row - rows.start][col - cols.start] = cell;
+                }
+                SheetItem::Merge(m) => {
+                    if m.first_row < rows.end
+                        && m.last_row >= rows.start
+                        && m.first_col < cols.end
+                        && m.last_col >= cols.start
+                    {
+                        resp.merges.push(m);
+                    }
+                }
+            },
+        )?;
+        resp.styles = styles.styles;
+        Ok(resp)
+    }
+
+    /// 流式遍历 sheet.xml（不整份读入内存）；只为 [rows) x [cols) 内的单元格构造值，
+    /// rows 之后的行整行跳过，合并区域在 sheetData 之后仍会给出
+    pub(crate) fn walk_sheet<R: Read + Seek>(
+        &self,
+        zip: &mut ZipArchive<R>,
+        sheet_name: &str,
+        rows: Range<usize>,
+        cols: Range<usize>,
+        on: &mut dyn FnMut(SheetItem),
+    ) -> Result<()> {
+        let sheet = self.sheet(sheet_name)?;
+        let f = zip
+            .by_name(&sheet.path)
+            .with_context(|| format!("读取sheet.xml失败: {}", sheet.path))?;
+        let mut reader = XmlReader::from_reader(BufReader::new(f));
+        reader.config_mut().trim_text(false);
+        self.walk(&mut reader, rows, cols, on)
+    }
+
+    fn walk<B: BufRead>(
+        &self,
+        reader: &mut XmlReader<B>,
+        rows: Range<usize>,
+        cols: Range<usize>,
+        on: &mut dyn FnMut(SheetItem),
+    ) -> Result<()> {
+        let mut buf = Vec::new();
+        let mut skip = Vec::new();
+        // 共享公式：si -> (主单元格位置, 公式)
+        let mut shared_formulas: HashMap<String, ((usize, usize), String)> = HashMap::new();
+        let mut row: Option<usize> = None;
+        let mut next_col = 0usize;
+
+        loop {
+            buf.clear();
+            let (e, is_start) = match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(e)) => (e, true),
+                Ok(Event::Empty(e)) => (e, false),
+                Ok(Event::Eof) => break,
+                Err(e) => return Err(anyhow!("XML解析失败: {e:?}")),
+                _ => continue,
+            };
+            match e.local_name().as_ref() {
+                b"col" => {
+                    let first = attr_usize(&e, b"min").unwrap_or(1).max(1) - 1;
+                    let last = attr_usize(&e, b"max").unwrap_or(first + 1).max(first + 1) - 1;
+                    on(SheetItem::Cols {
+                        first,
+                        last,
+                        width: attr(&e, b"width")
+                            .and_then(|v| v.parse::<f64>().ok())
+                            .map(|w| (w * PX_PER_CHAR).round()),
+                        hidden: attr_bool(&e, b"hidden"),
+                    });
+                }
+                b"row" => {
+                    let r = attr_usize(&e, b"r")
+                        .map(|r| r.saturating_sub(1))
+                        .unwrap_or_else(|| row.map(|r| r + 1).unwrap_or(0));
+                    row = Some(r);
+                    next_col = 0;
+                    let hidden = attr_bool(&e, b"hidden");
+                    let height = if attr_bool(&e, b"customHeight") || hidden {
+                        attr(&e, b"ht")
+                            .and_then(|v| v.parse::<f64>().ok())
+                            .map(|pt| (pt * PX_PER_PT).round())
+                    } else {
+                        None
+                    };
+                    on(SheetItem::Row {
+                        row: r,
+                        height,
+                        hidden,
+                    });
+                    if r >= rows.end && is_start {
+                        // 窗口之后的行不再解析单元格
+                        let name = e.name().as_ref().to_vec();
+                        skip.clear();
+                        reader.read_to_end_into(QName(&name), &mut skip)?;
+                    }
+                }
+                b"c" => {
+                    let r = row.unwrap_or(0);
+                    let c = attr(&e, b"r")
+                        .and_then(|v| parse_cell_ref(&v))
+                        .map(|(_, c)| c)
+                        .unwrap_or(next_col);
+                    next_col = c + 1;
+                    let mut raw = RawCell {
+                        t: attr(&e, b"t"),
+                        s: attr_usize(&e, b"s").unwrap_or(0),
+                        ..Default::default()
+                    };
+                    if is_start {
+                        read_cell_body(reader, &mut raw)?;
+                    }
+                    if let Some(f) = &raw.formula {
+                        if f.shared_index.is_some() && !f.text.is_empty() {
+                            let si = f.shared_index.clone().unwrap_or_default();
+                            shared_formulas.insert(si, ((r, c), f.text.clone()));
+                        }
+                    }
+                    if rows.contains(&r) && cols.contains(&c) {
+                        let formula = raw.formula.as_ref().and_then(|f| {
+                            if !f.text.is_empty() {
+                                return Some(f.text.clone());
+                            }
+                            let si = f.shared_index.as_ref()?;
+                            let ((mr, mc), text) = shared_formulas.get(si)?;
+                            Some(shift_formula(
+                                text,
+                                r as i64 - *mr as i64,
+                                c as i64 - *mc as i64,
+                            ))
+                        });
+                        let mut cell = self.build_cell(&raw);
+                        cell.formula = formula;
+                        on(SheetItem::Cell {
+                            row: r,
+                            col: c,
+                            cell,
+                            xf: raw.s,
+                        });
+                    }
+                }
+                b"mergeCell" => {
+                    if let Some(m) = attr(&e, b"ref").and_then(|v| parse_range(&v)) {
+                        on(SheetItem::Merge(m));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn build_cell(&self, raw: &RawCell) -> ExcelCell {
+        let v = raw.v.clone().unwrap_or_default();
+        match raw.t.as_deref() {
+            Some("s") => {
+                let s = v
+                    .trim()
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|i| self.shared.get(i).cloned())
+                    .unwrap_or_default();
+                ExcelCell::text(s)
+            }
+            Some("inlineStr") => ExcelCell::text(raw.inline.clone().unwrap_or_default()),
+            Some("str") => ExcelCell::text(v),
+            Some("b") => ExcelCell::bool(v.trim() == "1" || v.trim().eq_ignore_ascii_case("true")),
+            Some("e") => ExcelCell::error(v),
+            Some("d") => ExcelCell {
+                kind: CellKind::Date,
+                value: serde_json::Value::String(v.clone()),
+                display: v.replace('T', " "),
+                ..Default::default()
+            },
+            _ => {
+                if raw.v.is_none() {
+                    return ExcelCell::default();
+                }
+                let Ok(n) = v.trim().parse::<f64>() else {
+                    return ExcelCell::text(v);
+                };
+                let fmt = self
+                    .xfs
+                    .get(raw.s)
+                    .and_then(|x| x.num_fmt.as_deref())
+                    .unwrap_or("General");
+                let display = excel_format::format_value(n, fmt, self.date1904);
+                if excel_format::is_date_format(fmt) {
+                    if let Some(iso) = excel_format::serial_to_iso(n, self.date1904) {
+                        return ExcelCell {
+                            kind: CellKind::Date,
+                            value: serde_json::Value::String(iso),
+                            display,
+                            ..Default::default()
+                        };
+                    }
+                }
+                ExcelCell {
+                    kind: CellKind::Number,
+                    value: serde_json::json!(n),
+                    display,
+                    ..Default::default()
+                }
+            }
+        }
+    }
+}
+
+/// sheet.xml 中与显示有关的内容，按文档顺序给出
+pub(crate) enum SheetItem {
+    /// 列宽（像素）与隐藏，作用于 [first, last] 列
+    Cols {
+        first: usize,
+        last: usize,
+        width: Option<f64>,
+        hidden: bool,
+    },
+    /// 行高（像素，仅自定义行高）与隐藏
+    Row {
+        row: usize,
+        height: Option<f64>,
+        hidden: bool,
+    },
+    /// 值已按数字格式构造好；style 未填，xf 为 cellXfs 下标
+    Cell {
+        row: usize,
+        col: usize,
+        cell: ExcelCell,
+        xf: usize,
+    },
+    Merge(MergedRange),
+}
+
+/// 样式去重表：默认样式不下发，其余样式只出现一次
+#[derive(Debug, Default)]
+pub(crate) struct StyleTable {
+    pub(crate) styles: Vec<CellStyle>,
+    map: HashMap<usize, usize>,
+}
+
+impl StyleTable {
+    pub(crate) fn index(&mut self, book: &XlsxBook, xf_id: usize) -> Option<usize> {
+        let xf = book.xfs.get(xf_id)?;
+        if xf.style == CellStyle::default() {
+            return None;
+        }
+        let styles = &mut self.styles;
+        Some(*self.map.entry(xf_id).or_insert_with(|| {
+            styles.push(xf.style.clone());
+            styles.len() - 1
+        }))
+    }
+}
+
+#[derive(Debug, Default)]
+struct RawCell {
+    t: Option<String>,
+    s: usize,
+    v: Option<String>,
+    inline: Option<String>,
+    formula: Option<RawFormula>,
+}
+
+#[derive(Debug, Default)]
+struct RawFormula {
+    text: String,
+    /// t="shared" 时的 si
+    shared_index: Option<String>,
+}
+
+fn read_cell_body<B: BufRead>(reader: &mut XmlReader<B>, raw: &mut RawCell) -> Result<()> {
+    let mut buf = Vec::new();
+    let mut in_v = false;
+    let mut in_f = false;
+    let mut in_t = false;
+    let mut in_rph = false;
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => match e.local_name().as_ref() {
+                b"v" => in_v = true,
+                b"f" => {
+                    in_f = true;
+                    raw.formula = Some(formula_head(&e));
+                }
+                b"is" => raw.inline = Some(String::new()),
+                b"t" => in_t = true,
+                b"rPh" => in_rph = true,
+                _ => {}
+            },
+            Ok(Event::Empty(e)) => {
+                if e.local_name().as_ref() == b"f" {
+                    raw.formula = Some(formula_head(&e));
+                }
+            }
+            Ok(Event::Text(t)) => {
+                let s = t.unescape()?;
+                if in_v {
+                    raw.v.get_or_insert_with(String::new).push_str(&s);
+                } else if in_f {
+                    if let Some(f) = raw.formula.as_mut() {
+                        f.text.push_str(&s);
+                    }
+                } else if in_t && !in_rph {
+                    if let Some(inline) = raw.inline.as_mut() {
+                        inline.push_str(&s);
+                    }
+                }
+            }
+            Ok(Event::End(e)) => match e.local_name().as_ref() {
+                b"c" => break,
+                b"v" => in_v = false,
+                b"f" => in_f = false,
+                b"t" => in_t = false,
+                b"rPh" => in_rph = false,
+                _ => {}
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(anyhow!("XML解析失败: {e:?}")),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn formula_head(e: &BytesStart) -> RawFormula {
+    RawFormula {
+        text: String::new(),
+        shared_index: (attr(e, b"t").as_deref() == Some("shared"))
+            .then(|| attr(e, b"si"))
+            .flatten(),
+    }
+}
+
+/// 共享公式从主单元格平移到当前单元格：相对引用按行列差移动，$ 绝对引用不动
+fn shift_formula(formula: &str, dr: i64, dc: i64) -> String {
+    let chars: Vec<char> = formula.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        // 字符串常量与带引号的表名原样保留
+        if c == '"' || c == '\'' {
+            let end = chars[i + 1..]
+                .iter()
+                .position(|x| *x == c)
+                .map(|p| i + 1 + p)
+                .unwrap_or(chars.len() - 1);
+            out.extend(&chars[i..=end]);
+            i = end + 1;
+            continue;
+        }
+        let boundary =
+            i == 0 || !(chars[i - 1].is_alphanumeric() || matches!(chars[i - 1], '_' | '.'));
+        if boundary && (c == '$' || c.is_ascii_uppercase()) {
+            if let Some((len, shifted)) = shift_ref(&chars[i..], dr, dc) {
+                out.push_str(&shifted);
+                i += len;
+                continue;
+            }
+        }
+        out.push(c);
+        i += 1;
+    }
+    out
+}
+
+/// 识别开头的 A1 引用，返回 (消耗字符数, 平移后的引用)
+fn shift_ref(s: &[char], dr: i64, dc: i64) -> Option<(usize, String)> {
+    let mut i = 0;
+    let col_abs = s.first() == Some(&'$');
+    if col_abs {
+        i += 1;
+    }
+    let col_start = i;
+    while i < s.len() && s[i].is_ascii_uppercase() && i - col_start < 3 {
+        i += 1;
+    }
+    let col: String = s[col_start..i].iter().collect();
+    if col.is_empty() {
+        return None;
+    }
+    let row_abs = s.get(i) == Some(&'$');
+    if row_abs {
+        i += 1;
+    }
+    let row_start = i;
+    while i < s.len() && s[i].is_ascii_digit() {
+        i += 1;
+    }
+    let row: String = s[row_start..i].iter().collect();
+    // 后面跟字母/数字/( 的是函数名或名称，不是引用
+    if row.is_empty()
+        || s.get(i)
+            .map(|c| c.is_alphanumeric() || *c == '(' || *c == '_')
+            .unwrap_or(false)
+    {
+        return None;
+    }
+    let (r0, c0) = parse_cell_ref(&format!("{col}{row}"))?;
+    let r = if row_abs { r0 as i64 } else { r0 as i64 + dr };
+    let c = if col_abs { c0 as i64 } else { c0 as i64 + dc };
+    if r < 0 || c < 0 {
+        return None;
+    }
+    Some((
+        i,
+        format!(
+            "{}{}{}{}",
+            if col_abs { "$" } else { "" },
+            col_name(c as usize),
+            if row_abs { "$" } else { "" },
+            r + 1
+        ),
+    ))
+}
+
+/// 0 起列号 -> "A"/"AB"
+pub(crate) fn col_name(col: usize) -> String {
+    let mut n = col + 1;
+    let mut s = Vec::new();
+    while n > 0 {
+        let r = (n - 1) % 26;
+        s.push((b'A' + r as u8) as char);
+        n = (n - 1) / 26;
+    }
+    s.iter().rev().collect()
+}
+
+/// "BC12" -> (11, 54)，0 起
+pub(crate) fn parse_cell_ref(s: &str) -> Option<(usize, usize)> {
+    let s = s.replace('$', "");
+    let letters = s.chars().take_while(|c| c.is_ascii_alphabetic()).count();
+    if letters == 0 {
+        return None;
+    }
+    let mut col = 0usize;
+    for b in s[..letters].bytes() {
+        col = col * 26 + (b.to_ascii_uppercase() - b'A' + 1) as usize;
+    }
+    let row: usize = s[letters..].parse().ok()?;
+    Some((row.checked_sub(1)?, col - 1))
+}
+
+fn parse_range(s: &str) -> Option<MergedRange> {
+    let (a, b) = s.split_once(':').unwrap_or((s, s));
+    let (r1, c1) = parse_cell_ref(a)?;
+    let (r2, c2) = parse_cell_ref(b)?;
+    Some(MergedRange {
+        first_row: r1.min(r2),
+        first_col: c1.min(c2),
+        last_row: r1.max(r2),
+        last_col: c1.max(c2),
+    })
+}
+
+fn parse_workbook(xml: &str, rels: &HashMap<String, String>) -> Result<(Vec<XlsxSheetRef>, bool)> {
+    let mut reader = XmlReader::from_str(xml);
+    let mut buf = Vec::new();
+    let mut sheets = Vec::new();
+    let mut date1904 = false;
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => match e.local_name().as_ref() {
+                b"workbookPr" => date1904 = attr_bool(&e, b"date1904"),
+                b"sheet" => {
+                    let Some(name) = attr(&e, b"name") else {
+                        continue;
+                    };
+                    let Some(target) = attr(&e, b"id").and_then(|rid| rels.get(&rid).cloned())
+                    else {
+                        continue;
+                    };
+                    let target = target.trim_start_matches('/');
+                    let path = if target.starts_with("xl/") {
+                        target.to_string()
+                    } else {
+                        format!("xl/{}", docx::normalize_docx_rel_target(target))
+                    };
+                    let hidden = attr(&e, b"state").map(|s| s != "visible").unwrap_or(false);
+                    sheets.push(XlsxSheetRef { name, path, hidden });
+                }
+                _ => {}
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(anyhow!("XML解析失败: {e:?}")),
+            _ => {}
+        }
+    }
+    Ok((sheets, date1904))
+}
+
+/// <si> 内所有 <t> 拼接（跳过注音 rPh）
+fn parse_shared_strings(xml: &str) -> Result<Vec<String>> {
+    let mut reader = XmlReader::from_str(xml);
+    reader.config_mut().trim_text(false);
+    let mut buf = Vec::new();
+    let mut out = Vec::new();
+    let mut cur: Option<String> = None;
+    let mut in_t = false;
+    let mut in_rph = false;
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => match e.local_name().as_ref() {
+                b"si" => cur = Some(String::new()),
+                b"t" => in_t = true,
+                b"rPh" => in_rph = true,
+                _ => {}
+            },
+            Ok(Event::Empty(e)) => {
+                if e.local_name().as_ref() == b"si" {
+                    out.push(String::new());
+                }
+            }
+            Ok(Event::Text(t)) => {
+                if in_t && !in_rph {
+                    if let Some(s) = cur.as_mut() {
+                        s.push_str(&t.unescape()?);
+                    }
+                }
+            }
+            Ok(Event::End(e)) => match e.local_name().as_ref() {
+                b"si" => out.push(cur.take().unwrap_or_default()),
+                b"t" => in_t = false,
+                b"rPh" => in_rph = false,
+                _ => {}
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(anyhow!("XML解析失败: {e:?}")),
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StylesSection {
+    Other,
+    Fonts,
+    Fills,
+    CellXfs,
+}
+
+/// styles.xml -> 每个 cellXfs 条目的数字格式与基础样式
+fn parse_styles(xml: &str) -> Result<Vec<CellXf>> {
+    let mut reader = XmlReader::from_str(xml);
+    let mut buf = Vec::new();
+    let mut num_fmts: HashMap<u32, String> = HashMap::new();
+    let mut fonts: Vec<CellStyle> = Vec::new();
+    let mut fills: Vec<Option<String>> = Vec::new();
+    // (numFmtId, fontId, fillId, 对齐/换行)
+    let mut raw_xfs: Vec<(u32, usize, usize, CellStyle)> = Vec::new();
+    let mut section = StylesSection::Other;
+    let mut fill_solid = false;
+
+    loop {
+        buf.clear();
+        let (e, is_start) = match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => (e, true),
+            Ok(Event::Empty(e)) => (e, false),
+            Ok(Event::End(e)) => {
+                match e.local_name().as_ref() {
+                    b"fonts" | b"fills" | b"cellXfs" => section = StylesSection::Other,
+                    _ => {}
+                }
+                continue;
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(anyhow!("XML解析失败: {e:?}")),
+            _ => continue,
+        };
+        match (section, e.local_name().as_ref()) {
+            (_, b"numFmt") => {
+                if let (Some(id), Some(code)) =
+                    (attr_usize(&e, b"numFmtId"), attr(&e, b"formatCode"))
+                {
+                    num_fmts.insert(id as u32, code);
+                }
+            }
+            (_, b"fonts") if is_start => section = StylesSection::Fonts,
+            (_, b"fills") if is_start => section = StylesSection::Fills,
+            (_, b"cellXfs") if is_start => section = StylesSection::CellXfs,
+            (StylesSection::Fonts, b"font") => fonts.push(CellStyle::default()),
+            (StylesSection::Fonts, name) => {
+                if let Some(font) = fonts.last_mut() {
+                    match name {
+                        b"b" => font.bold = toggle_on(&e),
+                        b"i" => font.italic = toggle_on(&e),
+                        b"strike" => font.strike = toggle_on(&e),
+                        b"u" => {
+                            font.underline = attr(&e, b"val").map(|v| v != "none").unwrap_or(true)
+                        }
+                        b"color" => font.font_color = color_of(&e),
+                        _ => {}
+                    }
+                }
+            }
+            (StylesSection::Fills, b"fill") => fills.push(None),
+            (StylesSection::Fills, b"patternFill") => {
+                fill_solid = attr(&e, b"patternType")
+                    .map(|v| v != "none")
+                    .unwrap_or(false);
+            }
+            (StylesSection::Fills, b"fgColor") if fill_solid => {
+                if let Some(fill) = fills.last_mut() {
+                    *fill = color_of(&e);
+                }
+            }
+            (StylesSection::CellXfs, b"xf") => raw_xfs.push((
+                attr_usize(&e, b"numFmtId").unwrap_or(0) as u32,
+                attr_usize(&e, b"fontId").unwrap_or(0),
+                attr_usize(&e, b"fillId").unwrap_or(0),
+                CellStyle::default(),
+            )),
+            (StylesSection::CellXfs, b"alignment") => {
+                if let Some((_, _, _, st)) = raw_xfs.last_mut() {
+                    st.h_align = attr(&e, b"horizontal").filter(|v| v != "general");
+                    st.v_align = attr(&e, b"vertical");
+                    st.wrap = attr(&e, b"wrapText")
+                        .map(|v| v == "1" || v == "true")
+                        .unwrap_or(false);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(raw_xfs
+        .into_iter()
+        .map(|(num_fmt_id, font_id, fill_id, align)| {
+            let font = fonts.get(font_id).cloned().unwrap_or_default();
+            let num_fmt = num_fmts
+                .get(&num_fmt_id)
+                .cloned()
+                .or_else(|| excel_format::builtin_format(num_fmt_id).map(|s| s.to_string()));
+            CellXf {
+                num_fmt,
+                style: CellStyle {
+                    fill_color: fills.get(fill_id).cloned().flatten(),
+                    h_align: align.h_align,
+                    v_align: align.v_align,
+                    wrap: align.wrap,
+                    ..font
+                },
+            }
+        })
+        .collect())
+}
+
+/// rgb="FFRRGGBB" 或 indexed 调色板；主题色不解析
+fn color_of(e: &BytesStart) -> Option<String> {
+    if let Some(rgb) = attr(e, b"rgb") {
+        let rgb = rgb.trim();
+        if rgb.len() >= 6 {
+            return Some(rgb[rgb.len() - 6..].to_ascii_uppercase());
+        }
+    }
+    let idx = attr_usize(e, b"indexed")?;
+    INDEXED_COLORS.get(idx).map(|s| s.to_string())
+}
+
+/// 默认 indexed 调色板（0~63）
+const INDEXED_COLORS: [&str; 64] = [
+    "000000", "FFFFFF", "FF0000", "00FF00", "0000FF", "FFFF00", "FF00FF", "00FFFF", "000000",
+    "FFFFFF", "FF0000", "00FF00", "0000FF", "FFFF00", "FF00FF", "00FFFF", "800000", "008000",
+    "000080", "808000", "800080", "008080", "C0C0C0", "808080", "9999FF", "993366", "FFFFCC",
+    "CCFFFF", "660066", "FF8080", "0066CC", "CCCCFF", "000080", "FF00FF", "FFFF00", "00FFFF",
+    "800080", "800000", "008080", "0000FF", "00CCFF", "CCFFFF", "CCFFCC", "FFFF99", "99CCFF",
+    "FF99CC", "CC99FF", "FFCC99", "3366FF", "33CCCC", "99CC00", "FFCC00", "FF9900", "FF6600",
+    "666699", "969696", "003366", "339966", "003300", "333300", "993300", "993366", "333399",
+    "333333",
+];
+
+fn toggle_on(e: &BytesStart) -> bool {
+    attr(e, b"val")
+        .map(|v| !matches!(v.as_str(), "0" | "false"))
+        .unwrap_or(true)
+}
+
+fn attr(e: &BytesStart, key: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == key)
+        .and_then(|a| a.unescape_value().ok())
+        .map(|v| v.to_string())
+}
+
+fn attr_usize(e: &BytesStart, key: &[u8]) -> Option<usize> {
+    attr(e, key).and_then(|v| v.trim().parse().ok())
+}
+
+fn attr_bool(e: &BytesStart, key: &[u8]) -> bool {
+    attr(e, key)
+        .map(|v| v == "1" || v == "true")
+        .unwrap_or(false)
+}
+
+fn read_zip_text<R: Read + Seek>(zip: &mut ZipArchive<R>, name: &str) -> Result<String> {
+    let mut f = zip.by_name(name)?;
+    let mut s = String::new();
+    f.read_to_string(&mut s)?;
+    Ok(s)
+}